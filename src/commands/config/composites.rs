@@ -0,0 +1,178 @@
+use crate::commands::local::run_cmd_output;
+use serde_json::Value as JsonValue;
+use std::error::Error;
+use std::fmt;
+
+/// An XR or claim of a kind defined by a Configuration's XRDs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(super) struct CompositeResource {
+    /// `<plural>.<group>`, usable with `kubectl get`.
+    pub(super) resource: String,
+    pub(super) namespace: Option<String>,
+    pub(super) name: String,
+    pub(super) claim: bool,
+}
+
+impl fmt::Display for CompositeResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.resource, self.name)?;
+        if let Some(namespace) = &self.namespace {
+            write!(f, " -n {}", namespace)?;
+        }
+        if self.claim {
+            write!(f, " (claim)")?;
+        }
+        Ok(())
+    }
+}
+
+/// Claims and XRs whose XRD was installed by one of `config_names`.
+pub(super) fn composite_resources(
+    config_names: &[String],
+) -> Result<Vec<CompositeResource>, Box<dyn Error>> {
+    let mut found = Vec::new();
+    for name in config_names {
+        let selector = format!("pkg.crossplane.io/package={}", name);
+        let raw = run_cmd_output(
+            "kubectl",
+            &[
+                "get",
+                "configurationrevisions.pkg.crossplane.io",
+                "-l",
+                &selector,
+                "-o",
+                "json",
+            ],
+        )?;
+        for xrd in xrd_names(&serde_json::from_str(&raw)?) {
+            let Ok(raw) = run_cmd_output(
+                "kubectl",
+                &[
+                    "get",
+                    "compositeresourcedefinitions.apiextensions.crossplane.io",
+                    &xrd,
+                    "-o",
+                    "json",
+                ],
+            ) else {
+                continue;
+            };
+            let (xr_resource, claim_resource) = xrd_resources(&serde_json::from_str(&raw)?);
+            // Claims first: deleting them is what removes their XRs.
+            for (resource, claim) in claim_resource
+                .into_iter()
+                .map(|r| (r, true))
+                .chain(xr_resource.map(|r| (r, false)))
+            {
+                found.extend(list_resources(&resource, claim));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// XRD names in the `status.objectRefs` of a ConfigurationRevision list.
+fn xrd_names(revisions: &JsonValue) -> Vec<String> {
+    let mut names: Vec<String> = revisions
+        .get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|revision| revision.pointer("/status/objectRefs")?.as_array())
+        .flatten()
+        .filter(|r| {
+            r.get("kind").and_then(JsonValue::as_str) == Some("CompositeResourceDefinition")
+        })
+        .filter_map(|r| Some(r.get("name")?.as_str()?.to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// `<plural>.<group>` of the XR kind and, if it offers one, the claim kind.
+fn xrd_resources(xrd: &JsonValue) -> (Option<String>, Option<String>) {
+    let Some(group) = xrd.pointer("/spec/group").and_then(JsonValue::as_str) else {
+        return (None, None);
+    };
+    let resource = |pointer: &str| {
+        xrd.pointer(pointer)
+            .and_then(JsonValue::as_str)
+            .map(|plural| format!("{}.{}", plural, group))
+    };
+    (
+        resource("/spec/names/plural"),
+        resource("/spec/claimNames/plural"),
+    )
+}
+
+fn list_resources(resource: &str, claim: bool) -> Vec<CompositeResource> {
+    // The kind may not be served (e.g. the XRD never became established).
+    let Ok(raw) = run_cmd_output("kubectl", &["get", resource, "-A", "-o", "json"]) else {
+        log::debug!("Could not list {}", resource);
+        return Vec::new();
+    };
+    let Ok(list) = serde_json::from_str::<JsonValue>(&raw) else {
+        return Vec::new();
+    };
+    list.get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            Some(CompositeResource {
+                resource: resource.to_string(),
+                namespace: item
+                    .pointer("/metadata/namespace")
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string),
+                name: item.pointer("/metadata/name")?.as_str()?.to_string(),
+                claim,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn xrds_come_from_revision_object_refs() {
+        let revisions = json!({"items": [
+            {"status": {"objectRefs": [
+                {"kind": "CompositeResourceDefinition", "name": "xnetworks.aws.hops.ops.com.ai"},
+                {"kind": "Composition", "name": "xnetworks.aws.hops.ops.com.ai"}
+            ]}},
+            {"status": {"objectRefs": [
+                {"kind": "CompositeResourceDefinition", "name": "xnetworks.aws.hops.ops.com.ai"}
+            ]}}
+        ]});
+        assert_eq!(xrd_names(&revisions), ["xnetworks.aws.hops.ops.com.ai"]);
+
+        let xrd = json!({"spec": {
+            "group": "aws.hops.ops.com.ai",
+            "names": {"plural": "xnetworks"},
+            "claimNames": {"plural": "networks"}
+        }});
+        assert_eq!(
+            xrd_resources(&xrd),
+            (
+                Some("xnetworks.aws.hops.ops.com.ai".to_string()),
+                Some("networks.aws.hops.ops.com.ai".to_string())
+            )
+        );
+
+        let claim = CompositeResource {
+            resource: "networks.aws.hops.ops.com.ai".to_string(),
+            namespace: Some("dev".to_string()),
+            name: "main".to_string(),
+            claim: true,
+        };
+        assert_eq!(
+            claim.to_string(),
+            "networks.aws.hops.ops.com.ai/main -n dev (claim)"
+        );
+    }
+}
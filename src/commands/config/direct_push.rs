@@ -0,0 +1,201 @@
+use super::install::{split_ref, strip_registry};
+use super::uppkg::{DockerSaveManifestEntry, UppkgArchive};
+use crate::commands::local::registry;
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::io::Cursor;
+
+const MANIFEST_MEDIA_TYPE: &str = "application/vnd.docker.distribution.manifest.v2+json";
+const CONFIG_MEDIA_TYPE: &str = "application/vnd.docker.container.image.v1+json";
+const LAYER_MEDIA_TYPE: &str = "application/vnd.docker.image.rootfs.diff.tar.gzip";
+
+/// Whether every image in `archive` can be pushed straight from the tarball,
+/// without `docker load`: each has a valid image config (`rootfs.type` is
+/// `layers`; `up project build` render images lack it and need the
+/// `docker build` rebuild) and gzipped layers named by their digest
+/// (`<digest>.tar.gz`, or an OCI layout's blobs), so they can be uploaded as
+/// they are.
+pub(super) fn supported(archive: &UppkgArchive) -> bool {
+    !archive.manifest().is_empty()
+        && archive.manifest().iter().all(|entry| {
+            entry.layers.iter().all(|layer| {
+                layer_digest(layer).is_some() && archive.is_gzip(layer).unwrap_or(false)
+            }) && archive
+                .read_entry(&entry.config)
+                .ok()
+                .and_then(|config| serde_json::from_slice::<JsonValue>(&config).ok())
+                .is_some_and(|config| config.pointer("/rootfs/type") == Some(&json!("layers")))
+        })
+}
+
+/// The images in `archive`, named as `docker load` reports them: each
+/// RepoTag, or the image ID of an untagged image.
+pub(super) fn images(archive: &UppkgArchive) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut images = Vec::new();
+    for entry in archive.manifest() {
+        match entry.repo_tags.as_deref() {
+            Some(tags) if !tags.is_empty() => images.extend(tags.iter().cloned()),
+            _ => images.push(config_digest(archive, entry)?),
+        }
+    }
+    Ok(images)
+}
+
+/// The ID Docker would give `image` once loaded: the digest of its config.
+pub(super) fn image_id(archive: &UppkgArchive, image: &str) -> Result<String, Box<dyn Error>> {
+    config_digest(archive, archive.image(image)?)
+}
+
+/// Push `image` from `archive` as `push_ref` and return the manifest digest.
+pub(super) fn push(
+    archive: &UppkgArchive,
+    image: &str,
+    push_ref: &str,
+) -> Result<String, Box<dyn Error>> {
+    let entry = archive.image(image)?;
+    let config = archive.read_entry(&entry.config)?;
+    push_image(archive, entry, None, &config, push_ref)
+}
+
+/// Push `image` from `archive` as `push_ref` with its base layer replaced by
+/// `package_yaml`, and return the manifest digest.
+pub(super) fn push_patched(
+    archive: &UppkgArchive,
+    image: &str,
+    package_yaml: &str,
+    push_ref: &str,
+) -> Result<String, Box<dyn Error>> {
+    let entry = archive.image(image)?;
+    let patched = archive.patched_image(image, package_yaml, true)?;
+    push_image(
+        archive,
+        entry,
+        Some((patched.base, &patched.layer)),
+        &patched.config,
+        push_ref,
+    )
+}
+
+/// Upload the layers (streamed from the archive, except a `replaced` one)
+/// and config the registry doesn't have yet, then the manifest.
+fn push_image(
+    archive: &UppkgArchive,
+    entry: &DockerSaveManifestEntry,
+    replaced: Option<(usize, &[u8])>,
+    config: &[u8],
+    push_ref: &str,
+) -> Result<String, Box<dyn Error>> {
+    let (path, tag) = split_ref(push_ref);
+    let repository = strip_registry(path);
+
+    let mut layers = Vec::new();
+    for (i, name) in entry.layers.iter().enumerate() {
+        let layer = match replaced {
+            Some((base, layer)) if base == i => {
+                let digest = format!("sha256:{:x}", Sha256::digest(layer));
+                if !registry::blob_exists(repository, &digest)? {
+                    registry::upload_blob(
+                        repository,
+                        &digest,
+                        layer.len() as u64,
+                        Cursor::new(layer),
+                    )?;
+                }
+                descriptor(LAYER_MEDIA_TYPE, &digest, layer.len() as u64)
+            }
+            _ => {
+                let digest = layer_digest(name)
+                    .ok_or_else(|| format!("layer '{}' is not named by its digest", name))?;
+                let (reader, size) = archive.entry_reader(name)?;
+                if registry::blob_exists(repository, &digest)? {
+                    log::debug!("{} already has {}", repository, digest);
+                } else {
+                    registry::upload_blob(repository, &digest, size, reader)?;
+                }
+                descriptor(LAYER_MEDIA_TYPE, &digest, size)
+            }
+        };
+        layers.push(layer);
+    }
+
+    let config_digest = format!("sha256:{:x}", Sha256::digest(config));
+    if !registry::blob_exists(repository, &config_digest)? {
+        registry::upload_blob(
+            repository,
+            &config_digest,
+            config.len() as u64,
+            Cursor::new(config),
+        )?;
+    }
+    let manifest = manifest(
+        descriptor(CONFIG_MEDIA_TYPE, &config_digest, config.len() as u64),
+        layers,
+    )?;
+    registry::put_manifest(repository, tag, MANIFEST_MEDIA_TYPE, &manifest)?;
+    Ok(format!("sha256:{:x}", Sha256::digest(&manifest)))
+}
+
+fn manifest(config: JsonValue, layers: Vec<JsonValue>) -> Result<Vec<u8>, Box<dyn Error>> {
+    Ok(serde_json::to_vec(&json!({
+        "schemaVersion": 2,
+        "mediaType": MANIFEST_MEDIA_TYPE,
+        "config": config,
+        "layers": layers,
+    }))?)
+}
+
+fn descriptor(media_type: &str, digest: &str, size: u64) -> JsonValue {
+    json!({"mediaType": media_type, "size": size, "digest": digest})
+}
+
+/// The ID Docker gives an image: the digest of its config.
+pub(super) fn config_digest(
+    archive: &UppkgArchive,
+    entry: &DockerSaveManifestEntry,
+) -> Result<String, Box<dyn Error>> {
+    Ok(format!(
+        "sha256:{:x}",
+        Sha256::digest(archive.read_entry(&entry.config)?)
+    ))
+}
+
+/// `sha256:<hex>` for a layer stored as `<hex>.tar.gz` or `blobs/sha256/<hex>`.
+fn layer_digest(name: &str) -> Option<String> {
+    name.strip_suffix(".tar.gz")
+        .or_else(|| name.strip_prefix("blobs/sha256/"))
+        .filter(|hex| hex.len() == 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+        .map(|hex| format!("sha256:{}", hex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_describes_config_and_gzip_layers() {
+        let hex = "a".repeat(64);
+        assert_eq!(
+            layer_digest(&format!("{}.tar.gz", hex)),
+            Some(format!("sha256:{}", hex))
+        );
+        assert_eq!(
+            layer_digest(&format!("blobs/sha256/{}", hex)),
+            Some(format!("sha256:{}", hex))
+        );
+        assert_eq!(layer_digest("abc.tar.gz"), None);
+        assert_eq!(layer_digest(&format!("{}/layer.tar", hex)), None);
+
+        let manifest = manifest(
+            descriptor(CONFIG_MEDIA_TYPE, "sha256:c", 2),
+            vec![descriptor(LAYER_MEDIA_TYPE, "sha256:l", 3)],
+        )
+        .unwrap();
+        let manifest: JsonValue = serde_json::from_slice(&manifest).unwrap();
+        assert_eq!(manifest["schemaVersion"], 2);
+        assert_eq!(manifest["mediaType"], MANIFEST_MEDIA_TYPE);
+        assert_eq!(manifest["config"]["digest"], "sha256:c");
+        assert_eq!(manifest["layers"][0]["mediaType"], LAYER_MEDIA_TYPE);
+        assert_eq!(manifest["layers"][0]["size"], 3);
+    }
+}
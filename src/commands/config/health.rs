@@ -0,0 +1,108 @@
+use crate::commands::local::conditions::{
+    get_resource_json, pending_conditions, wait_until, PendingCondition,
+};
+use crate::commands::local::events;
+use serde_json::Value as JsonValue;
+use std::error::Error;
+use std::time::Duration;
+
+const EVENTS_ON_FAILURE: usize = 10;
+
+/// Wait until every Configuration reports Installed and Healthy, logging
+/// new problems (e.g. dependency resolution errors) as they appear. On
+/// timeout the outstanding conditions and recent Warning events are returned
+/// as the error. Names may be `provider/<name>` or `function/<name>` for
+/// standalone packages; bare names are Configurations.
+pub fn wait_for_configurations_healthy(
+    names: &[String],
+    timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+    if names.is_empty() {
+        return Ok(());
+    }
+    log::info!(
+        "Waiting up to {} for {} {} to become healthy...",
+        humantime::format_duration(timeout),
+        packages_label(names),
+        names.join(", ")
+    );
+
+    wait_until(timeout, || {
+        names
+            .iter()
+            .flat_map(|name| package_pending_conditions(name))
+            .collect()
+    })
+    .map_err(|pending| timeout_error(names, &pending, timeout))?;
+
+    crate::logging::success(&format!(
+        "{} {} healthy",
+        packages_label(names),
+        names.join(", ")
+    ));
+    Ok(())
+}
+
+fn packages_label(names: &[String]) -> &'static str {
+    if names.iter().all(|name| !name.contains('/')) {
+        "Configuration(s)"
+    } else {
+        "Package(s)"
+    }
+}
+
+/// Split `provider/<name>`/`function/<name>` into kind and name; bare names
+/// are Configurations.
+fn split_package_name(name: &str) -> (&str, &str) {
+    match name.split_once('/') {
+        Some((kind @ ("provider" | "function"), name)) => (kind, name),
+        _ => ("configuration", name),
+    }
+}
+
+fn timeout_error(names: &[String], pending: &[PendingCondition], timeout: Duration) -> String {
+    let mut lines = vec![format!(
+        "{} did not become healthy within {} (raise --timeout or `timeouts.configuration`, or use --no-wait to skip this check):",
+        packages_label(names),
+        humantime::format_duration(timeout)
+    )];
+    lines.extend(pending.iter().map(|condition| format!("  {}", condition)));
+    for name in names {
+        let (kind, name) = split_package_name(name);
+        let warnings = events::recent_warnings(kind, name, EVENTS_ON_FAILURE);
+        if !warnings.is_empty() {
+            lines.push(format!("Recent warning events for {}:", name));
+            lines.extend(warnings.into_iter().map(|line| format!("  {}", line)));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Conditions on the package and its current revision that are not True yet.
+fn package_pending_conditions(name: &str) -> Vec<PendingCondition> {
+    let (kind, name) = split_package_name(name);
+    let resource = format!("{}/{}", kind, name);
+    let Some(package) = get_resource_json(&format!("{}.pkg.crossplane.io", kind), name, None)
+    else {
+        return vec![PendingCondition::not_found(&resource)];
+    };
+
+    let mut pending = pending_conditions(&resource, &package, &["Installed", "Healthy"]);
+    let revision = package
+        .pointer("/status/currentRevision")
+        .and_then(JsonValue::as_str);
+    if let Some(revision) = revision {
+        if let Some(revision_json) = get_resource_json(
+            &format!("{}revision.pkg.crossplane.io", kind),
+            revision,
+            None,
+        ) {
+            pending.extend(pending_conditions(
+                &format!("{}revision/{}", kind, revision),
+                &revision_json,
+                &["Healthy"],
+            ));
+        }
+    }
+    pending
+}
@@ -1,39 +1,59 @@
+use super::direct_push;
+use super::health;
+use super::uppkg::UppkgArchive;
+use crate::commands::local::conditions::wait_for_deployment;
+use crate::commands::local::context;
+use crate::commands::local::history::{self, HistoryEntry};
+use crate::commands::local::process::{read_state, write_state};
 use crate::commands::local::{
-    kubectl_apply_stdin, kubectl_command, repo_cache_path, run_cmd, run_cmd_output,
-    sync_registry_hosts_entry, HOPS_KUBE_CONTEXT_ENV,
+    command_exists, kubectl_apply_stdin, kubectl_command, registry, repo_cache_path, run_cmd,
+    run_cmd_output, sync_registry_hosts_entry, HOPS_KUBE_CONTEXT_ENV,
 };
-use clap::Args;
-use flate2::read::GzDecoder;
+use crate::settings::RegistryMode;
+use clap::{Args, ValueEnum};
 use notify::{RecursiveMode, Watcher};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fs;
 use std::hash::{Hash, Hasher};
-use std::io::{self, Cursor, IsTerminal, Read, Write};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tar::Archive;
-
-const REGISTRY_YAML: &str = include_str!("../../../bootstrap/registry/registry.yaml");
-
-/// Host address for `docker push` (NodePort exposed by the in-cluster registry)
-const REGISTRY_PUSH: &str = "localhost:30500";
 
 /// Cluster-internal address used in Crossplane package references
-const REGISTRY_PULL: &str = "registry.crossplane-system.svc.cluster.local:5000";
+pub(super) const REGISTRY_PULL: &str = "registry.crossplane-system.svc.cluster.local:5000";
 const REGISTRY_HOSTNAME: &str = "registry.crossplane-system.svc.cluster.local";
 
+const PUSH_CACHE_FILE: &str = "push-cache.json";
+
+/// Concurrent `docker load`/build/push jobs.
+const MAX_PARALLEL_JOBS: usize = 4;
+
+/// Placeholder registry for untagged packages; stripped when pushing.
+const LOCAL_PACKAGE_REGISTRY: &str = "local.hops";
+
 #[derive(Args, Debug)]
 pub struct ConfigArgs {
     /// Path to the local XRD project directory (defaults to current directory)
     #[arg(long, conflicts_with = "repo")]
     pub path: Option<String>,
 
+    /// Push and apply an already-built package (.uppkg or single-image .xpkg)
+    /// instead of building the project
+    #[arg(long, conflicts_with_all = ["path", "repo", "watch"])]
+    pub file: Option<PathBuf>,
+
+    /// Install every configuration listed in a stack file (repos, paths,
+    /// package files, and optional AWS settings)
+    #[arg(long, conflicts_with_all = ["path", "repo", "version", "file", "watch"])]
+    pub stack: Option<PathBuf>,
+
     /// GitHub repository in <org>/<repo> format (for example hops-ops/helm-certmanager)
     #[arg(long, conflicts_with = "path")]
     pub repo: Option<String>,
@@ -42,6 +62,26 @@ pub struct ConfigArgs {
     #[arg(long, requires = "repo")]
     pub version: Option<String>,
 
+    /// Clone source builds from this URL instead of https://github.com/<org>/<repo>
+    /// (for example an SSH URL or a mirror; requires --repo)
+    #[arg(long, requires = "repo", conflicts_with = "version")]
+    pub git_url: Option<String>,
+
+    /// Branch, tag, or full commit SHA to build from source with --repo
+    /// (shallow-fetched instead of the default branch)
+    #[arg(
+        long = "ref",
+        value_name = "REF",
+        requires = "repo",
+        conflicts_with = "version"
+    )]
+    pub git_ref: Option<String>,
+
+    /// Tool used to build the project package (auto: `up` when upbound.yaml
+    /// exists, `crossplane` when crossplane.yaml does)
+    #[arg(long, value_enum, default_value_t = Builder::Auto, conflicts_with = "file")]
+    pub builder: Builder,
+
     /// Set spec.skipDependencyResolution=true on the generated Configuration
     #[arg(long)]
     pub skip_dependency_resolution: bool,
@@ -57,45 +97,189 @@ pub struct ConfigArgs {
     /// Debounce interval for --watch in seconds (default: 15)
     #[arg(long, requires = "watch", default_value = "15")]
     pub debounce: u64,
+
+    /// Return right after applying instead of waiting for the Configuration to become healthy
+    #[arg(long)]
+    pub no_wait: bool,
+
+    /// Build and show what would change (images to push, ImageConfig
+    /// rewrites, rendered YAML, dependency changes) without touching the
+    /// cluster or registry
+    #[arg(long, conflicts_with_all = ["watch", "stack"])]
+    pub plan: bool,
+
+    /// How long to wait for the Configuration to become healthy, e.g. 10m
+    /// (default: `timeouts.configuration` in ~/.hops/config.yaml, else 5m)
+    #[arg(long, value_parser = humantime::parse_duration, conflicts_with = "no_wait")]
+    pub timeout: Option<Duration>,
+
+    /// Check the project with `hops validate` before building it (and before
+    /// each --watch rebuild)
+    #[arg(long, conflicts_with_all = ["repo", "file", "stack"])]
+    pub validate: bool,
+
+    /// Turn on `hops local mock`: scale cloud providers to zero and report
+    /// their managed resources Ready, to exercise compositions without
+    /// credentials
+    #[arg(long, conflicts_with = "plan")]
+    pub mock_providers: bool,
+}
+
+/// Package build tool for source installs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Builder {
+    /// Detect from the project files
+    #[default]
+    Auto,
+    /// `up project build`, writing `.uppkg` files to `_output/`
+    Up,
+    /// `crossplane xpkg build`, writing a single `.xpkg`
+    Crossplane,
 }
 
 #[derive(Clone, Debug)]
 struct RepoSpec {
     org: String,
     repo: String,
+    /// Explicit clone URL (SSH `--repo` or `--git-url`); GitHub HTTPS otherwise.
+    clone_url: Option<String>,
+    /// Revision to check out instead of the default branch (`--ref`).
+    git_ref: Option<String>,
 }
 
 #[derive(Clone, Debug)]
 struct LoadedImage {
     source: String,
-    uppkg_path: PathBuf,
+    /// The package file the image was loaded from.
+    archive: Arc<UppkgArchive>,
+    /// Pushed straight from `archive` to the registry; the image was never
+    /// loaded into Docker.
+    direct: bool,
+    /// Set for a single-image Provider/Function package applied on its own.
+    standalone: Option<StandaloneKind>,
 }
 
-#[derive(Clone, Debug)]
-struct RenderRewrite {
+/// Non-Configuration package kinds that `config install` applies directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum StandaloneKind {
+    Provider,
+    Function,
+}
+
+impl StandaloneKind {
+    fn from_meta_kind(kind: &str) -> Option<Self> {
+        match kind {
+            "Provider" => Some(Self::Provider),
+            "Function" => Some(Self::Function),
+            _ => None,
+        }
+    }
+
+    fn kind(self) -> &'static str {
+        match self {
+            Self::Provider => "Provider",
+            Self::Function => "Function",
+        }
+    }
+
+    /// Lowercase kind: the image tag, and the prefix of the names returned
+    /// for health checks (`provider/<name>`).
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Provider => "provider",
+            Self::Function => "function",
+        }
+    }
+}
+
+/// Images pushed to the local registry, persisted in
+/// `~/.hops/local/push-cache.json` so later installs (and `--watch` cycles)
+/// skip images whose content did not change.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(super) struct PushCache {
+    pushed: HashMap<String, PushedImage>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct PushedImage {
+    push_ref: String,
+    /// Local image ID (plus patched package.yaml hash for configurations).
+    content_id: String,
+    /// Manifest digest computed for the push and verified in the registry.
     digest: String,
-    target_prefix: String,
 }
 
-#[derive(Debug, Deserialize)]
-struct DockerSaveManifestEntry {
-    #[serde(rename = "Config")]
-    config: String,
-    #[serde(rename = "RepoTags")]
-    repo_tags: Option<Vec<String>>,
-    #[serde(rename = "Layers")]
-    layers: Vec<String>,
+impl PushCache {
+    pub(super) fn load() -> Self {
+        read_state(PUSH_CACHE_FILE).unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        write_state(PUSH_CACHE_FILE, self)
+    }
+
+    /// The previous push of `push_ref` when it had the same content.
+    fn unchanged(&self, push_ref: &str, content_id: &str) -> Option<&PushedImage> {
+        self.pushed
+            .get(&push_cache_key(push_ref))
+            .filter(|pushed| pushed.push_ref == push_ref && pushed.content_id == content_id)
+    }
+
+    /// Digest last pushed for `push_ref`.
+    fn digest(&self, push_ref: &str) -> Option<&str> {
+        self.pushed
+            .get(&push_cache_key(push_ref))
+            .filter(|pushed| pushed.push_ref == push_ref)
+            .map(|pushed| pushed.digest.as_str())
+    }
+
+    fn record(&mut self, push_ref: &str, content_id: String, digest: String) {
+        self.pushed.insert(
+            push_cache_key(push_ref),
+            PushedImage {
+                push_ref: push_ref.to_string(),
+                content_id,
+                digest,
+            },
+        );
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct DockerImageConfig {
-    config: Option<DockerImageConfigSection>,
+/// Configuration images get a new `dev-<hash>` tag per build, so they are
+/// cached per repository to keep one entry each.
+fn push_cache_key(push_ref: &str) -> String {
+    let (path, tag) = split_ref(push_ref);
+    if tag.starts_with("dev-") {
+        path.to_string()
+    } else {
+        push_ref.to_string()
+    }
 }
 
-#[derive(Debug, Deserialize)]
-struct DockerImageConfigSection {
-    #[serde(rename = "Labels")]
-    labels: Option<HashMap<String, String>>,
+/// A cached push that the registry still serves with the same digest (the
+/// registry may have been reset since).
+fn unchanged_in_registry<'a>(
+    cache: &'a PushCache,
+    push_ref: &str,
+    content_id: &str,
+) -> Option<&'a PushedImage> {
+    let pushed = cache.unchanged(push_ref, content_id)?;
+    let (path, tag) = split_ref(push_ref);
+    match registry::manifest_digest(strip_registry(path), tag) {
+        Ok(Some(digest)) if digest == pushed.digest => Some(pushed),
+        Ok(_) => None,
+        Err(err) => {
+            log::debug!("Could not check {} in the registry: {}", push_ref, err);
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct RenderRewrite {
+    digest: String,
+    target_prefix: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -136,18 +320,77 @@ pub fn run(args: &ConfigArgs) -> Result<(), Box<dyn Error>> {
     if let Some(ctx) = &args.context {
         std::env::set_var(HOPS_KUBE_CONTEXT_ENV, ctx);
     }
+    // A stack may name its own context; `stack::run` checks that one.
+    if args.stack.is_none() && !args.plan {
+        context::ensure_local("config install")?;
+    }
+
+    let wait_timeout = if args.no_wait {
+        None
+    } else {
+        Some(crate::settings::timeouts(args.timeout)?.configuration)
+    };
+
+    if args.mock_providers {
+        crate::commands::local::mock::enable()?;
+    }
+
+    if let Some(stack) = &args.stack {
+        return super::stack::run(stack, args, wait_timeout);
+    }
+
+    if args.plan {
+        return run_plan(args);
+    }
+
+    if let Some(file) = &args.file {
+        let names = run_package_file(
+            file,
+            args.skip_dependency_resolution,
+            &mut PushCache::load(),
+        )?;
+        return wait_for_health(&names, wait_timeout);
+    }
 
     match (args.repo.as_deref(), args.version.as_deref()) {
         (Some(repo), Some(version)) => {
-            apply_repo_version(repo, version, args.skip_dependency_resolution)
+            let names = apply_repo_version(repo, version, args.skip_dependency_resolution)?;
+            wait_for_health(&names, wait_timeout)
+        }
+        (Some(repo), None) => {
+            let names = run_repo_install(
+                repo,
+                args.git_url.as_deref(),
+                args.git_ref.as_deref(),
+                args.builder,
+                args.skip_dependency_resolution,
+            )?;
+            wait_for_health(&names, wait_timeout)
         }
-        (Some(repo), None) => run_repo_install(repo, args.skip_dependency_resolution),
         (None, _) => {
             let path = args.path.as_deref().unwrap_or(".");
-            run_local_path(path, args.skip_dependency_resolution)?;
+            if args.validate {
+                crate::commands::validate::validate_project(Path::new(path))?;
+            }
+            let mut cache = PushCache::load();
+            let names = run_local_path(
+                path,
+                args.builder,
+                args.skip_dependency_resolution,
+                &mut cache,
+            )?;
+            wait_for_health(&names, wait_timeout)?;
 
             if args.watch {
-                run_watch(path, args.skip_dependency_resolution, args.debounce)?;
+                run_watch(
+                    path,
+                    args.builder,
+                    args.skip_dependency_resolution,
+                    args.validate,
+                    args.debounce,
+                    wait_timeout,
+                    cache,
+                )?;
             }
 
             Ok(())
@@ -155,6 +398,16 @@ pub fn run(args: &ConfigArgs) -> Result<(), Box<dyn Error>> {
     }
 }
 
+pub(super) fn wait_for_health(
+    names: &[String],
+    timeout: Option<Duration>,
+) -> Result<(), Box<dyn Error>> {
+    match timeout {
+        Some(timeout) => health::wait_for_configurations_healthy(names, timeout),
+        None => Ok(()),
+    }
+}
+
 fn should_ignore_path(path: &Path) -> bool {
     path.components().any(|c| {
         let s = c.as_os_str().to_string_lossy();
@@ -164,15 +417,19 @@ fn should_ignore_path(path: &Path) -> bool {
 
 fn run_watch(
     path: &str,
+    builder: Builder,
     skip_dependency_resolution: bool,
+    validate: bool,
     debounce_secs: u64,
+    wait_timeout: Option<Duration>,
+    mut cache: PushCache,
 ) -> Result<(), Box<dyn Error>> {
     let dir = Path::new(path).canonicalize()?;
     let debounce = Duration::from_secs(debounce_secs);
 
     let (tx, rx) = mpsc::channel();
-    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
-        match res {
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
             Ok(event) => {
                 let dominated_by_ignored = event.paths.iter().all(|p| should_ignore_path(p));
                 log::debug!(
@@ -186,8 +443,7 @@ fn run_watch(
                 }
             }
             Err(e) => log::debug!("watch error: {:?}", e),
-        }
-    })?;
+        })?;
     watcher.watch(&dir, RecursiveMode::Recursive)?;
 
     log::info!(
@@ -206,8 +462,18 @@ fn run_watch(
         log::info!("──────────────────────────────────────────────");
         log::info!("Change detected, rebuilding...");
 
-        match run_local_path(path, skip_dependency_resolution) {
-            Ok(()) => log::info!("Rebuild succeeded."),
+        let validated = if validate {
+            crate::commands::validate::validate_project(Path::new(path))
+        } else {
+            Ok(())
+        };
+        match crate::notification::notify_result(
+            "config install rebuild",
+            validated
+                .and_then(|_| run_local_path(path, builder, skip_dependency_resolution, &mut cache))
+                .and_then(|names| wait_for_health(&names, wait_timeout)),
+        ) {
+            Ok(()) => crate::logging::success("Rebuild succeeded."),
             Err(e) => log::error!("Rebuild failed: {}", e),
         }
 
@@ -237,11 +503,25 @@ fn wait_for_quiet(rx: &mpsc::Receiver<()>, debounce: Duration) -> Result<(), Box
 
 fn run_repo_install(
     repo: &str,
+    git_url: Option<&str>,
+    git_ref: Option<&str>,
+    builder: Builder,
     skip_dependency_resolution: bool,
-) -> Result<(), Box<dyn Error>> {
-    let spec = parse_repo_spec(repo)?;
-    match resolve_repo_install_target(&spec)? {
-        RepoInstallTarget::SourceBuild => run_repo_clone(&spec, skip_dependency_resolution),
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let spec = repo_spec_with_source(repo, git_url, git_ref)?;
+    // A ref only makes sense for a source build, so don't offer published versions.
+    let target = if spec.git_ref.is_some() {
+        RepoInstallTarget::SourceBuild
+    } else {
+        resolve_repo_install_target(&spec)?
+    };
+    match target {
+        RepoInstallTarget::SourceBuild => run_repo_clone(
+            &spec,
+            builder,
+            skip_dependency_resolution,
+            &mut PushCache::load(),
+        ),
         RepoInstallTarget::PublishedVersion(version) => {
             apply_repo_version_spec(&spec, &version, skip_dependency_resolution)
         }
@@ -250,14 +530,43 @@ fn run_repo_install(
 
 fn run_repo_clone(
     spec: &RepoSpec,
+    builder: Builder,
     skip_dependency_resolution: bool,
-) -> Result<(), Box<dyn Error>> {
-    let cache_path = ensure_cached_repo_checkout(&spec)?;
-    run_local_path(&cache_path.to_string_lossy(), skip_dependency_resolution)
+    cache: &mut PushCache,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    let cache_path = ensure_cached_repo_checkout(spec)?;
+    run_local_path(
+        &cache_path.to_string_lossy(),
+        builder,
+        skip_dependency_resolution,
+        cache,
+    )
+}
+
+/// Build `<org>/<repo>` from source without prompting (used by stacks).
+pub(super) fn run_repo_source(
+    repo: &str,
+    git_url: Option<&str>,
+    git_ref: Option<&str>,
+    builder: Builder,
+    skip_dependency_resolution: bool,
+    cache: &mut PushCache,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    run_repo_clone(
+        &repo_spec_with_source(repo, git_url, git_ref)?,
+        builder,
+        skip_dependency_resolution,
+        cache,
+    )
+}
+
+/// Clone or update the cached checkout of `<org>/<repo>` and return its path.
+pub fn repo_checkout(repo: &str, git_ref: Option<&str>) -> Result<PathBuf, Box<dyn Error>> {
+    ensure_cached_repo_checkout(&repo_spec_with_source(repo, None, git_ref)?)
 }
 
 fn resolve_repo_install_target(spec: &RepoSpec) -> Result<RepoInstallTarget, Box<dyn Error>> {
-    if !interactive_stdio_available() {
+    if !crate::interactive::prompts_allowed() {
         return Ok(RepoInstallTarget::SourceBuild);
     }
 
@@ -271,10 +580,6 @@ fn resolve_repo_install_target(spec: &RepoSpec) -> Result<RepoInstallTarget, Box
     }
 }
 
-fn interactive_stdio_available() -> bool {
-    io::stdin().is_terminal() && io::stdout().is_terminal()
-}
-
 fn prompt_for_repo_install_choice(spec: &RepoSpec) -> Result<RepoInstallChoice, Box<dyn Error>> {
     let repo_slug = format!("{}/{}", spec.org, spec.repo);
 
@@ -381,7 +686,7 @@ fn apply_repo_version_spec(
     spec: &RepoSpec,
     version: &str,
     skip_dependency_resolution: bool,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<Vec<String>, Box<dyn Error>> {
     let version = version.trim();
     if version.is_empty() {
         return Err("`--version` cannot be empty".into());
@@ -426,16 +731,38 @@ fn apply_repo_version_spec(
     // registry so they don't block dependency resolution for the published version.
     delete_local_registry_config_revisions(&config_name)?;
 
-    apply_configuration(&config_name, &package_ref, skip_dependency_resolution)
+    apply_configuration(&config_name, &package_ref, skip_dependency_resolution)?;
+    history::record(vec![HistoryEntry {
+        package: Some(package_ref),
+        source: Some(format!("{}/{}", spec.org, spec.repo)),
+        ..HistoryEntry::new("install", &config_name)
+    }]);
+    Ok(vec![config_name])
 }
 
 fn ensure_cached_repo_checkout(spec: &RepoSpec) -> Result<PathBuf, Box<dyn Error>> {
     let cache_path = repo_cache_path(&spec.org, &spec.repo)?;
-    let clone_url = format!("https://github.com/{}/{}", spec.org, spec.repo);
+    let clone_url = spec
+        .clone_url
+        .clone()
+        .unwrap_or_else(|| format!("https://github.com/{}/{}", spec.org, spec.repo));
+
+    if let Some(git_ref) = &spec.git_ref {
+        checkout_ref_into_cache(&clone_url, git_ref, &cache_path)?;
+        return Ok(cache_path);
+    }
+
+    if cache_path.join(".git").is_dir() && is_detached_checkout(&cache_path) {
+        log::info!(
+            "Cached repo at {} is pinned to a --ref checkout; re-cloning the default branch...",
+            cache_path.display()
+        );
+        fs::remove_dir_all(&cache_path)?;
+    }
 
     if cache_path.join(".git").is_dir() {
         log::info!("Updating cached repo at {}...", cache_path.display());
-        if let Err(err) = refresh_cached_repo(&cache_path) {
+        if let Err(err) = refresh_cached_repo(&clone_url, &cache_path) {
             log::warn!(
                 "Failed to update cached repo at {}: {}. Re-cloning...",
                 cache_path.display(),
@@ -466,30 +793,164 @@ fn clone_repo_into_cache(clone_url: &str, cache_path: &Path) -> Result<(), Box<d
     fs::create_dir_all(parent)?;
 
     let cache_path_str = cache_path.to_string_lossy().to_string();
+    // A clone cut short would otherwise be mistaken for a cached repo.
+    let _partial = crate::interrupt::temp_path(cache_path);
     log::info!(
         "Cloning {} into local cache at {}...",
         clone_url,
         cache_path.display()
     );
-    run_cmd("git", &["clone", clone_url, &cache_path_str])?;
+    run_git(clone_url, &["clone", clone_url, &cache_path_str]).map_err(private_repo_hint)?;
     Ok(())
 }
 
-fn refresh_cached_repo(cache_path: &Path) -> Result<(), Box<dyn Error>> {
+/// Shallow-fetch `git_ref` (branch, tag, or full SHA) into the cache and
+/// check it out detached.
+fn checkout_ref_into_cache(
+    clone_url: &str,
+    git_ref: &str,
+    cache_path: &Path,
+) -> Result<(), Box<dyn Error>> {
     let cache_path_str = cache_path.to_string_lossy().to_string();
+    if cache_path.join(".git").is_dir() {
+        run_cmd(
+            "git",
+            &[
+                "-C",
+                &cache_path_str,
+                "remote",
+                "set-url",
+                "origin",
+                clone_url,
+            ],
+        )?;
+    } else {
+        if cache_path.exists() {
+            fs::remove_dir_all(cache_path)?;
+        }
+        fs::create_dir_all(cache_path)?;
+        run_cmd("git", &["init", "--quiet", &cache_path_str])?;
+        run_cmd(
+            "git",
+            &["-C", &cache_path_str, "remote", "add", "origin", clone_url],
+        )?;
+    }
+
+    log::info!(
+        "Fetching {} from {} into local cache at {}...",
+        git_ref,
+        clone_url,
+        cache_path.display()
+    );
+    run_git(
+        clone_url,
+        &[
+            "-C",
+            &cache_path_str,
+            "fetch",
+            "--depth",
+            "1",
+            "origin",
+            git_ref,
+        ],
+    )
+    .map_err(|err| {
+        format!(
+            "failed to fetch ref '{}' (commit SHAs must be given in full): {}",
+            git_ref,
+            private_repo_hint(err)
+        )
+    })?;
     run_cmd(
         "git",
+        &[
+            "-C",
+            &cache_path_str,
+            "checkout",
+            "--force",
+            "--detach",
+            "FETCH_HEAD",
+        ],
+    )?;
+    Ok(())
+}
+
+fn is_detached_checkout(cache_path: &Path) -> bool {
+    let cache_path_str = cache_path.to_string_lossy().to_string();
+    run_cmd_output(
+        "git",
+        &["-C", &cache_path_str, "symbolic-ref", "-q", "HEAD"],
+    )
+    .is_err()
+}
+
+fn private_repo_hint(err: Box<dyn Error>) -> String {
+    format!(
+        "{}\nIf the repository is private, set GITHUB_TOKEN, run `gh auth login`, or pass an SSH URL with `--git-url git@github.com:<org>/<repo>.git`.",
+        err
+    )
+}
+
+fn refresh_cached_repo(clone_url: &str, cache_path: &Path) -> Result<(), Box<dyn Error>> {
+    let cache_path_str = cache_path.to_string_lossy().to_string();
+    // Keep origin in step with --git-url so switching URLs doesn't need a re-clone.
+    run_cmd(
+        "git",
+        &[
+            "-C",
+            &cache_path_str,
+            "remote",
+            "set-url",
+            "origin",
+            clone_url,
+        ],
+    )?;
+    run_git(
+        clone_url,
         &["-C", &cache_path_str, "fetch", "--prune", "origin"],
     )?;
-    run_cmd("git", &["-C", &cache_path_str, "pull", "--ff-only"])?;
+    run_git(clone_url, &["-C", &cache_path_str, "pull", "--ff-only"])?;
     Ok(())
 }
 
-fn apply_repo_version(
+/// Run git with GitHub HTTPS credentials wired in when `clone_url` needs them.
+fn run_git(clone_url: &str, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut full_args = github_auth_args(clone_url);
+    full_args.extend(args.iter().map(|arg| arg.to_string()));
+    let full_args: Vec<&str> = full_args.iter().map(String::as_str).collect();
+    run_cmd("git", &full_args)
+}
+
+/// `git -c` options adding a credential helper for github.com over HTTPS.
+/// Tokens come from GITHUB_TOKEN/GH_TOKEN (read by the helper at run time, so
+/// they never land on the command line or in `.git/config`), else from the
+/// gh CLI. SSH and non-GitHub URLs use the user's own git setup.
+fn github_auth_args(clone_url: &str) -> Vec<String> {
+    if !clone_url.starts_with("https://github.com/") {
+        return Vec::new();
+    }
+    let token_var = ["GITHUB_TOKEN", "GH_TOKEN"]
+        .into_iter()
+        .find(|var| std::env::var(var).is_ok_and(|value| !value.trim().is_empty()));
+    let helper = match token_var {
+        Some(var) => format!(
+            "!f() {{ echo username=x-access-token; echo \"password=${}\"; }}; f",
+            var
+        ),
+        None if command_exists("gh") => "!gh auth git-credential".to_string(),
+        None => return Vec::new(),
+    };
+    vec![
+        "-c".to_string(),
+        format!("credential.https://github.com.helper={}", helper),
+    ]
+}
+
+pub(super) fn apply_repo_version(
     repo: &str,
     version: &str,
     skip_dependency_resolution: bool,
-) -> Result<(), Box<dyn Error>> {
+) -> Result<Vec<String>, Box<dyn Error>> {
     let spec = parse_repo_spec(repo)?;
     apply_repo_version_spec(&spec, version, skip_dependency_resolution)
 }
@@ -500,8 +961,11 @@ fn parse_repo_spec(repo: &str) -> Result<RepoSpec, Box<dyn Error>> {
         return Err("`--repo` cannot be empty".into());
     }
 
-    let no_prefix = trimmed
-        .strip_prefix("https://github.com/")
+    let ssh_path = trimmed
+        .strip_prefix("git@github.com:")
+        .or_else(|| trimmed.strip_prefix("ssh://git@github.com/"));
+    let no_prefix = ssh_path
+        .or_else(|| trimmed.strip_prefix("https://github.com/"))
         .or_else(|| trimmed.strip_prefix("http://github.com/"))
         .or_else(|| trimmed.strip_prefix("github.com/"))
         .unwrap_or(trimmed);
@@ -515,9 +979,27 @@ fn parse_repo_spec(repo: &str) -> Result<RepoSpec, Box<dyn Error>> {
     Ok(RepoSpec {
         org: parts[0].to_string(),
         repo: parts[1].to_string(),
+        clone_url: ssh_path.map(|_| trimmed.to_string()),
+        git_ref: None,
     })
 }
 
+fn repo_spec_with_source(
+    repo: &str,
+    git_url: Option<&str>,
+    git_ref: Option<&str>,
+) -> Result<RepoSpec, Box<dyn Error>> {
+    let mut spec = parse_repo_spec(repo)?;
+    if let Some(git_url) = git_url.map(str::trim).filter(|url| !url.is_empty()) {
+        spec.clone_url = Some(git_url.to_string());
+    }
+    spec.git_ref = git_ref
+        .map(str::trim)
+        .filter(|git_ref| !git_ref.is_empty())
+        .map(str::to_string);
+    Ok(spec)
+}
+
 fn sanitize_name_component(input: &str) -> String {
     let mut out = input
         .to_ascii_lowercase()
@@ -537,20 +1019,100 @@ fn sanitize_name_component(input: &str) -> String {
     }
 }
 
-fn run_local_path(
+/// Build, push, and apply the configurations from a local project. Returns
+/// the names of the applied Configurations.
+pub(super) fn run_local_path(
     path: &str,
+    builder: Builder,
     skip_dependency_resolution: bool,
-) -> Result<(), Box<dyn Error>> {
+    cache: &mut PushCache,
+) -> Result<Vec<String>, Box<dyn Error>> {
     let dir = Path::new(path);
     if !dir.is_dir() {
         return Err(format!("{} is not a directory", path).into());
     }
+    let builder = resolve_builder(dir, builder)?;
 
     ensure_registry()?;
     sync_registry_hosts_entry("crossplane-system", "registry", REGISTRY_HOSTNAME)?;
 
-    // Build the Crossplane package
-    log::info!("Building Crossplane package in {}...", path);
+    let loaded = build_and_load(dir, builder, true)?;
+    push_and_apply(loaded, skip_dependency_resolution, cache)
+}
+
+/// Build the project in `dir` for `hops render` and return its embedded
+/// function images for this machine's architecture, rebuilt with a valid OCI
+/// config as `<path>:hops-render`. Crossplane-format projects have no
+/// embedded functions and are not built.
+pub fn build_render_functions(dir: &Path, builder: Builder) -> Result<Vec<String>, Box<dyn Error>> {
+    if resolve_builder(dir, builder)? == Builder::Crossplane {
+        return Ok(Vec::new());
+    }
+    let arch = docker_arch();
+    let mut images = Vec::new();
+    for img in build_and_load(dir, Builder::Up, false)? {
+        let (path, tag) = split_ref(&img.source);
+        if is_configuration_image(&img.source) || tag != arch {
+            continue;
+        }
+        let local = format!("{}:hops-render", path);
+        docker_build_from(&img.source, &local)?;
+        images.push(local);
+    }
+    Ok(images)
+}
+
+/// Build the project in `dir` and load the resulting packages (see
+/// [`load_package`]).
+fn build_and_load(
+    dir: &Path,
+    builder: Builder,
+    allow_direct: bool,
+) -> Result<Vec<LoadedImage>, Box<dyn Error>> {
+    let packages = match builder {
+        Builder::Crossplane => vec![build_with_crossplane(dir)?],
+        Builder::Up | Builder::Auto => build_with_up(dir)?,
+    };
+
+    let mut loaded = Vec::new();
+    for images in parallel_map(&packages, |pkg_path| {
+        load_package(pkg_path, allow_direct).map_err(|err| err.to_string())
+    }) {
+        loaded.extend(images?);
+    }
+    Ok(loaded)
+}
+
+/// The builder to use for `dir`: an explicit choice, else `up` for
+/// upbound.yaml projects and `crossplane` for crossplane.yaml projects.
+pub fn resolve_builder(dir: &Path, builder: Builder) -> Result<Builder, Box<dyn Error>> {
+    if builder != Builder::Auto {
+        return Ok(builder);
+    }
+    if dir.join("upbound.yaml").is_file() {
+        return Ok(Builder::Up);
+    }
+    if crossplane_package_root(dir).is_some() {
+        return Ok(Builder::Crossplane);
+    }
+    Err(format!(
+        "{} has neither upbound.yaml nor crossplane.yaml; pass `--builder up|crossplane`",
+        dir.display()
+    )
+    .into())
+}
+
+/// Directory holding crossplane.yaml: the project root or `package/`.
+pub fn crossplane_package_root(dir: &Path) -> Option<PathBuf> {
+    [dir.to_path_buf(), dir.join("package")]
+        .into_iter()
+        .find(|root| root.join("crossplane.yaml").is_file())
+}
+
+/// Run `up project build` and return the `.uppkg` files it wrote.
+fn build_with_up(dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    log::info!("Building Crossplane package in {}...", dir.display());
+    crate::logging::log_command("up", &["project", "build"]);
     let status = Command::new("up")
         .args(["project", "build"])
         .current_dir(dir)
@@ -564,50 +1126,244 @@ fn run_local_path(
 
     // Find .uppkg files in _output/
     let output_dir = dir.join("_output");
-    let packages: Vec<_> = fs::read_dir(&output_dir)
+    let packages: Vec<PathBuf> = fs::read_dir(&output_dir)
         .map_err(|e| format!("Failed to read {}: {}", output_dir.display(), e))?
         .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "uppkg"))
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "uppkg"))
         .collect();
 
     if packages.is_empty() {
         return Err(format!("No .uppkg files found in {}", output_dir.display()).into());
     }
+    Ok(packages)
+}
 
-    // Load each package into docker and collect image names.
-    let mut loaded = Vec::new();
-    for pkg in &packages {
-        let pkg_path = pkg.path();
-        let pkg_str = pkg_path.to_string_lossy();
+/// Run `crossplane xpkg build` into `_output/` and return the `.xpkg` path.
+fn build_with_crossplane(dir: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let package_root = crossplane_package_root(dir)
+        .ok_or_else(|| format!("No crossplane.yaml found in {}", dir.display()))?;
+    let output_dir = dir.join("_output");
+    fs::create_dir_all(&output_dir)?;
+    let package_file = output_dir.join("package.xpkg");
+
+    let args = crossplane_build_args(&package_root, &dir.join("examples"), &package_file);
+    let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    log::info!("Building Crossplane package in {}...", dir.display());
+    run_cmd("crossplane", &arg_refs)?;
+    Ok(package_file)
+}
+
+fn crossplane_build_args(
+    package_root: &Path,
+    examples_root: &Path,
+    package_file: &Path,
+) -> Vec<String> {
+    let mut args = vec![
+        "xpkg".to_string(),
+        "build".to_string(),
+        format!("--package-root={}", package_root.display()),
+        format!("--package-file={}", package_file.display()),
+    ];
+    if examples_root.is_dir() {
+        args.push(format!("--examples-root={}", examples_root.display()));
+    }
+    args
+}
+
+/// Push and apply an already-built package file without running a build.
+pub(super) fn run_package_file(
+    file: &Path,
+    skip_dependency_resolution: bool,
+    cache: &mut PushCache,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    if !file.is_file() {
+        return Err(format!("{} is not a file", file.display()).into());
+    }
+
+    ensure_registry()?;
+    sync_registry_hosts_entry("crossplane-system", "registry", REGISTRY_HOSTNAME)?;
+
+    let loaded = load_package(file, true)?;
+    push_and_apply(loaded, skip_dependency_resolution, cache)
+}
+
+/// `docker load` a package tarball and return its images. A `.uppkg` holds a
+/// `:configuration` image plus function images. A standard `.xpkg` holds a
+/// single image, often untagged; it is tagged `<path>:configuration` here so
+/// it takes the same path as the `.uppkg` configuration image. Provider and
+/// Function `.xpkg`s are tagged `<path>:provider`/`<path>:function` and
+/// applied as standalone packages.
+///
+/// With `allow_direct`, packages that need no `docker build` rebuild (see
+/// [`direct_push::supported`]) are not loaded at all: their layers are later
+/// streamed from the file to the registry.
+fn load_package(pkg_path: &Path, allow_direct: bool) -> Result<Vec<LoadedImage>, Box<dyn Error>> {
+    let pkg_str = pkg_path.to_string_lossy();
+    let archive = Arc::new(UppkgArchive::open(pkg_path)?);
+    let direct = allow_direct && direct_push::supported(&archive);
+    let images = if direct {
+        log::info!("Reading {} (pushing without Docker)...", pkg_str);
+        direct_push::images(&archive)?
+    } else {
         log::info!("Loading {}...", pkg_str);
+        docker_load(&archive, &pkg_str)?
+    };
 
-        let output = Command::new("docker")
-            .args(["load", "-i", &*pkg_str])
-            .output()?;
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(format!("docker load failed: {}", stderr).into());
-        }
+    if images.iter().any(|img| is_configuration_image(img)) {
+        return Ok(images
+            .into_iter()
+            .map(|source| LoadedImage {
+                source,
+                archive: Arc::clone(&archive),
+                direct,
+                standalone: None,
+            })
+            .collect());
+    }
+
+    let [image] = images.as_slice() else {
+        return Err(format!(
+            "{} has no `:configuration` image and is not a single-image package",
+            pkg_str
+        )
+        .into());
+    };
+    let package_yaml = archive.package_yaml(image)?;
+    let (kind, name) = package_meta(&package_yaml)
+        .ok_or_else(|| format!("package.yaml in {} has no kind or metadata.name", pkg_str))?;
+    let standalone = match kind.as_str() {
+        "Configuration" => None,
+        other => Some(StandaloneKind::from_meta_kind(other).ok_or_else(|| {
+            format!(
+                "{} is a {} package; only Configuration, Provider, and Function packages can be applied",
+                pkg_str, kind
+            )
+        })?),
+    };
+    let tag = standalone.map_or("configuration", StandaloneKind::resource);
+    let source = single_package_source(image, &name, tag);
+    if !direct {
+        run_cmd("docker", &["tag", image, &source])?;
+    }
+    Ok(vec![LoadedImage {
+        source,
+        archive,
+        direct,
+        standalone,
+    }])
+}
+
+/// `docker load` a package tarball and return its images by name.
+///
+/// Some Docker versions report an image only as `Loaded image ID: sha256:...`
+/// (untagged images, and the containerd image store even for tagged ones).
+/// Those are named after their RepoTags in the tarball's manifest and tagged
+/// explicitly, so later steps find them; images without tags keep their ID.
+fn docker_load(archive: &UppkgArchive, pkg_str: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    crate::logging::log_command("docker", &["load", "-i", pkg_str]);
+    let output = crate::interrupt::output(Command::new("docker").args(["load", "-i", pkg_str]))?;
+    crate::logging::log_command_output("docker", &output);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("docker load failed: {}", stderr).into());
+    }
 
-        for line in String::from_utf8_lossy(&output.stdout).lines() {
-            if let Some(img) = line.strip_prefix("Loaded image: ") {
-                loaded.push(LoadedImage {
-                    source: img.trim().to_string(),
-                    uppkg_path: pkg_path.clone(),
-                });
+    let loaded = parse_docker_load(&String::from_utf8_lossy(&output.stdout));
+    if loaded.is_empty() {
+        log::warn!(
+            "docker load reported no images for {}; using the images in its manifest",
+            pkg_str
+        );
+        return direct_push::images(archive);
+    }
+    let manifest_tags = archive
+        .manifest()
+        .iter()
+        .map(|entry| {
+            Ok((
+                direct_push::config_digest(archive, entry)?,
+                entry.repo_tags.clone().unwrap_or_default(),
+            ))
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    let mut images = Vec::new();
+    for image in loaded {
+        match image {
+            LoadedName::Tag(tag) => images.push(tag),
+            LoadedName::Id(id) => {
+                let tags = tags_for_id(&manifest_tags, &id);
+                for tag in &tags {
+                    run_cmd("docker", &["tag", &id, tag])?;
+                }
+                if tags.is_empty() {
+                    images.push(id);
+                } else {
+                    images.extend(tags);
+                }
             }
         }
     }
+    let mut seen = HashSet::new();
+    images.retain(|image| seen.insert(image.clone()));
+    Ok(images)
+}
+
+/// An image as `docker load` reports it.
+#[derive(Debug, PartialEq, Eq)]
+enum LoadedName {
+    Tag(String),
+    Id(String),
+}
+
+fn parse_docker_load(output: &str) -> Vec<LoadedName> {
+    output
+        .lines()
+        .filter_map(|line| {
+            if let Some(tag) = line.strip_prefix("Loaded image: ") {
+                Some(LoadedName::Tag(tag.trim().to_string()))
+            } else {
+                line.strip_prefix("Loaded image ID: ")
+                    .map(|id| LoadedName::Id(id.trim().to_string()))
+            }
+        })
+        .collect()
+}
+
+/// RepoTags of the manifest entry whose config digest is `id`. With the
+/// containerd image store the ID is the manifest's digest instead, which
+/// the tarball doesn't list; a single-image tarball's tags still apply.
+fn tags_for_id(manifest_tags: &[(String, Vec<String>)], id: &str) -> Vec<String> {
+    match manifest_tags.iter().find(|(digest, _)| digest == id) {
+        Some((_, tags)) => tags.clone(),
+        None => match manifest_tags {
+            [(_, tags)] => tags.clone(),
+            _ => Vec::new(),
+        },
+    }
+}
 
+/// Push the loaded images to the local registry and apply Configurations (or
+/// standalone Providers/Functions) for them. Returns the names to wait on:
+/// bare Configuration names and `provider/<name>`/`function/<name>`.
+fn push_and_apply(
+    mut loaded: Vec<LoadedImage>,
+    skip_dependency_resolution: bool,
+    cache: &mut PushCache,
+) -> Result<Vec<String>, Box<dyn Error>> {
     if loaded.is_empty() {
-        return Err("No images were loaded from .uppkg files".into());
+        return Err("No images were loaded from package files".into());
     }
 
     // De-duplicate images that can appear multiple times across loaded tarballs.
     let mut seen = HashSet::new();
     loaded.retain(|img| seen.insert(img.source.clone()));
 
-    let function_sources: HashSet<String> = loaded
+    let (standalone, loaded): (Vec<_>, Vec<_>) =
+        loaded.into_iter().partition(|img| img.standalone.is_some());
+
+    let mut function_sources: HashSet<String> = loaded
         .iter()
         .filter(|img| !is_configuration_image(&img.source))
         .map(|img| package_source(&img.source))
@@ -615,39 +1371,73 @@ fn run_local_path(
 
     let arch = docker_arch().to_string();
     let mut render_rewrites: HashMap<String, RenderRewrite> = HashMap::new();
+    let mut unchanged = 0usize;
 
     // Push non-Configuration images first. For local render functions, capture
     // the pushed digest so we can patch the corresponding configuration package
     // metadata and keep dependency resolution enabled.
+    let mut to_push = Vec::new();
     for img in &loaded {
         if is_configuration_image(&img.source) {
             continue;
         }
 
-        let push_ref = rewrite_registry(&img.source, REGISTRY_PUSH);
+        let push_ref = rewrite_registry(&img.source, &registry::push_host());
         let (img_path, tag) = split_ref(&img.source);
+        let target_prefix = format!("{}/{}", REGISTRY_PULL, strip_registry(img_path));
+
+        let content_id = image_id(img)?;
+        if let Some(pushed) = unchanged_in_registry(cache, &push_ref, &content_id) {
+            log::info!("Skipping unchanged {}", push_ref);
+            unchanged += 1;
+            // The running Function already uses this image; don't recreate it.
+            function_sources.remove(&package_source(&img.source));
+            if tag == arch {
+                render_rewrites.insert(
+                    img_path.to_string(),
+                    RenderRewrite {
+                        digest: pushed.digest.clone(),
+                        target_prefix,
+                    },
+                );
+            }
+            continue;
+        }
+        to_push.push((img, push_ref, content_id));
+    }
 
-        // All non-configuration images are Crossplane Function packages (the
-        // configuration filter ran above). Single-function repos historically
-        // produced one image named <repo>_render; multi-function repos produce
-        // <repo>_<funcname> per function. Both need the OCI-config rebuild +
-        // digest capture + ImageConfig rewrite treatment.
-        log::info!("Rebuilding {} (fix OCI config)...", push_ref);
-        docker_build_from(&img.source, &push_ref)?;
-
+    // All non-configuration images are Crossplane Function packages (the
+    // configuration filter ran above). Single-function repos historically
+    // produced one image named <repo>_render; multi-function repos produce
+    // <repo>_<funcname> per function. Both need the OCI-config rebuild +
+    // digest capture + ImageConfig rewrite treatment. They are independent,
+    // so they are rebuilt and pushed in parallel.
+    let results = parallel_map(&to_push, |(img, push_ref, _)| {
+        if img.direct {
+            log::info!("Pushing {}...", push_ref);
+            direct_push::push(&img.archive, &img.source, push_ref)
+        } else {
+            log::info!("Rebuilding and pushing {}...", push_ref);
+            docker_build_from(&img.source, push_ref)
+                .and_then(|()| docker_push_and_get_digest(push_ref))
+        }
+        .and_then(|digest| verify_push(push_ref, digest))
+        .map_err(|err| format!("{}: {}", push_ref, err))
+    });
+    let mut pushed_images = Vec::new();
+    for ((img, push_ref, content_id), digest) in to_push.into_iter().zip(results) {
+        let digest = digest?;
+        cache.record(&push_ref, content_id, digest.clone());
+        pushed_images.push(push_ref);
+        let (img_path, tag) = split_ref(&img.source);
         if tag == arch {
-            let digest = docker_push_and_get_digest(&push_ref)?;
-            let target_prefix = format!("{}/{}", REGISTRY_PULL, strip_registry(img_path));
             render_rewrites.insert(
                 img_path.to_string(),
                 RenderRewrite {
                     digest,
-                    target_prefix,
+                    target_prefix: format!("{}/{}", REGISTRY_PULL, strip_registry(img_path)),
                 },
             );
-        } else {
-            log::info!("Pushing {}...", push_ref);
-            run_cmd("docker", &["push", &push_ref])?;
         }
     }
 
@@ -684,39 +1474,99 @@ spec:
             continue;
         }
 
-        let dev_tag = dev_tag_for_uppkg(&img.uppkg_path)?;
-        let push_ref = rewrite_registry_with_tag(&img.source, REGISTRY_PUSH, &dev_tag);
+        let dev_tag = dev_tag_for_uppkg(img.archive.path())?;
+        let push_ref = rewrite_registry_with_tag(&img.source, &registry::push_host(), &dev_tag);
         let pull_ref = rewrite_registry_with_tag(&img.source, REGISTRY_PULL, &dev_tag);
         log::info!(
             "Using local build version '{}' for {}...",
             dev_tag,
             img.source
         );
-        config_pull_refs.push(pull_ref.clone());
+        config_pull_refs.push((pull_ref.clone(), img.archive.path().to_path_buf()));
 
-        let mut source_to_push = img.source.clone();
-        let package_yaml = extract_package_yaml_from_uppkg(&img.uppkg_path, &img.source)?;
+        let package_yaml = img.archive.package_yaml(&img.source)?;
         let (patched_yaml, changed) =
             rewrite_render_dependency_digests(&package_yaml, &render_rewrites);
+        let content_id = format!("{}+{}", image_id(img)?, short_hash(&patched_yaml));
+        if unchanged_in_registry(cache, &push_ref, &content_id).is_some() {
+            log::info!("Skipping unchanged {}", push_ref);
+            unchanged += 1;
+            continue;
+        }
         if changed {
             log::info!(
                 "Patching package metadata for {} to use local render digests...",
                 img.source
             );
-            source_to_push = build_patched_configuration_image(&img.source, &patched_yaml)?;
         }
+        log::info!("Pushing {}...", push_ref);
+        let digest = match (img.direct, changed) {
+            (true, true) => {
+                direct_push::push_patched(&img.archive, &img.source, &patched_yaml, &push_ref)?
+            }
+            (true, false) => direct_push::push(&img.archive, &img.source, &push_ref)?,
+            (false, changed) => {
+                let source_to_push = if changed {
+                    build_patched_configuration_image(&img.archive, &img.source, &patched_yaml)?
+                } else {
+                    img.source.clone()
+                };
+                run_cmd("docker", &["tag", &source_to_push, &push_ref])?;
+                docker_push_and_get_digest(&push_ref)?
+            }
+        };
+        let digest = verify_push(&push_ref, digest)?;
+        cache.record(&push_ref, content_id, digest);
+        pushed_images.push(push_ref);
+    }
 
-        run_cmd("docker", &["tag", &source_to_push, &push_ref])?;
+    // Standalone Provider/Function packages are pushed as-is under a dev tag.
+    let mut standalone_pull_refs = Vec::new();
+    for img in &standalone {
+        let dev_tag = dev_tag_for_uppkg(img.archive.path())?;
+        let push_ref = rewrite_registry_with_tag(&img.source, &registry::push_host(), &dev_tag);
+        let pull_ref = rewrite_registry_with_tag(&img.source, REGISTRY_PULL, &dev_tag);
+        standalone_pull_refs.push((img.standalone, pull_ref, img.archive.path().to_path_buf()));
+
+        let content_id = image_id(img)?;
+        if unchanged_in_registry(cache, &push_ref, &content_id).is_some() {
+            log::info!("Skipping unchanged {}", push_ref);
+            unchanged += 1;
+            continue;
+        }
         log::info!("Pushing {}...", push_ref);
-        run_cmd("docker", &["push", &push_ref])?;
+        let digest = if img.direct {
+            direct_push::push(&img.archive, &img.source, &push_ref)?
+        } else {
+            run_cmd("docker", &["tag", &img.source, &push_ref])?;
+            docker_push_and_get_digest(&push_ref)?
+        };
+        let digest = verify_push(&push_ref, digest)?;
+        cache.record(&push_ref, content_id, digest);
+        pushed_images.push(push_ref);
+    }
+    if unchanged > 0 {
+        log::info!("Skipped {} unchanged image(s)", unchanged);
+    }
+    if let Err(err) = cache.save() {
+        log::warn!("Failed to save push cache: {}", err);
     }
 
     // Apply Crossplane Configuration resources and let Crossplane resolve
     // dependencies (skipDependencyResolution is intentionally not set).
-    for pull_ref in &config_pull_refs {
-        let (img_path, _) = split_ref(pull_ref);
-        let path = strip_registry(img_path);
-        let name = path.replace('/', "-");
+    let mut applied = Vec::new();
+    let mut history = Vec::new();
+    let history_entry = |name: &str, pull_ref: &str, uppkg_path: &Path| HistoryEntry {
+        package: Some(pull_ref.to_string()),
+        source: Some(uppkg_path.display().to_string()),
+        digest: cache
+            .digest(&pull_ref.replacen(REGISTRY_PULL, &registry::push_host(), 1))
+            .map(str::to_string),
+        images: pushed_images.clone(),
+        ..HistoryEntry::new("install", name)
+    };
+    for (pull_ref, uppkg_path) in &config_pull_refs {
+        let name = configuration_name(pull_ref);
         let existing_package_ref = current_configuration_package_ref(&name)?;
         log_existing_install_replacement(&name, existing_package_ref.as_deref(), pull_ref);
 
@@ -727,7 +1577,24 @@ spec:
         delete_remote_registry_config_revisions(&name)?;
 
         apply_configuration(&name, pull_ref, skip_dependency_resolution)?;
+        history.push(history_entry(&name, pull_ref, uppkg_path));
+        applied.push(name);
+    }
+    for (kind, pull_ref, uppkg_path) in &standalone_pull_refs {
+        let kind = kind.expect("standalone images have a kind");
+        let name = configuration_name(pull_ref);
+        log::info!("Applying {} '{}'...", kind.kind(), name);
+        kubectl_apply_stdin(&build_package_yaml(
+            kind.kind(),
+            &name,
+            pull_ref,
+            skip_dependency_resolution,
+        ))?;
+        let name = format!("{}/{}", kind.resource(), name);
+        history.push(history_entry(&name, pull_ref, uppkg_path));
+        applied.push(name);
     }
+    history::record(history);
 
     // Delete existing Function packages only after the new Configuration has
     // been applied. This ensures Crossplane sees the new desired package
@@ -748,9 +1615,194 @@ spec:
         }
     }
 
+    Ok(applied)
+}
+
+/// `config install --plan`: build and load like a real install, then print
+/// what would be pushed and applied.
+fn run_plan(args: &ConfigArgs) -> Result<(), Box<dyn Error>> {
+    let skip = args.skip_dependency_resolution;
+    let loaded = match (&args.file, args.repo.as_deref(), args.version.as_deref()) {
+        (Some(file), _, _) => load_package(file, true)?,
+        (None, Some(repo), Some(version)) => {
+            return print_version_plan(&parse_repo_spec(repo)?, version, skip)
+        }
+        (None, Some(repo), None) => {
+            let spec =
+                repo_spec_with_source(repo, args.git_url.as_deref(), args.git_ref.as_deref())?;
+            let dir = ensure_cached_repo_checkout(&spec)?;
+            build_and_load(&dir, resolve_builder(&dir, args.builder)?, true)?
+        }
+        (None, None, _) => {
+            let dir = Path::new(args.path.as_deref().unwrap_or("."));
+            build_and_load(dir, resolve_builder(dir, args.builder)?, true)?
+        }
+    };
+    print_plan(loaded, skip, &PushCache::load())
+}
+
+/// Placeholder for a render function digest that is only known after push.
+const PLAN_PENDING_DIGEST: &str = "sha256:<digest-after-push>";
+
+/// Print the images `push_and_apply` would push, its ImageConfig rewrites,
+/// and the resources it would apply, without changing anything.
+fn print_plan(
+    mut loaded: Vec<LoadedImage>,
+    skip_dependency_resolution: bool,
+    cache: &PushCache,
+) -> Result<(), Box<dyn Error>> {
+    let mut seen = HashSet::new();
+    loaded.retain(|img| seen.insert(img.source.clone()));
+    let arch = docker_arch();
+
+    println!("Images:");
+    let mut render_rewrites: HashMap<String, RenderRewrite> = HashMap::new();
+    for img in &loaded {
+        let push_ref = match img.standalone {
+            None if is_configuration_image(&img.source) => continue,
+            None => rewrite_registry(&img.source, &registry::push_host()),
+            Some(_) => rewrite_registry_with_tag(
+                &img.source,
+                &registry::push_host(),
+                &dev_tag_for_uppkg(img.archive.path())?,
+            ),
+        };
+        let pushed = unchanged_in_registry(cache, &push_ref, &image_id(img)?);
+        println!(
+            "  {} {}",
+            if pushed.is_some() {
+                "unchanged"
+            } else {
+                "push     "
+            },
+            push_ref
+        );
+        let (img_path, tag) = split_ref(&img.source);
+        if img.standalone.is_none() && tag == arch {
+            render_rewrites.insert(
+                img_path.to_string(),
+                RenderRewrite {
+                    digest: pushed.map_or(PLAN_PENDING_DIGEST.to_string(), |p| p.digest.clone()),
+                    target_prefix: format!("{}/{}", REGISTRY_PULL, strip_registry(img_path)),
+                },
+            );
+        }
+    }
+
+    let mut configs = Vec::new();
+    for img in loaded
+        .iter()
+        .filter(|img| is_configuration_image(&img.source))
+    {
+        let dev_tag = dev_tag_for_uppkg(img.archive.path())?;
+        let push_ref = rewrite_registry_with_tag(&img.source, &registry::push_host(), &dev_tag);
+        let package_yaml = img.archive.package_yaml(&img.source)?;
+        let (patched_yaml, _) = rewrite_render_dependency_digests(&package_yaml, &render_rewrites);
+        let content_id = format!("{}+{}", image_id(img)?, short_hash(&patched_yaml));
+        let unchanged = unchanged_in_registry(cache, &push_ref, &content_id).is_some();
+        println!(
+            "  {} {}",
+            if unchanged { "unchanged" } else { "push     " },
+            push_ref
+        );
+        let pull_ref = rewrite_registry_with_tag(&img.source, REGISTRY_PULL, &dev_tag);
+        configs.push((pull_ref, patched_yaml));
+    }
+
+    if !render_rewrites.is_empty() {
+        println!("\nImageConfig rewrites:");
+        let mut sources: Vec<_> = render_rewrites.iter().collect();
+        sources.sort_by(|a, b| a.0.cmp(b.0));
+        for (source, rewrite) in sources {
+            println!(
+                "  {} ({} -> {})",
+                image_config_name(source),
+                source,
+                rewrite.target_prefix
+            );
+        }
+    }
+
+    for (pull_ref, patched_yaml) in &configs {
+        let name = configuration_name(pull_ref);
+        println!("\nConfiguration '{}':", name);
+        print_indented(&build_configuration_yaml(
+            &name,
+            pull_ref,
+            skip_dependency_resolution,
+        ));
+        print_dependency_changes(&name, patched_yaml);
+    }
+    for img in loaded.iter().filter(|img| img.standalone.is_some()) {
+        let kind = img.standalone.expect("filtered on standalone");
+        let dev_tag = dev_tag_for_uppkg(img.archive.path())?;
+        let pull_ref = rewrite_registry_with_tag(&img.source, REGISTRY_PULL, &dev_tag);
+        let name = configuration_name(&pull_ref);
+        println!("\n{} '{}':", kind.kind(), name);
+        print_indented(&build_package_yaml(
+            kind.kind(),
+            &name,
+            &pull_ref,
+            skip_dependency_resolution,
+        ));
+    }
+    println!("\nPlan only: nothing was pushed or applied.");
+    Ok(())
+}
+
+fn print_version_plan(
+    spec: &RepoSpec,
+    version: &str,
+    skip_dependency_resolution: bool,
+) -> Result<(), Box<dyn Error>> {
+    let package_ref = format!("ghcr.io/{}/{}:{}", spec.org, spec.repo, version.trim());
+    let name = format!(
+        "{}-{}",
+        sanitize_name_component(&spec.org),
+        sanitize_name_component(&spec.repo)
+    );
+    match current_configuration_package_ref(&name)? {
+        Some(current) if current == package_ref => println!("Package: {} (unchanged)", current),
+        Some(current) => println!("Package: {} -> {}", current, package_ref),
+        None => println!("Package: {} (new)", package_ref),
+    }
+    println!("\nConfiguration '{}':", name);
+    print_indented(&build_configuration_yaml(
+        &name,
+        &package_ref,
+        skip_dependency_resolution,
+    ));
+    println!("\nDependencies are resolved by Crossplane from the published package.");
+    println!("\nPlan only: nothing was applied.");
     Ok(())
 }
 
+fn print_dependency_changes(name: &str, package_yaml: &str) {
+    let planned = super::plan::package_dependencies(package_yaml);
+    let Some(installed) = super::plan::installed_dependencies(name) else {
+        println!("  Dependencies (not installed yet):");
+        for (package, version) in &planned {
+            println!("    + {} {}", package, version);
+        }
+        return;
+    };
+    let changes = super::plan::dependency_changes(&installed, &planned);
+    if changes.is_empty() {
+        println!("  Dependencies: unchanged");
+        return;
+    }
+    println!("  Dependency changes:");
+    for change in changes {
+        println!("    {}", change);
+    }
+}
+
+fn print_indented(yaml: &str) {
+    for line in yaml.lines() {
+        println!("    {}", line);
+    }
+}
+
 fn apply_configuration(
     name: &str,
     package_ref: &str,
@@ -803,7 +1855,7 @@ fn delete_package_resources_by_source(
     Ok(deleted)
 }
 
-fn package_source(package_ref: &str) -> String {
+pub(super) fn package_source(package_ref: &str) -> String {
     let trimmed = package_ref.trim();
     if let Some((source, _)) = trimmed.split_once('@') {
         return source.to_string();
@@ -820,7 +1872,7 @@ fn package_source(package_ref: &str) -> String {
     trimmed.to_string()
 }
 
-fn package_tag(package_ref: &str) -> Option<&str> {
+pub(super) fn package_tag(package_ref: &str) -> Option<&str> {
     if let Some((_, digest)) = package_ref.rsplit_once('@') {
         return Some(digest);
     }
@@ -886,15 +1938,35 @@ fn build_configuration_yaml(
     name: &str,
     package_ref: &str,
     skip_dependency_resolution: bool,
+) -> String {
+    build_package_yaml(
+        "Configuration",
+        name,
+        package_ref,
+        skip_dependency_resolution,
+    )
+}
+
+/// A `pkg.crossplane.io/v1` package resource (Configuration, Provider, or
+/// Function) that always re-pulls `package_ref`.
+fn build_package_yaml(
+    kind: &str,
+    name: &str,
+    package_ref: &str,
+    skip_dependency_resolution: bool,
 ) -> String {
     let mut yaml = format!(
         "apiVersion: pkg.crossplane.io/v1
-kind: Configuration
+kind: {kind}
 metadata:
   name: {name}
+  labels:
+    {label}: {value}
 spec:
   package: {package_ref}
-  packagePullPolicy: Always\n"
+  packagePullPolicy: Always\n",
+        label = super::MANAGED_BY_LABEL,
+        value = super::MANAGED_BY_VALUE,
     );
 
     if skip_dependency_resolution {
@@ -905,7 +1977,16 @@ spec:
 }
 
 /// Ensure the in-cluster registry is deployed and available.
+/// Deploy the registry if needed and make it reachable for pushes.
 fn ensure_registry() -> Result<(), Box<dyn Error>> {
+    deploy_registry()?;
+    crate::commands::local::registry_forward::ensure_reachable()
+}
+
+fn deploy_registry() -> Result<(), Box<dyn Error>> {
+    if registry::mode() == RegistryMode::Host {
+        return crate::commands::local::registry_host::ensure_running();
+    }
     let result = run_cmd_output(
         "kubectl",
         &[
@@ -926,130 +2007,65 @@ fn ensure_registry() -> Result<(), Box<dyn Error>> {
     }
 
     log::info!("Deploying local package registry...");
-    kubectl_apply_stdin(REGISTRY_YAML)?;
+    kubectl_apply_stdin(&registry::manifest()?)?;
 
     // Wait for the registry pod to become ready
-    for _ in 0..60 {
-        let out = run_cmd_output(
-            "kubectl",
-            &[
-                "get",
-                "deployment",
-                "registry",
-                "-n",
-                "crossplane-system",
-                "-o",
-                "jsonpath={.status.availableReplicas}",
-            ],
-        );
-        if let Ok(r) = out {
-            if r.trim() == "1" {
-                return Ok(());
-            }
-        }
-        std::thread::sleep(std::time::Duration::from_secs(2));
-    }
-
-    Err("Timed out waiting for registry deployment".into())
+    wait_for_deployment(
+        "crossplane-system",
+        "registry",
+        crate::settings::timeouts(None)?.registry,
+    )
 }
 
 fn is_configuration_image(image: &str) -> bool {
     split_ref(image).1 == "configuration"
 }
 
-fn extract_package_yaml_from_uppkg(
-    uppkg_path: &Path,
-    configuration_image: &str,
-) -> Result<String, Box<dyn Error>> {
-    let manifest_bytes = read_entry_from_tar(uppkg_path, "manifest.json")?;
-    let manifest: Vec<DockerSaveManifestEntry> = serde_json::from_slice(&manifest_bytes)?;
-
-    let config_entry = manifest
-        .iter()
-        .find(|entry| {
-            entry
-                .repo_tags
-                .as_ref()
-                .map(|tags| tags.iter().any(|t| t == configuration_image))
-                .unwrap_or(false)
-        })
-        .ok_or_else(|| {
-            format!(
-                "Could not find '{}' in manifest {}",
-                configuration_image,
-                uppkg_path.display()
-            )
-        })?;
-
-    let mut base_layer: Option<String> = None;
-    let config_json = read_entry_from_tar(uppkg_path, &config_entry.config)?;
-    if let Ok(image_config) = serde_json::from_slice::<DockerImageConfig>(&config_json) {
-        if let Some(labels) = image_config.config.and_then(|c| c.labels) {
-            for (key, value) in labels {
-                if value != "base" {
-                    continue;
-                }
-                if let Some(digest) = key.strip_prefix("io.crossplane.xpkg:sha256:") {
-                    let candidate = format!("{}.tar.gz", digest);
-                    if config_entry.layers.iter().any(|l| l == &candidate) {
-                        base_layer = Some(candidate);
-                        break;
-                    }
-                }
-            }
-        }
-    }
+/// `kind` and `metadata.name` of the package's meta object (the first
+/// document in package.yaml).
+fn package_meta(package_yaml: &str) -> Option<(String, String)> {
+    let first = serde_yaml::Deserializer::from_str(package_yaml).next()?;
+    let meta = serde_yaml::Value::deserialize(first).ok()?;
+    let kind = meta.get("kind")?.as_str()?;
+    let name = meta.get("metadata")?.get("name")?.as_str()?;
+    Some((kind.to_string(), name.to_string()))
+}
 
-    let base_layer = base_layer
-        .or_else(|| config_entry.layers.first().cloned())
-        .ok_or_else(|| {
-            format!(
-                "Configuration image '{}' has no layers in {}",
-                configuration_image,
-                uppkg_path.display()
-            )
-        })?;
-    let layer_bytes = read_entry_from_tar(uppkg_path, &base_layer)?;
-    let decoder = GzDecoder::new(Cursor::new(layer_bytes));
-    let mut layer_archive = Archive::new(decoder);
-
-    for entry in layer_archive.entries()? {
-        let mut entry = entry?;
-        let path = entry.path()?.to_string_lossy().into_owned();
-        if path == "package.yaml" {
-            let mut contents = Vec::new();
-            entry.read_to_end(&mut contents)?;
-            return Ok(String::from_utf8(contents)?);
-        }
-    }
+/// Configuration name for a package reference: its repository path without
+/// the registry, with `/` replaced by `-`.
+fn configuration_name(package_ref: &str) -> String {
+    let (img_path, _) = split_ref(package_ref);
+    strip_registry(img_path).replace('/', "-")
+}
 
-    Err(format!(
-        "package.yaml not found in base layer '{}' from {}",
-        &base_layer,
-        uppkg_path.display()
-    )
-    .into())
+/// Name of the Configuration `config install` applies for a single-image
+/// `.xpkg`, or `None` when the file holds several images or another kind.
+pub(super) fn xpkg_configuration_name(pkg_path: &Path) -> Result<Option<String>, Box<dyn Error>> {
+    let archive = UppkgArchive::open(pkg_path)?;
+    let [entry] = archive.manifest() else {
+        return Ok(None);
+    };
+    let image = entry
+        .repo_tags
+        .as_ref()
+        .and_then(|tags| tags.first().cloned())
+        .unwrap_or_else(|| "sha256:".to_string());
+    let package_yaml = archive.package_yaml(&image)?;
+    Ok(package_meta(&package_yaml)
+        .filter(|(kind, _)| kind == "Configuration")
+        .map(|(_, name)| {
+            configuration_name(&single_package_source(&image, &name, "configuration"))
+        }))
 }
 
-fn read_entry_from_tar(tar_path: &Path, entry_name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
-    let file = fs::File::open(tar_path)?;
-    let mut archive = Archive::new(file);
-    for entry in archive.entries()? {
-        let mut entry = entry?;
-        let path = entry.path()?.to_string_lossy().into_owned();
-        if path == entry_name {
-            let mut out = Vec::new();
-            entry.read_to_end(&mut out)?;
-            return Ok(out);
-        }
+/// Source reference for a single-image package: a tagged image keeps its
+/// repository, an untagged one (loaded by ID) is named after the package.
+fn single_package_source(image: &str, package_name: &str, tag: &str) -> String {
+    if image.starts_with("sha256:") {
+        return format!("{}/{}:{}", LOCAL_PACKAGE_REGISTRY, package_name, tag);
     }
-
-    Err(format!(
-        "entry '{}' not found in tar {}",
-        entry_name,
-        tar_path.display()
-    )
-    .into())
+    let (path, _) = split_ref(image);
+    format!("{}:{}", path, tag)
 }
 
 fn rewrite_render_dependency_digests(
@@ -1119,94 +2135,105 @@ fn clean_yaml_scalar(s: &str) -> String {
     s.trim().trim_matches('"').trim_matches('\'').to_string()
 }
 
+/// Tag a copy of a configuration image whose package.yaml is replaced by
+/// `package_yaml`, reusing its other layers (see `UppkgArchive::write_patched`).
+/// The tag is keyed by the source image ID and the patched package.yaml, so
+/// an unchanged patch reuses the image already in Docker.
 fn build_patched_configuration_image(
+    archive: &UppkgArchive,
     source_image: &str,
     package_yaml: &str,
 ) -> Result<String, Box<dyn Error>> {
-    let build_dir = std::env::temp_dir().join(format!(
-        "hops-cli-config-{}-{}",
+    let target_tag = patched_image_tag(source_image, &docker_image_id(source_image)?, package_yaml);
+    if docker_image_id(&target_tag).is_ok() {
+        log::debug!("Reusing patched image {}", target_tag);
+        return Ok(target_tag);
+    }
+    let tarball = std::env::temp_dir().join(format!(
+        "hops-cli-config-{}-{}.tar",
         std::process::id(),
         unique_suffix()
     ));
-    fs::create_dir_all(&build_dir)?;
-
-    // Extract the source image's filesystem via docker create + export,
-    // avoiding multi-stage FROM which breaks when Docker's snapshot cache
-    // is stale for images loaded via `docker load`.
-    let container_name = format!("hops-extract-{}", unique_suffix());
-    let create_out = Command::new("docker")
-        .args(["create", "--name", &container_name, source_image, "true"])
-        .output()?;
-    if !create_out.status.success() {
-        return Err(format!(
-            "docker create failed: {}",
-            String::from_utf8_lossy(&create_out.stderr)
-        )
-        .into());
-    }
-
-    let content_dir = build_dir.join("content");
-    fs::create_dir_all(&content_dir)?;
-
-    let export_status = Command::new("sh")
-        .args([
-            "-c",
-            &format!(
-                "docker export {} | tar -xf - -C {}",
-                container_name,
-                content_dir.to_string_lossy()
-            ),
-        ])
-        .status()?;
-
-    // Always remove the temp container.
-    let _ = Command::new("docker")
-        .args(["rm", "-f", &container_name])
-        .output();
-
-    if !export_status.success() {
-        let _ = fs::remove_dir_all(&build_dir);
-        return Err("docker export failed".into());
-    }
-
-    // Replace package.yaml with the patched version.
-    fs::write(content_dir.join("package.yaml"), package_yaml)?;
+    let _tarball = crate::interrupt::temp_path(&tarball);
+    let result = archive
+        .write_patched(source_image, package_yaml, &target_tag, &tarball)
+        .and_then(|()| {
+            run_cmd_output("docker", &["load", "-i", &tarball.to_string_lossy()])?;
+            Ok(())
+        });
+    let _ = fs::remove_file(&tarball);
+    result?;
 
-    // Build from scratch using the extracted + patched content.
-    fs::write(
-        build_dir.join("Dockerfile"),
-        "FROM scratch\nCOPY content/ /\n",
-    )?;
+    Ok(target_tag)
+}
 
-    let target_tag = format!(
+/// `hops-local/config-patched-<source>:<content>`, where `<content>` hashes
+/// the source image ID and the patched package.yaml.
+fn patched_image_tag(source_image: &str, source_id: &str, package_yaml: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(source_id.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(package_yaml.as_bytes());
+    let content = format!("{:x}", hasher.finalize());
+    format!(
         "hops-local/config-patched-{}:{}",
         short_hash(source_image),
-        unique_suffix()
-    );
-
-    let status = Command::new("docker")
-        .args([
-            "build",
-            "-t",
-            &target_tag,
-            build_dir.to_string_lossy().as_ref(),
-        ])
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status()?;
+        &content[..16]
+    )
+}
 
-    let _ = fs::remove_dir_all(&build_dir);
+/// Run `f` over `items` on up to `MAX_PARALLEL_JOBS` threads, returning the
+/// results in input order.
+fn parallel_map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for _ in 0..MAX_PARALLEL_JOBS.min(items.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item is processed"))
+        .collect()
+}
 
-    if !status.success() {
-        return Err(format!("docker build exited with {}", status).into());
+/// Image ID (`sha256:...`) of a loaded image, read from its package when it
+/// was never loaded into Docker.
+fn image_id(img: &LoadedImage) -> Result<String, Box<dyn Error>> {
+    if img.direct {
+        direct_push::image_id(&img.archive, &img.source)
+    } else {
+        docker_image_id(&img.source)
     }
+}
 
-    Ok(target_tag)
+/// Local image ID (`sha256:...`) of a loaded image.
+fn docker_image_id(image: &str) -> Result<String, Box<dyn Error>> {
+    let id = run_cmd_output(
+        "docker",
+        &["image", "inspect", "--format", "{{.Id}}", image],
+    )?;
+    Ok(id.trim().to_string())
 }
 
 fn docker_push_and_get_digest(image: &str) -> Result<String, Box<dyn Error>> {
-    let output = Command::new("docker").args(["push", image]).output()?;
+    crate::logging::log_command("docker", &["push", image]);
+    let output = crate::interrupt::output(Command::new("docker").args(["push", image]))?;
     std::io::stdout().write_all(&output.stdout)?;
     std::io::stderr().write_all(&output.stderr)?;
 
@@ -1229,6 +2256,16 @@ fn docker_push_and_get_digest(image: &str) -> Result<String, Box<dyn Error>> {
     })
 }
 
+/// Fail unless the registry serves `push_ref` with the `digest` computed
+/// locally for the push (by `docker push` or [`direct_push`]); a corrupted
+/// push would otherwise only show up as a failing Crossplane revision.
+fn verify_push(push_ref: &str, digest: String) -> Result<String, Box<dyn Error>> {
+    let (path, tag) = split_ref(push_ref);
+    registry::verify_manifest(strip_registry(path), tag, &digest)?;
+    log::debug!("Verified {} as {}", push_ref, digest);
+    Ok(digest)
+}
+
 fn parse_docker_push_digest(output: &str) -> Option<String> {
     for line in output.lines() {
         if let Some(idx) = line.find("digest: sha256:") {
@@ -1242,7 +2279,8 @@ fn parse_docker_push_digest(output: &str) -> Option<String> {
     None
 }
 
-fn image_config_name(source: &str) -> String {
+/// Name of the ImageConfig rewriting pulls of `source` to the local registry.
+pub fn image_config_name(source: &str) -> String {
     let hash = short_hash(source);
     let mut body: String = source
         .to_ascii_lowercase()
@@ -1295,7 +2333,7 @@ fn rewrite_registry_with_tag(image: &str, registry: &str, tag: &str) -> String {
 }
 
 /// Strip the registry prefix from an image path.
-fn strip_registry(path: &str) -> &str {
+pub fn strip_registry(path: &str) -> &str {
     if let Some(pos) = path.find('/') {
         let prefix = &path[..pos];
         if prefix.contains('.') || prefix.contains(':') {
@@ -1306,7 +2344,7 @@ fn strip_registry(path: &str) -> &str {
 }
 
 /// Split "path:tag" into ("path", "tag").
-fn split_ref(image: &str) -> (&str, &str) {
+pub(super) fn split_ref(image: &str) -> (&str, &str) {
     image.rsplit_once(':').unwrap_or((image, "latest"))
 }
 
@@ -1328,7 +2366,7 @@ fn dev_tag_for_uppkg(uppkg_path: &Path) -> Result<String, Box<dyn Error>> {
 }
 
 /// Map Rust arch constant to Docker platform architecture name.
-fn docker_arch() -> &'static str {
+pub(super) fn docker_arch() -> &'static str {
     match std::env::consts::ARCH {
         "aarch64" => "arm64",
         "x86_64" => "amd64",
@@ -1341,20 +2379,29 @@ fn docker_arch() -> &'static str {
 /// render function images).
 fn docker_build_from(src: &str, tag: &str) -> Result<(), Box<dyn Error>> {
     let dockerfile = format!("FROM {}\n", src);
+    crate::logging::log_command("docker", &["build", "-t", tag, "-"]);
+    // Output is captured rather than inherited: builds run in parallel.
     let mut child = Command::new("docker")
         .args(["build", "-t", tag, "-"])
         .stdin(Stdio::piped())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()?;
+    let _guard = crate::interrupt::child(child.id());
 
-    if let Some(ref mut stdin) = child.stdin {
+    if let Some(mut stdin) = child.stdin.take() {
         stdin.write_all(dockerfile.as_bytes())?;
     }
 
-    let status = child.wait()?;
-    if !status.success() {
-        return Err(format!("docker build exited with {}", status).into());
+    let output = child.wait_with_output()?;
+    crate::logging::log_command_output("docker", &output);
+    if !output.status.success() {
+        return Err(format!(
+            "docker build exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
     }
     Ok(())
 }
@@ -1445,6 +2492,29 @@ fn delete_remote_registry_config_revisions(config_name: &str) -> Result<(), Box<
 mod tests {
     use super::*;
 
+    #[test]
+    fn id_only_docker_load_output_is_named_from_the_manifest() {
+        let output =
+            "Loaded image: ghcr.io/hops-ops/demo:configuration\nLoaded image ID: sha256:abc\n";
+        assert_eq!(
+            parse_docker_load(output),
+            vec![
+                LoadedName::Tag("ghcr.io/hops-ops/demo:configuration".to_string()),
+                LoadedName::Id("sha256:abc".to_string()),
+            ]
+        );
+
+        let render = vec!["ghcr.io/hops-ops/demo_render:arm64".to_string()];
+        let manifest = vec![
+            ("sha256:abc".to_string(), render.clone()),
+            ("sha256:def".to_string(), Vec::new()),
+        ];
+        assert_eq!(tags_for_id(&manifest, "sha256:abc"), render);
+        assert!(tags_for_id(&manifest, "sha256:def").is_empty());
+        assert!(tags_for_id(&manifest, "sha256:unknown").is_empty());
+        assert_eq!(tags_for_id(&manifest[..1], "sha256:unknown"), render);
+    }
+
     #[test]
     fn parse_push_digest() {
         let out = "latest: digest: sha256:0123456789abcdef size: 1234";
@@ -1495,6 +2565,29 @@ spec:
         let url = parse_repo_spec("https://github.com/hops-ops/helm-certmanager.git").unwrap();
         assert_eq!(url.org, "hops-ops");
         assert_eq!(url.repo, "helm-certmanager");
+        assert_eq!(url.clone_url, None);
+
+        let ssh = parse_repo_spec("git@github.com:hops-ops/helm-certmanager.git").unwrap();
+        assert_eq!(ssh.org, "hops-ops");
+        assert_eq!(ssh.repo, "helm-certmanager");
+        assert_eq!(
+            ssh.clone_url.as_deref(),
+            Some("git@github.com:hops-ops/helm-certmanager.git")
+        );
+    }
+
+    #[test]
+    fn git_url_overrides_clone_url_and_skips_github_auth() {
+        let spec = repo_spec_with_source(
+            "hops-ops/private-xrds",
+            Some("git@example.com:x/y.git"),
+            Some(" feature-x "),
+        )
+        .unwrap();
+        assert_eq!(spec.clone_url.as_deref(), Some("git@example.com:x/y.git"));
+        assert_eq!(spec.git_ref.as_deref(), Some("feature-x"));
+        assert!(github_auth_args("git@github.com:hops-ops/private-xrds.git").is_empty());
+        assert!(github_auth_args("https://gitlab.com/hops-ops/private-xrds").is_empty());
     }
 
     #[test]
@@ -1567,6 +2660,7 @@ spec:
 
         let without_skip = build_configuration_yaml("cfg", "ghcr.io/hops-ops/x:v1", false);
         assert!(!without_skip.contains("skipDependencyResolution: true"));
+        assert!(without_skip.contains("    hops.ops.com.ai/managed-by: hops\n"));
     }
 
     #[test]
@@ -1594,4 +2688,145 @@ spec:
             Some("sha256:abcdef")
         );
     }
+
+    #[test]
+    fn single_image_packages_become_configuration_sources() {
+        let yaml = "apiVersion: meta.pkg.crossplane.io/v1\nkind: Configuration\nmetadata:\n  name: platform\n---\napiVersion: apiextensions.k8s.io/v1\nkind: CustomResourceDefinition\n";
+        assert_eq!(
+            package_meta(yaml),
+            Some(("Configuration".to_string(), "platform".to_string()))
+        );
+        assert_eq!(package_meta("kind: Configuration\n"), None);
+
+        let untagged = single_package_source("sha256:0123abcd", "platform", "configuration");
+        assert_eq!(untagged, "local.hops/platform:configuration");
+        assert!(is_configuration_image(&untagged));
+        assert_eq!(
+            rewrite_registry(&untagged, "localhost:30500"),
+            "localhost:30500/platform:configuration"
+        );
+        assert_eq!(
+            single_package_source("ghcr.io/acme/platform:v1.2.0", "platform", "configuration"),
+            "ghcr.io/acme/platform:configuration"
+        );
+    }
+
+    #[test]
+    fn function_packages_are_applied_standalone() {
+        let kind = StandaloneKind::from_meta_kind("Function").unwrap();
+        assert_eq!(StandaloneKind::from_meta_kind("Configuration"), None);
+        let source = single_package_source("sha256:0123abcd", "function-demo", kind.resource());
+        assert_eq!(source, "local.hops/function-demo:function");
+        assert!(!is_configuration_image(&source));
+
+        let pull_ref = rewrite_registry_with_tag(&source, REGISTRY_PULL, "dev-abc");
+        assert_eq!(configuration_name(&pull_ref), "function-demo");
+        let yaml = build_package_yaml(kind.kind(), "function-demo", &pull_ref, false);
+        assert!(yaml.contains("kind: Function\n"));
+        assert!(yaml.contains(
+            "package: registry.crossplane-system.svc.cluster.local:5000/function-demo:dev-abc\n"
+        ));
+    }
+
+    #[test]
+    fn resolve_builder_detects_project_layout() {
+        let dir = std::env::temp_dir().join(format!("hops-builder-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("package")).unwrap();
+        assert!(resolve_builder(&dir, Builder::Auto).is_err());
+        assert_eq!(
+            resolve_builder(&dir, Builder::Crossplane).unwrap(),
+            Builder::Crossplane
+        );
+
+        fs::write(dir.join("package/crossplane.yaml"), "kind: Configuration\n").unwrap();
+        assert_eq!(
+            resolve_builder(&dir, Builder::Auto).unwrap(),
+            Builder::Crossplane
+        );
+        assert_eq!(crossplane_package_root(&dir), Some(dir.join("package")));
+
+        fs::write(dir.join("upbound.yaml"), "kind: Project\n").unwrap();
+        assert_eq!(resolve_builder(&dir, Builder::Auto).unwrap(), Builder::Up);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let args = crossplane_build_args(
+            Path::new("/p/package"),
+            Path::new("/p/missing-examples"),
+            Path::new("/p/_output/package.xpkg"),
+        );
+        assert_eq!(
+            args,
+            [
+                "xpkg",
+                "build",
+                "--package-root=/p/package",
+                "--package-file=/p/_output/package.xpkg"
+            ]
+        );
+    }
+
+    #[test]
+    fn patched_image_tag_is_keyed_by_source_and_package_yaml() {
+        let source = "ghcr.io/hops-ops/demo:configuration";
+        let tag = patched_image_tag(source, "sha256:1", "kind: Configuration\n");
+        assert!(tag.starts_with("hops-local/config-patched-"));
+        assert_eq!(
+            tag,
+            patched_image_tag(source, "sha256:1", "kind: Configuration\n")
+        );
+        assert_ne!(
+            tag,
+            patched_image_tag(source, "sha256:2", "kind: Configuration\n")
+        );
+        assert_ne!(
+            tag,
+            patched_image_tag(source, "sha256:1", "kind: Patched\n")
+        );
+    }
+
+    #[test]
+    fn push_cache_skips_only_identical_content() {
+        let mut cache = PushCache::default();
+        let push_ref = "localhost:30500/hops-ops/x_render:arm64";
+        assert!(cache.unchanged(push_ref, "sha256:a").is_none());
+
+        cache.record(push_ref, "sha256:a".to_string(), "sha256:d1".to_string());
+        assert_eq!(
+            cache
+                .unchanged(push_ref, "sha256:a")
+                .map(|pushed| pushed.digest.as_str()),
+            Some("sha256:d1")
+        );
+        assert!(cache.unchanged(push_ref, "sha256:b").is_none());
+        assert!(cache
+            .unchanged("localhost:30500/hops-ops/x_render:amd64", "sha256:a")
+            .is_none());
+    }
+
+    #[test]
+    fn push_cache_keeps_one_entry_per_configuration_repository() {
+        let mut cache = PushCache::default();
+        let first = "localhost:30500/hops-ops/x:dev-111111111111";
+        let second = "localhost:30500/hops-ops/x:dev-222222222222";
+        cache.record(first, "c1".to_string(), "sha256:1".to_string());
+        cache.record(second, "c2".to_string(), "sha256:2".to_string());
+        assert_eq!(cache.pushed.len(), 1);
+        assert!(cache.unchanged(first, "c1").is_none());
+        assert!(cache.unchanged(second, "c2").is_some());
+
+        let json = serde_json::to_string(&cache).unwrap();
+        let parsed: PushCache = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.pushed, cache.pushed);
+    }
+
+    #[test]
+    fn parallel_map_keeps_input_order() {
+        let items: Vec<u64> = (0..10).collect();
+        let results = parallel_map(&items, |n| {
+            std::thread::sleep(Duration::from_millis(10 - n));
+            n * 2
+        });
+        assert_eq!(results, (0..10).map(|n| n * 2).collect::<Vec<_>>());
+        assert!(parallel_map(&Vec::<u64>::new(), |n| *n).is_empty());
+    }
 }
@@ -0,0 +1,187 @@
+use super::install::{package_source, package_tag, REGISTRY_PULL};
+use crate::commands::local::{registry, run_cmd_output, OutputFormat, HOPS_KUBE_CONTEXT_ENV};
+use clap::Args;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::error::Error;
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    /// Output format
+    #[arg(long, short = 'o', value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+
+    /// Kubernetes context to use for all kubectl commands (e.g. "colima")
+    #[arg(long)]
+    pub context: Option<String>,
+}
+
+/// One installed Configuration.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+struct ConfigurationRow {
+    name: String,
+    package: String,
+    source: String,
+    /// Tag, or digest for digest-pinned references.
+    version: String,
+    /// Manifest digest, when known (always for local-registry packages).
+    digest: Option<String>,
+    /// `local` (in-cluster registry), `ghcr`, or `remote`.
+    origin: &'static str,
+    installed: String,
+    healthy: String,
+    /// Applied by `config install`.
+    managed: bool,
+}
+
+pub fn run(args: &ListArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(ctx) = &args.context {
+        std::env::set_var(HOPS_KUBE_CONTEXT_ENV, ctx);
+    }
+
+    let raw = run_cmd_output(
+        "kubectl",
+        &["get", "configurations.pkg.crossplane.io", "-o", "json"],
+    )?;
+    let list: JsonValue = serde_json::from_str(&raw)?;
+    let mut rows: Vec<ConfigurationRow> = list
+        .get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(configuration_row)
+        .collect();
+    rows.sort_by(|a, b| a.name.cmp(&b.name));
+    for row in rows.iter_mut().filter(|row| row.origin == "local") {
+        row.digest = local_digest(&row.package);
+    }
+
+    match args.output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        OutputFormat::Text if rows.is_empty() => println!("No Configurations installed"),
+        OutputFormat::Text => print_table(&rows),
+    }
+    Ok(())
+}
+
+fn configuration_row(item: &JsonValue) -> Option<ConfigurationRow> {
+    let name = item.pointer("/metadata/name")?.as_str()?.to_string();
+    let package = item
+        .pointer("/spec/package")
+        .and_then(JsonValue::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let version = package_tag(&package).unwrap_or("-").to_string();
+    let digest = version.starts_with("sha256:").then(|| version.clone());
+    let managed = item
+        .pointer("/metadata/labels")
+        .and_then(|labels| labels.get(super::MANAGED_BY_LABEL))
+        .and_then(JsonValue::as_str)
+        == Some(super::MANAGED_BY_VALUE);
+    Some(ConfigurationRow {
+        source: package_source(&package),
+        origin: package_origin(&package),
+        installed: condition_status(item, "Installed"),
+        healthy: condition_status(item, "Healthy"),
+        name,
+        package,
+        version,
+        digest,
+        managed,
+    })
+}
+
+fn package_origin(package: &str) -> &'static str {
+    if package.starts_with(&format!("{}/", REGISTRY_PULL)) {
+        "local"
+    } else if package.starts_with("ghcr.io/") {
+        "ghcr"
+    } else {
+        "remote"
+    }
+}
+
+/// `True`/`False` for a status condition, `Unknown` when it is missing.
+fn condition_status(object: &JsonValue, condition_type: &str) -> String {
+    object
+        .pointer("/status/conditions")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .find(|c| c.get("type").and_then(JsonValue::as_str) == Some(condition_type))
+        .and_then(|c| c.get("status").and_then(JsonValue::as_str))
+        .unwrap_or("Unknown")
+        .to_string()
+}
+
+/// Digest the in-cluster registry serves for a local package reference.
+fn local_digest(package: &str) -> Option<String> {
+    let repository_ref = package.strip_prefix(&format!("{}/", REGISTRY_PULL))?;
+    let (repository, tag) = repository_ref.rsplit_once(':')?;
+    registry::manifest_digest(repository, tag).ok().flatten()
+}
+
+fn print_table(rows: &[ConfigurationRow]) {
+    let name_width = rows
+        .iter()
+        .map(|row| row.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(4);
+    let version_width = rows
+        .iter()
+        .map(|row| row.version.len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    println!(
+        "{:<name_width$} {:<6} {:<version_width$} {:<9} {:<7} SOURCE",
+        "NAME", "ORIGIN", "VERSION", "INSTALLED", "HEALTHY"
+    );
+    for row in rows {
+        println!(
+            "{:<name_width$} {:<6} {:<version_width$} {:<9} {:<7} {}",
+            row.name, row.origin, row.version, row.installed, row.healthy, row.source
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn configuration_row_reads_origin_version_and_health() {
+        let item = json!({
+            "metadata": {"name": "hops-ops-network", "labels": {"hops.ops.com.ai/managed-by": "hops"}},
+            "spec": {"package": "registry.crossplane-system.svc.cluster.local:5000/hops-ops/network:dev-abc"},
+            "status": {"conditions": [
+                {"type": "Installed", "status": "True"},
+                {"type": "Healthy", "status": "False"}
+            ]}
+        });
+        let row = configuration_row(&item).unwrap();
+        assert_eq!(row.origin, "local");
+        assert_eq!(row.version, "dev-abc");
+        assert_eq!(
+            row.source,
+            "registry.crossplane-system.svc.cluster.local:5000/hops-ops/network"
+        );
+        assert_eq!(
+            (row.installed.as_str(), row.healthy.as_str()),
+            ("True", "False")
+        );
+        assert!(row.managed);
+
+        let pinned = json!({
+            "metadata": {"name": "eks"},
+            "spec": {"package": "ghcr.io/hops-ops/eks@sha256:abc"}
+        });
+        let row = configuration_row(&pinned).unwrap();
+        assert_eq!(row.origin, "ghcr");
+        assert_eq!(row.digest.as_deref(), Some("sha256:abc"));
+        assert_eq!(row.healthy, "Unknown");
+        assert!(!row.managed);
+    }
+}
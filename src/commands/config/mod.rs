@@ -1,9 +1,30 @@
+mod composites;
+mod direct_push;
+mod health;
 mod install;
+mod list;
+mod plan;
+mod prune;
+mod rollback;
+mod stack;
 mod uninstall;
+mod uppkg;
 
 use clap::{Args, Subcommand};
 use std::error::Error;
 
+pub use install::{
+    build_render_functions, crossplane_package_root, image_config_name, repo_checkout,
+    resolve_builder, strip_registry, Builder,
+};
+pub use plan::package_dependencies;
+pub use prune::{run as prune, PruneArgs};
+
+/// Label `config install` puts on the packages it applies, so
+/// `config uninstall --all` can find them again.
+const MANAGED_BY_LABEL: &str = "hops.ops.com.ai/managed-by";
+const MANAGED_BY_VALUE: &str = "hops";
+
 #[derive(Args, Debug)]
 pub struct ConfigArgs {
     #[command(subcommand)]
@@ -16,11 +37,38 @@ pub enum ConfigCommands {
     Install(install::ConfigArgs),
     /// Remove a Crossplane configuration and prune orphaned package dependencies
     Uninstall(uninstall::UnconfigArgs),
+    /// List installed Configurations with their source, version, and health
+    List(list::ListArgs),
+    /// Point a Configuration back at a previous revision's package
+    Rollback(rollback::RollbackArgs),
 }
 
 pub fn run(args: &ConfigArgs) -> Result<(), Box<dyn Error>> {
+    let read_only = match &args.command {
+        ConfigCommands::Install(install_args) => install_args.plan,
+        ConfigCommands::Uninstall(uninstall_args) => uninstall_args.dry_run,
+        ConfigCommands::List(_) => true,
+        ConfigCommands::Rollback(_) => false,
+    };
+    crate::commands::local::retry::set_enabled(true);
+    let _lock = if read_only {
+        None
+    } else {
+        Some(crate::commands::local::lock::acquire()?)
+    };
     match &args.command {
-        ConfigCommands::Install(install_args) => install::run(install_args),
-        ConfigCommands::Uninstall(uninstall_args) => uninstall::run(uninstall_args),
+        ConfigCommands::Install(install_args) => {
+            let _pending = crate::interrupt::pending("config install");
+            crate::notification::notify_result("config install", install::run(install_args))
+        }
+        ConfigCommands::Uninstall(uninstall_args) => {
+            let _pending = crate::interrupt::pending("config uninstall");
+            crate::notification::notify_result("config uninstall", uninstall::run(uninstall_args))
+        }
+        ConfigCommands::List(list_args) => list::run(list_args),
+        ConfigCommands::Rollback(rollback_args) => {
+            let _pending = crate::interrupt::pending("config rollback");
+            crate::notification::notify_result("config rollback", rollback::run(rollback_args))
+        }
     }
 }
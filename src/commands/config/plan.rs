@@ -0,0 +1,118 @@
+use crate::commands::local::conditions::get_resource_json;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::collections::BTreeMap;
+
+/// Package reference -> version constraint.
+pub type Dependencies = BTreeMap<String, String>;
+
+/// `spec.dependsOn` of a package.yaml meta object. Accepts both the
+/// `provider:`/`function:`/`configuration:` keys and the newer `package:` key.
+pub fn package_dependencies(package_yaml: &str) -> Dependencies {
+    let Some(first) = serde_yaml::Deserializer::from_str(package_yaml).next() else {
+        return Dependencies::new();
+    };
+    let Ok(meta) = serde_yaml::Value::deserialize(first) else {
+        return Dependencies::new();
+    };
+    meta.get("spec")
+        .and_then(|spec| spec.get("dependsOn"))
+        .and_then(serde_yaml::Value::as_sequence)
+        .into_iter()
+        .flatten()
+        .filter_map(|dep| {
+            let package = ["package", "provider", "function", "configuration"]
+                .iter()
+                .find_map(|key| dep.get(key).and_then(serde_yaml::Value::as_str))?;
+            let version = dep
+                .get("version")
+                .and_then(serde_yaml::Value::as_str)
+                .unwrap_or("*");
+            Some((package.to_string(), version.to_string()))
+        })
+        .collect()
+}
+
+/// Dependencies of the Configuration's current revision as recorded in the
+/// Crossplane package Lock, or `None` when it is not installed.
+pub fn installed_dependencies(configuration: &str) -> Option<Dependencies> {
+    let config = get_resource_json("configuration.pkg.crossplane.io", configuration, None)?;
+    let revision = config.pointer("/status/currentRevision")?.as_str()?;
+    let lock = get_resource_json("lock.pkg.crossplane.io", "lock", None)?;
+    lock_dependencies(&lock, revision)
+}
+
+fn lock_dependencies(lock: &JsonValue, revision: &str) -> Option<Dependencies> {
+    let package = lock
+        .get("packages")?
+        .as_array()?
+        .iter()
+        .find(|package| package.get("name").and_then(JsonValue::as_str) == Some(revision))?;
+    Some(
+        package
+            .get("dependencies")
+            .and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|dep| {
+                let name = dep.get("package")?.as_str()?;
+                let constraints = dep
+                    .get("constraints")
+                    .and_then(JsonValue::as_str)
+                    .unwrap_or("*");
+                Some((name.to_string(), constraints.to_string()))
+            })
+            .collect(),
+    )
+}
+
+/// `+ added`, `- removed`, and `~ changed` lines between two dependency sets.
+pub fn dependency_changes(installed: &Dependencies, planned: &Dependencies) -> Vec<String> {
+    let mut changes = Vec::new();
+    for (package, version) in planned {
+        match installed.get(package) {
+            None => changes.push(format!("+ {} {}", package, version)),
+            Some(old) if old != version => {
+                changes.push(format!("~ {} {} -> {}", package, old, version))
+            }
+            Some(_) => {}
+        }
+    }
+    for (package, version) in installed {
+        if !planned.contains_key(package) {
+            changes.push(format!("- {} {}", package, version));
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn dependency_changes_compare_lock_with_package_yaml() {
+        let planned = package_dependencies(
+            "apiVersion: meta.pkg.crossplane.io/v1\nkind: Configuration\nmetadata:\n  name: platform\nspec:\n  dependsOn:\n    - provider: xpkg.upbound.io/upbound/provider-aws-s3\n      version: \">=v1.2.0\"\n    - apiVersion: pkg.crossplane.io/v1beta1\n      kind: Function\n      package: xpkg.crossplane.io/crossplane-contrib/function-auto-ready\n      version: \">=v0.2.0\"\n",
+        );
+        let lock = json!({"packages": [{
+            "name": "platform-abc",
+            "dependencies": [
+                {"package": "xpkg.upbound.io/upbound/provider-aws-s3", "constraints": ">=v1.1.0"},
+                {"package": "xpkg.upbound.io/upbound/provider-aws-iam", "constraints": ">=v1.1.0"}
+            ]
+        }]});
+        let installed = lock_dependencies(&lock, "platform-abc").unwrap();
+        assert_eq!(lock_dependencies(&lock, "other"), None);
+
+        assert_eq!(
+            dependency_changes(&installed, &planned),
+            vec![
+                "+ xpkg.crossplane.io/crossplane-contrib/function-auto-ready >=v0.2.0",
+                "~ xpkg.upbound.io/upbound/provider-aws-s3 >=v1.1.0 -> >=v1.2.0",
+                "- xpkg.upbound.io/upbound/provider-aws-iam >=v1.1.0",
+            ]
+        );
+    }
+}
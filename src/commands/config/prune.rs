@@ -0,0 +1,193 @@
+use super::uninstall::{
+    fetch_lock_packages, package_source, prune_image_configs_for_sources,
+    prune_packages_for_removed_sources, LockPackage, SourceKey,
+};
+use crate::commands::local::run_cmd_output;
+use clap::Args;
+use serde_json::Value as JsonValue;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+/// Providers hops installs itself (`local start`, `local aws`, `local azure`,
+/// `local github`); nothing depends on them, but they are not orphans.
+const HOPS_PACKAGES: &[&str] = &[
+    "crossplane-contrib-provider-helm",
+    "crossplane-contrib-provider-kubernetes",
+    "crossplane-contrib-provider-family-aws",
+    "crossplane-contrib-provider-family-azure",
+    "crossplane-contrib-provider-upjet-github",
+];
+
+#[derive(Args, Debug)]
+pub struct PruneArgs {
+    /// Only list orphaned packages; delete nothing
+    #[arg(long)]
+    pub report: bool,
+
+    /// Package resource name to keep even when nothing depends on it (repeatable)
+    #[arg(long)]
+    pub keep: Vec<String>,
+}
+
+/// An installed package resource.
+#[derive(Debug)]
+struct Package {
+    kind: String,
+    name: String,
+    source: String,
+    managed: bool,
+}
+
+pub fn run(args: &PruneArgs) -> Result<(), Box<dyn Error>> {
+    if !args.report {
+        crate::commands::local::context::ensure_local("local prune")?;
+    }
+    let raw = run_cmd_output(
+        "kubectl",
+        &[
+            "get",
+            "configurations.pkg.crossplane.io,providers.pkg.crossplane.io,functions.pkg.crossplane.io",
+            "-o",
+            "json",
+        ],
+    )?;
+    let packages = parse_packages(&serde_json::from_str(&raw)?);
+    let lock = fetch_lock_packages();
+    if lock.is_empty() {
+        return Err("the Crossplane package Lock is empty or unreadable".into());
+    }
+
+    let roots: HashSet<&str> = packages
+        .iter()
+        .filter(|p| {
+            p.kind == "Configuration"
+                || p.managed
+                || HOPS_PACKAGES.contains(&p.name.as_str())
+                || args.keep.contains(&p.name)
+        })
+        .map(|p| p.source.as_str())
+        .collect();
+    let orphaned = unreachable_sources(&lock, &roots);
+    if orphaned.is_empty() {
+        crate::logging::success("No orphaned packages: everything installed is depended on");
+        return Ok(());
+    }
+
+    let names: HashMap<(&str, &str), &str> = packages
+        .iter()
+        .map(|p| ((p.kind.as_str(), p.source.as_str()), p.name.as_str()))
+        .collect();
+    let mut rows: Vec<(&str, &str, &str)> = orphaned
+        .iter()
+        .map(|o| {
+            let name = names
+                .get(&(o.kind.as_str(), o.source.as_str()))
+                .copied()
+                .unwrap_or("-");
+            (o.kind.as_str(), name, o.source.as_str())
+        })
+        .collect();
+    rows.sort();
+    let name_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(4);
+    println!("{:<13} {:<name_width$} SOURCE", "KIND", "NAME");
+    for (kind, name, source) in &rows {
+        println!("{:<13} {:<name_width$} {}", kind, name, source);
+    }
+
+    if args.report {
+        return Ok(());
+    }
+    if !crate::interactive::confirm(
+        &format!("Delete these {} orphaned package(s)?", rows.len()),
+        false,
+    )? {
+        return Err("aborted".into());
+    }
+    prune_packages_for_removed_sources(&orphaned)?;
+    let render_sources: HashSet<String> = orphaned
+        .iter()
+        .filter(|o| o.kind == "Function" && o.source.contains("_render"))
+        .map(|o| o.source.clone())
+        .collect();
+    if !render_sources.is_empty() {
+        prune_image_configs_for_sources(&render_sources)?;
+    }
+    crate::logging::success(&format!("Pruned {} orphaned package(s)", rows.len()));
+    Ok(())
+}
+
+fn parse_packages(list: &JsonValue) -> Vec<Package> {
+    list.get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            Some(Package {
+                kind: item.get("kind")?.as_str()?.to_string(),
+                name: item.pointer("/metadata/name")?.as_str()?.to_string(),
+                source: package_source(item.pointer("/spec/package")?.as_str()?),
+                managed: item
+                    .pointer("/metadata/labels")
+                    .and_then(|labels| labels.get(super::MANAGED_BY_LABEL))
+                    .and_then(JsonValue::as_str)
+                    == Some(super::MANAGED_BY_VALUE),
+            })
+        })
+        .collect()
+}
+
+/// Lock entries that no root reaches through `dependencies`.
+fn unreachable_sources(lock: &[LockPackage], roots: &HashSet<&str>) -> HashSet<SourceKey> {
+    let mut reached: HashSet<&str> = HashSet::new();
+    let mut pending: Vec<&str> = roots.iter().copied().collect();
+    while let Some(source) = pending.pop() {
+        if !reached.insert(source) {
+            continue;
+        }
+        for package in lock.iter().filter(|p| p.source == source) {
+            pending.extend(package.dependencies.iter().map(|d| d.package.as_str()));
+        }
+    }
+    lock.iter()
+        .filter(|p| !reached.contains(p.source.as_str()))
+        .map(|p| SourceKey {
+            kind: p.kind.clone(),
+            source: p.source.clone(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unreachable_sources_skip_everything_a_root_depends_on() {
+        let lock: Vec<LockPackage> = serde_json::from_value(json!([
+            {"kind": "Configuration", "name": "eks-abc", "source": "reg/eks",
+             "dependencies": [{"package": "xpkg/provider-aws-eks"}]},
+            {"kind": "Provider", "name": "provider-aws-eks-1", "source": "xpkg/provider-aws-eks",
+             "dependencies": [{"package": "xpkg/provider-family-aws"}]},
+            {"kind": "Provider", "name": "provider-family-aws-1", "source": "xpkg/provider-family-aws"},
+            {"kind": "Provider", "name": "provider-aws-ec2-1", "source": "xpkg/provider-aws-ec2",
+             "dependencies": [{"package": "xpkg/provider-family-aws"}]},
+            {"kind": "Function", "name": "function-kcl-1", "source": "xpkg/function-kcl"}
+        ]))
+        .unwrap();
+
+        let packages = parse_packages(&json!({"items": [
+            {"kind": "Configuration", "metadata": {"name": "eks"}, "spec": {"package": "reg/eks:dev-1"}},
+            {"kind": "Function", "metadata": {"name": "kcl", "labels": {"hops.ops.com.ai/managed-by": "hops"}},
+             "spec": {"package": "xpkg/function-kcl:v1"}}
+        ]}));
+        assert_eq!(packages[0].source, "reg/eks");
+        assert!(packages[1].managed);
+
+        let roots = HashSet::from(["reg/eks"]);
+        let orphaned = unreachable_sources(&lock, &roots);
+        let mut sources: Vec<&str> = orphaned.iter().map(|o| o.source.as_str()).collect();
+        sources.sort();
+        assert_eq!(sources, ["xpkg/function-kcl", "xpkg/provider-aws-ec2"]);
+    }
+}
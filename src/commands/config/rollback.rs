@@ -0,0 +1,237 @@
+use crate::commands::local::history::{self, HistoryEntry};
+use crate::commands::local::{run_cmd, run_cmd_output, HOPS_KUBE_CONTEXT_ENV};
+use clap::Args;
+use serde_json::{json, Value as JsonValue};
+use std::error::Error;
+use std::time::Duration;
+
+#[derive(Args, Debug)]
+pub struct RollbackArgs {
+    /// Configuration to roll back
+    pub name: String,
+
+    /// ConfigurationRevision number (`spec.revision`) to return to (default:
+    /// the newest revision older than the active one)
+    #[arg(long)]
+    pub to_revision: Option<i64>,
+
+    /// Kubernetes context to use for all kubectl commands (e.g. "colima")
+    #[arg(long)]
+    pub context: Option<String>,
+
+    /// Return right after re-pointing the Configuration instead of waiting for it to become healthy
+    #[arg(long)]
+    pub no_wait: bool,
+
+    /// How long to wait for the Configuration to become healthy, e.g. 10m
+    /// (default: `timeouts.configuration` in ~/.hops/config.yaml, else 5m)
+    #[arg(long, value_parser = humantime::parse_duration, conflicts_with = "no_wait")]
+    pub timeout: Option<Duration>,
+}
+
+/// A ConfigurationRevision Crossplane still keeps for the Configuration.
+#[derive(Debug, PartialEq, Eq)]
+struct Revision {
+    name: String,
+    number: i64,
+    image: String,
+    active: bool,
+}
+
+pub fn run(args: &RollbackArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(ctx) = &args.context {
+        std::env::set_var(HOPS_KUBE_CONTEXT_ENV, ctx);
+    }
+    crate::commands::local::context::ensure_local("config rollback")?;
+
+    let selector = format!("pkg.crossplane.io/package={}", args.name);
+    let raw = run_cmd_output(
+        "kubectl",
+        &[
+            "get",
+            "configurationrevisions.pkg.crossplane.io",
+            "-l",
+            &selector,
+            "-o",
+            "json",
+        ],
+    )?;
+    let revisions = parse_revisions(&serde_json::from_str(&raw)?);
+
+    // Crossplane keeps few inactive revisions; fall back to the packages
+    // `config install` recorded in `hops local history`.
+    let (package, label) = match select_target(&revisions, args.to_revision) {
+        Ok(target) => (target.image.clone(), format!("revision {}", target.number)),
+        Err(err) if args.to_revision.is_some() => return Err(err.into()),
+        Err(err) => {
+            let current = revisions
+                .iter()
+                .find(|r| r.active)
+                .map(|r| r.image.as_str());
+            let history = history::load();
+            let previous = previous_package(&history.installed_packages(&args.name), current)
+                .ok_or_else(|| {
+                    format!("{}; no earlier package in `hops local history` either", err)
+                })?
+                .to_string();
+            (previous, "the previous package from history".to_string())
+        }
+    };
+    log::info!(
+        "Rolling back Configuration '{}' to {} ({})...",
+        args.name,
+        label,
+        package
+    );
+    let patch = json!({"spec": {"package": package}});
+    run_cmd(
+        "kubectl",
+        &[
+            "patch",
+            "configuration.pkg.crossplane.io",
+            &args.name,
+            "--type",
+            "merge",
+            "-p",
+            &patch.to_string(),
+        ],
+    )?;
+
+    history::record(vec![HistoryEntry {
+        package: Some(package.clone()),
+        ..HistoryEntry::new("rollback", &args.name)
+    }]);
+
+    let wait_timeout = if args.no_wait {
+        None
+    } else {
+        Some(crate::settings::timeouts(args.timeout)?.configuration)
+    };
+    super::install::wait_for_health(std::slice::from_ref(&args.name), wait_timeout)?;
+    crate::logging::success(&format!(
+        "Configuration '{}' rolled back to {} ({})",
+        args.name, label, package
+    ));
+    Ok(())
+}
+
+/// Revisions sorted by `spec.revision`.
+fn parse_revisions(list: &JsonValue) -> Vec<Revision> {
+    let mut revisions: Vec<Revision> = list
+        .get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            Some(Revision {
+                name: item.pointer("/metadata/name")?.as_str()?.to_string(),
+                number: item.pointer("/spec/revision")?.as_i64()?,
+                image: item.pointer("/spec/image")?.as_str()?.to_string(),
+                active: item
+                    .pointer("/spec/desiredState")
+                    .and_then(JsonValue::as_str)
+                    == Some("Active"),
+            })
+        })
+        .collect();
+    revisions.sort_by_key(|revision| revision.number);
+    revisions
+}
+
+fn select_target(revisions: &[Revision], to_revision: Option<i64>) -> Result<&Revision, String> {
+    let available = || {
+        revisions
+            .iter()
+            .map(|r| {
+                format!(
+                    "{} ({}{})",
+                    r.number,
+                    r.image,
+                    if r.active { ", active" } else { "" }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let target = match to_revision {
+        Some(number) => revisions
+            .iter()
+            .find(|r| r.number == number)
+            .ok_or_else(|| format!("revision {} not found; available: {}", number, available()))?,
+        None => {
+            let active = revisions
+                .iter()
+                .find(|r| r.active)
+                .map_or(i64::MAX, |r| r.number);
+            revisions
+                .iter()
+                .rev()
+                .find(|r| r.number < active)
+                .ok_or_else(|| {
+                    format!(
+                        "no earlier revision is kept (Crossplane keeps `revisionHistoryLimit` inactive revisions); available: {}",
+                        available()
+                    )
+                })?
+        }
+    };
+    if target.active {
+        return Err(format!("revision {} is already active", target.number));
+    }
+    Ok(target)
+}
+
+/// The newest recorded package (newest first) before the current one.
+fn previous_package<'a>(installed: &[&'a str], current: Option<&str>) -> Option<&'a str> {
+    let start = match current {
+        Some(current) => installed.iter().position(|p| *p == current)? + 1,
+        None => 0,
+    };
+    installed[start..]
+        .iter()
+        .copied()
+        .find(|p| Some(*p) != current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn select_target_defaults_to_previous_revision() {
+        let list = json!({"items": [
+            {"metadata": {"name": "cfg-b"}, "spec": {"revision": 2, "image": "reg/cfg:dev-b", "desiredState": "Inactive"}},
+            {"metadata": {"name": "cfg-c"}, "spec": {"revision": 3, "image": "reg/cfg:dev-c", "desiredState": "Active"}},
+            {"metadata": {"name": "cfg-a"}, "spec": {"revision": 1, "image": "reg/cfg:dev-a", "desiredState": "Inactive"}}
+        ]});
+        let revisions = parse_revisions(&list);
+        assert_eq!(revisions[0].name, "cfg-a");
+
+        assert_eq!(
+            select_target(&revisions, None).unwrap().image,
+            "reg/cfg:dev-b"
+        );
+        assert_eq!(
+            select_target(&revisions, Some(1)).unwrap().image,
+            "reg/cfg:dev-a"
+        );
+        assert!(select_target(&revisions, Some(3)).is_err());
+        assert!(select_target(&revisions, Some(9)).is_err());
+        assert!(select_target(&revisions[2..], None).is_err());
+    }
+
+    #[test]
+    fn previous_package_skips_to_the_install_before_the_current_one() {
+        let installed = ["reg/cfg:dev-c", "reg/cfg:dev-b", "reg/cfg:dev-a"];
+        assert_eq!(
+            previous_package(&installed, Some("reg/cfg:dev-c")),
+            Some("reg/cfg:dev-b")
+        );
+        assert_eq!(
+            previous_package(&installed, Some("reg/cfg:dev-b")),
+            Some("reg/cfg:dev-a")
+        );
+        assert_eq!(previous_package(&installed, Some("reg/cfg:dev-a")), None);
+        assert_eq!(previous_package(&installed, Some("ghcr.io/x:v1")), None);
+    }
+}
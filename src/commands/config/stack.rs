@@ -0,0 +1,224 @@
+use super::install::{self, Builder, ConfigArgs, PushCache};
+use crate::commands::local::{run_cmd, HOPS_KUBE_CONTEXT_ENV};
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// A stack file: the configurations that make up a local environment.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Stack {
+    /// Kubernetes context (`--context` on the command line wins).
+    pub context: Option<String>,
+    /// Default for entries that don't set `skip-dependency-resolution`.
+    pub skip_dependency_resolution: bool,
+    pub configurations: Vec<StackEntry>,
+    /// Run `hops local aws` once the configurations are healthy.
+    pub aws: Option<StackAws>,
+}
+
+/// One configuration: a repo (optionally pinned to a version), a project
+/// path, or a built package file. Paths are relative to the stack file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct StackEntry {
+    pub repo: Option<String>,
+    pub version: Option<String>,
+    pub git_url: Option<String>,
+    #[serde(rename = "ref")]
+    pub git_ref: Option<String>,
+    pub path: Option<PathBuf>,
+    pub file: Option<PathBuf>,
+    pub builder: Option<Builder>,
+    pub skip_dependency_resolution: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct StackAws {
+    pub profile: Option<String>,
+    pub namespace: Option<String>,
+    pub provider_config_name: Option<String>,
+}
+
+pub fn run(
+    stack_path: &Path,
+    args: &ConfigArgs,
+    wait_timeout: Option<Duration>,
+) -> Result<(), Box<dyn Error>> {
+    let stack = load(stack_path)?;
+    if args.context.is_none() {
+        if let Some(ctx) = &stack.context {
+            std::env::set_var(HOPS_KUBE_CONTEXT_ENV, ctx);
+        }
+    }
+    crate::commands::local::context::ensure_local("config install --stack")?;
+    let base_dir = stack_path.parent().unwrap_or(Path::new("."));
+
+    // Apply everything first and wait once, so configurations that share
+    // dependencies resolve them together instead of one at a time.
+    let mut cache = PushCache::load();
+    let mut names = Vec::new();
+    for (index, entry) in stack.configurations.iter().enumerate() {
+        let skip = args.skip_dependency_resolution
+            || entry
+                .skip_dependency_resolution
+                .unwrap_or(stack.skip_dependency_resolution);
+        let builder = entry.builder.unwrap_or(args.builder);
+        log::info!(
+            "Stack entry {}/{}: {}",
+            index + 1,
+            stack.configurations.len(),
+            entry_label(entry)
+        );
+        let applied = match (&entry.repo, &entry.version, &entry.path, &entry.file) {
+            (Some(repo), Some(version), _, _) => install::apply_repo_version(repo, version, skip)?,
+            (Some(repo), None, _, _) => install::run_repo_source(
+                repo,
+                entry.git_url.as_deref(),
+                entry.git_ref.as_deref(),
+                builder,
+                skip,
+                &mut cache,
+            )?,
+            (None, _, Some(path), _) => install::run_local_path(
+                &base_dir.join(path).to_string_lossy(),
+                builder,
+                skip,
+                &mut cache,
+            )?,
+            (None, _, None, Some(file)) => {
+                install::run_package_file(&base_dir.join(file), skip, &mut cache)?
+            }
+            (None, _, None, None) => unreachable!("validated in load"),
+        };
+        names.extend(applied);
+    }
+    install::wait_for_health(&names, wait_timeout)?;
+
+    if let Some(aws) = &stack.aws {
+        let exe = std::env::current_exe()?;
+        let aws_args = aws_command_args(aws);
+        let aws_args: Vec<&str> = aws_args.iter().map(String::as_str).collect();
+        run_cmd(&exe.to_string_lossy(), &aws_args)?;
+    }
+
+    crate::logging::success(&format!(
+        "Stack {} installed ({} configuration(s))",
+        stack_path.display(),
+        names.len()
+    ));
+    Ok(())
+}
+
+fn load(path: &Path) -> Result<Stack, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read stack file {}: {}", path.display(), err))?;
+    let stack = parse(&contents)
+        .map_err(|err| format!("invalid stack file {}: {}", path.display(), err))?;
+    Ok(stack)
+}
+
+fn parse(contents: &str) -> Result<Stack, Box<dyn Error>> {
+    let stack: Stack = serde_yaml::from_str(contents)?;
+    if stack.configurations.is_empty() {
+        return Err("`configurations` lists no entries".into());
+    }
+    for (index, entry) in stack.configurations.iter().enumerate() {
+        let sources = [
+            entry.repo.is_some(),
+            entry.path.is_some(),
+            entry.file.is_some(),
+        ];
+        if sources.iter().filter(|set| **set).count() != 1 {
+            return Err(format!(
+                "configuration {} must set exactly one of `repo`, `path`, or `file`",
+                index + 1
+            )
+            .into());
+        }
+        let source_only = entry.git_url.is_some() || entry.git_ref.is_some();
+        if source_only && (entry.repo.is_none() || entry.version.is_some()) {
+            return Err(format!(
+                "configuration {}: `git-url` and `ref` require `repo` without `version`",
+                index + 1
+            )
+            .into());
+        }
+        if entry.version.is_some() && entry.repo.is_none() {
+            return Err(format!("configuration {}: `version` requires `repo`", index + 1).into());
+        }
+    }
+    Ok(stack)
+}
+
+fn entry_label(entry: &StackEntry) -> String {
+    match (&entry.repo, &entry.version, &entry.path, &entry.file) {
+        (Some(repo), Some(version), _, _) => format!("{}@{}", repo, version),
+        (Some(repo), None, _, _) => match &entry.git_ref {
+            Some(git_ref) => format!("{} (source, {})", repo, git_ref),
+            None => format!("{} (source)", repo),
+        },
+        (None, _, Some(path), _) => path.display().to_string(),
+        (None, _, None, Some(file)) => file.display().to_string(),
+        (None, _, None, None) => String::new(),
+    }
+}
+
+fn aws_command_args(aws: &StackAws) -> Vec<String> {
+    let mut args = vec!["local".to_string(), "aws".to_string()];
+    let options = [
+        ("--profile", &aws.profile),
+        ("--namespace", &aws.namespace),
+        ("--provider-config-name", &aws.provider_config_name),
+    ];
+    for (flag, value) in options {
+        if let Some(value) = value {
+            args.push(flag.to_string());
+            args.push(value.clone());
+        }
+    }
+    args
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_entries_and_aws_settings() {
+        let stack = parse(
+            "context: colima\nconfigurations:\n  - repo: hops-ops/aws-auto-eks-cluster\n    version: v0.11.0\n  - path: ./platform\n    builder: crossplane\n  - file: ./pkg.xpkg\n    skip-dependency-resolution: true\naws:\n  profile: hops\n  namespace: platform\n",
+        )
+        .unwrap();
+        assert_eq!(stack.context.as_deref(), Some("colima"));
+        assert_eq!(stack.configurations.len(), 3);
+        assert_eq!(
+            entry_label(&stack.configurations[0]),
+            "hops-ops/aws-auto-eks-cluster@v0.11.0"
+        );
+        assert_eq!(stack.configurations[1].builder, Some(Builder::Crossplane));
+        assert_eq!(
+            aws_command_args(stack.aws.as_ref().unwrap()),
+            [
+                "local",
+                "aws",
+                "--profile",
+                "hops",
+                "--namespace",
+                "platform"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_rejects_ambiguous_entries() {
+        assert!(parse("configurations: []\n").is_err());
+        assert!(parse("configurations:\n  - repo: a/b\n    path: .\n").is_err());
+        assert!(parse("configurations:\n  - path: .\n    version: v1\n").is_err());
+        assert!(parse("configurations:\n  - path: .\n    typo: 1\n").is_err());
+        assert!(parse("configurations:\n  - path: .\n    ref: main\n").is_err());
+    }
+}
@@ -1,34 +1,70 @@
+use super::composites::{composite_resources, CompositeResource};
+use super::uppkg::UppkgArchive;
+use crate::commands::local::backoff::poll_until;
+use crate::commands::local::conditions::{
+    get_resource_json, timed_out, wait_until, PendingCondition,
+};
+use crate::commands::local::history::{self, HistoryEntry};
+use crate::commands::local::trace::{self, ObjectRef};
 use crate::commands::local::{repo_cache_path, run_cmd, run_cmd_output};
 use clap::Args;
 use serde::Deserialize;
 use std::collections::{HashMap, HashSet};
 use std::error::Error;
 use std::fs;
-use std::io::Read;
 use std::path::Path;
-use std::thread;
 use std::time::Duration;
-use tar::Archive;
 
 #[derive(Args, Debug)]
 pub struct UnconfigArgs {
     /// Configuration resource name to remove
-    #[arg(long, conflicts_with_all = ["repo", "path"])]
+    #[arg(long, conflicts_with_all = ["repo", "path", "all"])]
     pub name: Option<String>,
 
     /// GitHub repository in <org>/<repo> format (derives name as <org>-<repo>)
-    #[arg(long, conflicts_with_all = ["name", "path"])]
+    #[arg(long, conflicts_with_all = ["name", "path", "all"])]
     pub repo: Option<String>,
 
-    /// Path to an XRD project directory (derives names from _output/*.uppkg)
-    #[arg(long, conflicts_with_all = ["name", "repo"])]
+    /// Path to an XRD project directory (derives names from _output/*.uppkg or *.xpkg)
+    #[arg(long, conflicts_with_all = ["name", "repo", "all"])]
     pub path: Option<String>,
+
+    /// Remove every Configuration (and standalone Provider/Function) applied
+    /// by `config install`
+    #[arg(long)]
+    pub all: bool,
+
+    /// Print the Configurations, Functions, Providers, revisions, and
+    /// ImageConfigs that would be deleted, without deleting anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Remove the Configurations even while claims or XRs of their XRDs
+    /// still exist (their managed resources lose their controllers)
+    #[arg(long)]
+    pub force: bool,
+
+    /// Delete those claims and XRs first and wait until their composed
+    /// managed resources are gone before removing the Configurations
+    #[arg(long, conflicts_with = "force")]
+    pub cascade: bool,
+
+    /// How long to wait for `--cascade` deletions to finish, e.g. 15m
+    /// (default: `timeouts.cascade` in ~/.hops/config.yaml, else 10m)
+    #[arg(long, value_parser = humantime::parse_duration, requires = "cascade")]
+    pub cascade_timeout: Option<Duration>,
+
+    /// How long to wait for the Configurations and their package lock
+    /// entries to go away, e.g. 5m (default: `timeouts.uninstall` in
+    /// ~/.hops/config.yaml, else 2m)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct SourceKey {
-    kind: String,
-    source: String,
+pub(super) struct SourceKey {
+    pub(super) kind: String,
+    pub(super) source: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -71,21 +107,22 @@ struct ImageConfigResource {
 }
 
 #[derive(Clone, Debug, Deserialize)]
-struct LockPackage {
-    kind: String,
-    name: String,
-    source: String,
+pub(super) struct LockPackage {
+    pub(super) kind: String,
+    pub(super) name: String,
+    pub(super) source: String,
+    #[serde(default)]
+    pub(super) dependencies: Vec<LockDependency>,
 }
 
-#[derive(Debug, Deserialize)]
-struct LockResource {
-    packages: Option<Vec<LockPackage>>,
+#[derive(Clone, Debug, Deserialize)]
+pub(super) struct LockDependency {
+    pub(super) package: String,
 }
 
 #[derive(Debug, Deserialize)]
-struct DockerSaveManifestEntry {
-    #[serde(rename = "RepoTags")]
-    repo_tags: Option<Vec<String>>,
+struct LockResource {
+    packages: Option<Vec<LockPackage>>,
 }
 
 #[derive(Debug)]
@@ -95,12 +132,74 @@ struct RepoSpec {
 }
 
 pub fn run(args: &UnconfigArgs) -> Result<(), Box<dyn Error>> {
-    let config_names = resolve_configuration_names(args)?;
-    if config_names.is_empty() {
+    if !args.dry_run {
+        crate::commands::local::context::ensure_local("config uninstall")?;
+    }
+    let mut standalone = Vec::new();
+    let config_names = if args.all {
+        let names = list_managed("configurations.pkg.crossplane.io")?;
+        for kind in ["providers.pkg.crossplane.io", "functions.pkg.crossplane.io"] {
+            standalone.extend(list_managed(kind)?.into_iter().map(|name| (kind, name)));
+        }
+        if names.is_empty() && standalone.is_empty() {
+            log::info!("No packages applied by `config install` were found");
+            return Ok(());
+        }
+        let summary = names
+            .iter()
+            .cloned()
+            .chain(
+                standalone
+                    .iter()
+                    .map(|(kind, name)| format!("{}/{}", kind, name)),
+            )
+            .collect::<Vec<_>>()
+            .join(", ");
+        if !args.dry_run && !crate::interactive::confirm(&format!("Remove {}?", summary), false)? {
+            return Err("aborted".into());
+        }
+        names
+    } else {
+        resolve_configuration_names(args)?
+    };
+    if config_names.is_empty() && standalone.is_empty() {
         return Err("no target configurations resolved".into());
     }
 
     let hinted_sources = resolve_hinted_sources(args)?;
+    let composites = composite_resources(&config_names)?;
+    if args.dry_run {
+        return print_dry_run(
+            args,
+            &config_names,
+            &standalone,
+            &hinted_sources,
+            &composites,
+        );
+    }
+    if args.cascade {
+        let timeout = crate::settings::timeouts(args.cascade_timeout)?.cascade;
+        delete_composites(&composites, timeout)?;
+    } else if !composites.is_empty() {
+        let listing = composites
+            .iter()
+            .map(|c| format!("  {}", c))
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !args.force {
+            return Err(format!(
+                "{} claim(s)/XR(s) still use these Configurations' XRDs:\n{}\nDelete them first (--cascade does), or pass --force to remove the Configurations anyway",
+                composites.len(),
+                listing
+            )
+            .into());
+        }
+        log::warn!(
+            "Removing Configurations while {} claim(s)/XR(s) still exist (--force):\n{}",
+            composites.len(),
+            listing
+        );
+    }
 
     log::info!(
         "Preparing to remove configurations: {}",
@@ -110,9 +209,25 @@ pub fn run(args: &UnconfigArgs) -> Result<(), Box<dyn Error>> {
     let pre_sources = lock_source_set(&pre_lock);
 
     delete_configurations(&config_names)?;
-    wait_for_configurations_deleted(&config_names)?;
+    for (kind, name) in &standalone {
+        log::info!("Deleting {} '{}'...", kind, name);
+        run_cmd("kubectl", &["delete", kind, name, "--ignore-not-found"])?;
+    }
+    history::record(
+        config_names
+            .iter()
+            .cloned()
+            .chain(standalone.iter().map(|(kind, name)| {
+                let kind = kind.trim_end_matches(".pkg.crossplane.io");
+                format!("{}/{}", kind.trim_end_matches('s'), name)
+            }))
+            .map(|name| HistoryEntry::new("uninstall", &name))
+            .collect(),
+    );
+    let timeout = crate::settings::timeouts(args.timeout)?.uninstall;
+    wait_for_configurations_deleted(&config_names, timeout)?;
 
-    wait_for_lock_without_configurations(&config_names)?;
+    wait_for_lock_without_configurations(&config_names, timeout)?;
     let post_lock = fetch_lock_packages();
     let post_sources = lock_source_set(&post_lock);
 
@@ -154,14 +269,172 @@ pub fn run(args: &UnconfigArgs) -> Result<(), Box<dyn Error>> {
         prune_image_configs_for_sources(&removed_render_sources)?;
     }
 
-    log::info!(
+    crate::logging::success(&format!(
         "Removed configurations; lock-diff orphaned sources: {}, source-hint package resources pruned: {}",
         removed_sources.len(),
         hinted_resource_prunes
+    ));
+    Ok(())
+}
+
+/// Lists what `run` would delete. The lock diff is predicted from the
+/// current Lock instead of observed after the Configurations are gone.
+fn print_dry_run(
+    args: &UnconfigArgs,
+    config_names: &[String],
+    standalone: &[(&str, String)],
+    hinted_sources: &HashSet<String>,
+    composites: &[CompositeResource],
+) -> Result<(), Box<dyn Error>> {
+    let mut deletions: Vec<(String, String)> = config_names
+        .iter()
+        .map(|name| ("configuration.pkg.crossplane.io".to_string(), name.clone()))
+        .chain(
+            standalone
+                .iter()
+                .map(|(kind, name)| (kind.to_string(), name.clone())),
+        )
+        .collect();
+
+    let orphaned = orphaned_sources(&fetch_lock_packages(), config_names);
+    let mut render_sources = HashSet::new();
+    for (kind, resource, revision_resource) in PACKAGE_RESOURCES {
+        let sources: HashSet<String> = orphaned
+            .iter()
+            .filter(|source| source.kind == kind)
+            .map(|source| source.source.clone())
+            .chain(hinted_sources.iter().cloned())
+            .collect();
+        if sources.is_empty() {
+            continue;
+        }
+        for resource in [resource, revision_resource] {
+            for name in resources_by_source(resource, &sources)? {
+                deletions.push((resource.to_string(), name));
+            }
+        }
+        render_sources.extend(sources.into_iter().filter(|s| s.contains("_render")));
+    }
+    if !render_sources.is_empty() {
+        for name in image_configs_for_sources(&render_sources)? {
+            deletions.push(("imageconfig.pkg.crossplane.io".to_string(), name));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    deletions.retain(|deletion| seen.insert(deletion.clone()));
+    println!("Would delete:");
+    for (resource, name) in &deletions {
+        println!("  {}/{}", resource, name);
+    }
+    let mut orphaned: Vec<&SourceKey> = orphaned.iter().collect();
+    orphaned.sort_by(|a, b| (&a.kind, &a.source).cmp(&(&b.kind, &b.source)));
+    if !orphaned.is_empty() {
+        println!("Package sources orphaned by the lock diff:");
+        for source in orphaned {
+            println!("  {} {}", source.kind, source.source);
+        }
+    }
+    if !composites.is_empty() && args.cascade {
+        println!("Would delete first (--cascade):");
+        for composite in composites {
+            println!("  {}", composite);
+            for composed in composed_objects(composite) {
+                println!("    {}/{}", composed.kind, composed.name);
+            }
+        }
+    } else if !composites.is_empty() {
+        println!("Still in use by (the uninstall refuses without --force or --cascade):");
+        for composite in composites {
+            println!("  {}", composite);
+        }
+    }
+    Ok(())
+}
+
+/// Delete claims, then XRs, and wait until they and every resource they
+/// composed are gone, so managed resources are removed while their
+/// providers are still running.
+fn delete_composites(
+    composites: &[CompositeResource],
+    timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+    if composites.is_empty() {
+        return Ok(());
+    }
+    let mut remaining: Vec<ObjectRef> = Vec::new();
+    for composite in composites {
+        remaining.extend(composed_objects(composite));
+    }
+    for composite in composites {
+        log::info!("Deleting {}...", composite);
+        let mut delete_args = vec![
+            "delete",
+            composite.resource.as_str(),
+            composite.name.as_str(),
+            "--ignore-not-found",
+            "--wait=false",
+        ];
+        if let Some(namespace) = &composite.namespace {
+            delete_args.extend(["-n", namespace.as_str()]);
+        }
+        run_cmd("kubectl", &delete_args)?;
+    }
+
+    log::info!(
+        "Waiting for {} claim(s)/XR(s) and {} composed resource(s) to be deleted...",
+        composites.len(),
+        remaining.len()
     );
+    let resources: Vec<(String, String, Option<String>)> = composites
+        .iter()
+        .map(|c| (c.resource.clone(), c.name.clone(), c.namespace.clone()))
+        .chain(
+            remaining
+                .iter()
+                .map(|r| (r.kubectl_resource(), r.name.clone(), r.namespace.clone())),
+        )
+        .collect();
+    wait_until(timeout, || {
+        resources
+            .iter()
+            .filter(|(resource, name, namespace)| {
+                get_resource_json(resource, name, namespace.as_deref()).is_some()
+            })
+            .map(|(resource, name, _)| PendingCondition {
+                resource: format!("{}/{}", resource, name),
+                condition_type: "Deleted".to_string(),
+                reason: "StillExists".to_string(),
+                message: String::new(),
+            })
+            .collect()
+    })
+    .map_err(|pending| {
+        let listing = pending
+            .iter()
+            .map(|p| format!("  {}", p.resource))
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "{}; still present:\n{}",
+            timed_out("deletions", timeout),
+            listing
+        )
+    })?;
     Ok(())
 }
 
+/// Objects composed under a claim or XR (empty when it can't be read).
+fn composed_objects(composite: &CompositeResource) -> Vec<ObjectRef> {
+    get_resource_json(
+        &composite.resource,
+        &composite.name,
+        composite.namespace.as_deref(),
+    )
+    .map(|object| trace::composed_objects(&object))
+    .unwrap_or_default()
+}
+
 fn resolve_configuration_names(args: &UnconfigArgs) -> Result<Vec<String>, Box<dyn Error>> {
     if let Some(name) = args.name.as_deref() {
         let name = name.trim();
@@ -185,7 +458,46 @@ fn resolve_configuration_names(args: &UnconfigArgs) -> Result<Vec<String>, Box<d
         return resolve_names_from_path(path);
     }
 
-    Err("pass one of `--name`, `--repo`, or `--path`".into())
+    if crate::interactive::prompts_allowed() {
+        let installed = list_installed_configurations()?;
+        if installed.is_empty() {
+            return Err("no Configurations are installed in the cluster".into());
+        }
+        let name =
+            crate::interactive::fuzzy_select("Select a Configuration to remove", &installed)?;
+        return Ok(vec![name]);
+    }
+
+    Err("pass one of `--name`, `--repo`, `--path`, or `--all`".into())
+}
+
+/// Names of `kind` resources carrying the `config install` management label.
+fn list_managed(kind: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let selector = format!("{}={}", super::MANAGED_BY_LABEL, super::MANAGED_BY_VALUE);
+    let raw = run_cmd_output("kubectl", &["get", kind, "-l", &selector, "-o", "json"])?;
+    let list: KubeList<PackageResource> = serde_json::from_str(&raw)?;
+    let mut names: Vec<String> = list
+        .items
+        .into_iter()
+        .map(|item| item.metadata.name)
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+fn list_installed_configurations() -> Result<Vec<String>, Box<dyn Error>> {
+    let raw = run_cmd_output(
+        "kubectl",
+        &["get", "configurations.pkg.crossplane.io", "-o", "json"],
+    )?;
+    let list: KubeList<PackageResource> = serde_json::from_str(&raw)?;
+    let mut names = list
+        .items
+        .into_iter()
+        .map(|item| item.metadata.name)
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
 }
 
 fn resolve_hinted_sources(args: &UnconfigArgs) -> Result<HashSet<String>, Box<dyn Error>> {
@@ -231,58 +543,50 @@ fn delete_configurations(names: &[String]) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn wait_for_configurations_deleted(names: &[String]) -> Result<(), Box<dyn Error>> {
-    for _ in 0..60 {
-        let mut any_exists = false;
-        for name in names {
-            if run_cmd_output(
+fn wait_for_configurations_deleted(
+    names: &[String],
+    timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+    let deleted = poll_until(timeout, || {
+        !names.iter().any(|name| {
+            run_cmd_output(
                 "kubectl",
                 &["get", "configuration.pkg.crossplane.io", name, "-o", "name"],
             )
             .is_ok()
-            {
-                any_exists = true;
-                break;
-            }
-        }
-
-        if !any_exists {
-            return Ok(());
-        }
-
-        thread::sleep(Duration::from_secs(2));
+        })
+    });
+    if deleted {
+        Ok(())
+    } else {
+        Err(timed_out("configurations to be deleted", timeout).into())
     }
-
-    Err("timed out waiting for configurations to be deleted".into())
 }
 
-fn wait_for_lock_without_configurations(config_names: &[String]) -> Result<(), Box<dyn Error>> {
-    for _ in 0..45 {
+fn wait_for_lock_without_configurations(
+    config_names: &[String],
+    timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+    let dropped = poll_until(timeout, || {
         let lock = fetch_lock_packages();
-        let mut still_present = false;
-        for name in config_names {
+        !config_names.iter().any(|name| {
             let prefix = format!("{}-", name);
-            if lock
-                .iter()
+            lock.iter()
                 .any(|p| p.kind == "Configuration" && p.name.starts_with(&prefix))
-            {
-                still_present = true;
-                break;
-            }
-        }
-
-        if !still_present {
-            return Ok(());
-        }
-
-        thread::sleep(Duration::from_secs(2));
+        })
+    });
+    if dropped {
+        return Ok(());
     }
 
-    log::warn!("Timed out waiting for lock to drop configuration revisions; continuing cleanup");
+    log::warn!(
+        "{}; continuing cleanup",
+        timed_out("the lock to drop configuration revisions", timeout)
+    );
     Ok(())
 }
 
-fn fetch_lock_packages() -> Vec<LockPackage> {
+pub(super) fn fetch_lock_packages() -> Vec<LockPackage> {
     let raw = match run_cmd_output(
         "kubectl",
         &["get", "lock.pkg.crossplane.io", "lock", "-o", "json"],
@@ -297,6 +601,56 @@ fn fetch_lock_packages() -> Vec<LockPackage> {
         .unwrap_or_default()
 }
 
+/// Sources the lock diff would drop once `config_names` are removed: their
+/// revisions, then every dependency no remaining package depends on.
+fn orphaned_sources(lock: &[LockPackage], config_names: &[String]) -> HashSet<SourceKey> {
+    let mut removed: Vec<bool> = lock
+        .iter()
+        .map(|p| {
+            p.kind == "Configuration"
+                && config_names
+                    .iter()
+                    .any(|name| p.name.starts_with(&format!("{}-", name)))
+        })
+        .collect();
+    loop {
+        let kept_deps: HashSet<&str> = lock
+            .iter()
+            .zip(&removed)
+            .filter(|(_, removed)| !**removed)
+            .flat_map(|(p, _)| p.dependencies.iter().map(|d| d.package.as_str()))
+            .collect();
+        let removed_deps: HashSet<&str> = lock
+            .iter()
+            .zip(&removed)
+            .filter(|(_, removed)| **removed)
+            .flat_map(|(p, _)| p.dependencies.iter().map(|d| d.package.as_str()))
+            .collect();
+        let mut changed = false;
+        for (package, removed) in lock.iter().zip(removed.iter_mut()) {
+            let source = package.source.as_str();
+            if !*removed && removed_deps.contains(source) && !kept_deps.contains(source) {
+                *removed = true;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let kept: Vec<LockPackage> = lock
+        .iter()
+        .zip(&removed)
+        .filter(|(_, removed)| !**removed)
+        .map(|(p, _)| p.clone())
+        .collect();
+    lock_source_set(lock)
+        .difference(&lock_source_set(&kept))
+        .cloned()
+        .collect()
+}
+
 fn lock_source_set(lock: &[LockPackage]) -> HashSet<SourceKey> {
     lock.iter()
         .map(|p| SourceKey {
@@ -306,7 +660,26 @@ fn lock_source_set(lock: &[LockPackage]) -> HashSet<SourceKey> {
         .collect()
 }
 
-fn prune_packages_for_removed_sources(
+/// Lock kind, package resource, and revision resource for each package type.
+const PACKAGE_RESOURCES: [(&str, &str, &str); 3] = [
+    (
+        "Configuration",
+        "configuration.pkg.crossplane.io",
+        "configurationrevision.pkg.crossplane.io",
+    ),
+    (
+        "Function",
+        "function.pkg.crossplane.io",
+        "functionrevision.pkg.crossplane.io",
+    ),
+    (
+        "Provider",
+        "provider.pkg.crossplane.io",
+        "providerrevision.pkg.crossplane.io",
+    ),
+];
+
+pub(super) fn prune_packages_for_removed_sources(
     removed_sources: &HashSet<SourceKey>,
 ) -> Result<(), Box<dyn Error>> {
     let mut by_kind: HashMap<&str, HashSet<&str>> = HashMap::new();
@@ -317,24 +690,9 @@ fn prune_packages_for_removed_sources(
             .insert(source.source.as_str());
     }
 
-    prune_resource_group(
-        "Configuration",
-        "configuration.pkg.crossplane.io",
-        "configurationrevision.pkg.crossplane.io",
-        by_kind.get("Configuration"),
-    )?;
-    prune_resource_group(
-        "Function",
-        "function.pkg.crossplane.io",
-        "functionrevision.pkg.crossplane.io",
-        by_kind.get("Function"),
-    )?;
-    prune_resource_group(
-        "Provider",
-        "provider.pkg.crossplane.io",
-        "providerrevision.pkg.crossplane.io",
-        by_kind.get("Provider"),
-    )?;
+    for (kind, resource, revision_resource) in PACKAGE_RESOURCES {
+        prune_resource_group(kind, resource, revision_resource, by_kind.get(kind))?;
+    }
 
     Ok(())
 }
@@ -345,12 +703,10 @@ fn prune_packages_for_source_hints(sources: &HashSet<String>) -> Result<usize, B
     }
 
     let mut deleted = 0usize;
-    deleted += delete_resource_by_source("configuration.pkg.crossplane.io", sources)?;
-    deleted += delete_resource_by_source("configurationrevision.pkg.crossplane.io", sources)?;
-    deleted += delete_resource_by_source("function.pkg.crossplane.io", sources)?;
-    deleted += delete_resource_by_source("functionrevision.pkg.crossplane.io", sources)?;
-    deleted += delete_resource_by_source("provider.pkg.crossplane.io", sources)?;
-    deleted += delete_resource_by_source("providerrevision.pkg.crossplane.io", sources)?;
+    for (_, resource, revision_resource) in PACKAGE_RESOURCES {
+        deleted += delete_resource_by_source(resource, sources)?;
+        deleted += delete_resource_by_source(revision_resource, sources)?;
+    }
     Ok(deleted)
 }
 
@@ -383,14 +739,15 @@ fn prune_resource_group(
     Ok(())
 }
 
-fn delete_resource_by_source(
+/// Names of `resource` objects whose package comes from one of `sources`.
+fn resources_by_source(
     resource: &str,
     sources: &HashSet<String>,
-) -> Result<usize, Box<dyn Error>> {
+) -> Result<Vec<String>, Box<dyn Error>> {
     let raw = run_cmd_output("kubectl", &["get", resource, "-o", "json"])?;
     let list: KubeList<PackageResource> = serde_json::from_str(&raw)?;
 
-    let mut deleted = 0usize;
+    let mut names = Vec::new();
     for item in list.items {
         let Some(spec) = item.spec else {
             continue;
@@ -400,30 +757,33 @@ fn delete_resource_by_source(
         };
 
         if sources.contains(&package_source(&package_ref)) {
-            run_cmd(
-                "kubectl",
-                &[
-                    "delete",
-                    resource,
-                    &item.metadata.name,
-                    "--ignore-not-found",
-                ],
-            )?;
-            deleted += 1;
+            names.push(item.metadata.name);
         }
     }
 
-    Ok(deleted)
+    Ok(names)
+}
+
+fn delete_resource_by_source(
+    resource: &str,
+    sources: &HashSet<String>,
+) -> Result<usize, Box<dyn Error>> {
+    let names = resources_by_source(resource, sources)?;
+    for name in &names {
+        run_cmd("kubectl", &["delete", resource, name, "--ignore-not-found"])?;
+    }
+    Ok(names.len())
 }
 
-fn prune_image_configs_for_sources(sources: &HashSet<String>) -> Result<(), Box<dyn Error>> {
+/// Names of ImageConfigs with a `matchImages` prefix in `sources`.
+fn image_configs_for_sources(sources: &HashSet<String>) -> Result<Vec<String>, Box<dyn Error>> {
     let raw = run_cmd_output(
         "kubectl",
         &["get", "imageconfig.pkg.crossplane.io", "-o", "json"],
     )?;
     let list: KubeList<ImageConfigResource> = serde_json::from_str(&raw)?;
 
-    let mut deleted = 0usize;
+    let mut names = Vec::new();
     for item in list.items {
         let matches = item
             .spec
@@ -439,19 +799,30 @@ fn prune_image_configs_for_sources(sources: &HashSet<String>) -> Result<(), Box<
             .unwrap_or(false);
 
         if matches {
-            run_cmd(
-                "kubectl",
-                &[
-                    "delete",
-                    "imageconfig.pkg.crossplane.io",
-                    &item.metadata.name,
-                    "--ignore-not-found",
-                ],
-            )?;
-            deleted += 1;
+            names.push(item.metadata.name);
         }
     }
 
+    Ok(names)
+}
+
+pub(super) fn prune_image_configs_for_sources(
+    sources: &HashSet<String>,
+) -> Result<(), Box<dyn Error>> {
+    let names = image_configs_for_sources(sources)?;
+    for name in &names {
+        run_cmd(
+            "kubectl",
+            &[
+                "delete",
+                "imageconfig.pkg.crossplane.io",
+                name,
+                "--ignore-not-found",
+            ],
+        )?;
+    }
+
+    let deleted = names.len();
     if deleted > 0 {
         log::info!("Pruned {} orphaned ImageConfig resource(s)", deleted);
     }
@@ -459,7 +830,7 @@ fn prune_image_configs_for_sources(sources: &HashSet<String>) -> Result<(), Box<
     Ok(())
 }
 
-fn package_source(package_ref: &str) -> String {
+pub(super) fn package_source(package_ref: &str) -> String {
     let trimmed = package_ref.trim();
     if let Some((source, _)) = trimmed.split_once('@') {
         return source.to_string();
@@ -483,7 +854,9 @@ fn parse_repo_spec(repo: &str) -> Result<RepoSpec, Box<dyn Error>> {
     }
 
     let no_prefix = trimmed
-        .strip_prefix("https://github.com/")
+        .strip_prefix("git@github.com:")
+        .or_else(|| trimmed.strip_prefix("ssh://git@github.com/"))
+        .or_else(|| trimmed.strip_prefix("https://github.com/"))
         .or_else(|| trimmed.strip_prefix("http://github.com/"))
         .or_else(|| trimmed.strip_prefix("github.com/"))
         .unwrap_or(trimmed);
@@ -538,6 +911,9 @@ fn resolve_names_from_path(path: &str) -> Result<Vec<String>, Box<dyn Error>> {
                 names.insert(name);
             }
         }
+        if path.extension().map(|e| e == "xpkg").unwrap_or(false) {
+            names.extend(super::install::xpkg_configuration_name(&path)?);
+        }
     }
 
     if names.is_empty() {
@@ -578,12 +954,11 @@ fn resolve_sources_from_path(path: &str) -> Result<HashSet<String>, Box<dyn Erro
 }
 
 fn names_from_uppkg_manifest(uppkg_path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-    let manifest_bytes = read_entry_from_tar(uppkg_path, "manifest.json")?;
-    let entries: Vec<DockerSaveManifestEntry> = serde_json::from_slice(&manifest_bytes)?;
+    let archive = UppkgArchive::open(uppkg_path)?;
 
     let mut names = HashSet::new();
-    for entry in entries {
-        let Some(tags) = entry.repo_tags else {
+    for entry in archive.manifest() {
+        let Some(tags) = &entry.repo_tags else {
             continue;
         };
         for tag in tags {
@@ -603,16 +978,15 @@ fn names_from_uppkg_manifest(uppkg_path: &Path) -> Result<Vec<String>, Box<dyn E
 }
 
 fn sources_from_uppkg_manifest(uppkg_path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-    let manifest_bytes = read_entry_from_tar(uppkg_path, "manifest.json")?;
-    let entries: Vec<DockerSaveManifestEntry> = serde_json::from_slice(&manifest_bytes)?;
+    let archive = UppkgArchive::open(uppkg_path)?;
 
     let mut sources = HashSet::new();
-    for entry in entries {
-        let Some(tags) = entry.repo_tags else {
+    for entry in archive.manifest() {
+        let Some(tags) = &entry.repo_tags else {
             continue;
         };
         for tag in tags {
-            let source = package_source(&tag);
+            let source = package_source(tag);
             if !source.is_empty() {
                 sources.insert(source);
             }
@@ -624,27 +998,6 @@ fn sources_from_uppkg_manifest(uppkg_path: &Path) -> Result<Vec<String>, Box<dyn
     Ok(sources)
 }
 
-fn read_entry_from_tar(tar_path: &Path, entry_name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
-    let file = fs::File::open(tar_path)?;
-    let mut archive = Archive::new(file);
-    for entry in archive.entries()? {
-        let mut entry = entry?;
-        let path = entry.path()?.to_string_lossy().into_owned();
-        if path == entry_name {
-            let mut out = Vec::new();
-            entry.read_to_end(&mut out)?;
-            return Ok(out);
-        }
-    }
-
-    Err(format!(
-        "entry '{}' not found in tar {}",
-        entry_name,
-        tar_path.display()
-    )
-    .into())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -676,6 +1029,36 @@ mod tests {
         assert_eq!(url.repo, "aws-auto-eks-cluster");
     }
 
+    #[test]
+    fn orphaned_sources_follow_dependencies_nothing_else_needs() {
+        let lock: Vec<LockPackage> = serde_json::from_value(serde_json::json!([
+            {"kind": "Configuration", "name": "network-abc", "source": "reg/network",
+             "dependencies": [{"package": "xpkg/provider-aws-ec2"}, {"package": "xpkg/function-kcl"}]},
+            {"kind": "Configuration", "name": "eks-def", "source": "reg/eks",
+             "dependencies": [{"package": "xpkg/function-kcl"}]},
+            {"kind": "Provider", "name": "provider-aws-ec2-1", "source": "xpkg/provider-aws-ec2",
+             "dependencies": [{"package": "xpkg/provider-family-aws"}]},
+            {"kind": "Provider", "name": "provider-family-aws-1", "source": "xpkg/provider-family-aws"},
+            {"kind": "Function", "name": "function-kcl-1", "source": "xpkg/function-kcl"}
+        ]))
+        .unwrap();
+
+        let mut orphaned: Vec<String> = orphaned_sources(&lock, &["network".to_string()])
+            .into_iter()
+            .map(|s| s.source)
+            .collect();
+        orphaned.sort();
+        assert_eq!(
+            orphaned,
+            [
+                "reg/network",
+                "xpkg/provider-aws-ec2",
+                "xpkg/provider-family-aws"
+            ]
+        );
+        assert!(orphaned_sources(&lock, &["other".to_string()]).is_empty());
+    }
+
     #[test]
     fn sanitize_name_component_normalizes_name() {
         assert_eq!(sanitize_name_component("Hops_Ops"), "hops-ops");
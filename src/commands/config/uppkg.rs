@@ -0,0 +1,596 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use tar::Archive;
+
+/// Config label prefix marking a layer's role: `io.crossplane.xpkg:sha256:<digest>`.
+const XPKG_LAYER_LABEL: &str = "io.crossplane.xpkg:sha256:";
+/// Annotations naming an image in an OCI layout's index.json; containerd's
+/// holds the full reference, the OCI one may be just the tag.
+const CONTAINERD_NAME_ANNOTATION: &str = "io.containerd.image.name";
+const OCI_REF_NAME_ANNOTATION: &str = "org.opencontainers.image.ref.name";
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// One image in a `docker save` tarball's manifest.json (OCI layouts are
+/// read into the same shape, with `blobs/sha256/<hex>` entry names).
+#[derive(Debug, Deserialize)]
+pub(super) struct DockerSaveManifestEntry {
+    #[serde(rename = "Config")]
+    pub config: String,
+    #[serde(rename = "RepoTags")]
+    pub repo_tags: Option<Vec<String>>,
+    #[serde(rename = "Layers")]
+    pub layers: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DockerImageConfig {
+    config: Option<DockerImageConfigSection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DockerImageConfigSection {
+    #[serde(rename = "Labels")]
+    labels: Option<HashMap<String, String>>,
+}
+
+/// An image whose base layer was replaced by [`UppkgArchive::patched_image`].
+pub(super) struct PatchedImage {
+    /// Index of the replaced layer.
+    pub base: usize,
+    pub layer: Vec<u8>,
+    pub config: Vec<u8>,
+}
+
+/// A `.uppkg` or `.xpkg` (a `docker save` tarball or an OCI image layout,
+/// with gzipped or uncompressed layers) indexed in a single pass
+/// over its headers, so the manifest, image configs, and layers can be read
+/// by seeking straight to them instead of rescanning a multi-GB archive for
+/// every entry.
+#[derive(Debug)]
+pub(super) struct UppkgArchive {
+    path: PathBuf,
+    /// Data offset and size of every entry, by path.
+    index: HashMap<String, (u64, u64)>,
+    manifest: Vec<DockerSaveManifestEntry>,
+}
+
+impl UppkgArchive {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let mut archive = Archive::new(fs::File::open(path)?);
+        let mut index = HashMap::new();
+        for entry in archive.entries_with_seek()? {
+            let entry = entry?;
+            let name = entry.path()?.to_string_lossy().into_owned();
+            let name = name.strip_prefix("./").unwrap_or(&name).to_string();
+            index.insert(name, (entry.raw_file_position(), entry.size()));
+        }
+        let mut archive = Self {
+            path: path.to_path_buf(),
+            index,
+            manifest: Vec::new(),
+        };
+        archive.manifest = if archive.index.contains_key("manifest.json") {
+            serde_json::from_slice(&archive.read_entry("manifest.json")?)?
+        } else if archive.index.contains_key("index.json") {
+            archive.oci_manifest()?
+        } else {
+            return Err(format!(
+                "{} is neither a `docker save` tarball (manifest.json) nor an OCI image layout (index.json)",
+                path.display()
+            )
+            .into());
+        };
+        Ok(archive)
+    }
+
+    /// The images of an OCI layout: each manifest in index.json, following
+    /// nested indexes (multi-platform images) to the image for this machine
+    /// (see [`platform_manifest`]).
+    fn oci_manifest(&self) -> Result<Vec<DockerSaveManifestEntry>, Box<dyn Error>> {
+        let index: JsonValue = serde_json::from_slice(&self.read_entry("index.json")?)?;
+        let mut images = Vec::new();
+        for descriptor in index["manifests"].as_array().into_iter().flatten() {
+            let name = [CONTAINERD_NAME_ANNOTATION, OCI_REF_NAME_ANNOTATION]
+                .iter()
+                .filter_map(|key| descriptor["annotations"][key].as_str())
+                // A bare tag isn't a reference docker or a registry can use.
+                .find(|name| name.contains('/') || name.contains(':'))
+                .map(str::to_string);
+            let mut manifest: JsonValue =
+                serde_json::from_slice(&self.read_entry(&blob_path(descriptor)?)?)?;
+            while let Some(nested) = manifest["manifests"].as_array() {
+                let platform = platform_manifest(nested, super::install::docker_arch())
+                    .ok_or_else(|| format!("no image in the index in {}", self.path.display()))?;
+                manifest = serde_json::from_slice(&self.read_entry(&blob_path(platform)?)?)?;
+            }
+            images.push(DockerSaveManifestEntry {
+                config: blob_path(&manifest["config"])?,
+                repo_tags: name.map(|name| vec![name]),
+                layers: manifest["layers"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(blob_path)
+                    .collect::<Result<_, _>>()?,
+            });
+        }
+        Ok(images)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn manifest(&self) -> &[DockerSaveManifestEntry] {
+        &self.manifest
+    }
+
+    pub fn read_entry(&self, name: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+        let (reader, size) = self.entry_reader(name)?;
+        let mut out = Vec::with_capacity(size as usize);
+        reader.take(size).read_to_end(&mut out)?;
+        if out.len() as u64 != size {
+            return Err(format!("{} is truncated at '{}'", self.path.display(), name).into());
+        }
+        Ok(out)
+    }
+
+    /// Whether an entry starts like a gzip stream.
+    pub fn is_gzip(&self, name: &str) -> Result<bool, Box<dyn Error>> {
+        let (reader, size) = self.entry_reader(name)?;
+        let mut magic = Vec::with_capacity(GZIP_MAGIC.len());
+        reader
+            .take(size.min(GZIP_MAGIC.len() as u64))
+            .read_to_end(&mut magic)?;
+        Ok(magic == GZIP_MAGIC)
+    }
+
+    /// The archive positioned at an entry's data, and the entry's size.
+    pub fn entry_reader(&self, name: &str) -> Result<(fs::File, u64), Box<dyn Error>> {
+        let &(offset, size) = self
+            .index
+            .get(name)
+            .ok_or_else(|| format!("entry '{}' not found in tar {}", name, self.path.display()))?;
+        let mut file = fs::File::open(&self.path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Ok((file, size))
+    }
+
+    /// The manifest entry for `image` (or the only image, for an untagged
+    /// single-image `.xpkg`).
+    pub fn image(&self, image: &str) -> Result<&DockerSaveManifestEntry, Box<dyn Error>> {
+        // A single-image .xpkg may be untagged; its only entry is the package.
+        self.manifest
+            .iter()
+            .find(|entry| {
+                entry
+                    .repo_tags
+                    .as_ref()
+                    .is_some_and(|tags| tags.iter().any(|t| t == image))
+            })
+            .or(match self.manifest.as_slice() {
+                [only] => Some(only),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                format!(
+                    "Could not find '{}' in manifest {}",
+                    image,
+                    self.path.display()
+                )
+                .into()
+            })
+    }
+
+    /// package.yaml from the base layer of `image`.
+    pub fn package_yaml(&self, image: &str) -> Result<String, Box<dyn Error>> {
+        let entry = self.image(image)?;
+        let base_layer = &entry.layers[self.base_layer(image, entry)?];
+        self.layer_package_yaml(base_layer)?.ok_or_else(|| {
+            format!(
+                "package.yaml not found in base layer '{}' from {}",
+                base_layer,
+                self.path.display()
+            )
+            .into()
+        })
+    }
+
+    /// package.yaml from a layer, gzipped or not, read without buffering it.
+    fn layer_package_yaml(&self, layer: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let (reader, size) = self.entry_reader(layer)?;
+        let mut reader = BufReader::new(reader.take(size));
+        let gzipped = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+        let reader: Box<dyn Read> = if gzipped {
+            Box::new(GzDecoder::new(reader))
+        } else {
+            Box::new(reader)
+        };
+        for entry in Archive::new(reader).entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().into_owned();
+            if path.strip_prefix("./").unwrap_or(&path) == "package.yaml" {
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents)?;
+                return Ok(Some(contents));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Write a `docker save` tarball of `image` tagged `tag` whose base layer
+    /// holds just `package_yaml`. The other layers (examples, schemas) are
+    /// copied as they are, so `docker load` and `docker push` only handle the
+    /// new layer and config.
+    pub fn write_patched(
+        &self,
+        image: &str,
+        package_yaml: &str,
+        tag: &str,
+        out: &Path,
+    ) -> Result<(), Box<dyn Error>> {
+        let entry = self.image(image)?;
+        let patched = self.patched_image(image, package_yaml, false)?;
+        let layer_name = format!("{:x}.tar", Sha256::digest(&patched.layer));
+        let config_name = format!("{:x}.json", Sha256::digest(&patched.config));
+
+        let mut layers = entry.layers.clone();
+        layers[patched.base] = layer_name.clone();
+        let manifest = serde_json::to_vec(&serde_json::json!([{
+            "Config": config_name,
+            "RepoTags": [tag],
+            "Layers": layers,
+        }]))?;
+
+        let mut builder = tar::Builder::new(fs::File::create(out)?);
+        for (i, name) in entry.layers.iter().enumerate() {
+            if i != patched.base {
+                self.copy_entry(name, &mut builder)?;
+            }
+        }
+        append(&mut builder, &layer_name, &patched.layer)?;
+        append(&mut builder, &config_name, &patched.config)?;
+        append(&mut builder, "manifest.json", &manifest)?;
+        builder.finish()?;
+        Ok(())
+    }
+
+    /// `image` with a base layer holding just `package_yaml`, gzipped when
+    /// `compress`. A gzipped layer is pushed as it is, so the base label is
+    /// pointed at its digest; an uncompressed one is recompressed by
+    /// `docker push`, so the label is dropped instead (Crossplane reads
+    /// package.yaml from the flattened image without it).
+    pub fn patched_image(
+        &self,
+        image: &str,
+        package_yaml: &str,
+        compress: bool,
+    ) -> Result<PatchedImage, Box<dyn Error>> {
+        let entry = self.image(image)?;
+        let base = self.base_layer(image, entry)?;
+
+        let tar = single_file_tar("package.yaml", package_yaml.as_bytes())?;
+        let diff_id = format!("sha256:{:x}", Sha256::digest(&tar));
+        let layer = if compress {
+            let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+            gz.write_all(&tar)?;
+            gz.finish()?
+        } else {
+            tar
+        };
+
+        let mut config: JsonValue = serde_json::from_slice(&self.read_entry(&entry.config)?)?;
+        let diff_ids = config
+            .pointer_mut("/rootfs/diff_ids")
+            .and_then(JsonValue::as_array_mut)
+            .filter(|diff_ids| diff_ids.len() == entry.layers.len())
+            .ok_or_else(|| {
+                format!(
+                    "image config of '{}' in {} doesn't list one diff ID per layer",
+                    image,
+                    self.path.display()
+                )
+            })?;
+        diff_ids[base] = JsonValue::String(diff_id);
+        if let Some(labels) = config
+            .pointer_mut("/config/Labels")
+            .and_then(JsonValue::as_object_mut)
+        {
+            labels.retain(|key, value| {
+                !(key.starts_with(XPKG_LAYER_LABEL) && value.as_str() == Some("base"))
+            });
+            if compress {
+                labels.insert(
+                    format!("{}{:x}", XPKG_LAYER_LABEL, Sha256::digest(&layer)),
+                    JsonValue::String("base".into()),
+                );
+            }
+        }
+        Ok(PatchedImage {
+            base,
+            layer,
+            config: serde_json::to_vec(&config)?,
+        })
+    }
+
+    /// Index in `entry.layers` of the layer the image config labels `base`
+    /// (`io.crossplane.xpkg:sha256:<digest>`), else the first layer holding
+    /// package.yaml (the label names the gzipped digest, so it matches no
+    /// layer of an image saved uncompressed), else the first layer.
+    fn base_layer(
+        &self,
+        image: &str,
+        entry: &DockerSaveManifestEntry,
+    ) -> Result<usize, Box<dyn Error>> {
+        if entry.layers.is_empty() {
+            return Err(format!(
+                "Configuration image '{}' has no layers in {}",
+                image,
+                self.path.display()
+            )
+            .into());
+        }
+        let config_json = self.read_entry(&entry.config)?;
+        let labels = serde_json::from_slice::<DockerImageConfig>(&config_json)
+            .ok()
+            .and_then(|image_config| image_config.config)
+            .and_then(|c| c.labels)
+            .unwrap_or_default();
+        let labelled = labels
+            .iter()
+            .filter(|(_, value)| *value == "base")
+            .filter_map(|(key, _)| key.strip_prefix(XPKG_LAYER_LABEL))
+            .find_map(|digest| {
+                let candidates = [
+                    format!("{}.tar.gz", digest),
+                    format!("blobs/sha256/{}", digest),
+                ];
+                entry
+                    .layers
+                    .iter()
+                    .position(|layer| candidates.contains(layer))
+            });
+        if let Some(base) = labelled {
+            return Ok(base);
+        }
+        for (i, layer) in entry.layers.iter().enumerate() {
+            if self.layer_package_yaml(layer)?.is_some() {
+                return Ok(i);
+            }
+        }
+        Ok(0)
+    }
+
+    /// Stream an entry into another tarball without buffering it.
+    fn copy_entry<W: Write>(
+        &self,
+        name: &str,
+        builder: &mut tar::Builder<W>,
+    ) -> Result<(), Box<dyn Error>> {
+        let (reader, size) = self.entry_reader(name)?;
+        builder.append_data(&mut entry_header(size), name, reader.take(size))?;
+        Ok(())
+    }
+}
+
+/// The entry of a multi-platform index to load: linux on `arch`, else the
+/// first entry that isn't an attestation (platform `unknown/unknown`).
+fn platform_manifest<'a>(manifests: &'a [JsonValue], arch: &str) -> Option<&'a JsonValue> {
+    fn platform(entry: &JsonValue) -> (Option<&str>, Option<&str>) {
+        (
+            entry["platform"]["os"].as_str(),
+            entry["platform"]["architecture"].as_str(),
+        )
+    }
+    manifests
+        .iter()
+        .find(|entry| platform(entry) == (Some("linux"), Some(arch)))
+        .or_else(|| {
+            manifests
+                .iter()
+                .find(|entry| platform(entry) != (Some("unknown"), Some("unknown")))
+        })
+}
+
+/// Entry name of the blob an OCI descriptor points at.
+fn blob_path(descriptor: &JsonValue) -> Result<String, Box<dyn Error>> {
+    let digest = descriptor["digest"]
+        .as_str()
+        .ok_or("OCI descriptor without a digest")?;
+    let (algorithm, hex) = digest
+        .split_once(':')
+        .ok_or_else(|| format!("malformed digest '{}'", digest))?;
+    Ok(format!("blobs/{}/{}", algorithm, hex))
+}
+
+fn entry_header(size: u64) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(size);
+    header.set_mode(0o644);
+    header.set_cksum();
+    header
+}
+
+fn append<W: Write>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    data: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    builder.append_data(&mut entry_header(data.len() as u64), name, data)?;
+    Ok(())
+}
+
+/// An uncompressed layer holding one file.
+fn single_file_tar(name: &str, data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    append(&mut builder, name, data)?;
+    Ok(builder.into_inner()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAG: &str = "ghcr.io/hops-ops/demo:configuration";
+
+    fn write_package(path: &Path) {
+        let layer = single_file_tar("package.yaml", b"kind: Configuration\n").unwrap();
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&layer).unwrap();
+        let layer = gz.finish().unwrap();
+
+        let mut pkg = tar::Builder::new(Vec::new());
+        append(&mut pkg, "other.tar.gz", b"not the base layer").unwrap();
+        append(&mut pkg, "abc.tar.gz", &layer).unwrap();
+        append(
+            &mut pkg,
+            "config.json",
+            br#"{"config":{"Labels":{"io.crossplane.xpkg:sha256:abc":"base","io.crossplane.xpkg:sha256:other":"upbound"}},"rootfs":{"type":"layers","diff_ids":["sha256:1","sha256:2"]}}"#,
+        )
+        .unwrap();
+        append(
+            &mut pkg,
+            "manifest.json",
+            br#"[{"Config":"config.json","RepoTags":["ghcr.io/hops-ops/demo:configuration"],"Layers":["other.tar.gz","abc.tar.gz"]}]"#,
+        )
+        .unwrap();
+        fs::write(path, pkg.into_inner().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn package_yaml_reads_the_base_layer_through_the_index() {
+        let path = std::env::temp_dir().join(format!("hops-uppkg-{}.uppkg", std::process::id()));
+        write_package(&path);
+
+        let archive = UppkgArchive::open(&path).unwrap();
+        assert_eq!(archive.manifest().len(), 1);
+        assert_eq!(archive.package_yaml(TAG).unwrap(), "kind: Configuration\n");
+        assert!(archive.read_entry("missing.json").is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn oci_layouts_with_uncompressed_layers_are_read() {
+        let path = std::env::temp_dir().join(format!("hops-uppkg-oci-{}.xpkg", std::process::id()));
+        let blob = |data: &[u8]| (format!("sha256:{:x}", Sha256::digest(data)), data.to_vec());
+        let (other, other_data) = blob(&single_file_tar("other.yaml", b"x").unwrap());
+        let (base, base_data) =
+            blob(&single_file_tar("package.yaml", b"kind: Configuration\n").unwrap());
+        let (config, config_data) = blob(
+            br#"{"config":{"Labels":{"io.crossplane.xpkg:sha256:gzipped":"base"}},"rootfs":{"type":"layers","diff_ids":["sha256:1","sha256:2"]}}"#,
+        );
+        let (manifest, manifest_data) = blob(
+            serde_json::json!({
+                "config": {"digest": config},
+                "layers": [{"digest": other}, {"digest": base}],
+            })
+            .to_string()
+            .as_bytes(),
+        );
+        let (platforms, platforms_data) = blob(
+            serde_json::json!({"manifests": [{"digest": manifest}]})
+                .to_string()
+                .as_bytes(),
+        );
+        let index = serde_json::json!({"manifests": [{
+            "digest": platforms,
+            "annotations": {"org.opencontainers.image.ref.name": TAG},
+        }]});
+
+        let mut pkg = tar::Builder::new(Vec::new());
+        append(&mut pkg, "oci-layout", br#"{"imageLayoutVersion":"1.0.0"}"#).unwrap();
+        append(&mut pkg, "index.json", index.to_string().as_bytes()).unwrap();
+        for (digest, data) in [
+            (&other, &other_data),
+            (&base, &base_data),
+            (&config, &config_data),
+            (&manifest, &manifest_data),
+            (&platforms, &platforms_data),
+        ] {
+            let name = format!("blobs/sha256/{}", digest.trim_start_matches("sha256:"));
+            append(&mut pkg, &name, data).unwrap();
+        }
+        fs::write(&path, pkg.into_inner().unwrap()).unwrap();
+
+        let archive = UppkgArchive::open(&path).unwrap();
+        let [entry] = archive.manifest() else {
+            panic!("expected one image");
+        };
+        assert_eq!(entry.repo_tags.as_deref(), Some(&[TAG.to_string()][..]));
+        assert_eq!(entry.layers.len(), 2);
+        assert_eq!(archive.package_yaml(TAG).unwrap(), "kind: Configuration\n");
+        assert_eq!(
+            archive.patched_image(TAG, "kind: X\n", true).unwrap().base,
+            1
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn multi_platform_indexes_pick_this_machines_image() {
+        let index = serde_json::json!([
+            {"digest": "sha256:att", "platform": {"os": "unknown", "architecture": "unknown"}},
+            {"digest": "sha256:amd", "platform": {"os": "linux", "architecture": "amd64"}},
+            {"digest": "sha256:arm", "platform": {"os": "linux", "architecture": "arm64"}},
+        ]);
+        let manifests = index.as_array().unwrap();
+        let pick = |arch| platform_manifest(manifests, arch).map(|entry| &entry["digest"]);
+        assert_eq!(pick("arm64").unwrap(), "sha256:arm");
+        assert_eq!(pick("amd64").unwrap(), "sha256:amd");
+        assert_eq!(pick("s390x").unwrap(), "sha256:amd");
+        assert_eq!(platform_manifest(&manifests[..1], "arm64"), None);
+    }
+
+    #[test]
+    fn write_patched_replaces_only_the_base_layer() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("hops-uppkg-src-{}.uppkg", std::process::id()));
+        let out = dir.join(format!("hops-uppkg-out-{}.tar", std::process::id()));
+        write_package(&path);
+
+        let archive = UppkgArchive::open(&path).unwrap();
+        archive
+            .write_patched(TAG, "kind: Patched\n", "hops-local/patched:1", &out)
+            .unwrap();
+        let patched = UppkgArchive::open(&out).unwrap();
+        let [entry] = patched.manifest() else {
+            panic!("expected one image");
+        };
+        assert_eq!(
+            entry.repo_tags.as_deref(),
+            Some(&["hops-local/patched:1".to_string()][..])
+        );
+        assert_eq!(entry.layers[0], "other.tar.gz");
+        assert_eq!(
+            patched.read_entry("other.tar.gz").unwrap(),
+            b"not the base layer"
+        );
+
+        let layer = patched.read_entry(&entry.layers[1]).unwrap();
+        assert_eq!(
+            layer,
+            single_file_tar("package.yaml", b"kind: Patched\n").unwrap()
+        );
+        let config: JsonValue =
+            serde_json::from_slice(&patched.read_entry(&entry.config).unwrap()).unwrap();
+        assert_eq!(config["rootfs"]["diff_ids"][0], "sha256:1");
+        assert_eq!(
+            config["rootfs"]["diff_ids"][1],
+            format!("sha256:{:x}", Sha256::digest(&layer))
+        );
+        let labels = config["config"]["Labels"].as_object().unwrap();
+        assert_eq!(labels.len(), 1);
+        assert!(labels.contains_key("io.crossplane.xpkg:sha256:other"));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&out).unwrap();
+    }
+}
@@ -0,0 +1,484 @@
+use crate::commands::local::context::local_context;
+use crate::commands::local::{
+    command_exists, parse_df_kib, registry, run_cmd_output, OutputFormat, HOPS_KUBE_CONTEXT_ENV,
+};
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+use std::error::Error;
+use std::net::TcpListener;
+
+/// Minimum free space (GiB) in the home directory before warning/failing.
+/// Colima allocates a sparse 60 GiB disk that grows as images are pulled.
+const DISK_WARN_GIB: u64 = 20;
+const DISK_FAIL_GIB: u64 = 5;
+
+#[derive(Args, Debug)]
+pub struct DoctorArgs {
+    /// Output format (json includes the detected tool version matrix)
+    #[arg(long, short = 'o', value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    name: String,
+    status: CheckStatus,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Detected version of one external tool, for the `--output json` matrix.
+#[derive(Debug, Serialize)]
+struct ToolReport {
+    program: &'static str,
+    installed: bool,
+    version: Option<String>,
+    min_version: String,
+    required: bool,
+    known_issues: Vec<&'static str>,
+}
+
+#[derive(Debug, Serialize)]
+struct DoctorReport {
+    checks: Vec<Check>,
+    tools: Vec<ToolReport>,
+}
+
+/// A tool version range with a known problem. `warn` issues turn the check
+/// into a warning; the rest are recorded as notes in the version matrix.
+struct KnownIssue {
+    program: &'static str,
+    /// Inclusive lower bound
+    from: (u64, u64, u64),
+    /// Exclusive upper bound; None while no fixed release is known
+    until: Option<(u64, u64, u64)>,
+    warn: bool,
+    note: &'static str,
+}
+
+impl KnownIssue {
+    fn affects(&self, program: &str, version: (u64, u64, u64)) -> bool {
+        self.program == program
+            && version >= self.from
+            && self.until.is_none_or(|until| version < until)
+    }
+}
+
+const KNOWN_ISSUES: &[KnownIssue] = &[KnownIssue {
+    program: "up",
+    from: (0, 0, 0),
+    until: None,
+    warn: false,
+    note: "`up project build` can emit render function images with an empty rootfs.type; `config install` rebuilds them with `docker build` to work around it",
+}];
+
+/// An external tool hops shells out to, with the oldest supported version.
+struct Tool {
+    program: &'static str,
+    version_args: &'static [&'static str],
+    min_version: (u64, u64, u64),
+    /// Missing required tools fail; missing optional tools only warn.
+    required: bool,
+    install_hint: &'static str,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        program: "colima",
+        version_args: &["version"],
+        min_version: (0, 6, 0),
+        required: true,
+        install_hint: "run `hops local install` (or `brew install colima`)",
+    },
+    Tool {
+        program: "docker",
+        version_args: &["--version"],
+        min_version: (24, 0, 0),
+        required: true,
+        install_hint: "run `brew install docker`",
+    },
+    Tool {
+        program: "kubectl",
+        version_args: &["version", "--client"],
+        min_version: (1, 28, 0),
+        required: true,
+        install_hint: "run `brew install kubectl`",
+    },
+    Tool {
+        program: "helm",
+        version_args: &["version", "--short"],
+        min_version: (3, 12, 0),
+        required: true,
+        install_hint: "run `brew install helm`",
+    },
+    Tool {
+        program: "up",
+        version_args: &["version"],
+        min_version: (0, 31, 0),
+        required: false,
+        install_hint:
+            "install the Upbound CLI: `brew install upbound/tap/up` (needed by `config install`)",
+    },
+    Tool {
+        program: "kubefwd",
+        version_args: &["version"],
+        min_version: (1, 22, 0),
+        required: false,
+        install_hint:
+            "run `brew install txn2/tap/kubefwd` (needed to reach cluster services by name)",
+    },
+    Tool {
+        program: "aws",
+        version_args: &["--version"],
+        min_version: (2, 0, 0),
+        required: false,
+        install_hint: "install AWS CLI v2: `brew install awscli` (needed by `local aws`)",
+    },
+];
+
+pub fn run(args: &DoctorArgs) -> Result<(), Box<dyn Error>> {
+    let (mut checks, tools): (Vec<Check>, Vec<ToolReport>) = TOOLS.iter().map(check_tool).unzip();
+    checks.push(check_disk_space());
+    checks.push(check_registry_port());
+    if command_exists("kubectl") {
+        checks.push(check_kube_context());
+    }
+
+    let report = DoctorReport { checks, tools };
+    match args.output {
+        OutputFormat::Text => {
+            for check in &report.checks {
+                print_check(check);
+            }
+            println!();
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+    }
+    let checks = report.checks;
+
+    let failed = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Fail)
+        .count();
+    let warned = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Warn)
+        .count();
+    if failed > 0 {
+        return Err(format!(
+            "doctor found {} failing check(s) and {} warning(s)",
+            failed, warned
+        )
+        .into());
+    }
+    if warned > 0 {
+        log::warn!("All required checks passed with {} warning(s)", warned);
+    } else {
+        crate::logging::success("All checks passed");
+    }
+    Ok(())
+}
+
+fn print_check(check: &Check) {
+    let marker = match check.status {
+        CheckStatus::Pass => "PASS".green().bold(),
+        CheckStatus::Warn => "WARN".yellow().bold(),
+        CheckStatus::Fail => "FAIL".red().bold(),
+    };
+    println!("{} {:<14} {}", marker, check.name, check.detail);
+    if let Some(hint) = &check.hint {
+        println!("     {:<14} {} {}", "", "hint:".dimmed(), hint);
+    }
+}
+
+fn check_tool(tool: &Tool) -> (Check, ToolReport) {
+    let mut report = ToolReport {
+        program: tool.program,
+        installed: command_exists(tool.program),
+        version: None,
+        min_version: format_version(tool.min_version),
+        required: tool.required,
+        known_issues: Vec::new(),
+    };
+    let check = check_tool_version(tool, &mut report);
+    (check, report)
+}
+
+fn check_tool_version(tool: &Tool, report: &mut ToolReport) -> Check {
+    let missing = |detail: &str| {
+        if tool.required {
+            Check::fail(tool.program, detail, tool.install_hint)
+        } else {
+            Check::warn(tool.program, detail, tool.install_hint)
+        }
+    };
+
+    if !report.installed {
+        return missing("not found on PATH");
+    }
+
+    let output = match run_cmd_output(tool.program, tool.version_args) {
+        Ok(output) => output,
+        Err(err) => {
+            return Check::warn(
+                tool.program,
+                format!(
+                    "installed, but version check failed: {}",
+                    first_line(&err.to_string())
+                ),
+                format!(
+                    "run `{} {}` manually to inspect",
+                    tool.program,
+                    tool.version_args.join(" ")
+                ),
+            )
+        }
+    };
+
+    let parsed = parse_version(&output);
+    report.version = parsed.map(format_version);
+    let Some(version) = parsed else {
+        return Check::warn(
+            tool.program,
+            format!(
+                "installed, unrecognized version output: {}",
+                first_line(&output)
+            ),
+            format!("supported: >= {}", format_version(tool.min_version)),
+        );
+    };
+
+    if version < tool.min_version {
+        return missing(&format!(
+            "{} is older than the supported minimum {}",
+            format_version(version),
+            format_version(tool.min_version)
+        ));
+    }
+
+    let issues: Vec<&KnownIssue> = KNOWN_ISSUES
+        .iter()
+        .filter(|issue| issue.affects(tool.program, version))
+        .collect();
+    report.known_issues = issues.iter().map(|issue| issue.note).collect();
+    if let Some(issue) = issues.iter().find(|issue| issue.warn) {
+        return Check::warn(
+            tool.program,
+            format!("{} has a known issue", format_version(version)),
+            issue.note,
+        );
+    }
+    let mut check = Check::pass(tool.program, format_version(version));
+    check.hint = issues.first().map(|issue| format!("note: {}", issue.note));
+    check
+}
+
+/// Find the first `major.minor[.patch]` version in command output, e.g.
+/// `v1.31.0` in "Client Version: v1.31.0" or `2.15.0` in "aws-cli/2.15.0".
+fn parse_version(output: &str) -> Option<(u64, u64, u64)> {
+    output
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .filter(|token| token.contains('.'))
+        .find_map(|token| {
+            let mut parts = token.split('.').filter(|p| !p.is_empty());
+            let major = parts.next()?.parse().ok()?;
+            let minor = parts.next()?.parse().ok()?;
+            let patch = parts.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+            Some((major, minor, patch))
+        })
+}
+
+fn format_version((major, minor, patch): (u64, u64, u64)) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+fn check_disk_space() -> Check {
+    const NAME: &str = "disk space";
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
+    let available = run_cmd_output("df", &["-Pk", &home])
+        .ok()
+        .and_then(|output| parse_df_kib(&output))
+        .map(|usage| usage.available_kib);
+
+    let Some(available_kib) = available else {
+        return Check::warn(
+            NAME,
+            format!("could not determine free space for {}", home),
+            format!("run `df -h {}` manually", home),
+        );
+    };
+
+    let available_gib = available_kib / (1024 * 1024);
+    let detail = format!("{} GiB free in {}", available_gib, home);
+    if available_gib < DISK_FAIL_GIB {
+        Check::fail(
+            NAME,
+            detail,
+            "free up disk space; the Colima VM disk grows up to 60 GiB",
+        )
+    } else if available_gib < DISK_WARN_GIB {
+        Check::warn(
+            NAME,
+            detail,
+            "the Colima VM disk grows up to 60 GiB as images are pulled",
+        )
+    } else {
+        Check::pass(NAME, detail)
+    }
+}
+
+fn check_registry_port() -> Check {
+    let port = registry::node_port();
+    let name = format!("port {}", port);
+    if TcpListener::bind(("127.0.0.1", port)).is_ok() {
+        return Check::pass(&name, "free for the local package registry");
+    }
+    if registry::catalog().is_ok() {
+        return Check::pass(&name, "in use by the hops package registry");
+    }
+    Check::fail(
+        &name,
+        "in use by another process",
+        format!(
+            "find it with `lsof -nP -iTCP:{} -sTCP:LISTEN` and stop it, or set `registry.node-port` in ~/.hops/config.yaml; `hops local start` exposes the registry on this port",
+            port
+        ),
+    )
+}
+
+fn check_kube_context() -> Check {
+    const NAME: &str = "kube context";
+    let contexts = run_cmd_output("kubectl", &["config", "get-contexts", "-o", "name"])
+        .map(|output| output.lines().map(str::to_string).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    if let Ok(pinned) = std::env::var(HOPS_KUBE_CONTEXT_ENV) {
+        if !pinned.is_empty() {
+            return if contexts.contains(&pinned) {
+                Check::pass(NAME, format!("{}={} exists", HOPS_KUBE_CONTEXT_ENV, pinned))
+            } else {
+                Check::fail(
+                    NAME,
+                    format!(
+                        "{}={} is not in your kubeconfig",
+                        HOPS_KUBE_CONTEXT_ENV, pinned
+                    ),
+                    format!(
+                        "unset {} or point it at one of: {}",
+                        HOPS_KUBE_CONTEXT_ENV,
+                        contexts.join(", ")
+                    ),
+                )
+            };
+        }
+    }
+
+    let local = local_context();
+    match run_cmd_output("kubectl", &["config", "current-context"]) {
+        Ok(current) if current.trim() == local => {
+            Check::pass(NAME, format!("current context is {}", local))
+        }
+        Ok(current) => Check::warn(
+            NAME,
+            format!(
+                "current context is {}; hops commands act on the current context",
+                current.trim()
+            ),
+            format!(
+                "run `kubectl config use-context {}` or set {}",
+                local, HOPS_KUBE_CONTEXT_ENV
+            ),
+        ),
+        Err(_) if contexts.contains(&local) => Check::warn(
+            NAME,
+            "no current context set",
+            format!("run `kubectl config use-context {}`", local),
+        ),
+        Err(_) => Check::warn(
+            NAME,
+            "no kube context configured yet",
+            "run `hops local start` to create the colima context",
+        ),
+    }
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_issue_matches_program_and_range() {
+        let issue = KnownIssue {
+            program: "colima",
+            from: (0, 8, 0),
+            until: Some((0, 8, 2)),
+            warn: true,
+            note: "",
+        };
+        assert!(issue.affects("colima", (0, 8, 1)));
+        assert!(!issue.affects("colima", (0, 8, 2)));
+        assert!(!issue.affects("colima", (0, 7, 9)));
+        assert!(!issue.affects("kubectl", (0, 8, 1)));
+    }
+
+    #[test]
+    fn parse_version_handles_common_tool_formats() {
+        assert_eq!(
+            parse_version("colima version 0.8.1\ngit commit: abc"),
+            Some((0, 8, 1))
+        );
+        assert_eq!(
+            parse_version("Client Version: v1.31.0\nKustomize Version: v5.4.2"),
+            Some((1, 31, 0))
+        );
+        assert_eq!(parse_version("v3.16.2+g13654a5"), Some((3, 16, 2)));
+        assert_eq!(
+            parse_version("aws-cli/2.15.0 Python/3.11.6 Darwin/23.3.0"),
+            Some((2, 15, 0))
+        );
+        assert_eq!(parse_version("kubefwd version 1.22"), Some((1, 22, 0)));
+        assert_eq!(parse_version("no version here"), None);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::commands::config::{crossplane_package_root, resolve_builder, Builder};
+use clap::{Args, ValueEnum};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HOPS_REPO: &str = "hops-ops/hops-cli";
+
+#[derive(Args, Debug)]
+pub struct CiArgs {
+    /// CI system to generate for
+    #[arg(long, value_enum, default_value_t = CiProvider::GithubActions)]
+    pub provider: CiProvider,
+
+    /// Path to the XRD project directory (defaults to current directory)
+    #[arg(long, default_value = ".")]
+    pub path: PathBuf,
+
+    /// Workflow file to write, relative to the project
+    #[arg(long, default_value = ".github/workflows/hops.yaml")]
+    pub output: PathBuf,
+
+    /// Also publish the package to ghcr.io when a v* tag is pushed
+    #[arg(long)]
+    pub publish: bool,
+
+    /// Branch whose pushes run the workflow (pull requests always do)
+    #[arg(long, default_value = "main")]
+    pub branch: String,
+
+    /// hops release to install in CI, e.g. v1.4.0 (defaults to the latest)
+    #[arg(long)]
+    pub hops_version: Option<String>,
+
+    /// Tool used to build the package (auto: `up` when upbound.yaml exists,
+    /// `crossplane` when crossplane.yaml does)
+    #[arg(long, value_enum, default_value_t = Builder::Auto)]
+    pub builder: Builder,
+
+    /// Overwrite the workflow file if it exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum CiProvider {
+    /// GitHub Actions workflow
+    GithubActions,
+}
+
+/// What the generated workflow runs, resolved from the project.
+struct Workflow<'a> {
+    branch: &'a str,
+    hops_version: Option<&'a str>,
+    builder: Builder,
+    /// Directory holding crossplane.yaml, relative to the project.
+    package_root: String,
+    has_examples: bool,
+    has_tests: bool,
+    publish: bool,
+}
+
+pub fn run(args: &CiArgs) -> Result<(), Box<dyn Error>> {
+    let builder = resolve_builder(&args.path, args.builder)?;
+    let has_tests = args.path.join("tests").is_dir();
+    if !has_tests {
+        log::warn!(
+            "{} has no tests/ directory; the workflow skips `hops test` until you regenerate it",
+            args.path.display()
+        );
+    }
+    let package_root = match crossplane_package_root(&args.path) {
+        Some(root) if root == args.path.join("package") => "package".to_string(),
+        _ => ".".to_string(),
+    };
+    let workflow = Workflow {
+        branch: &args.branch,
+        hops_version: args.hops_version.as_deref(),
+        builder,
+        package_root,
+        has_examples: args.path.join("examples").is_dir(),
+        has_tests,
+        publish: args.publish,
+    };
+    let contents = match args.provider {
+        CiProvider::GithubActions => github_actions(&workflow),
+    };
+
+    let output = args.path.join(&args.output);
+    if output.exists() && !args.force {
+        return Err(format!(
+            "{} already exists; pass --force to overwrite it",
+            output.display()
+        )
+        .into());
+    }
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&output, contents)?;
+    crate::logging::success(&format!("Wrote {}", display_relative(&output)));
+    Ok(())
+}
+
+fn github_actions(workflow: &Workflow) -> String {
+    let mut yaml = format!(
+        r#"# Generated by `hops generate ci`; runs the same commands as local development.
+name: Package CI
+
+on:
+  pull_request:
+  push:
+    branches:
+      - {branch}
+{tags}
+jobs:
+  test:
+    runs-on: ubuntu-latest
+    steps:
+      - uses: actions/checkout@v4
+{install}      - name: Validate
+        run: hops validate
+"#,
+        branch = workflow.branch,
+        tags = if workflow.publish {
+            "    tags:\n      - \"v*.*.*\"\n"
+        } else {
+            ""
+        },
+        install = install_steps(workflow),
+    );
+    if workflow.has_tests {
+        yaml.push_str("      - name: Test\n        run: hops test\n");
+    }
+    yaml.push_str(&format!(
+        "      - name: Build\n        run: {}\n",
+        build_command(workflow)
+    ));
+
+    if workflow.publish {
+        yaml.push_str(&format!(
+            r#"
+  publish:
+    needs: test
+    if: startsWith(github.ref, 'refs/tags/v')
+    runs-on: ubuntu-latest
+    permissions:
+      contents: read
+      packages: write
+    steps:
+      - uses: actions/checkout@v4
+{install}      - uses: docker/login-action@v3
+        with:
+          registry: ghcr.io
+          username: ${{{{ github.actor }}}}
+          password: ${{{{ secrets.GITHUB_TOKEN }}}}
+      - name: Build
+        run: {build}
+      - name: Publish
+        run: {push}
+"#,
+            install = builder_install_step(workflow.builder),
+            build = build_command(workflow),
+            push = push_command(workflow.builder),
+        ));
+    }
+    yaml
+}
+
+const INSTALL_CROSSPLANE: &str = r#"      - name: Install crossplane CLI
+        run: |
+          curl -sL https://raw.githubusercontent.com/crossplane/crossplane/main/install.sh | sh
+          sudo mv crossplane /usr/local/bin/
+"#;
+
+const INSTALL_UP: &str = r#"      - name: Install up
+        run: |
+          curl -sL https://cli.upbound.io | sh
+          sudo mv up /usr/local/bin/
+"#;
+
+/// hops, plus the CLIs `hops test` (crossplane, for `crossplane render`)
+/// and the build need.
+fn install_steps(workflow: &Workflow) -> String {
+    let tag = workflow
+        .hops_version
+        .map(|version| format!(" --tag {}", version))
+        .unwrap_or_default();
+    let mut steps = format!(
+        r#"      - name: Install hops
+        run: |
+          curl --silent --location https://raw.githubusercontent.com/houseabsolute/ubi/master/bootstrap/bootstrap-ubi.sh | TARGET="$HOME/.ubi/bin" sh
+          "$HOME/.ubi/bin/ubi" --project {}{} --in "$HOME/.ubi/bin" --rename-exe hops
+          echo "$HOME/.ubi/bin" >> "$GITHUB_PATH"
+"#,
+        HOPS_REPO, tag
+    );
+    steps.push_str(INSTALL_CROSSPLANE);
+    if workflow.builder != Builder::Crossplane {
+        steps.push_str(INSTALL_UP);
+    }
+    steps
+}
+
+fn builder_install_step(builder: Builder) -> &'static str {
+    match builder {
+        Builder::Crossplane => INSTALL_CROSSPLANE,
+        Builder::Up | Builder::Auto => INSTALL_UP,
+    }
+}
+
+/// The build `config install` runs for this builder.
+fn build_command(workflow: &Workflow) -> String {
+    match workflow.builder {
+        Builder::Crossplane => {
+            let mut command = format!(
+                "crossplane xpkg build --package-root={} --package-file=_output/package.xpkg",
+                workflow.package_root
+            );
+            if workflow.has_examples {
+                command.push_str(" --examples-root=examples");
+            }
+            command
+        }
+        Builder::Up | Builder::Auto => "up project build".to_string(),
+    }
+}
+
+fn push_command(builder: Builder) -> &'static str {
+    match builder {
+        Builder::Crossplane => {
+            "crossplane xpkg push -f _output/package.xpkg \"ghcr.io/${GITHUB_REPOSITORY}:${GITHUB_REF_NAME}\""
+        }
+        Builder::Up | Builder::Auto => "up project push --tag \"${GITHUB_REF_NAME}\"",
+    }
+}
+
+fn display_relative(path: &Path) -> String {
+    path.strip_prefix(".").unwrap_or(path).display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github_actions_workflow_follows_builder_and_publish() {
+        let mut workflow = Workflow {
+            branch: "main",
+            hops_version: Some("v1.2.0"),
+            builder: Builder::Up,
+            package_root: ".".to_string(),
+            has_examples: true,
+            has_tests: true,
+            publish: false,
+        };
+        let yaml = github_actions(&workflow);
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        let steps = parsed["jobs"]["test"]["steps"].as_sequence().unwrap();
+        let runs: Vec<&str> = steps
+            .iter()
+            .filter_map(|step| step["run"].as_str())
+            .collect();
+        assert!(runs.contains(&"hops test"));
+        assert!(runs.contains(&"up project build"));
+        assert!(runs.iter().any(|run| run.contains("--tag v1.2.0")));
+        assert!(parsed["jobs"]["publish"].is_null());
+        assert!(parsed["on"]["push"]["tags"].is_null());
+
+        workflow.builder = Builder::Crossplane;
+        workflow.package_root = "package".to_string();
+        workflow.has_tests = false;
+        workflow.publish = true;
+        let yaml = github_actions(&workflow);
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert!(!yaml.contains("hops test"));
+        assert!(!yaml.contains("cli.upbound.io"));
+        assert!(yaml.contains("--package-root=package"));
+        assert_eq!(
+            parsed["jobs"]["publish"]["steps"][2]["with"]["password"],
+            "${{ secrets.GITHUB_TOKEN }}"
+        );
+        assert!(parsed["jobs"]["publish"]["steps"][4]["run"]
+            .as_str()
+            .unwrap()
+            .starts_with("crossplane xpkg push"));
+    }
+}
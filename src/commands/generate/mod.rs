@@ -0,0 +1,22 @@
+mod ci;
+
+use clap::{Args, Subcommand};
+use std::error::Error;
+
+#[derive(Args, Debug)]
+pub struct GenerateArgs {
+    #[command(subcommand)]
+    pub command: GenerateCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GenerateCommands {
+    /// Write a CI workflow that validates, tests, builds, and publishes the package
+    Ci(ci::CiArgs),
+}
+
+pub fn run(args: &GenerateArgs) -> Result<(), Box<dyn Error>> {
+    match &args.command {
+        GenerateCommands::Ci(ci_args) => ci::run(ci_args),
+    }
+}
@@ -0,0 +1,135 @@
+use super::{kubectl_apply_stdin, registry, registry_tls, run_cmd};
+use clap::{Args, Subcommand};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::error::Error;
+use std::time::Duration;
+
+const REGISTRY_UI_MANIFEST: &str = include_str!("../../../bootstrap/addons/registry-ui.yaml");
+const REGISTRY_UI_NODE_PORT: u16 = 30580;
+const NAMESPACE: &str = "crossplane-system";
+
+#[derive(Args, Debug)]
+pub struct AddonArgs {
+    #[command(subcommand)]
+    pub command: AddonCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AddonCommands {
+    /// Deploy a web UI for browsing the in-cluster package registry
+    RegistryUi(RegistryUiArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct RegistryUiArgs {
+    /// Remove the UI instead of deploying it
+    #[arg(long)]
+    pub remove: bool,
+}
+
+pub fn run(args: &AddonArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local addon")?;
+    match &args.command {
+        AddonCommands::RegistryUi(ui_args) => registry_ui(ui_args),
+    }
+}
+
+fn registry_ui(args: &RegistryUiArgs) -> Result<(), Box<dyn Error>> {
+    if args.remove {
+        run_cmd(
+            "kubectl",
+            &[
+                "delete",
+                "deployment/registry-ui",
+                "service/registry-ui",
+                "-n",
+                NAMESPACE,
+                "--ignore-not-found",
+            ],
+        )?;
+        crate::logging::success("Registry UI removed");
+        return Ok(());
+    }
+
+    log::info!("Deploying registry UI...");
+    let auth = registry::credentials().map(|credentials| credentials.basic_auth());
+    kubectl_apply_stdin(&registry_ui_manifest(
+        registry_tls::enabled(),
+        auth.as_deref(),
+    )?)?;
+    run_cmd(
+        "kubectl",
+        &[
+            "rollout",
+            "status",
+            "deployment/registry-ui",
+            "-n",
+            NAMESPACE,
+            "--timeout=120s",
+        ],
+    )?;
+
+    let url = format!("http://localhost:{}", REGISTRY_UI_NODE_PORT);
+    let reachable = ureq::get(&url)
+        .timeout(Duration::from_secs(5))
+        .call()
+        .is_ok();
+    if !reachable {
+        log::warn!(
+            "{} is not answering yet; if NodePorts aren't forwarded to this machine, run `hops local forward start -n {} --service registry-ui`",
+            url,
+            NAMESPACE
+        );
+    }
+    crate::logging::success(&format!("Registry UI is available at {}", url));
+    Ok(())
+}
+
+/// The UI manifest, proxying to the registry's plain-HTTP port in TLS mode
+/// and sending the registry credentials when auth is enabled.
+fn registry_ui_manifest(tls: bool, auth: Option<&str>) -> Result<String, Box<dyn Error>> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(REGISTRY_UI_MANIFEST) {
+        let mut object = JsonValue::deserialize(document)?;
+        if object.get("kind").and_then(JsonValue::as_str) == Some("Deployment") {
+            let env = object
+                .pointer_mut("/spec/template/spec/containers/0/env")
+                .and_then(JsonValue::as_array_mut)
+                .ok_or("registry UI manifest has no container env")?;
+            if tls {
+                for var in env.iter_mut() {
+                    if var["name"] == "NGINX_PROXY_PASS_URL" {
+                        var["value"] = json!(format!(
+                            "http://registry.{}.svc.cluster.local:{}",
+                            NAMESPACE,
+                            registry_tls::HTTP_PORT
+                        ));
+                    }
+                }
+            }
+            if let Some(auth) = auth {
+                env.push(json!({"name": "NGINX_PROXY_HEADER_Authorization", "value": auth}));
+            }
+        }
+        documents.push(serde_yaml::to_string(&object)?);
+    }
+    Ok(documents.join("---\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_ui_manifest_follows_tls_and_auth() {
+        assert!(registry_ui_manifest(false, None)
+            .unwrap()
+            .contains("http://registry.crossplane-system.svc.cluster.local:5000"));
+
+        let manifest = registry_ui_manifest(true, Some("Basic aG9wczpzZWNyZXQ=")).unwrap();
+        assert!(manifest.contains("http://registry.crossplane-system.svc.cluster.local:5001"));
+        assert!(manifest.contains("NGINX_PROXY_HEADER_Authorization"));
+        assert!(manifest.contains(&format!("nodePort: {}", REGISTRY_UI_NODE_PORT)));
+    }
+}
@@ -0,0 +1,210 @@
+use super::process::state_path;
+use super::{run_cmd, run_cmd_output};
+use clap::{Args, Subcommand};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const LAUNCHD_LABEL: &str = "io.hops.local";
+const SYSTEMD_UNIT: &str = "hops-local.service";
+const LOG_FILE: &str = "autostart.log";
+
+#[derive(Args, Debug)]
+pub struct AutostartArgs {
+    #[command(subcommand)]
+    pub command: AutostartCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AutostartCommands {
+    /// Run `hops local start --resume` at login (launchd on macOS, a systemd
+    /// user unit on Linux)
+    Enable(EnableArgs),
+    /// Remove the login item
+    Disable,
+}
+
+#[derive(Args, Debug)]
+pub struct EnableArgs {
+    /// Also forward services in this namespace with `hops local forward`
+    /// once the cluster is up (repeatable)
+    #[arg(long, short = 'n')]
+    pub forward_namespace: Vec<String>,
+}
+
+pub fn run(args: &AutostartArgs) -> Result<(), Box<dyn Error>> {
+    match &args.command {
+        AutostartCommands::Enable(enable_args) => enable(enable_args),
+        AutostartCommands::Disable => disable(),
+    }
+}
+
+fn enable(args: &EnableArgs) -> Result<(), Box<dyn Error>> {
+    let exe = std::env::current_exe()?;
+    let command = login_command(&exe, &args.forward_namespace);
+    // Login sessions get a minimal PATH; keep the one colima, kubectl, and
+    // helm were found on.
+    let path_env = std::env::var("PATH").unwrap_or_default();
+    let log_path = state_path(LOG_FILE)?;
+    if let Some(parent) = log_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let unit_path = unit_path()?;
+    if let Some(parent) = unit_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if cfg!(target_os = "macos") {
+        fs::write(
+            &unit_path,
+            launchd_plist(&command, &path_env, &log_path.display().to_string()),
+        )?;
+    } else {
+        fs::write(
+            &unit_path,
+            systemd_unit(&command, &path_env, &log_path.display().to_string()),
+        )?;
+        run_cmd("systemctl", &["--user", "daemon-reload"])?;
+        run_cmd("systemctl", &["--user", "enable", SYSTEMD_UNIT])?;
+    }
+    crate::logging::success(&format!(
+        "The local environment will start at login ({}; log {})",
+        unit_path.display(),
+        log_path.display()
+    ));
+    Ok(())
+}
+
+fn disable() -> Result<(), Box<dyn Error>> {
+    let unit_path = unit_path()?;
+    if !unit_path.exists() {
+        log::info!("Autostart is not enabled");
+        return Ok(());
+    }
+    if cfg!(target_os = "macos") {
+        // Only loaded in sessions that started after `enable`.
+        let _ = run_cmd_output("launchctl", &["remove", LAUNCHD_LABEL]);
+        fs::remove_file(&unit_path)?;
+    } else {
+        let _ = run_cmd_output("systemctl", &["--user", "disable", SYSTEMD_UNIT]);
+        fs::remove_file(&unit_path)?;
+        run_cmd("systemctl", &["--user", "daemon-reload"])?;
+    }
+    crate::logging::success(&format!("Removed {}", unit_path.display()));
+    Ok(())
+}
+
+fn unit_path() -> Result<PathBuf, Box<dyn Error>> {
+    let home = PathBuf::from(std::env::var("HOME").map_err(|_| "HOME is not set")?);
+    Ok(if cfg!(target_os = "macos") {
+        home.join("Library/LaunchAgents")
+            .join(format!("{}.plist", LAUNCHD_LABEL))
+    } else {
+        home.join(".config/systemd/user").join(SYSTEMD_UNIT)
+    })
+}
+
+/// Shell command run at login. `--resume` skips the phases that are still
+/// in place and restarts the VM, so a login after a clean stop is quick.
+fn login_command(exe: &Path, forward_namespaces: &[String]) -> String {
+    let exe = shell_quote(&exe.display().to_string());
+    let mut command = format!("{} local start --resume", exe);
+    if !forward_namespaces.is_empty() {
+        command.push_str(&format!(" && {} local forward start", exe));
+        for namespace in forward_namespaces {
+            command.push_str(&format!(" -n {}", shell_quote(namespace)));
+        }
+    }
+    command
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn launchd_plist(command: &str, path_env: &str, log_path: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{label}</string>
+  <key>ProgramArguments</key>
+  <array>
+    <string>/bin/sh</string>
+    <string>-c</string>
+    <string>{command}</string>
+  </array>
+  <key>EnvironmentVariables</key>
+  <dict>
+    <key>PATH</key>
+    <string>{path}</string>
+  </dict>
+  <key>RunAtLoad</key>
+  <true/>
+  <key>StandardOutPath</key>
+  <string>{log}</string>
+  <key>StandardErrorPath</key>
+  <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = LAUNCHD_LABEL,
+        command = xml_escape(command),
+        path = xml_escape(path_env),
+        log = xml_escape(log_path),
+    )
+}
+
+fn systemd_unit(command: &str, path_env: &str, log_path: &str) -> String {
+    // systemd expands `%` specifiers and `$` variables in these lines.
+    let escape = |value: &str| value.replace('%', "%%").replace('$', "$$");
+    format!(
+        "[Unit]
+Description=hops local environment
+After=network-online.target
+
+[Service]
+Type=oneshot
+Environment=\"PATH={path}\"
+ExecStart=/bin/sh -c \"{command}\"
+StandardOutput=append:{log}
+StandardError=append:{log}
+
+[Install]
+WantedBy=default.target
+",
+        path = escape(path_env),
+        command = escape(command).replace('\\', "\\\\").replace('"', "\\\""),
+        log = escape(log_path),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn login_command_resumes_start_and_forwards() {
+        let command = login_command(
+            Path::new("/Users/me/bin/hops"),
+            &["default".to_string(), "apps".to_string()],
+        );
+        assert_eq!(
+            command,
+            "'/Users/me/bin/hops' local start --resume && '/Users/me/bin/hops' local forward start -n 'default' -n 'apps'"
+        );
+        let plist = launchd_plist(&command, "/opt/homebrew/bin:/usr/bin", "/tmp/a.log");
+        assert!(plist.contains("local start --resume &amp;&amp;"));
+        let unit = systemd_unit(&command, "/usr/bin", "/tmp/a.log");
+        assert!(unit.contains("ExecStart=/bin/sh -c \"'/Users/me/bin/hops' local start --resume"));
+    }
+}
@@ -1,22 +1,106 @@
-use super::{kubectl_apply_stdin, run_cmd, run_cmd_output};
+use super::conditions::{wait_for_all, ReadyTarget};
+use super::process::{
+    process_alive, read_state, remove_state, spawn_detached, state_path, terminate_process_group,
+    write_state,
+};
+use super::{
+    command_exists, kubectl_apply_stdin, lock, run_cmd, run_cmd_output, run_cmd_output_secret,
+};
 use clap::Args;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::io::{self, IsTerminal, Write};
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 const DEFAULT_PROVIDER_PACKAGE: &str =
     "xpkg.crossplane.io/crossplane-contrib/provider-family-aws:v2.4.0";
 const DEFAULT_PROVIDER_NAME: &str = "crossplane-contrib-provider-family-aws";
 const PROVIDER_CONFIG_CRD: &str = "providerconfigs.aws.m.upbound.io";
 
+const REFRESH_STATE_FILE: &str = "aws-refresh.json";
+const REFRESH_LOG_FILE: &str = "aws-refresh.log";
+/// Refresh this long before the exported credentials expire.
+const REFRESH_MARGIN: Duration = Duration::from_secs(10 * 60);
+/// Used when `export-credentials` reports no expiration.
+const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_secs(45 * 60);
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+/// Retry delay after a failed refresh (e.g. SSO session expired).
+const RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Wait before trying again while another hops command holds the state lock.
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
 #[derive(Args, Debug)]
 pub struct AwsArgs {
-    /// AWS CLI profile to source credentials from
-    /// (falls back to AWS_PROFILE/AWS_DEFAULT_PROFILE, then prompts)
-    #[arg(long, short = 'p')]
-    pub profile: Option<String>,
+    /// AWS CLI profile to source credentials from, optionally mapped to a
+    /// ProviderConfig name as PROFILE=PROVIDERCONFIG (repeatable; falls back
+    /// to AWS_PROFILE/AWS_DEFAULT_PROFILE, then prompts)
+    #[arg(long, short = 'p', value_name = "PROFILE[=PROVIDERCONFIG]")]
+    pub profile: Vec<String>,
+
+    /// YAML list of profiles to configure, each with `profile` and optional
+    /// `provider-config` and `secret-name`
+    #[arg(long)]
+    pub profiles_file: Option<PathBuf>,
+
+    /// Assume this IAM role with the profile's credentials (sts assume-role)
+    /// and store the role's temporary credentials instead
+    #[arg(long)]
+    pub role_arn: Option<String>,
+
+    /// External ID required by the role's trust policy
+    #[arg(long, requires = "role_arn")]
+    pub external_id: Option<String>,
+
+    /// MFA device ARN for the role (defaults to the profile's `mfa_serial`)
+    #[arg(long, requires = "role_arn")]
+    pub mfa_serial: Option<String>,
+
+    /// Current MFA code; prompted for when omitted in an interactive terminal
+    #[arg(long, requires = "role_arn")]
+    pub mfa_token: Option<String>,
+
+    /// Install LocalStack in the cluster and point the ProviderConfig at it
+    /// with dummy credentials instead of using an AWS profile
+    #[arg(long, conflicts_with_all = ["profile", "profiles_file", "role_arn", "daemon"])]
+    pub localstack: bool,
+
+    /// Authenticate the provider with this role via web identity
+    /// (AssumeRoleWithWebIdentity) instead of writing access keys
+    #[arg(
+        long,
+        value_name = "ROLE_ARN",
+        conflicts_with_all = ["profile", "profiles_file", "role_arn", "daemon", "localstack"]
+    )]
+    pub web_identity_role_arn: Option<String>,
+
+    /// OIDC token to use with --web-identity-role-arn, stored in the Secret
+    /// (default: a projected service account token with audience
+    /// sts.amazonaws.com; the cluster's issuer must be trusted by IAM)
+    #[arg(long, requires = "web_identity_role_arn")]
+    pub web_identity_token_file: Option<PathBuf>,
+
+    /// Source credentials from this command instead of the AWS CLI; it must
+    /// print credential_process JSON (run with `sh -c`)
+    #[arg(
+        long,
+        value_name = "CMD",
+        conflicts_with_all = ["profile", "profiles_file", "role_arn", "localstack", "web_identity_role_arn", "aws_vault"]
+    )]
+    pub credential_process: Option<String>,
+
+    /// Source the profile's credentials from aws-vault (`aws-vault exec
+    /// <profile> --json`) instead of the AWS CLI
+    #[arg(long, conflicts_with_all = ["role_arn", "localstack", "web_identity_role_arn"])]
+    pub aws_vault: bool,
+
+    /// How long to wait for the provider's ProviderConfig CRD (and the
+    /// LocalStack install), e.g. 10m (default: `timeouts.providers` and
+    /// `timeouts.localstack` in ~/.hops/config.yaml, else 5m)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
 
     /// Namespace for the generated Secret and ProviderConfig
     #[arg(long, short = 'n', default_value = "default")]
@@ -41,8 +125,72 @@ pub struct AwsArgs {
     /// Refresh credentials in the secret only; skips Provider and ProviderConfig apply
     #[arg(long)]
     pub refresh: bool,
+
+    /// With --refresh, keep refreshing the secret in the background before
+    /// the credentials expire
+    #[arg(long, requires = "refresh")]
+    pub daemon: bool,
+
+    /// Stop the background refresh started with --refresh --daemon
+    #[arg(long, conflicts_with_all = ["refresh", "daemon"])]
+    pub stop_daemon: bool,
+
+    /// Run the refresh loop in the foreground (used by --daemon)
+    #[arg(long, hide = true, requires = "refresh")]
+    pub daemon_loop: bool,
 }
 
+/// Background refresher and what it refreshes.
+#[derive(Debug, Serialize, Deserialize)]
+struct RefreshState {
+    pid: u32,
+    profile: String,
+    namespace: String,
+    secret_name: String,
+    #[serde(default)]
+    role_arn: Option<String>,
+}
+
+/// Where credentials come from.
+#[derive(Clone, Debug)]
+enum CredentialSource {
+    /// `aws configure export-credentials`, optionally assuming a role.
+    AwsCli(Option<AssumeRole>),
+    /// `aws-vault exec <profile> --json`.
+    AwsVault,
+    /// A credential_process command.
+    Process(String),
+}
+
+/// Target label used when credentials come from `--credential-process`.
+const CREDENTIAL_PROCESS_LABEL: &str = "credential-process";
+
+/// Role to assume on top of a profile's credentials.
+#[derive(Clone, Debug, Default)]
+struct AssumeRole {
+    role_arn: String,
+    external_id: Option<String>,
+    mfa_serial: Option<String>,
+    mfa_token: Option<String>,
+}
+
+/// `aws sts assume-role` output; `Credentials` uses the same keys as
+/// `export-credentials --format process`.
+#[derive(Debug, Deserialize)]
+struct AssumeRoleOutput {
+    #[serde(rename = "Credentials")]
+    credentials: AwsExportCredentials,
+}
+
+const ROLE_SESSION_NAME: &str = "hops-local";
+
+const WEB_IDENTITY_TOKEN_DIR: &str = "/var/run/secrets/aws-web-identity";
+const WEB_IDENTITY_TOKEN_KEY: &str = "token";
+
+const LOCALSTACK_NAMESPACE: &str = "localstack";
+const LOCALSTACK_ENDPOINT: &str = "http://localstack.localstack.svc.cluster.local:4566";
+const LOCALSTACK_REGION: &str = "us-east-1";
+
 #[derive(Debug, Deserialize)]
 struct AwsExportCredentials {
     #[serde(rename = "AccessKeyId")]
@@ -51,32 +199,85 @@ struct AwsExportCredentials {
     secret_access_key: String,
     #[serde(rename = "SessionToken")]
     session_token: Option<String>,
+    #[serde(rename = "Expiration", default)]
+    expiration: Option<String>,
 }
 
-pub fn run(args: &AwsArgs) -> Result<(), Box<dyn Error>> {
-    let profile = resolve_profile(args.profile.as_deref())?;
-
-    log::info!("Exporting AWS credentials from profile '{}'...", profile);
-    let creds = export_credentials(&profile)?;
-    let credentials_ini = build_credentials_ini(&creds);
+/// One AWS profile and where its credentials go in the cluster.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ProfileTarget {
+    profile: String,
+    #[serde(default)]
+    provider_config: String,
+    #[serde(default)]
+    secret_name: String,
+}
 
-    if args.refresh {
-        log::info!(
-            "Refreshing secret '{}/{}' with generated credentials...",
-            args.namespace,
-            args.secret_name
-        );
-        kubectl_apply_stdin(&build_secret_yaml(
+pub fn run(args: &AwsArgs) -> Result<(), Box<dyn Error>> {
+    if args.stop_daemon {
+        return stop_refresh_daemon();
+    }
+    super::context::ensure_local("local aws")?;
+    if args.localstack {
+        return run_localstack(args);
+    }
+    if let Some(role_arn) = &args.web_identity_role_arn {
+        return run_web_identity(args, role_arn);
+    }
+    let targets = resolve_targets(args)?;
+    let source = credential_source(args);
+    if args.daemon_loop {
+        let target = &targets[0];
+        return refresh_loop(
+            &target.profile,
+            &source,
             &args.namespace,
-            &args.secret_name,
-            &credentials_ini,
-        ))?;
+            &target.secret_name,
+        );
+    }
+    if args.daemon && targets.len() > 1 {
+        return Err("--daemon refreshes a single profile; pass one `--profile`".into());
+    }
+
+    let mut exported = Vec::new();
+    for target in &targets {
         log::info!(
-            "AWS credentials secret refreshed from profile '{}' ({}/{})",
-            profile,
-            args.namespace,
-            args.secret_name
+            "Exporting AWS credentials from profile '{}'...",
+            target.profile
         );
+        let creds = fetch_credentials(&target.profile, &source)?;
+        exported.push((target, build_credentials_ini(&creds)));
+    }
+
+    if args.refresh {
+        for (target, credentials_ini) in &exported {
+            log::info!(
+                "Refreshing secret '{}/{}' with generated credentials...",
+                args.namespace,
+                target.secret_name
+            );
+            kubectl_apply_stdin(&build_secret_yaml(
+                &args.namespace,
+                &target.secret_name,
+                credentials_ini,
+            ))?;
+            log::info!(
+                "AWS credentials secret refreshed from profile '{}' ({}/{})",
+                target.profile,
+                args.namespace,
+                target.secret_name
+            );
+        }
+        if args.daemon {
+            let target = &targets[0];
+            start_refresh_daemon(
+                &target.profile,
+                &source,
+                &args.namespace,
+                &target.secret_name,
+            )?;
+        }
         return Ok(());
     }
 
@@ -89,40 +290,398 @@ pub fn run(args: &AwsArgs) -> Result<(), Box<dyn Error>> {
         &args.provider_package,
     ))?;
 
-    wait_for_crd(PROVIDER_CONFIG_CRD)?;
+    wait_for_provider(args)?;
+
+    for (target, credentials_ini) in &exported {
+        log::info!(
+            "Applying secret '{}/{}' with generated credentials...",
+            args.namespace,
+            target.secret_name
+        );
+        kubectl_apply_stdin(&build_secret_yaml(
+            &args.namespace,
+            &target.secret_name,
+            credentials_ini,
+        ))?;
+
+        log::info!(
+            "Applying ProviderConfig '{}/{}'...",
+            args.namespace,
+            target.provider_config
+        );
+        kubectl_apply_stdin(&build_provider_config_yaml(
+            &args.namespace,
+            &target.provider_config,
+            &target.secret_name,
+        ))?;
+
+        log::info!(
+            "AWS provider configured from profile '{}' (ProviderConfig: {}/{})",
+            target.profile,
+            args.namespace,
+            target.provider_config
+        );
+    }
+    Ok(())
+}
+
+/// Install LocalStack and configure provider-family-aws against it.
+fn run_localstack(args: &AwsArgs) -> Result<(), Box<dyn Error>> {
+    log::info!("Installing LocalStack...");
+    run_cmd(
+        "helm",
+        &[
+            "repo",
+            "add",
+            "localstack",
+            "https://localstack.github.io/helm-charts",
+        ],
+    )?;
+    run_cmd("helm", &["repo", "update", "localstack"])?;
+    let timeout = crate::settings::timeouts(args.timeout)?.localstack;
+    let timeout = format!("{}s", timeout.as_secs().max(1));
+    run_cmd(
+        "helm",
+        &[
+            "upgrade",
+            "--install",
+            "localstack",
+            "localstack/localstack",
+            "-n",
+            LOCALSTACK_NAMESPACE,
+            "--create-namespace",
+            "--wait",
+            "--timeout",
+            &timeout,
+        ],
+    )?;
+
+    // LocalStack accepts any key pair; `test`/`test` is its documented default.
+    let creds = AwsExportCredentials {
+        access_key_id: "test".to_string(),
+        secret_access_key: "test".to_string(),
+        session_token: None,
+        expiration: None,
+    };
+    let credentials_ini = build_credentials_ini(&creds);
+
+    if !args.refresh {
+        log::info!(
+            "Applying provider-family-aws package '{}'...",
+            args.provider_package
+        );
+        kubectl_apply_stdin(&build_provider_yaml(
+            &args.provider_name,
+            &args.provider_package,
+        ))?;
+        wait_for_provider(args)?;
+    }
 
-    log::info!(
-        "Applying secret '{}/{}' with generated credentials...",
-        args.namespace,
-        args.secret_name
-    );
     kubectl_apply_stdin(&build_secret_yaml(
         &args.namespace,
         &args.secret_name,
         &credentials_ini,
     ))?;
+    kubectl_apply_stdin(&build_localstack_provider_config_yaml(
+        &args.namespace,
+        &args.provider_config_name,
+        &args.secret_name,
+    ))?;
+    crate::logging::success(&format!(
+        "AWS provider configured against LocalStack at {} (ProviderConfig: {}/{})",
+        LOCALSTACK_ENDPOINT, args.namespace, args.provider_config_name
+    ));
+    Ok(())
+}
 
-    log::info!(
-        "Applying ProviderConfig '{}/{}'...",
-        args.namespace,
-        args.provider_config_name
-    );
-    kubectl_apply_stdin(&build_provider_config_yaml(
+/// Configure provider-family-aws to exchange an OIDC token for role
+/// credentials itself, so no long-lived keys are stored in the cluster.
+fn run_web_identity(args: &AwsArgs, role_arn: &str) -> Result<(), Box<dyn Error>> {
+    if !args.refresh {
+        log::info!(
+            "Applying provider-family-aws package '{}'...",
+            args.provider_package
+        );
+        kubectl_apply_stdin(&build_provider_yaml(
+            &args.provider_name,
+            &args.provider_package,
+        ))?;
+        wait_for_provider(args)?;
+    }
+
+    let token_source = match &args.web_identity_token_file {
+        Some(path) => {
+            let token = fs::read_to_string(path)
+                .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+            log::info!(
+                "Storing web identity token in secret '{}/{}'...",
+                args.namespace,
+                args.secret_name
+            );
+            kubectl_apply_stdin(&build_token_secret_yaml(
+                &args.namespace,
+                &args.secret_name,
+                token.trim(),
+            ))?;
+            WebIdentityToken::Secret
+        }
+        None => {
+            // Sub-providers of the family are installed with the `default`
+            // runtime config, so the token volume goes there.
+            log::info!("Projecting a service account token into provider pods...");
+            kubectl_apply_stdin(&build_web_identity_runtime_config_yaml())?;
+            WebIdentityToken::Projected
+        }
+    };
+    if args.refresh {
+        crate::logging::success(&format!(
+            "Web identity token refreshed ({}/{})",
+            args.namespace, args.secret_name
+        ));
+        return Ok(());
+    }
+
+    kubectl_apply_stdin(&build_web_identity_provider_config_yaml(
         &args.namespace,
         &args.provider_config_name,
+        role_arn,
+        token_source,
         &args.secret_name,
     ))?;
+    crate::logging::success(&format!(
+        "AWS provider configured for web identity role {} (ProviderConfig: {}/{})",
+        role_arn, args.namespace, args.provider_config_name
+    ));
+    Ok(())
+}
 
-    log::info!(
-        "AWS provider configured from profile '{}' (ProviderConfig: {}/{})",
+/// Profiles to configure. A single plain `--profile` (or the env/prompt
+/// fallback) uses `--secret-name`/`--provider-config-name` as before; with
+/// several profiles, or `PROFILE=PROVIDERCONFIG` mappings, each gets a
+/// ProviderConfig named after the mapping (default: the profile) and a
+/// Secret named `<secret-name>-<providerconfig>`.
+fn resolve_targets(args: &AwsArgs) -> Result<Vec<ProfileTarget>, Box<dyn Error>> {
+    let mut targets = Vec::new();
+    if let Some(path) = &args.profiles_file {
+        let contents = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        targets.extend(
+            serde_yaml::from_str::<Vec<ProfileTarget>>(&contents)
+                .map_err(|err| format!("invalid profiles file {}: {}", path.display(), err))?,
+        );
+    }
+    targets.extend(args.profile.iter().map(|spec| parse_profile_spec(spec)));
+
+    if targets.is_empty() && args.credential_process.is_some() {
+        targets.push(ProfileTarget {
+            profile: CREDENTIAL_PROCESS_LABEL.to_string(),
+            provider_config: String::new(),
+            secret_name: String::new(),
+        });
+    }
+    if targets.is_empty() {
+        let profile = resolve_profile(None)?;
+        targets.push(ProfileTarget {
+            profile,
+            provider_config: String::new(),
+            secret_name: String::new(),
+        });
+    }
+
+    let single_plain = targets.len() == 1
+        && targets[0].provider_config.is_empty()
+        && targets[0].secret_name.is_empty();
+    for target in &mut targets {
+        if target.profile.trim().is_empty() {
+            return Err("AWS profile names must not be empty".into());
+        }
+        if single_plain {
+            target.provider_config = args.provider_config_name.clone();
+            target.secret_name = args.secret_name.clone();
+            continue;
+        }
+        if target.provider_config.is_empty() {
+            target.provider_config = target.profile.clone();
+        }
+        if target.secret_name.is_empty() {
+            target.secret_name = format!("{}-{}", args.secret_name, target.provider_config);
+        }
+    }
+
+    let mut names: Vec<&str> = targets.iter().map(|t| t.provider_config.as_str()).collect();
+    names.sort_unstable();
+    if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(format!("ProviderConfig '{}' is mapped more than once", pair[0]).into());
+    }
+    Ok(targets)
+}
+
+fn parse_profile_spec(spec: &str) -> ProfileTarget {
+    let (profile, provider_config) = spec.split_once('=').unwrap_or((spec, ""));
+    ProfileTarget {
+        profile: profile.trim().to_string(),
+        provider_config: provider_config.trim().to_string(),
+        secret_name: String::new(),
+    }
+}
+
+fn start_refresh_daemon(
+    profile: &str,
+    source: &CredentialSource,
+    namespace: &str,
+    secret_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let role = match source {
+        CredentialSource::AwsCli(role) => role.as_ref(),
+        _ => None,
+    };
+    if role.is_some_and(|role| resolve_mfa_serial(profile, role).is_some()) {
+        return Err(
+            "--daemon cannot answer MFA prompts; refresh MFA-protected roles with --refresh instead"
+                .into(),
+        );
+    }
+    if let Some(state) =
+        read_state::<RefreshState>(REFRESH_STATE_FILE).filter(|state| process_alive(state.pid))
+    {
+        return Err(format!(
+            "AWS refresh daemon is already running for profile '{}' (pid {}); run `hops local aws --stop-daemon` first",
+            state.profile, state.pid
+        )
+        .into());
+    }
+
+    let exe = std::env::current_exe()?;
+    let log_path = state_path(REFRESH_LOG_FILE)?;
+    let mut daemon_args = vec![
+        "local",
+        "aws",
+        "--refresh",
+        "--daemon-loop",
+        "--namespace",
+        namespace,
+        "--secret-name",
+        secret_name,
+    ];
+    match source {
+        CredentialSource::Process(command) => {
+            daemon_args.extend(["--credential-process", command.as_str()])
+        }
+        CredentialSource::AwsVault => daemon_args.extend(["--profile", profile, "--aws-vault"]),
+        CredentialSource::AwsCli(_) => daemon_args.extend(["--profile", profile]),
+    }
+    if let Some(role) = role {
+        daemon_args.extend(["--role-arn", role.role_arn.as_str()]);
+        if let Some(external_id) = role.external_id.as_deref() {
+            daemon_args.extend(["--external-id", external_id]);
+        }
+    }
+    let pid = spawn_detached(&exe, &daemon_args, &log_path)?;
+    write_state(
+        REFRESH_STATE_FILE,
+        &RefreshState {
+            pid,
+            profile: profile.to_string(),
+            namespace: namespace.to_string(),
+            secret_name: secret_name.to_string(),
+            role_arn: role.map(|role| role.role_arn.clone()),
+        },
+    )?;
+    crate::logging::success(&format!(
+        "AWS refresh daemon started for profile '{}' (pid {}, log {})",
         profile,
-        args.namespace,
-        args.provider_config_name
-    );
+        pid,
+        log_path.display()
+    ));
+    Ok(())
+}
+
+fn stop_refresh_daemon() -> Result<(), Box<dyn Error>> {
+    match read_state::<RefreshState>(REFRESH_STATE_FILE) {
+        Some(state) => {
+            terminate_process_group(state.pid, false)?;
+            remove_state(REFRESH_STATE_FILE)?;
+            crate::logging::success(&format!(
+                "AWS refresh daemon for profile '{}' stopped",
+                state.profile
+            ));
+        }
+        None => log::info!("AWS refresh daemon is not running"),
+    }
     Ok(())
 }
 
-fn resolve_profile(cli_profile: Option<&str>) -> Result<String, Box<dyn Error>> {
+/// Refresh the secret, then sleep until shortly before the credentials
+/// expire. Runs without a terminal, so an expired SSO session can't be
+/// fixed here: it is logged and retried until `aws sso login` is run.
+fn refresh_loop(
+    profile: &str,
+    source: &CredentialSource,
+    namespace: &str,
+    secret_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    loop {
+        // Apply only between other hops commands; they hold the state lock.
+        let Some(guard) = lock::try_acquire()? else {
+            thread::sleep(LOCK_RETRY_INTERVAL);
+            continue;
+        };
+        let delay = match fetch_credentials(profile, source) {
+            Ok(creds) => match kubectl_apply_stdin(&build_secret_yaml(
+                namespace,
+                secret_name,
+                &build_credentials_ini(&creds),
+            )) {
+                Ok(()) => {
+                    let delay = refresh_delay(creds.expiration.as_deref(), SystemTime::now());
+                    log::info!(
+                        "Refreshed {}/{} from profile '{}'; next refresh in {}",
+                        namespace,
+                        secret_name,
+                        profile,
+                        humantime::format_duration(delay)
+                    );
+                    delay
+                }
+                Err(err) => {
+                    log::warn!("Failed to apply {}/{}: {}", namespace, secret_name, err);
+                    RETRY_INTERVAL
+                }
+            },
+            Err(err) => {
+                log::warn!("Failed to export credentials: {}", err);
+                RETRY_INTERVAL
+            }
+        };
+        drop(guard);
+        thread::sleep(delay);
+    }
+}
+
+/// Time until `REFRESH_MARGIN` before `expiration` (RFC 3339, as printed by
+/// `aws configure export-credentials`).
+fn refresh_delay(expiration: Option<&str>, now: SystemTime) -> Duration {
+    let Some(expires_at) = expiration.and_then(parse_expiration) else {
+        return DEFAULT_REFRESH_INTERVAL;
+    };
+    expires_at
+        .duration_since(now)
+        .ok()
+        .and_then(|remaining| remaining.checked_sub(REFRESH_MARGIN))
+        .unwrap_or_default()
+        .max(MIN_REFRESH_INTERVAL)
+}
+
+fn parse_expiration(value: &str) -> Option<SystemTime> {
+    // AWS prints UTC as either `Z` or `+00:00`; humantime wants neither.
+    let value = value.trim();
+    let utc = value
+        .strip_suffix("+00:00")
+        .or_else(|| value.strip_suffix('Z'))?;
+    humantime::parse_rfc3339_weak(utc).ok()
+}
+
+pub(super) fn resolve_profile(cli_profile: Option<&str>) -> Result<String, Box<dyn Error>> {
     let env_profile = std::env::var("AWS_PROFILE").ok();
     let env_default_profile = std::env::var("AWS_DEFAULT_PROFILE").ok();
 
@@ -151,13 +710,21 @@ fn select_profile(
 }
 
 fn prompt_for_profile() -> Result<String, Box<dyn Error>> {
-    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+    if !crate::interactive::prompts_allowed() {
         return Err(
-            "AWS profile is not set. Pass `--profile <name>` or set AWS_PROFILE/AWS_DEFAULT_PROFILE."
+            "AWS profile is not set and prompting is unavailable (no terminal or --yes). Pass `--profile <name>` or set AWS_PROFILE/AWS_DEFAULT_PROFILE."
                 .into(),
         );
     }
 
+    let profiles = list_config_profiles();
+    if !profiles.is_empty() {
+        return crate::interactive::fuzzy_select(
+            "AWS profile is not set. Select a profile",
+            &profiles,
+        );
+    }
+
     print!("AWS profile is not set. Enter AWS profile name: ");
     io::stdout().flush()?;
 
@@ -172,14 +739,165 @@ fn prompt_for_profile() -> Result<String, Box<dyn Error>> {
     Ok(profile.to_string())
 }
 
+/// Profile names from the AWS CLI config file (`AWS_CONFIG_FILE` or
+/// `~/.aws/config`). Missing or unreadable files yield an empty list.
+fn list_config_profiles() -> Vec<String> {
+    let path = match std::env::var_os("AWS_CONFIG_FILE") {
+        Some(path) => PathBuf::from(path),
+        None => match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".aws").join("config"),
+            None => return Vec::new(),
+        },
+    };
+    fs::read_to_string(&path)
+        .map(|contents| parse_config_profiles(&contents))
+        .unwrap_or_default()
+}
+
+/// Extract profile names from `[default]` and `[profile <name>]` sections.
+fn parse_config_profiles(contents: &str) -> Vec<String> {
+    let mut profiles = contents
+        .lines()
+        .filter_map(|line| {
+            let section = line.trim().strip_prefix('[')?.strip_suffix(']')?.trim();
+            if section == "default" {
+                return Some(section.to_string());
+            }
+            let name = section.strip_prefix("profile ")?.trim();
+            (!name.is_empty()).then(|| name.to_string())
+        })
+        .collect::<Vec<_>>();
+    profiles.sort();
+    profiles.dedup();
+    profiles
+}
+
+fn credential_source(args: &AwsArgs) -> CredentialSource {
+    if let Some(command) = &args.credential_process {
+        return CredentialSource::Process(command.clone());
+    }
+    if args.aws_vault {
+        return CredentialSource::AwsVault;
+    }
+    CredentialSource::AwsCli(args.role_arn.as_ref().map(|role_arn| AssumeRole {
+        role_arn: role_arn.clone(),
+        external_id: args.external_id.clone(),
+        mfa_serial: args.mfa_serial.clone(),
+        mfa_token: args.mfa_token.clone(),
+    }))
+}
+
+/// Credentials for `profile` from `source` (the profile is only a label for
+/// `--credential-process`).
+fn fetch_credentials(
+    profile: &str,
+    source: &CredentialSource,
+) -> Result<AwsExportCredentials, Box<dyn Error>> {
+    match source {
+        CredentialSource::AwsCli(Some(role)) => assume_role(profile, role),
+        CredentialSource::AwsCli(None) => export_credentials(profile),
+        CredentialSource::AwsVault => {
+            let output = run_cmd_output_secret("aws-vault", &["exec", profile, "--json"])
+                .map_err(|err| format!("aws-vault failed for profile '{}': {}", profile, err))?;
+            parse_process_credentials(&output, &format!("aws-vault profile '{}'", profile))
+        }
+        CredentialSource::Process(command) => {
+            let output = run_cmd_output_secret("sh", &["-c", command])
+                .map_err(|err| format!("credential process failed: {}", err))?;
+            parse_process_credentials(&output, "credential process")
+        }
+    }
+}
+
+/// Parse credential_process JSON (`Version`, `AccessKeyId`, ...), as printed
+/// by `aws-vault exec --json` and other credential helpers.
+fn parse_process_credentials(
+    output: &str,
+    source: &str,
+) -> Result<AwsExportCredentials, Box<dyn Error>> {
+    let creds: AwsExportCredentials = serde_json::from_str(output)
+        .map_err(|err| format!("failed to parse credential JSON from {}: {}", source, err))?;
+    if creds.access_key_id.trim().is_empty() || creds.secret_access_key.trim().is_empty() {
+        return Err(format!("{} returned empty access key or secret key", source).into());
+    }
+    Ok(creds)
+}
+
+fn assume_role(profile: &str, role: &AssumeRole) -> Result<AwsExportCredentials, Box<dyn Error>> {
+    // Make sure the base profile works first (runs `aws sso login` if needed).
+    export_credentials(profile)?;
+
+    let mut args = vec![
+        "sts".to_string(),
+        "assume-role".to_string(),
+        "--profile".to_string(),
+        profile.to_string(),
+        "--role-arn".to_string(),
+        role.role_arn.clone(),
+        "--role-session-name".to_string(),
+        ROLE_SESSION_NAME.to_string(),
+        "--output".to_string(),
+        "json".to_string(),
+    ];
+    if let Some(external_id) = &role.external_id {
+        args.extend(["--external-id".to_string(), external_id.clone()]);
+    }
+    if let Some(serial) = resolve_mfa_serial(profile, role) {
+        let token = match &role.mfa_token {
+            Some(token) => token.clone(),
+            None if crate::interactive::prompts_allowed() => {
+                crate::interactive::input(&format!("MFA code for {}", serial))?
+            }
+            None => {
+                return Err(format!(
+                    "role {} requires MFA ({}) but prompting is unavailable (no terminal or --yes). Pass `--mfa-token <code>`.",
+                    role.role_arn, serial
+                )
+                .into())
+            }
+        };
+        args.extend([
+            "--serial-number".to_string(),
+            serial,
+            "--token-code".to_string(),
+            token,
+        ]);
+    }
+
+    log::info!(
+        "Assuming role {} with profile '{}'...",
+        role.role_arn,
+        profile
+    );
+    let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = run_cmd_output_secret("aws", &refs)
+        .map_err(|err| format!("failed to assume role {}: {}", role.role_arn, err))?;
+    let parsed: AssumeRoleOutput = serde_json::from_str(&output)
+        .map_err(|err| format!("failed to parse sts assume-role output: {}", err))?;
+    Ok(parsed.credentials)
+}
+
+/// `--mfa-serial`, or the profile's `mfa_serial` setting.
+fn resolve_mfa_serial(profile: &str, role: &AssumeRole) -> Option<String> {
+    role.mfa_serial.clone().or_else(|| {
+        run_cmd_output(
+            "aws",
+            &["configure", "get", "mfa_serial", "--profile", profile],
+        )
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    })
+}
+
 fn export_credentials(profile: &str) -> Result<AwsExportCredentials, Box<dyn Error>> {
     let output = match run_aws_export_credentials(profile) {
         Ok(output) => output,
         Err(initial_err) => {
             if sso_login_required(&initial_err) {
-                if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+                if !crate::interactive::prompts_allowed() {
                     return Err(format!(
-                        "failed to export credentials for profile '{}': {}\nSSO login is required, but no interactive terminal was detected. Run `aws sso login --profile {}` first.",
+                        "failed to export credentials for profile '{}': {}\nSSO login is required, but prompting is unavailable (no terminal or --yes). Run `aws sso login --profile {}` first.",
                         profile, initial_err, profile
                     )
                     .into());
@@ -204,35 +922,25 @@ fn export_credentials(profile: &str) -> Result<AwsExportCredentials, Box<dyn Err
                     )
                 })?
             } else {
-                return Err(format!(
+                let mut message = format!(
                     "failed to export credentials for profile '{}': {}\nIf this is an SSO profile, run `aws sso login --profile {}` first.",
                     profile, initial_err, profile
-                )
-                .into());
+                );
+                if command_exists("aws-vault") {
+                    message.push_str(
+                        "\naws-vault is installed; if it manages this profile, pass `--aws-vault`.",
+                    );
+                }
+                return Err(message.into());
             }
         }
     };
 
-    let creds: AwsExportCredentials = serde_json::from_str(&output).map_err(|err| {
-        format!(
-            "failed to parse credential JSON for profile '{}': {}",
-            profile, err
-        )
-    })?;
-
-    if creds.access_key_id.trim().is_empty() || creds.secret_access_key.trim().is_empty() {
-        return Err(format!(
-            "AWS profile '{}' returned empty access key or secret key",
-            profile
-        )
-        .into());
-    }
-
-    Ok(creds)
+    parse_process_credentials(&output, &format!("AWS profile '{}'", profile))
 }
 
 fn run_aws_export_credentials(profile: &str) -> Result<String, String> {
-    run_cmd_output(
+    run_cmd_output_secret(
         "aws",
         &[
             "configure",
@@ -253,16 +961,20 @@ fn sso_login_required(error: &str) -> bool {
         || lower.contains("sso session associated with this profile has expired")
 }
 
-fn wait_for_crd(crd: &str) -> Result<(), Box<dyn Error>> {
-    log::info!("Waiting for CRD {}...", crd);
-    for _ in 0..60 {
-        if run_cmd_output("kubectl", &["get", "crd", crd]).is_ok() {
-            return Ok(());
-        }
-        thread::sleep(Duration::from_secs(5));
-    }
-
-    Err(format!("Timed out waiting for CRD {}", crd).into())
+/// Wait for the provider package and its ProviderConfig CRD together.
+fn wait_for_provider(args: &AwsArgs) -> Result<(), Box<dyn Error>> {
+    log::info!(
+        "Waiting for provider '{}' and CRD {}...",
+        args.provider_name,
+        PROVIDER_CONFIG_CRD
+    );
+    wait_for_all(
+        &[
+            ReadyTarget::provider(&args.provider_name),
+            ReadyTarget::crd(PROVIDER_CONFIG_CRD),
+        ],
+        crate::settings::timeouts(args.timeout)?.providers,
+    )
 }
 
 fn build_credentials_ini(creds: &AwsExportCredentials) -> String {
@@ -303,6 +1015,60 @@ fn build_provider_config_yaml(
     )
 }
 
+/// Where the provider reads the web identity token from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum WebIdentityToken {
+    Secret,
+    Projected,
+}
+
+fn build_token_secret_yaml(namespace: &str, secret_name: &str, token: &str) -> String {
+    format!(
+        "apiVersion: v1\nkind: Secret\nmetadata:\n  name: {secret_name}\n  namespace: {namespace}\ntype: Opaque\nstringData:\n  {WEB_IDENTITY_TOKEN_KEY}: {token}\n"
+    )
+}
+
+/// `default` DeploymentRuntimeConfig that mounts a service account token
+/// with the STS audience into every provider pod using it.
+fn build_web_identity_runtime_config_yaml() -> String {
+    format!(
+        "apiVersion: pkg.crossplane.io/v1beta1\nkind: DeploymentRuntimeConfig\nmetadata:\n  name: default\nspec:\n  deploymentTemplate:\n    spec:\n      selector: {{}}\n      template:\n        spec:\n          containers:\n            - name: package-runtime\n              volumeMounts:\n                - name: aws-web-identity-token\n                  mountPath: {WEB_IDENTITY_TOKEN_DIR}\n                  readOnly: true\n          volumes:\n            - name: aws-web-identity-token\n              projected:\n                sources:\n                  - serviceAccountToken:\n                      audience: sts.amazonaws.com\n                      expirationSeconds: 3600\n                      path: {WEB_IDENTITY_TOKEN_KEY}\n"
+    )
+}
+
+fn build_web_identity_provider_config_yaml(
+    namespace: &str,
+    provider_config_name: &str,
+    role_arn: &str,
+    token: WebIdentityToken,
+    secret_name: &str,
+) -> String {
+    let token_config = match token {
+        WebIdentityToken::Secret => format!(
+            "      tokenConfig:\n        source: Secret\n        secretRef:\n          namespace: {namespace}\n          name: {secret_name}\n          key: {WEB_IDENTITY_TOKEN_KEY}\n"
+        ),
+        WebIdentityToken::Projected => format!(
+            "      tokenConfig:\n        source: Filesystem\n        fs:\n          path: {WEB_IDENTITY_TOKEN_DIR}/{WEB_IDENTITY_TOKEN_KEY}\n"
+        ),
+    };
+    format!(
+        "apiVersion: aws.m.upbound.io/v1beta1\nkind: ProviderConfig\nmetadata:\n  name: {provider_config_name}\n  namespace: {namespace}\nspec:\n  credentials:\n    source: WebIdentity\n    webIdentity:\n      roleARN: {role_arn}\n      roleSessionName: {ROLE_SESSION_NAME}\n{token_config}"
+    )
+}
+
+/// ProviderConfig with every service endpoint pointed at LocalStack and the
+/// account/region checks that need real AWS turned off.
+fn build_localstack_provider_config_yaml(
+    namespace: &str,
+    provider_config_name: &str,
+    secret_name: &str,
+) -> String {
+    format!(
+        "{}  endpoint:\n    hostnameImmutable: true\n    signingRegion: {LOCALSTACK_REGION}\n    url:\n      type: Static\n      static: {LOCALSTACK_ENDPOINT}\n  skip_credentials_validation: true\n  skip_metadata_api_check: true\n  skip_region_validation: true\n  skip_requesting_account_id: true\n  s3_use_path_style: true\n",
+        build_provider_config_yaml(namespace, provider_config_name, secret_name)
+    )
+}
+
 fn indent_block(text: &str, spaces: usize) -> String {
     let pad = " ".repeat(spaces);
     text.lines()
@@ -330,6 +1096,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_config_profiles_reads_default_and_named_sections() {
+        let config = "[default]\nregion = us-east-1\n\n[profile dev]\nsso_session = corp\n\n[sso-session corp]\nsso_region = us-east-1\n[ profile  prod ]\n";
+        assert_eq!(
+            parse_config_profiles(config),
+            vec!["default".to_string(), "dev".to_string(), "prod".to_string()]
+        );
+    }
+
     #[test]
     fn select_profile_ignores_blank_values() {
         assert_eq!(
@@ -358,6 +1133,7 @@ mod tests {
             access_key_id: "AKIA...".to_string(),
             secret_access_key: "secret".to_string(),
             session_token: Some("token".to_string()),
+            expiration: None,
         };
 
         let ini = build_credentials_ini(&creds);
@@ -366,6 +1142,154 @@ mod tests {
         assert!(ini.contains("aws_session_token = token"));
     }
 
+    fn test_args(profiles: &[&str]) -> AwsArgs {
+        AwsArgs {
+            profile: profiles.iter().map(|p| p.to_string()).collect(),
+            profiles_file: None,
+            role_arn: None,
+            external_id: None,
+            mfa_serial: None,
+            mfa_token: None,
+            localstack: false,
+            web_identity_role_arn: None,
+            web_identity_token_file: None,
+            credential_process: None,
+            aws_vault: false,
+            timeout: None,
+            namespace: "default".to_string(),
+            secret_name: "aws-creds".to_string(),
+            provider_config_name: "default".to_string(),
+            provider_name: DEFAULT_PROVIDER_NAME.to_string(),
+            provider_package: DEFAULT_PROVIDER_PACKAGE.to_string(),
+            refresh: false,
+            daemon: false,
+            stop_daemon: false,
+            daemon_loop: false,
+        }
+    }
+
+    #[test]
+    fn resolve_targets_keeps_single_profile_defaults() {
+        let targets = resolve_targets(&test_args(&["hops"])).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].provider_config, "default");
+        assert_eq!(targets[0].secret_name, "aws-creds");
+    }
+
+    #[test]
+    fn credential_process_uses_a_label_and_parses_process_json() {
+        let mut args = test_args(&[]);
+        args.credential_process = Some("aws-vault exec dev --json".to_string());
+        let targets = resolve_targets(&args).unwrap();
+        assert_eq!(targets[0].profile, CREDENTIAL_PROCESS_LABEL);
+        assert_eq!(targets[0].secret_name, "aws-creds");
+
+        let creds = parse_process_credentials(
+            r#"{"Version":1,"AccessKeyId":"AKIA","SecretAccessKey":"secret","SessionToken":"token","Expiration":"2026-01-01T00:00:00Z"}"#,
+            "test",
+        )
+        .unwrap();
+        assert_eq!(creds.access_key_id, "AKIA");
+        assert_eq!(creds.expiration.as_deref(), Some("2026-01-01T00:00:00Z"));
+        assert!(parse_process_credentials(
+            r#"{"Version":1,"AccessKeyId":"","SecretAccessKey":"x"}"#,
+            "test"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn resolve_targets_names_one_config_per_profile() {
+        let targets = resolve_targets(&test_args(&["dev", "prod-admin=staging"])).unwrap();
+        assert_eq!(targets[0].provider_config, "dev");
+        assert_eq!(targets[0].secret_name, "aws-creds-dev");
+        assert_eq!(targets[1].profile, "prod-admin");
+        assert_eq!(targets[1].provider_config, "staging");
+        assert_eq!(targets[1].secret_name, "aws-creds-staging");
+
+        assert!(resolve_targets(&test_args(&["a=dup", "b=dup"])).is_err());
+    }
+
+    #[test]
+    fn assume_role_output_reads_credentials() {
+        let output = r#"{"Credentials": {"AccessKeyId": "ASIA", "SecretAccessKey": "s", "SessionToken": "t", "Expiration": "2024-05-01T13:00:00+00:00"}, "AssumedRoleUser": {"Arn": "arn"}}"#;
+        let parsed: AssumeRoleOutput = serde_json::from_str(output).unwrap();
+        assert_eq!(parsed.credentials.access_key_id, "ASIA");
+        assert_eq!(parsed.credentials.session_token.as_deref(), Some("t"));
+        assert!(parsed.credentials.expiration.is_some());
+    }
+
+    #[test]
+    fn refresh_delay_targets_margin_before_expiration() {
+        let now = humantime::parse_rfc3339("2024-05-01T12:00:00Z").unwrap();
+        assert_eq!(
+            refresh_delay(Some("2024-05-01T13:00:00+00:00"), now),
+            Duration::from_secs(50 * 60)
+        );
+        assert_eq!(
+            refresh_delay(Some("2024-05-01T12:05:00Z"), now),
+            MIN_REFRESH_INTERVAL
+        );
+        assert_eq!(refresh_delay(None, now), DEFAULT_REFRESH_INTERVAL);
+        assert_eq!(refresh_delay(Some("soon"), now), DEFAULT_REFRESH_INTERVAL);
+    }
+
+    #[test]
+    fn localstack_provider_config_overrides_endpoint() {
+        let yaml = build_localstack_provider_config_yaml("default", "localstack", "aws-creds");
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(
+            value["spec"]["endpoint"]["url"]["static"].as_str(),
+            Some(LOCALSTACK_ENDPOINT)
+        );
+        assert_eq!(value["spec"]["s3_use_path_style"].as_bool(), Some(true));
+        assert_eq!(
+            value["spec"]["credentials"]["secretRef"]["name"].as_str(),
+            Some("aws-creds")
+        );
+    }
+
+    #[test]
+    fn web_identity_provider_config_uses_token_source() {
+        let yaml = build_web_identity_provider_config_yaml(
+            "default",
+            "default",
+            "arn:aws:iam::123456789012:role/dev",
+            WebIdentityToken::Projected,
+            "aws-creds",
+        );
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        let web_identity = &value["spec"]["credentials"]["webIdentity"];
+        assert_eq!(
+            value["spec"]["credentials"]["source"].as_str(),
+            Some("WebIdentity")
+        );
+        assert_eq!(
+            web_identity["roleARN"].as_str(),
+            Some("arn:aws:iam::123456789012:role/dev")
+        );
+        assert_eq!(
+            web_identity["tokenConfig"]["fs"]["path"].as_str(),
+            Some("/var/run/secrets/aws-web-identity/token")
+        );
+
+        let yaml = build_web_identity_provider_config_yaml(
+            "default",
+            "default",
+            "arn",
+            WebIdentityToken::Secret,
+            "aws-creds",
+        );
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(
+            value["spec"]["credentials"]["webIdentity"]["tokenConfig"]["secretRef"]["key"].as_str(),
+            Some("token")
+        );
+        let drc: serde_yaml::Value =
+            serde_yaml::from_str(&build_web_identity_runtime_config_yaml()).unwrap();
+        assert_eq!(drc["metadata"]["name"].as_str(), Some("default"));
+    }
+
     #[test]
     fn provider_config_yaml_uses_secret_ref() {
         let yaml = build_provider_config_yaml("default", "default", "aws-creds");
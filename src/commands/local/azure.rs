@@ -0,0 +1,395 @@
+use super::conditions::{wait_for_all, ReadyTarget};
+use super::{command_exists, kubectl_apply_stdin, run_cmd, run_cmd_output, run_cmd_output_secret};
+use clap::Args;
+use serde::Deserialize;
+use serde_json::json;
+use std::error::Error;
+use std::time::Duration;
+
+const DEFAULT_PROVIDER_PACKAGE: &str =
+    "xpkg.crossplane.io/crossplane-contrib/provider-family-azure:v2.1.0";
+const DEFAULT_PROVIDER_NAME: &str = "crossplane-contrib-provider-family-azure";
+const PROVIDER_CONFIG_CRD: &str = "providerconfigs.azure.m.upbound.io";
+const DEFAULT_SERVICE_PRINCIPAL_NAME: &str = "hops-local";
+
+#[derive(Args, Debug)]
+pub struct AzureArgs {
+    /// Subscription to configure (defaults to the active `az account`)
+    #[arg(long, short = 's')]
+    pub subscription: Option<String>,
+
+    /// Service principal client (app) ID (falls back to AZURE_CLIENT_ID);
+    /// the secret is read from AZURE_CLIENT_SECRET
+    #[arg(long)]
+    pub client_id: Option<String>,
+
+    /// Create a service principal with Contributor on the subscription via
+    /// `az ad sp create-for-rbac` when no client ID/secret is given
+    #[arg(long)]
+    pub create_service_principal: bool,
+
+    /// Display name for --create-service-principal
+    #[arg(long, default_value = DEFAULT_SERVICE_PRINCIPAL_NAME)]
+    pub service_principal_name: String,
+
+    /// How long to wait for the provider's ProviderConfig CRD, e.g. 10m
+    /// (default: `timeouts.providers` in ~/.hops/config.yaml, else 5m)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Namespace for the generated Secret and ProviderConfig
+    #[arg(long, short = 'n', default_value = "default")]
+    pub namespace: String,
+
+    /// Secret name that stores generated Azure credentials JSON
+    #[arg(long, default_value = "azure-creds")]
+    pub secret_name: String,
+
+    /// ProviderConfig name to create/update
+    #[arg(long, default_value = "default")]
+    pub provider_config_name: String,
+
+    /// Provider resource name for provider-family-azure
+    #[arg(long, default_value = DEFAULT_PROVIDER_NAME)]
+    pub provider_name: String,
+
+    /// provider-family-azure package reference
+    #[arg(long, default_value = DEFAULT_PROVIDER_PACKAGE)]
+    pub provider_package: String,
+
+    /// Refresh credentials in the secret only; skips Provider and ProviderConfig apply
+    #[arg(long)]
+    pub refresh: bool,
+}
+
+/// Subset of `az account show -o json`.
+#[derive(Debug, Deserialize)]
+struct AzAccount {
+    id: String,
+    #[serde(rename = "tenantId")]
+    tenant_id: String,
+    name: Option<String>,
+}
+
+/// Subset of `az ad sp create-for-rbac -o json`.
+#[derive(Debug, Deserialize)]
+struct AzServicePrincipal {
+    #[serde(rename = "appId")]
+    app_id: String,
+    password: String,
+    tenant: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct AzureCredentials {
+    client_id: String,
+    client_secret: String,
+    subscription_id: String,
+    tenant_id: String,
+}
+
+pub fn run(args: &AzureArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local azure")?;
+    if !command_exists("az") {
+        return Err(
+            "Azure CLI (`az`) is not installed or not in PATH. Install it first, then rerun `hops local azure`."
+                .into(),
+        );
+    }
+
+    let account = account_show(args.subscription.as_deref())?;
+    log::info!(
+        "Using Azure subscription '{}' ({})",
+        account.name.as_deref().unwrap_or("unnamed"),
+        account.id
+    );
+    let creds = resolve_credentials(args, &account)?;
+    let credentials_json = build_credentials_json(&creds)?;
+
+    if args.refresh {
+        log::info!(
+            "Refreshing secret '{}/{}' with generated credentials...",
+            args.namespace,
+            args.secret_name
+        );
+        kubectl_apply_stdin(&build_secret_yaml(
+            &args.namespace,
+            &args.secret_name,
+            &credentials_json,
+        ))?;
+        log::info!(
+            "Azure credentials secret refreshed for subscription '{}' ({}/{})",
+            creds.subscription_id,
+            args.namespace,
+            args.secret_name
+        );
+        return Ok(());
+    }
+
+    log::info!(
+        "Applying provider-family-azure package '{}'...",
+        args.provider_package
+    );
+    kubectl_apply_stdin(&build_provider_yaml(
+        &args.provider_name,
+        &args.provider_package,
+    ))?;
+
+    wait_for_provider(args)?;
+
+    log::info!(
+        "Applying secret '{}/{}' with generated credentials...",
+        args.namespace,
+        args.secret_name
+    );
+    kubectl_apply_stdin(&build_secret_yaml(
+        &args.namespace,
+        &args.secret_name,
+        &credentials_json,
+    ))?;
+
+    log::info!(
+        "Applying ProviderConfig '{}/{}'...",
+        args.namespace,
+        args.provider_config_name
+    );
+    kubectl_apply_stdin(&build_provider_config_yaml(
+        &args.namespace,
+        &args.provider_config_name,
+        &args.secret_name,
+    ))?;
+
+    log::info!(
+        "Azure provider configured for subscription '{}' (ProviderConfig: {}/{})",
+        creds.subscription_id,
+        args.namespace,
+        args.provider_config_name
+    );
+    Ok(())
+}
+
+/// provider-family-azure only accepts service principal credentials in a
+/// Secret (short-lived `az account get-access-token` tokens are not a
+/// supported source), so use an existing principal or create one.
+fn resolve_credentials(
+    args: &AzureArgs,
+    account: &AzAccount,
+) -> Result<AzureCredentials, Box<dyn Error>> {
+    let client_id = args
+        .client_id
+        .clone()
+        .or_else(|| std::env::var("AZURE_CLIENT_ID").ok())
+        .filter(|id| !id.trim().is_empty());
+    let client_secret = std::env::var("AZURE_CLIENT_SECRET")
+        .ok()
+        .filter(|secret| !secret.trim().is_empty());
+
+    match (client_id, client_secret) {
+        (Some(client_id), Some(client_secret)) => Ok(AzureCredentials {
+            client_id,
+            client_secret,
+            subscription_id: account.id.clone(),
+            tenant_id: account.tenant_id.clone(),
+        }),
+        (Some(_), None) => {
+            Err("AZURE_CLIENT_SECRET must be set when a client ID is given.".into())
+        }
+        (None, _) if args.create_service_principal => {
+            create_service_principal(&args.service_principal_name, account)
+        }
+        (None, _) => Err(
+            "No service principal given. Set AZURE_CLIENT_ID/AZURE_CLIENT_SECRET (or pass `--client-id`), or pass `--create-service-principal` to create one with `az ad sp create-for-rbac`."
+                .into(),
+        ),
+    }
+}
+
+fn create_service_principal(
+    name: &str,
+    account: &AzAccount,
+) -> Result<AzureCredentials, Box<dyn Error>> {
+    let scope = format!("/subscriptions/{}", account.id);
+    let prompt = format!(
+        "Create service principal '{}' with Contributor on {}?",
+        name, scope
+    );
+    if !crate::interactive::confirm(&prompt, false)? {
+        return Err("Service principal creation cancelled.".into());
+    }
+
+    log::info!("Creating service principal '{}'...", name);
+    let output = run_cmd_output_secret(
+        "az",
+        &[
+            "ad",
+            "sp",
+            "create-for-rbac",
+            "--name",
+            name,
+            "--role",
+            "Contributor",
+            "--scopes",
+            &scope,
+            "-o",
+            "json",
+        ],
+    )?;
+    let principal: AzServicePrincipal = serde_json::from_str(&output)
+        .map_err(|err| format!("failed to parse `az ad sp create-for-rbac` output: {}", err))?;
+    log::info!(
+        "Created service principal {}; reuse it with AZURE_CLIENT_ID={} and its secret",
+        name,
+        principal.app_id
+    );
+    Ok(AzureCredentials {
+        client_id: principal.app_id,
+        client_secret: principal.password,
+        subscription_id: account.id.clone(),
+        tenant_id: principal.tenant,
+    })
+}
+
+fn account_show(subscription: Option<&str>) -> Result<AzAccount, Box<dyn Error>> {
+    let output = match run_az_account_show(subscription) {
+        Ok(output) => output,
+        Err(initial_err) => {
+            if !az_login_required(&initial_err) {
+                return Err(format!("failed to read Azure account: {}", initial_err).into());
+            }
+            if !crate::interactive::prompts_allowed() {
+                return Err(format!(
+                    "failed to read Azure account: {}\nAzure CLI login is required, but prompting is unavailable (no terminal or --yes). Run `az login` first.",
+                    initial_err
+                )
+                .into());
+            }
+
+            log::info!("Azure CLI is not logged in. Running `az login`...");
+            run_cmd("az", &["login"]).map_err(|login_err| {
+                format!(
+                    "failed to read Azure account: {}\nAttempted `az login`, but login failed: {}",
+                    initial_err, login_err
+                )
+            })?;
+            run_az_account_show(subscription).map_err(|retry_err| {
+                format!(
+                    "failed to read Azure account: {}\nAttempted `az login` and retried, but it still failed: {}",
+                    initial_err, retry_err
+                )
+            })?
+        }
+    };
+
+    serde_json::from_str(&output)
+        .map_err(|err| format!("failed to parse `az account show` output: {}", err).into())
+}
+
+fn run_az_account_show(subscription: Option<&str>) -> Result<String, String> {
+    let mut args = vec!["account", "show", "-o", "json"];
+    if let Some(subscription) = subscription {
+        args.extend(["--subscription", subscription]);
+    }
+    run_cmd_output("az", &args).map_err(|err| err.to_string())
+}
+
+fn az_login_required(error: &str) -> bool {
+    let lower = error.to_ascii_lowercase();
+    lower.contains("az login") || lower.contains("please run 'az login'")
+}
+
+/// Wait for the provider package and its ProviderConfig CRD together.
+fn wait_for_provider(args: &AzureArgs) -> Result<(), Box<dyn Error>> {
+    log::info!(
+        "Waiting for provider '{}' and CRD {}...",
+        args.provider_name,
+        PROVIDER_CONFIG_CRD
+    );
+    wait_for_all(
+        &[
+            ReadyTarget::provider(&args.provider_name),
+            ReadyTarget::crd(PROVIDER_CONFIG_CRD),
+        ],
+        crate::settings::timeouts(args.timeout)?.providers,
+    )
+}
+
+fn build_credentials_json(creds: &AzureCredentials) -> Result<String, Box<dyn Error>> {
+    serde_json::to_string(&json!({
+        "clientId": creds.client_id,
+        "clientSecret": creds.client_secret,
+        "subscriptionId": creds.subscription_id,
+        "tenantId": creds.tenant_id,
+    }))
+    .map_err(|err| format!("failed to serialize Azure credentials JSON: {}", err).into())
+}
+
+fn build_provider_yaml(provider_name: &str, provider_package: &str) -> String {
+    format!(
+        "apiVersion: pkg.crossplane.io/v1\nkind: Provider\nmetadata:\n  name: {provider_name}\nspec:\n  package: {provider_package}\n"
+    )
+}
+
+fn build_secret_yaml(namespace: &str, secret_name: &str, credentials_json: &str) -> String {
+    let credentials_block = indent_block(credentials_json, 4);
+    format!(
+        "apiVersion: v1\nkind: Secret\nmetadata:\n  name: {secret_name}\n  namespace: {namespace}\ntype: Opaque\nstringData:\n  credentials: |\n{credentials_block}"
+    )
+}
+
+fn build_provider_config_yaml(
+    namespace: &str,
+    provider_config_name: &str,
+    secret_name: &str,
+) -> String {
+    format!(
+        "apiVersion: azure.m.upbound.io/v1beta1\nkind: ProviderConfig\nmetadata:\n  name: {provider_config_name}\n  namespace: {namespace}\nspec:\n  credentials:\n    source: Secret\n    secretRef:\n      namespace: {namespace}\n      name: {secret_name}\n      key: credentials\n"
+    )
+}
+
+fn indent_block(text: &str, spaces: usize) -> String {
+    let pad = " ".repeat(spaces);
+    text.lines()
+        .map(|line| format!("{pad}{line}\n"))
+        .collect::<String>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn az_login_required_detects_missing_login_errors() {
+        assert!(az_login_required(
+            "az exited with exit status: 1: ERROR: Please run 'az login' to setup account."
+        ));
+        assert!(!az_login_required(
+            "az exited with exit status: 1: ERROR: Subscription 'x' not found."
+        ));
+    }
+
+    #[test]
+    fn credentials_json_uses_provider_keys() {
+        let json = build_credentials_json(&AzureCredentials {
+            client_id: "app".to_string(),
+            client_secret: "secret".to_string(),
+            subscription_id: "sub".to_string(),
+            tenant_id: "tenant".to_string(),
+        })
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["clientId"], "app");
+        assert_eq!(value["clientSecret"], "secret");
+        assert_eq!(value["subscriptionId"], "sub");
+        assert_eq!(value["tenantId"], "tenant");
+    }
+
+    #[test]
+    fn provider_config_yaml_uses_secret_ref() {
+        let yaml = build_provider_config_yaml("default", "default", "azure-creds");
+        assert!(yaml.contains("apiVersion: azure.m.upbound.io/v1beta1"));
+        assert!(yaml.contains("kind: ProviderConfig"));
+        assert!(yaml.contains("name: azure-creds"));
+        assert!(yaml.contains("key: credentials"));
+    }
+}
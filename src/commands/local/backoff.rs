@@ -0,0 +1,109 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// First delay between polls; fast enough to catch things that are nearly ready.
+const INITIAL_DELAY: Duration = Duration::from_millis(500);
+/// Longest delay between polls, so a long wait doesn't overshoot by much.
+const MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// Delays between polls that double from `initial` up to `max`, each with
+/// jitter so concurrent waits (e.g. `local start` phases) don't poll the API
+/// server in lockstep, and never sleeping past the deadline.
+#[derive(Debug)]
+pub struct Backoff {
+    delay: Duration,
+    max: Duration,
+    deadline: Instant,
+}
+
+impl Backoff {
+    pub fn new(timeout: Duration) -> Self {
+        Self::with_delays(timeout, INITIAL_DELAY, MAX_DELAY)
+    }
+
+    pub fn with_delays(timeout: Duration, initial: Duration, max: Duration) -> Self {
+        Self {
+            delay: initial.min(max),
+            max,
+            deadline: Instant::now() + timeout,
+        }
+    }
+
+    /// Sleep until the next poll. Returns false, without sleeping, once the
+    /// deadline has passed.
+    pub fn wait(&mut self) -> bool {
+        let Some(remaining) = self.deadline.checked_duration_since(Instant::now()) else {
+            return false;
+        };
+        if remaining.is_zero() {
+            return false;
+        }
+        thread::sleep(self.next_delay().min(remaining));
+        true
+    }
+
+    /// The delay for this poll: between half and all of the current delay,
+    /// which then doubles.
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(self.max);
+        let half = delay / 2;
+        half + half.mul_f64(jitter())
+    }
+}
+
+/// Call `ready` with backoff between calls until it returns true or
+/// `timeout` passes; returns whether it did.
+pub fn poll_until(timeout: Duration, mut ready: impl FnMut() -> bool) -> bool {
+    let mut backoff = Backoff::new(timeout);
+    loop {
+        if ready() {
+            return true;
+        }
+        if !backoff.wait() {
+            return false;
+        }
+    }
+}
+
+/// A value in [0, 1) from the std hasher's random keys, which is all the
+/// randomness jitter needs.
+fn jitter() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_double_with_jitter_up_to_the_max() {
+        let mut backoff = Backoff::with_delays(
+            Duration::from_secs(60),
+            Duration::from_millis(100),
+            Duration::from_millis(400),
+        );
+        let bounds = [(50, 100), (100, 200), (200, 400), (200, 400)];
+        for (low, high) in bounds {
+            let delay = backoff.next_delay();
+            assert!(
+                delay >= Duration::from_millis(low) && delay <= Duration::from_millis(high),
+                "{:?} not in {}..={}ms",
+                delay,
+                low,
+                high
+            );
+        }
+
+        let mut expired = Backoff::new(Duration::ZERO);
+        assert!(!expired.wait());
+        let mut calls = 0;
+        assert!(poll_until(Duration::from_secs(5), || {
+            calls += 1;
+            calls == 2
+        }));
+    }
+}
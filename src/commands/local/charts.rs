@@ -0,0 +1,175 @@
+use super::run_cmd_output;
+use super::upgrade::compare_versions;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// `~/.hops/cache/charts`: Crossplane chart tarballs as `crossplane-<version>.tgz`.
+const CHART_CACHE_DIR: &str = ".hops/cache/charts";
+const CROSSPLANE_REPO: &str = "https://charts.crossplane.io/stable";
+const CROSSPLANE_CHART: &str = "crossplane";
+/// How long a fetched latest chart is reused before checking for a newer one.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The Crossplane chart tarball to install: `version` (or the newest) from
+/// the cache, fetched first unless `offline`. The latest chart is checked at
+/// most once per `REFRESH_INTERVAL`, and a failed fetch falls back to the
+/// cache.
+pub fn crossplane_chart(version: Option<&str>, offline: bool) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = cache_dir()?;
+    let cached = cached_chart(&dir, version);
+    let fresh = match (version, &cached) {
+        (Some(_), Some(_)) => true,
+        (None, Some(chart)) => modified_within(chart, REFRESH_INTERVAL),
+        (_, None) => false,
+    };
+    if offline || fresh {
+        return cached.ok_or_else(|| {
+            format!(
+                "no cached Crossplane chart{} in {}; run `hops local start` once with network access",
+                version.map(|v| format!(" {}", v)).unwrap_or_default(),
+                dir.display()
+            )
+            .into()
+        });
+    }
+    match fetch(&dir, version) {
+        Ok(chart) => Ok(chart),
+        Err(err) => match cached {
+            Some(chart) => {
+                log::warn!(
+                    "Could not fetch the Crossplane chart ({}); using cached {}",
+                    err,
+                    chart.display()
+                );
+                Ok(chart)
+            }
+            None => Err(err),
+        },
+    }
+}
+
+/// Fetch the latest Crossplane chart into the cache and return its version.
+pub fn latest_crossplane_version() -> Result<String, Box<dyn Error>> {
+    let chart = fetch(&cache_dir()?, None)?;
+    chart_version(&chart)
+        .map(str::to_string)
+        .ok_or_else(|| format!("unexpected chart file {}", chart.display()).into())
+}
+
+fn fetch(dir: &Path, version: Option<&str>) -> Result<PathBuf, Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    log::info!(
+        "Fetching Crossplane chart {}...",
+        version.unwrap_or("(latest)")
+    );
+    let dir_arg = dir.display().to_string();
+    let mut args = vec![
+        "pull",
+        CROSSPLANE_CHART,
+        "--repo",
+        CROSSPLANE_REPO,
+        "--destination",
+        &dir_arg,
+    ];
+    if let Some(version) = version {
+        args.extend(["--version", version]);
+    }
+    let started = SystemTime::now();
+    run_cmd_output("helm", &args)?;
+    // `helm pull` doesn't print the file name; it is the one just written.
+    let mut charts = charts(dir);
+    charts.retain(|chart| {
+        fs::metadata(chart)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified >= started - Duration::from_secs(1))
+    });
+    newest(charts).ok_or_else(|| format!("helm pull wrote no chart to {}", dir.display()).into())
+}
+
+fn cached_chart(dir: &Path, version: Option<&str>) -> Option<PathBuf> {
+    let charts = charts(dir);
+    match version {
+        Some(version) => {
+            let version = version.trim_start_matches('v');
+            charts.into_iter().find(|chart| {
+                chart_version(chart).map(|v| v.trim_start_matches('v')) == Some(version)
+            })
+        }
+        None => newest(charts),
+    }
+}
+
+fn charts(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| chart_version(path).is_some())
+        .collect()
+}
+
+/// The chart with the highest version.
+fn newest(charts: Vec<PathBuf>) -> Option<PathBuf> {
+    charts.into_iter().max_by(|a, b| {
+        compare_versions(
+            chart_version(a).unwrap_or_default(),
+            chart_version(b).unwrap_or_default(),
+        )
+    })
+}
+
+/// `1.20.0` for `crossplane-1.20.0.tgz`.
+fn chart_version(path: &Path) -> Option<&str> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix("crossplane-")?
+        .strip_suffix(".tgz")
+        .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+fn modified_within(path: &Path, interval: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < interval)
+}
+
+fn cache_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let home =
+        std::env::var("HOME").map_err(|_| "HOME is not set; unable to find the chart cache")?;
+    Ok(Path::new(&home).join(CHART_CACHE_DIR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_chart_picks_requested_or_newest_version() {
+        let dir = std::env::temp_dir().join(format!("hops-charts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for file in [
+            "crossplane-1.9.3.tgz",
+            "crossplane-1.20.0.tgz",
+            "crossplane-2.0.2.tgz",
+            "crossplane-latest.tgz",
+            "notes.txt",
+        ] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        assert_eq!(
+            cached_chart(&dir, None),
+            Some(dir.join("crossplane-2.0.2.tgz"))
+        );
+        assert_eq!(
+            cached_chart(&dir, Some("v1.20.0")),
+            Some(dir.join("crossplane-1.20.0.tgz"))
+        );
+        assert_eq!(cached_chart(&dir, Some("1.21.0")), None);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,239 @@
+use super::conditions::{get_resource_json, pending_conditions, wait_until, PendingCondition};
+use super::kubectl_apply_stdin;
+use super::trace::ObjectRef;
+use super::wait::XR_CONDITIONS;
+use crate::commands::render::{read_documents, yaml_files};
+use clap::{Args, Subcommand};
+use serde_json::Value as JsonValue;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Where projects keep example XRs and claims, relative to the project
+/// root (`package/` is the crossplane.yaml layout).
+const EXAMPLE_DIRS: &[&str] = &["examples", "package/examples"];
+
+#[derive(Args, Debug)]
+pub struct ClaimArgs {
+    #[command(subcommand)]
+    pub command: ClaimCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ClaimCommands {
+    /// Apply a project's example XRs and claims and wait until they are Ready
+    Apply(ApplyArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ApplyArgs {
+    /// Path to the XRD project directory (defaults to current directory)
+    #[arg(long, conflicts_with = "repo")]
+    pub path: Option<PathBuf>,
+
+    /// GitHub repository in <org>/<repo> format, read from the same cached
+    /// clone `config install --repo` uses
+    #[arg(long)]
+    pub repo: Option<String>,
+
+    /// Branch, tag, or commit of --repo to take the examples from
+    #[arg(long = "ref", value_name = "REF", requires = "repo")]
+    pub git_ref: Option<String>,
+
+    /// Apply only these example files (relative to the project) instead of
+    /// every XR and claim under examples/ (repeatable)
+    #[arg(long = "example", value_name = "FILE")]
+    pub examples: Vec<PathBuf>,
+
+    /// Namespace for claims and namespaced XRs that don't set one
+    #[arg(long, short = 'n', default_value = "default")]
+    pub namespace: String,
+
+    /// How long to wait for the examples to become Ready, e.g. 90s, 10m
+    #[arg(long, default_value = "10m", value_parser = humantime::parse_duration)]
+    pub timeout: Duration,
+
+    /// Return right after applying instead of waiting for Ready
+    #[arg(long)]
+    pub no_wait: bool,
+}
+
+pub fn run(args: &ClaimArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local claim")?;
+    match &args.command {
+        ClaimCommands::Apply(apply_args) => apply(apply_args),
+    }
+}
+
+fn apply(args: &ApplyArgs) -> Result<(), Box<dyn Error>> {
+    let dir = match &args.repo {
+        Some(repo) => crate::commands::config::repo_checkout(repo, args.git_ref.as_deref())?,
+        None => args.path.clone().unwrap_or_else(|| PathBuf::from(".")),
+    };
+    let files: Vec<PathBuf> = if args.examples.is_empty() {
+        EXAMPLE_DIRS
+            .iter()
+            .flat_map(|examples| yaml_files(&dir.join(examples)))
+            .collect()
+    } else {
+        args.examples.iter().map(|file| dir.join(file)).collect()
+    };
+
+    let definitions = project_definitions(&dir);
+    let mut examples = Vec::new();
+    for file in &files {
+        for mut document in read_documents(file)? {
+            if is_example(&document, &definitions) {
+                if document.pointer("/metadata/namespace").is_none() {
+                    document["metadata"]["namespace"] = args.namespace.clone().into();
+                }
+                examples.push(document);
+            }
+        }
+    }
+    if examples.is_empty() {
+        return Err(format!(
+            "no example XRs or claims found in {} (looked in {})",
+            dir.display(),
+            EXAMPLE_DIRS.join(", ")
+        )
+        .into());
+    }
+
+    let targets: Vec<ObjectRef> = examples.iter().map(object_ref).collect();
+    for (target, example) in targets.iter().zip(&examples) {
+        log::info!("Applying {}...", label(target));
+        kubectl_apply_stdin(&serde_yaml::to_string(example)?)?;
+    }
+    if args.no_wait {
+        crate::logging::success(&format!("Applied {} example(s)", targets.len()));
+        return Ok(());
+    }
+
+    log::info!(
+        "Waiting up to {} for {} example(s) to become Ready...",
+        humantime::format_duration(args.timeout),
+        targets.len()
+    );
+    wait_until(args.timeout, || {
+        targets.iter().flat_map(pending_example).collect()
+    })
+    .map_err(|pending| {
+        let mut lines = vec![format!(
+            "examples not ready after {}:",
+            humantime::format_duration(args.timeout)
+        )];
+        lines.extend(pending.iter().map(|condition| format!("  {}", condition)));
+        if let Some(first) = pending.first() {
+            lines.push(format!(
+                "Run `hops local trace {}` to see which composed resources are stuck",
+                first.resource
+            ));
+        }
+        lines.join("\n")
+    })?;
+
+    crate::logging::success(&format!(
+        "{} ready",
+        targets.iter().map(label).collect::<Vec<_>>().join(", ")
+    ));
+    Ok(())
+}
+
+/// `(group, kind)` of every XR and claim type the project's XRDs define.
+fn project_definitions(dir: &Path) -> Vec<(String, String)> {
+    let mut definitions = Vec::new();
+    for file in yaml_files(dir) {
+        for xrd in read_documents(&file).unwrap_or_default() {
+            if xrd["kind"] != "CompositeResourceDefinition" {
+                continue;
+            }
+            let group = xrd.pointer("/spec/group").and_then(JsonValue::as_str);
+            for kind in ["/spec/names/kind", "/spec/claimNames/kind"] {
+                if let (Some(group), Some(kind)) =
+                    (group, xrd.pointer(kind).and_then(JsonValue::as_str))
+                {
+                    definitions.push((group.to_string(), kind.to_string()));
+                }
+            }
+        }
+    }
+    definitions
+}
+
+/// Whether `document` is an XR or claim of one of the project's types. When
+/// the project defines none (e.g. a repo of examples for another package),
+/// every named object counts.
+fn is_example(document: &JsonValue, definitions: &[(String, String)]) -> bool {
+    let (Some(api_version), Some(kind)) =
+        (document["apiVersion"].as_str(), document["kind"].as_str())
+    else {
+        return false;
+    };
+    if document.pointer("/metadata/name").is_none() {
+        return false;
+    }
+    let group = api_version.split_once('/').map_or("", |(group, _)| group);
+    definitions.is_empty()
+        || definitions
+            .iter()
+            .any(|(defined_group, defined_kind)| defined_group == group && defined_kind == kind)
+}
+
+fn object_ref(document: &JsonValue) -> ObjectRef {
+    let field = |pointer: &str| {
+        document
+            .pointer(pointer)
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    ObjectRef {
+        api_version: field("/apiVersion"),
+        kind: field("/kind"),
+        name: field("/metadata/name"),
+        namespace: Some(field("/metadata/namespace")),
+    }
+}
+
+fn label(target: &ObjectRef) -> String {
+    format!("{}/{}", target.kubectl_resource(), target.name)
+}
+
+fn pending_example(target: &ObjectRef) -> Vec<PendingCondition> {
+    let label = label(target);
+    match get_resource_json(
+        &target.kubectl_resource(),
+        &target.name,
+        target.namespace.as_deref(),
+    ) {
+        Some(object) => pending_conditions(&label, &object, XR_CONDITIONS),
+        None => vec![PendingCondition::not_found(&label)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn is_example_matches_project_xr_and_claim_kinds() {
+        let definitions = vec![
+            ("aws.hops.ops.com.ai".to_string(), "XNetwork".to_string()),
+            ("aws.hops.ops.com.ai".to_string(), "Network".to_string()),
+        ];
+        let document = |api_version: &str, kind: &str| json!({"apiVersion": api_version, "kind": kind, "metadata": {"name": "demo"}});
+        assert!(is_example(
+            &document("aws.hops.ops.com.ai/v1alpha1", "Network"),
+            &definitions
+        ));
+        assert!(is_example(
+            &document("aws.hops.ops.com.ai/v1alpha1", "XNetwork"),
+            &definitions
+        ));
+        assert!(!is_example(&document("v1", "Secret"), &definitions));
+        assert!(is_example(&document("v1", "Secret"), &[]));
+        assert!(!is_example(&json!({"kind": "Network"}), &[]));
+    }
+}
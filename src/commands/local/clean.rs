@@ -0,0 +1,160 @@
+use super::du::{dir_size, format_bytes, hops_docker_images, leftover_temp_dirs};
+use super::{local_state_dir, run_cmd_output, REPO_CACHE_DIR};
+use clap::Args;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+#[derive(Args, Debug)]
+pub struct CleanArgs {
+    /// Only list what would be removed
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Remove cached repo clones that have not been fetched for this long (e.g. "30d")
+    #[arg(long, default_value = "30d", value_parser = humantime::parse_duration)]
+    pub clones_older_than: Duration,
+}
+
+pub fn run(args: &CleanArgs) -> Result<(), Box<dyn Error>> {
+    let verb = if args.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    let mut reclaimed = 0u64;
+
+    // Images share layers, so their sizes are an upper bound.
+    let mut image_bytes = 0u64;
+    let mut image_count = 0usize;
+    for image in hops_docker_images() {
+        if !args.dry_run {
+            if let Err(err) = run_cmd_output("docker", &["image", "rm", &image.reference]) {
+                log::warn!("Failed to remove image {}: {}", image.reference, err);
+                continue;
+            }
+        }
+        println!("  {}", image.reference);
+        image_bytes += image.size_bytes;
+        image_count += 1;
+    }
+    println!(
+        "{} {} Docker image(s), up to {}",
+        verb,
+        image_count,
+        format_bytes(image_bytes)
+    );
+    reclaimed += image_bytes;
+
+    let temp_dirs = leftover_temp_dirs();
+    reclaimed += remove_dirs(&temp_dirs, "build dir", args.dry_run, verb);
+
+    let cache_root = local_state_dir()?.join(REPO_CACHE_DIR);
+    let clones = stale_clones(&cache_root, args.clones_older_than, SystemTime::now());
+    reclaimed += remove_dirs(&clones, "repo clone", args.dry_run, verb);
+    if !args.dry_run {
+        remove_empty_org_dirs(&cache_root);
+    }
+
+    crate::logging::success(&format!(
+        "{} up to {} of host-side artifacts",
+        if args.dry_run {
+            "Would reclaim"
+        } else {
+            "Reclaimed"
+        },
+        format_bytes(reclaimed)
+    ));
+    Ok(())
+}
+
+/// Remove each directory, returning the bytes freed.
+fn remove_dirs(dirs: &[PathBuf], label: &str, dry_run: bool, verb: &str) -> u64 {
+    let mut bytes = 0u64;
+    let mut count = 0usize;
+    for dir in dirs {
+        let size = dir_size(dir);
+        if !dry_run {
+            if let Err(err) = fs::remove_dir_all(dir) {
+                log::warn!("Failed to remove {}: {}", dir.display(), err);
+                continue;
+            }
+        }
+        println!("  {}", dir.display());
+        bytes += size;
+        count += 1;
+    }
+    println!("{} {} {}(s), {}", verb, count, label, format_bytes(bytes));
+    bytes
+}
+
+/// `<org>/<repo>` clones under `cache_root` not fetched within `max_age`.
+fn stale_clones(cache_root: &Path, max_age: Duration, now: SystemTime) -> Vec<PathBuf> {
+    let mut stale = Vec::new();
+    for org in read_dirs(cache_root) {
+        for clone in read_dirs(&org) {
+            let last_used = last_used(&clone);
+            if now.duration_since(last_used).unwrap_or_default() >= max_age {
+                stale.push(clone);
+            }
+        }
+    }
+    stale.sort();
+    stale
+}
+
+/// Last fetch of a clone, falling back to the directory's mtime.
+fn last_used(clone: &Path) -> SystemTime {
+    [
+        clone.join(".git/FETCH_HEAD"),
+        clone.join(".git"),
+        clone.to_path_buf(),
+    ]
+    .iter()
+    .find_map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+    .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+fn read_dirs(path: &Path) -> Vec<PathBuf> {
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn remove_empty_org_dirs(cache_root: &Path) {
+    for org in read_dirs(cache_root) {
+        // Fails (and is ignored) when the org still has clones.
+        let _ = fs::remove_dir(org);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_clones_uses_age_of_last_fetch() {
+        let root = std::env::temp_dir().join(format!("hops-clean-test-{}", std::process::id()));
+        fs::create_dir_all(root.join("hops-ops/network/.git")).unwrap();
+        fs::write(root.join("hops-ops/network/.git/FETCH_HEAD"), "").unwrap();
+        fs::create_dir_all(root.join("hops-ops/eks")).unwrap();
+
+        let now = SystemTime::now() + Duration::from_secs(3600);
+        let day = Duration::from_secs(86400);
+        assert!(stale_clones(&root, day, now).is_empty());
+        assert_eq!(
+            stale_clones(&root, Duration::from_secs(60), now),
+            [root.join("hops-ops/eks"), root.join("hops-ops/network")]
+        );
+        assert!(stale_clones(&root.join("missing"), day, now).is_empty());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
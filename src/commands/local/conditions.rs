@@ -0,0 +1,278 @@
+use super::backoff::{poll_until, Backoff};
+use super::run_cmd_output;
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::error::Error;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest delay between condition checks in `wait_until`.
+const MAX_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// A status condition that is not yet True.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PendingCondition {
+    pub resource: String,
+    pub condition_type: String,
+    pub reason: String,
+    pub message: String,
+}
+
+impl PendingCondition {
+    /// Placeholder for an object that does not exist (yet).
+    pub fn not_found(resource: &str) -> Self {
+        Self {
+            resource: resource.to_string(),
+            condition_type: "Exists".to_string(),
+            reason: "NotFound".to_string(),
+            message: String::new(),
+        }
+    }
+}
+
+impl std::fmt::Display for PendingCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}={}",
+            self.resource, self.condition_type, self.reason
+        )?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Poll `check` until it reports no pending conditions or `timeout` passes.
+/// New conditions with a message are logged as warnings once. On timeout the
+/// last pending conditions are returned.
+pub fn wait_until<F>(timeout: Duration, mut check: F) -> Result<(), Vec<PendingCondition>>
+where
+    F: FnMut() -> Vec<PendingCondition>,
+{
+    let mut backoff = Backoff::with_delays(timeout, Duration::from_millis(500), MAX_POLL_INTERVAL);
+    let mut reported = HashSet::new();
+    loop {
+        let pending = check();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        for condition in &pending {
+            if !condition.message.is_empty() && reported.insert(condition.clone()) {
+                log::warn!("{}", condition);
+            }
+        }
+
+        if !backoff.wait() {
+            return Err(pending);
+        }
+    }
+}
+
+/// An object to wait for: `kind/name` (in `namespace`) with `condition` True.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReadyTarget {
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+    pub condition: String,
+}
+
+impl ReadyTarget {
+    /// A CRD that is established, so objects of its kind can be applied.
+    pub fn crd(name: &str) -> Self {
+        Self::new("crd", name, None, "Established")
+    }
+
+    pub fn deployment(namespace: &str, name: &str) -> Self {
+        Self::new("deployment", name, Some(namespace), "Available")
+    }
+
+    /// A Provider package whose revision is installed (its CRDs exist).
+    pub fn provider(name: &str) -> Self {
+        Self::new("provider.pkg.crossplane.io", name, None, "Installed")
+    }
+
+    fn new(kind: &str, name: &str, namespace: Option<&str>, condition: &str) -> Self {
+        Self {
+            kind: kind.to_string(),
+            name: name.to_string(),
+            namespace: namespace.map(str::to_string),
+            condition: condition.to_string(),
+        }
+    }
+
+    fn target(&self) -> String {
+        format!("{}/{}", self.kind, self.name)
+    }
+
+    /// Wait until the object exists and its condition is True. Existence is
+    /// polled with backoff; the condition itself is left to `kubectl wait`,
+    /// which watches the object instead of polling it. The error says what
+    /// is still missing.
+    fn wait(&self, timeout: Duration) -> Result<(), String> {
+        let deadline = Instant::now() + timeout;
+        let target = self.target();
+        let mut args = vec!["get", target.as_str(), "-o", "name"];
+        if let Some(namespace) = &self.namespace {
+            args.extend(["-n", namespace]);
+        }
+        if !poll_until(timeout, || run_cmd_output("kubectl", &args).is_ok()) {
+            return Err(format!("{} to exist", target));
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let for_condition = format!("--for=condition={}", self.condition);
+        let timeout_arg = format!("--timeout={}s", remaining.as_secs().max(1));
+        let mut args = vec!["wait", target.as_str(), &for_condition, &timeout_arg];
+        if let Some(namespace) = &self.namespace {
+            args.extend(["-n", namespace]);
+        }
+        run_cmd_output("kubectl", &args)
+            .map(|_| ())
+            .map_err(|_| format!("{} to be {}", target, self.condition))
+    }
+}
+
+/// Wait for every target at once, so the slowest one bounds the wait rather
+/// than the sum of them. On timeout the error names only the targets that
+/// are still pending.
+pub fn wait_for_all(targets: &[ReadyTarget], timeout: Duration) -> Result<(), Box<dyn Error>> {
+    let pending: Vec<String> = thread::scope(|scope| {
+        let handles: Vec<_> = targets
+            .iter()
+            .map(|target| {
+                scope.spawn(move || {
+                    let result = target.wait(timeout);
+                    if result.is_ok() && targets.len() > 1 {
+                        log::info!("{} is {}", target.target(), target.condition);
+                    }
+                    result
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| match handle.join() {
+                Ok(result) => result.err(),
+                Err(_) => Some("a readiness check that panicked".to_string()),
+            })
+            .collect()
+    });
+    if pending.is_empty() {
+        Ok(())
+    } else {
+        Err(timed_out(&pending.join(", "), timeout).into())
+    }
+}
+
+/// "Timed out after 5m waiting for <what>", with a pointer to the settings
+/// that raise the limit.
+pub fn timed_out(what: &str, timeout: Duration) -> String {
+    format!(
+        "Timed out after {} waiting for {} (raise it with --timeout or `timeouts:` in ~/.hops/config.yaml)",
+        humantime::format_duration(timeout),
+        what
+    )
+}
+
+/// Wait until a Deployment is Available.
+pub fn wait_for_deployment(
+    namespace: &str,
+    name: &str,
+    timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+    wait_for_all(&[ReadyTarget::deployment(namespace, name)], timeout)
+}
+
+/// `kubectl get <resource> <name> -o json`, or None when it cannot be read.
+pub fn get_resource_json(resource: &str, name: &str, namespace: Option<&str>) -> Option<JsonValue> {
+    let mut args = vec!["get", resource, name, "-o", "json"];
+    if let Some(namespace) = namespace {
+        args.extend(["-n", namespace]);
+    }
+    run_cmd_output("kubectl", &args)
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+/// Required conditions on `object` that are missing or not True.
+pub fn pending_conditions(
+    resource: &str,
+    object: &JsonValue,
+    required: &[&str],
+) -> Vec<PendingCondition> {
+    let conditions = object
+        .pointer("/status/conditions")
+        .and_then(JsonValue::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let field = |condition: &JsonValue, key: &str| {
+        condition
+            .get(key)
+            .and_then(JsonValue::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    required
+        .iter()
+        .filter_map(|condition_type| {
+            let condition = conditions
+                .iter()
+                .find(|c| c.get("type").and_then(JsonValue::as_str) == Some(condition_type));
+            match condition {
+                Some(c) if field(c, "status") == "True" => None,
+                Some(c) => Some(PendingCondition {
+                    resource: resource.to_string(),
+                    condition_type: condition_type.to_string(),
+                    reason: field(c, "reason"),
+                    message: field(c, "message"),
+                }),
+                None => Some(PendingCondition {
+                    resource: resource.to_string(),
+                    condition_type: condition_type.to_string(),
+                    reason: "Unknown".to_string(),
+                    message: String::new(),
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn pending_conditions_reports_missing_and_false_conditions() {
+        let object = json!({
+            "status": {
+                "conditions": [
+                    {"type": "Installed", "status": "True", "reason": "ActivePackageRevision"},
+                    {
+                        "type": "Healthy",
+                        "status": "False",
+                        "reason": "UnhealthyPackageRevision",
+                        "message": "cannot resolve package dependencies: incompatible dependencies"
+                    }
+                ]
+            }
+        });
+
+        let pending = pending_conditions("configuration/test", &object, &["Installed", "Healthy"]);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].condition_type, "Healthy");
+        assert_eq!(pending[0].reason, "UnhealthyPackageRevision");
+        assert_eq!(
+            pending[0].to_string(),
+            "configuration/test Healthy=UnhealthyPackageRevision: cannot resolve package dependencies: incompatible dependencies"
+        );
+
+        let pending = pending_conditions("configuration/test", &json!({}), &["Healthy"]);
+        assert_eq!(pending[0].reason, "Unknown");
+    }
+}
@@ -0,0 +1,61 @@
+use super::{profile, run_cmd_output, HOPS_KUBE_CONTEXT_ENV};
+use std::error::Error;
+
+/// The kube context `hops local start` creates for the Colima cluster:
+/// `colima`, or `colima-<profile>` for another `--colima-profile`.
+pub fn local_context() -> String {
+    profile::instance_name(&profile::current())
+}
+
+/// The context kubectl commands will act on: `HOPS_KUBE_CONTEXT` (set by
+/// `--context`) when given, else kubeconfig's current context.
+pub fn target_context() -> Result<String, Box<dyn Error>> {
+    if let Ok(context) = std::env::var(HOPS_KUBE_CONTEXT_ENV) {
+        if !context.is_empty() {
+            return Ok(context);
+        }
+    }
+    run_cmd_output("kubectl", &["config", "current-context"])
+        .map(|context| context.trim().to_string())
+        .map_err(|_| {
+            "no kube context is set; run `hops local start` to create the Colima context".into()
+        })
+}
+
+/// Refuse to go on unless `command` would act on the local cluster or a
+/// context listed in `allowed-contexts:` in ~/.hops/config.yaml, so it
+/// can't change a shared or production cluster by accident.
+pub fn ensure_local(command: &str) -> Result<(), Box<dyn Error>> {
+    let context = target_context()?;
+    let allowed = crate::settings::load()?.allowed_contexts;
+    check(command, &context, &local_context(), &allowed)?;
+    Ok(())
+}
+
+fn check(command: &str, context: &str, local: &str, allowed: &[String]) -> Result<(), String> {
+    if context == local || allowed.iter().any(|a| a == context) {
+        return Ok(());
+    }
+    Err(format!(
+        "refusing to run `{}` against kube context '{}': hops only changes the local cluster ('{}') unless told otherwise. Switch with `kubectl config use-context {}`, pass --context {}, or add '{}' to `allowed-contexts:` in ~/.hops/config.yaml",
+        command, context, local, local, local, context
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_local_and_allowed_contexts_pass() {
+        let allowed = vec!["kind-dev".to_string()];
+        assert!(check("config install", "colima", "colima", &[]).is_ok());
+        assert!(check("config install", "kind-dev", "colima", &allowed).is_ok());
+
+        let err = check("config uninstall", "prod-eks", "colima", &allowed).unwrap_err();
+        assert!(
+            err.starts_with("refusing to run `config uninstall` against kube context 'prod-eks'")
+        );
+        assert!(err.contains("add 'prod-eks' to `allowed-contexts:`"));
+    }
+}
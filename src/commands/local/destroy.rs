@@ -1,9 +1,17 @@
-use super::run_cmd;
+use super::start::StartState;
+use super::stop::stop_forwarding;
+use super::{idle, mock, profile, run_cmd};
 use std::error::Error;
 
 pub fn run() -> Result<(), Box<dyn Error>> {
+    profile::ensure_owned("local destroy")?;
+    stop_forwarding();
+    idle::stop_watchdog()?;
+    mock::forget()?;
     log::info!("Destroying Colima VM...");
     run_cmd("colima", &["delete", "--force"])?;
+    StartState::remove()?;
+    profile::forget_owned()?;
     log::info!("Colima VM destroyed");
     Ok(())
 }
@@ -0,0 +1,272 @@
+use super::{command_exists, parse_df_kib, registry, run_cmd_output};
+use clap::Args;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Repository prefix of images hops builds on the host; images tagged for
+/// the registry (`localhost:<node port>/`) are hops' too.
+const HOPS_IMAGE_PREFIX: &str = "hops-local/";
+/// Prefix of the temporary build directories created by `config install`.
+const TEMP_DIR_PREFIX: &str = "hops-cli-config-";
+
+const KIB: u64 = 1024;
+
+#[derive(Args, Debug)]
+pub struct DuArgs {
+    /// Also print commands that reclaim the reported space
+    #[arg(long)]
+    pub prune: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(super) struct DockerImage {
+    pub(super) reference: String,
+    pub(super) size_bytes: u64,
+}
+
+pub fn run(args: &DuArgs) -> Result<(), Box<dyn Error>> {
+    report_vm_disk();
+    report_registry();
+    let images = report_docker_images();
+    let temp_dirs = report_temp_dirs();
+
+    if args.prune {
+        print_prune_suggestions(&images, &temp_dirs);
+    }
+    Ok(())
+}
+
+fn report_vm_disk() {
+    if !command_exists("colima") {
+        println!("Colima VM:    colima not installed");
+        return;
+    }
+    match run_cmd_output("colima", &["ssh", "--", "df", "-Pk", "/"])
+        .ok()
+        .and_then(|output| parse_df_kib(&output))
+    {
+        Some(usage) => println!(
+            "Colima VM:    {} used, {} free on /",
+            format_bytes(usage.used_kib * KIB),
+            format_bytes(usage.available_kib * KIB)
+        ),
+        None => println!("Colima VM:    unavailable (is the VM running?)"),
+    }
+}
+
+fn report_registry() {
+    match registry::storage_kib() {
+        Some(kib) => {
+            let repositories = registry::catalog().map(|r| r.len()).ok();
+            println!(
+                "Registry:     {} in {}{}",
+                format_bytes(kib * KIB),
+                registry::REGISTRY_STORAGE,
+                repositories
+                    .map(|count| format!(" ({} repositories)", count))
+                    .unwrap_or_default()
+            );
+        }
+        None => println!("Registry:     unavailable (is the cluster running?)"),
+    }
+}
+
+/// Docker images hops built or tagged on the host.
+pub(super) fn hops_docker_images() -> Vec<DockerImage> {
+    run_cmd_output(
+        "docker",
+        &[
+            "image",
+            "ls",
+            "--format",
+            "{{.Repository}}:{{.Tag}}\t{{.Size}}",
+        ],
+    )
+    .map(|output| parse_docker_images(&output, &super::registry::push_host()))
+    .unwrap_or_default()
+}
+
+fn report_docker_images() -> Vec<DockerImage> {
+    let images = hops_docker_images();
+
+    // Images share layers, so this is an upper bound rather than exact usage.
+    let total: u64 = images.iter().map(|image| image.size_bytes).sum();
+    println!(
+        "Docker:       {} hops image(s), up to {}",
+        images.len(),
+        format_bytes(total)
+    );
+    for image in &images {
+        println!(
+            "  {:<70} {}",
+            image.reference,
+            format_bytes(image.size_bytes)
+        );
+    }
+    images
+}
+
+/// Build directories `config install` left in the system temp dir.
+pub(super) fn leftover_temp_dirs() -> Vec<PathBuf> {
+    fs::read_dir(std::env::temp_dir())
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|entry| {
+                    entry
+                        .file_name()
+                        .to_string_lossy()
+                        .starts_with(TEMP_DIR_PREFIX)
+                })
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn report_temp_dirs() -> Vec<PathBuf> {
+    let temp = std::env::temp_dir();
+    let dirs = leftover_temp_dirs();
+
+    let total: u64 = dirs.iter().map(|dir| dir_size(dir)).sum();
+    println!(
+        "Temp dirs:    {} leftover build dir(s) in {}, {}",
+        dirs.len(),
+        temp.display(),
+        format_bytes(total)
+    );
+    dirs
+}
+
+fn print_prune_suggestions(images: &[DockerImage], temp_dirs: &[PathBuf]) {
+    println!();
+    println!("To reclaim space:");
+    if !images.is_empty() {
+        let references = images
+            .iter()
+            .map(|image| image.reference.as_str())
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("  docker image rm {}", references);
+    }
+    if !temp_dirs.is_empty() {
+        let paths = temp_dirs
+            .iter()
+            .map(|dir| dir.display().to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("  rm -rf {}", paths);
+    }
+    println!("  docker image prune");
+    println!("  hops local registry gc");
+    println!("  hops local destroy   # last resort: recreates the VM disk from scratch");
+}
+
+/// Parse `docker image ls` lines of `<repo>:<tag>\t<size>`, keeping
+/// only images hops created.
+fn parse_docker_images(output: &str, push_host: &str) -> Vec<DockerImage> {
+    let push_prefix = format!("{}/", push_host);
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let reference = fields.next()?.trim();
+            let size = fields.next()?.trim();
+            if !reference.starts_with(HOPS_IMAGE_PREFIX) && !reference.starts_with(&push_prefix) {
+                return None;
+            }
+            Some(DockerImage {
+                reference: reference.to_string(),
+                size_bytes: parse_docker_size(size)?,
+            })
+        })
+        .collect()
+}
+
+/// Parse Docker's human-readable (SI) sizes such as `512kB`, `12.3MB`, `1.2GB`.
+fn parse_docker_size(size: &str) -> Option<u64> {
+    let split = size
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(size.len());
+    let (number, unit) = size.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some((number * multiplier) as u64)
+}
+
+pub(super) fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| dir_size(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+pub(super) fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_docker_images_keeps_hops_images() {
+        let output = "hops-local/config-patched-abc:123\t1.5MB\nlocalhost:30500/hops-ops/test:dev-1\t512kB\nubuntu:latest\t78MB\n";
+        let images = parse_docker_images(output, "localhost:30500");
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].reference, "hops-local/config-patched-abc:123");
+        assert_eq!(images[0].size_bytes, 1_500_000);
+        assert_eq!(images[1].size_bytes, 512_000);
+    }
+
+    #[test]
+    fn parse_df_kib_reads_posix_output() {
+        let output = "Filesystem 1024-blocks Used Available Capacity Mounted on\n/dev/vda1 61255492 40123456 18000000 70% /\n";
+        assert_eq!(
+            parse_df_kib(output),
+            Some(crate::commands::local::DiskUsage {
+                used_kib: 40123456,
+                available_kib: 18000000
+            })
+        );
+        assert_eq!(parse_df_kib(""), None);
+    }
+
+    #[test]
+    fn parse_docker_size_and_format_bytes() {
+        assert_eq!(parse_docker_size("1.2GB"), Some(1_200_000_000));
+        assert_eq!(parse_docker_size("0B"), Some(0));
+        assert_eq!(parse_docker_size("lots"), None);
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+}
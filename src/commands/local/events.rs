@@ -0,0 +1,330 @@
+use super::{kubectl_command, run_cmd_output};
+use clap::Args;
+use colored::Colorize;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::error::Error;
+use std::io::BufReader;
+use std::process::Stdio;
+
+#[derive(Args, Debug)]
+pub struct EventsArgs {
+    /// Only show events for one object, as <kind>/<name> (e.g.
+    /// configuration/hops-ops-aws); revisions of a package are included
+    #[arg(long = "for", value_name = "KIND/NAME")]
+    pub for_object: Option<String>,
+
+    /// Print current events and exit instead of streaming new ones
+    #[arg(long)]
+    pub no_watch: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Event {
+    #[serde(default)]
+    metadata: EventMetadata,
+    #[serde(default)]
+    involved_object: InvolvedObject,
+    #[serde(default, rename = "type")]
+    event_type: String,
+    #[serde(default)]
+    reason: String,
+    #[serde(default)]
+    message: String,
+    last_timestamp: Option<String>,
+    event_time: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventMetadata {
+    #[serde(default)]
+    uid: String,
+    /// Changes each time the event recurs (its count is bumped).
+    #[serde(default)]
+    resource_version: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvolvedObject {
+    #[serde(default)]
+    api_version: String,
+    #[serde(default)]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    namespace: String,
+}
+
+/// Which events to show.
+#[derive(Debug)]
+enum EventFilter {
+    /// Crossplane packages/revisions plus composite resources by kind.
+    Crossplane { xr_kinds: HashSet<String> },
+    /// A single object and, for packages, its revisions.
+    Object { kind: String, name: String },
+}
+
+impl EventFilter {
+    fn matches(&self, object: &InvolvedObject) -> bool {
+        match self {
+            EventFilter::Crossplane { xr_kinds } => {
+                let group = object.api_version.split('/').next().unwrap_or_default();
+                group.ends_with("crossplane.io") || xr_kinds.contains(&object.kind)
+            }
+            EventFilter::Object { kind, name } => {
+                // Accept singular or plural resource names (`configuration`,
+                // `configurations`).
+                let kind_matches = |object_kind: &str| {
+                    *kind == object_kind || *kind == format!("{}s", object_kind)
+                };
+                let object_kind = object.kind.to_ascii_lowercase();
+                if kind_matches(&object_kind) {
+                    return object.name == *name;
+                }
+                // ConfigurationRevision/ProviderRevision/FunctionRevision are
+                // named <package>-<hash>.
+                object_kind
+                    .strip_suffix("revision")
+                    .is_some_and(kind_matches)
+                    && object
+                        .name
+                        .strip_prefix(name.as_str())
+                        .and_then(|rest| rest.strip_prefix('-'))
+                        .is_some_and(|hash| {
+                            !hash.is_empty() && hash.chars().all(|c| c.is_ascii_alphanumeric())
+                        })
+            }
+        }
+    }
+}
+
+pub fn run(args: &EventsArgs) -> Result<(), Box<dyn Error>> {
+    let filter = match args.for_object.as_deref() {
+        Some(spec) => parse_object_filter(spec)?,
+        None => EventFilter::Crossplane {
+            xr_kinds: composite_kinds(),
+        },
+    };
+
+    let mut kubectl_args = vec!["get", "events", "-A", "-o", "json"];
+    if !args.no_watch {
+        kubectl_args.push("--watch");
+        log::info!("Streaming events (Ctrl+C to stop)...");
+    }
+
+    let mut child = kubectl_command(&kubectl_args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or("failed to capture kubectl output")?;
+
+    // `kubectl get -o json` prints a List; with --watch it prints one JSON
+    // object per event instead.
+    for value in serde_json::Deserializer::from_reader(BufReader::new(stdout)).into_iter() {
+        let value: JsonValue = value?;
+        let events = match value.get("items").and_then(JsonValue::as_array) {
+            Some(items) => items.clone(),
+            None => vec![value],
+        };
+        for event in events {
+            let Ok(event) = serde_json::from_value::<Event>(event) else {
+                continue;
+            };
+            if filter.matches(&event.involved_object) {
+                println!("{}", format_event(&event));
+            }
+        }
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("kubectl exited with {}", status).into());
+    }
+    Ok(())
+}
+
+/// Recent Warning events for one object (and its package revisions),
+/// formatted like `local events`, oldest first. Errors yield an empty list.
+pub fn recent_warnings(kind: &str, name: &str, limit: usize) -> Vec<String> {
+    let filter = EventFilter::Object {
+        kind: kind.to_ascii_lowercase(),
+        name: name.to_string(),
+    };
+    let Ok(raw) = run_cmd_output("kubectl", &["get", "events", "-A", "-o", "json"]) else {
+        return Vec::new();
+    };
+    let Ok(list) = serde_json::from_str::<JsonValue>(&raw) else {
+        return Vec::new();
+    };
+
+    let mut events = list
+        .get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| serde_json::from_value::<Event>(item.clone()).ok())
+        .filter(|event| event.event_type == "Warning" && filter.matches(&event.involved_object))
+        .collect::<Vec<_>>();
+    events.sort_by(|a, b| event_timestamp(a).cmp(event_timestamp(b)));
+    let skip = events.len().saturating_sub(limit);
+    events.iter().skip(skip).map(format_event).collect()
+}
+
+/// Events about any of `objects` (`(kind, name)` pairs), oldest first, as
+/// `(key, line)` with lines formatted like `local events`. The key changes
+/// when an event recurs. Errors yield an empty list.
+pub fn events_for(objects: &HashSet<(String, String)>) -> Vec<(String, String)> {
+    let Ok(raw) = run_cmd_output("kubectl", &["get", "events", "-A", "-o", "json"]) else {
+        return Vec::new();
+    };
+    let Ok(list) = serde_json::from_str::<JsonValue>(&raw) else {
+        return Vec::new();
+    };
+    let mut events = list
+        .get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| serde_json::from_value::<Event>(item.clone()).ok())
+        .filter(|event| {
+            let object = &event.involved_object;
+            objects.contains(&(object.kind.clone(), object.name.clone()))
+        })
+        .collect::<Vec<_>>();
+    events.sort_by(|a, b| event_timestamp(a).cmp(event_timestamp(b)));
+    events
+        .iter()
+        .map(|event| {
+            (
+                format!("{}/{}", event.metadata.uid, event.metadata.resource_version),
+                format_event(event),
+            )
+        })
+        .collect()
+}
+
+fn event_timestamp(event: &Event) -> &str {
+    event
+        .last_timestamp
+        .as_deref()
+        .or(event.event_time.as_deref())
+        .unwrap_or("-")
+}
+
+fn parse_object_filter(spec: &str) -> Result<EventFilter, Box<dyn Error>> {
+    let (kind, name) = spec
+        .split_once('/')
+        .filter(|(kind, name)| !kind.trim().is_empty() && !name.trim().is_empty())
+        .ok_or_else(|| format!("`--for` must be <kind>/<name>, got '{}'", spec))?;
+    Ok(EventFilter::Object {
+        // Drop the group from names like `configuration.pkg.crossplane.io`.
+        kind: kind
+            .trim()
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase(),
+        name: name.trim().to_string(),
+    })
+}
+
+/// Kinds served by CompositeResourceDefinitions in the cluster.
+fn composite_kinds() -> HashSet<String> {
+    run_cmd_output(
+        "kubectl",
+        &[
+            "get",
+            "compositeresourcedefinitions.apiextensions.crossplane.io",
+            "-o",
+            "jsonpath={range .items[*]}{.spec.names.kind}{\"\\n\"}{end}",
+        ],
+    )
+    .map(|output| {
+        output
+            .lines()
+            .map(str::trim)
+            .filter(|kind| !kind.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn format_event(event: &Event) -> String {
+    let timestamp = event_timestamp(event);
+    let event_type = if event.event_type == "Warning" {
+        event.event_type.yellow().bold()
+    } else {
+        event.event_type.normal()
+    };
+    let object = &event.involved_object;
+    let location = if object.namespace.is_empty() {
+        String::new()
+    } else {
+        format!(" -n {}", object.namespace)
+    };
+    format!(
+        "{} {} {} {}/{}{}: {}",
+        timestamp,
+        event_type,
+        event.reason,
+        object.kind,
+        object.name,
+        location,
+        event.message.trim()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(api_version: &str, kind: &str, name: &str) -> InvolvedObject {
+        InvolvedObject {
+            api_version: api_version.to_string(),
+            kind: kind.to_string(),
+            name: name.to_string(),
+            namespace: String::new(),
+        }
+    }
+
+    #[test]
+    fn object_filter_matches_package_and_its_revisions() {
+        let filter = parse_object_filter("configurations/hops-ops-aws").unwrap();
+        assert!(filter.matches(&object(
+            "pkg.crossplane.io/v1",
+            "Configuration",
+            "hops-ops-aws"
+        )));
+        assert!(filter.matches(&object(
+            "pkg.crossplane.io/v1",
+            "ConfigurationRevision",
+            "hops-ops-aws-1a2b3c"
+        )));
+        assert!(!filter.matches(&object(
+            "pkg.crossplane.io/v1",
+            "ConfigurationRevision",
+            "hops-ops-aws-network-1a2b3c"
+        )));
+        assert!(!filter.matches(&object("pkg.crossplane.io/v1", "Provider", "hops-ops-aws")));
+        assert!(parse_object_filter("hops-ops-aws").is_err());
+    }
+
+    #[test]
+    fn crossplane_filter_matches_crossplane_groups_and_xr_kinds() {
+        let filter = EventFilter::Crossplane {
+            xr_kinds: HashSet::from(["Network".to_string()]),
+        };
+        assert!(filter.matches(&object("pkg.crossplane.io/v1", "Provider", "p")));
+        assert!(filter.matches(&object("aws.hops.ops.com.ai/v1alpha1", "Network", "n")));
+        assert!(!filter.matches(&object("v1", "Pod", "crossplane-abc")));
+    }
+}
@@ -0,0 +1,548 @@
+use super::process::{
+    process_alive, read_state, remove_state, spawn_detached, state_path, terminate_process_group,
+    write_state,
+};
+use super::{command_exists, kubectl_command, run_cmd_output, OutputFormat};
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::error::Error;
+use std::net::TcpListener;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const STATE_FILE: &str = "forward.json";
+const LOG_FILE: &str = "forward.log";
+/// Offset applied to privileged service ports (80 -> 10080).
+const PRIVILEGED_PORT_OFFSET: u16 = 10000;
+const RESTART_DELAY: Duration = Duration::from_secs(2);
+const SUPERVISE_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Args, Debug)]
+pub struct ForwardArgs {
+    #[command(subcommand)]
+    pub command: ForwardCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ForwardCommands {
+    // Forwards are supervised `kubectl port-forward` processes rather than
+    // an in-process (kube-rs) engine: hops reaches the cluster only through
+    // kubectl and helm and links no Kubernetes client, and kubectl already
+    // honours the kubeconfig context and its auth plugins.
+    /// Forward cluster services to localhost ports in the background; each
+    /// forward is restarted if it exits
+    Start(ForwardStartArgs),
+    /// Stop all forwards started by `hops local forward start`
+    Stop,
+    /// Show the service -> localhost port mapping
+    List(ForwardListArgs),
+    /// Run the forward supervisor in the foreground (used by `start`)
+    #[command(hide = true)]
+    Supervise,
+}
+
+#[derive(Args, Debug)]
+pub struct ForwardStartArgs {
+    /// Namespace whose services are forwarded (repeatable, default: default)
+    #[arg(long, short = 'n')]
+    pub namespace: Vec<String>,
+
+    /// Only forward this service, as [namespace/]name (repeatable)
+    #[arg(long)]
+    pub service: Vec<String>,
+
+    /// Also publish <service>.local names via mDNS (dns-sd or avahi-publish),
+    /// resolvable on this machine without editing the hosts file
+    #[arg(long)]
+    pub mdns: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct ForwardListArgs {
+    /// Output format
+    #[arg(long, short = 'o', value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+/// One service port forwarded to a localhost port.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Forward {
+    pub namespace: String,
+    pub service: String,
+    pub port: u16,
+    pub local_port: u16,
+}
+
+impl Forward {
+    fn label(&self) -> String {
+        format!("{}/{}:{}", self.namespace, self.service, self.port)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ForwardState {
+    /// PID (and process group) of the supervisor.
+    pub pid: u32,
+    pub forwards: Vec<Forward>,
+    /// Names published via mDNS (`--mdns`).
+    #[serde(default)]
+    pub mdns: Vec<MdnsRecord>,
+}
+
+pub fn run(args: &ForwardArgs) -> Result<(), Box<dyn Error>> {
+    match &args.command {
+        ForwardCommands::Start(start_args) => start(start_args),
+        ForwardCommands::Stop => stop(),
+        ForwardCommands::List(list_args) => list(list_args),
+        ForwardCommands::Supervise => supervise(),
+    }
+}
+
+/// Current forward state when the supervisor is still running.
+pub fn running_state() -> Option<ForwardState> {
+    read_state::<ForwardState>(STATE_FILE).filter(|state| process_alive(state.pid))
+}
+
+fn start(args: &ForwardStartArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(state) = running_state() {
+        return Err(format!(
+            "forwards are already running (pid {}); run `hops local forward stop` first",
+            state.pid
+        )
+        .into());
+    }
+
+    let namespaces = if args.namespace.is_empty() {
+        vec!["default".to_string()]
+    } else {
+        args.namespace.clone()
+    };
+    let wanted = args
+        .service
+        .iter()
+        .map(|spec| match spec.split_once('/') {
+            Some((ns, name)) => (ns.to_string(), name.to_string()),
+            None => (namespaces[0].clone(), spec.to_string()),
+        })
+        .collect::<HashSet<_>>();
+    let mut lookup_namespaces: Vec<String> = namespaces.clone();
+    for (ns, _) in &wanted {
+        if !lookup_namespaces.contains(ns) {
+            lookup_namespaces.push(ns.clone());
+        }
+    }
+
+    let mut ports = Vec::new();
+    for namespace in &lookup_namespaces {
+        let output = run_cmd_output(
+            "kubectl",
+            &["get", "services", "-n", namespace, "-o", "json"],
+        )?;
+        let services: JsonValue = serde_json::from_str(&output)?;
+        ports.extend(
+            forwardable_ports(&services)
+                .into_iter()
+                .filter(|(ns, name, _)| {
+                    if wanted.is_empty() {
+                        namespaces.contains(ns)
+                    } else {
+                        wanted.contains(&(ns.clone(), name.clone()))
+                    }
+                }),
+        );
+    }
+    for (ns, name) in &wanted {
+        if !ports.iter().any(|(n, s, _)| n == ns && s == name) {
+            return Err(format!(
+                "service {}/{} not found or has no forwardable TCP port",
+                ns, name
+            )
+            .into());
+        }
+    }
+    if ports.is_empty() {
+        return Err(format!(
+            "no forwardable services in namespace(s) {}",
+            namespaces.join(", ")
+        )
+        .into());
+    }
+
+    if args.mdns && mdns_publisher().is_none() {
+        return Err("--mdns needs `dns-sd` (macOS) or `avahi-publish` (Linux, avahi-utils)".into());
+    }
+
+    let forwards = allocate_local_ports(&ports, port_is_free);
+    let mut state = ForwardState {
+        pid: 0,
+        mdns: if args.mdns {
+            mdns_records(&forwards)
+        } else {
+            Vec::new()
+        },
+        forwards,
+    };
+    // The supervisor reads its jobs from the state file on startup.
+    write_state(STATE_FILE, &state)?;
+    let exe = std::env::current_exe()?;
+    let log_path = state_path(LOG_FILE)?;
+    let pid = spawn_detached(&exe, &["local", "forward", "supervise"], &log_path)?;
+    state.pid = pid;
+    write_state(STATE_FILE, &state)?;
+
+    crate::logging::success(&format!(
+        "Forwarding {} service port(s) (supervisor pid {}, log {})",
+        ports.len(),
+        pid,
+        log_path.display()
+    ));
+    print_mapping(&running_state().unwrap_or_default());
+    Ok(())
+}
+
+pub fn stop() -> Result<(), Box<dyn Error>> {
+    match read_state::<ForwardState>(STATE_FILE) {
+        Some(state) => {
+            terminate_process_group(state.pid, false)?;
+            remove_state(STATE_FILE)?;
+            crate::logging::success(&format!("Stopped {} forward(s)", state.forwards.len()));
+        }
+        None => log::info!("No forwards running"),
+    }
+    Ok(())
+}
+
+fn list(args: &ForwardListArgs) -> Result<(), Box<dyn Error>> {
+    let state = running_state().unwrap_or_default();
+    match args.output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&state)?),
+        OutputFormat::Text if state.forwards.is_empty() => {
+            println!("No forwards running (start them with `hops local forward start`)")
+        }
+        OutputFormat::Text => print_mapping(&state),
+    }
+    Ok(())
+}
+
+fn print_mapping(state: &ForwardState) {
+    println!("{:<50} LOCAL ADDRESS", "SERVICE");
+    for forward in &state.forwards {
+        let host = state
+            .mdns
+            .iter()
+            .find(|r| r.namespace == forward.namespace && r.service == forward.service)
+            .map(|r| r.hostname.as_str())
+            .unwrap_or("127.0.0.1");
+        println!("{:<50} {}:{}", forward.label(), host, forward.local_port);
+    }
+}
+
+/// Something the supervisor keeps running.
+enum Job {
+    PortForward(Forward),
+    Mdns(MdnsRecord),
+}
+
+impl Job {
+    fn label(&self) -> String {
+        match self {
+            Job::PortForward(forward) => format!("port-forward for {}", forward.label()),
+            Job::Mdns(record) => format!("mDNS publisher for {}", record.hostname),
+        }
+    }
+
+    fn spawn(&self) -> std::io::Result<Child> {
+        match self {
+            Job::PortForward(forward) => {
+                let local = format!("{}:{}", forward.local_port, forward.port);
+                let target = format!("svc/{}", forward.service);
+                kubectl_command(&[
+                    "port-forward",
+                    "-n",
+                    &forward.namespace,
+                    &target,
+                    &local,
+                    "--address",
+                    "127.0.0.1",
+                ])
+                .spawn()
+            }
+            Job::Mdns(record) => {
+                let (program, args) = mdns_publish_command(record).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::NotFound,
+                        "neither dns-sd nor avahi-publish is installed",
+                    )
+                })?;
+                crate::logging::log_command(program, &args);
+                Command::new(program).args(&args).spawn()
+            }
+        }
+    }
+}
+
+/// Keep one `kubectl port-forward` per forward (and one mDNS publisher per
+/// published name) running, restarting any that exit (pod restarts, API
+/// server hiccups). A job whose program is missing is given up on rather than
+/// retried. Exits once `stop` removes the state file or another supervisor
+/// takes it over.
+fn supervise() -> Result<(), Box<dyn Error>> {
+    let state = read_state::<ForwardState>(STATE_FILE).ok_or("no forward state to supervise")?;
+    let own_pid = std::process::id();
+    let jobs = state
+        .forwards
+        .into_iter()
+        .map(Job::PortForward)
+        .chain(state.mdns.into_iter().map(Job::Mdns));
+    let mut children: Vec<(Job, Option<Child>, Instant)> =
+        jobs.map(|job| (job, None, Instant::now())).collect();
+    let mut abandoned = Vec::new();
+
+    loop {
+        let still_owned = read_state::<ForwardState>(STATE_FILE)
+            .is_some_and(|state| state.pid == own_pid || state.pid == 0);
+        if !still_owned {
+            for (_, child, _) in &mut children {
+                if let Some(child) = child {
+                    let _ = child.kill();
+                }
+            }
+            return Ok(());
+        }
+
+        for (index, (job, child, next_start)) in children.iter_mut().enumerate() {
+            if let Some(running) = child {
+                if let Ok(Some(status)) = running.try_wait() {
+                    log::warn!("{} exited ({}); restarting", job.label(), status);
+                    *child = None;
+                    *next_start = Instant::now() + RESTART_DELAY;
+                }
+            }
+            if child.is_none() && Instant::now() >= *next_start {
+                match job.spawn() {
+                    Ok(spawned) => *child = Some(spawned),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                        log::error!("can't start {}: {}; giving up on it", job.label(), err);
+                        abandoned.push(index);
+                    }
+                    Err(err) => {
+                        log::warn!("failed to start {}: {}", job.label(), err);
+                        *next_start = Instant::now() + RESTART_DELAY;
+                    }
+                }
+            }
+        }
+        for index in abandoned.drain(..).rev() {
+            children.remove(index);
+        }
+        thread::sleep(SUPERVISE_INTERVAL);
+    }
+}
+
+/// One `<name>.local` hostname published for a forwarded service.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MdnsRecord {
+    pub hostname: String,
+    pub namespace: String,
+    pub service: String,
+    /// Local port advertised with the name (the service's first forward).
+    pub local_port: u16,
+}
+
+/// The mDNS publisher available on this machine: `dns-sd` (macOS) or
+/// `avahi-publish` (Linux with Avahi).
+fn mdns_publisher() -> Option<&'static str> {
+    ["dns-sd", "avahi-publish"]
+        .into_iter()
+        .find(|program| command_exists(program))
+}
+
+/// The publisher and its arguments for `record`; `None` when neither is
+/// installed.
+fn mdns_publish_command(record: &MdnsRecord) -> Option<(&'static str, Vec<String>)> {
+    let program = mdns_publisher()?;
+    Some(match program {
+        // dns-sd -P registers a service plus an A record for the host name.
+        "dns-sd" => (
+            "dns-sd",
+            vec![
+                "-P".to_string(),
+                record.service.clone(),
+                "_http._tcp".to_string(),
+                "local".to_string(),
+                record.local_port.to_string(),
+                record.hostname.clone(),
+                "127.0.0.1".to_string(),
+            ],
+        ),
+        _ => (
+            program,
+            vec![
+                "-a".to_string(),
+                "-R".to_string(),
+                record.hostname.clone(),
+                "127.0.0.1".to_string(),
+            ],
+        ),
+    })
+}
+
+/// `<service>.local` per forwarded service, or `<service>-<namespace>.local`
+/// when the same service name is forwarded from several namespaces.
+fn mdns_records(forwards: &[Forward]) -> Vec<MdnsRecord> {
+    let mut records: Vec<MdnsRecord> = Vec::new();
+    for forward in forwards {
+        if records
+            .iter()
+            .any(|r| r.namespace == forward.namespace && r.service == forward.service)
+        {
+            continue;
+        }
+        let ambiguous = forwards
+            .iter()
+            .any(|other| other.service == forward.service && other.namespace != forward.namespace);
+        let hostname = if ambiguous {
+            format!("{}-{}.local", forward.service, forward.namespace)
+        } else {
+            format!("{}.local", forward.service)
+        };
+        records.push(MdnsRecord {
+            hostname,
+            namespace: forward.namespace.clone(),
+            service: forward.service.clone(),
+            local_port: forward.local_port,
+        });
+    }
+    records
+}
+
+/// TCP ports of services that `kubectl port-forward svc/...` can reach:
+/// services with a pod selector, excluding the API server's own service.
+fn forwardable_ports(services: &JsonValue) -> Vec<(String, String, u16)> {
+    let Some(items) = services.get("items").and_then(JsonValue::as_array) else {
+        return Vec::new();
+    };
+    let mut ports = Vec::new();
+    for item in items {
+        let field = |pointer: &str| item.pointer(pointer).and_then(JsonValue::as_str);
+        let (Some(namespace), Some(name)) = (field("/metadata/namespace"), field("/metadata/name"))
+        else {
+            continue;
+        };
+        let has_selector = item
+            .pointer("/spec/selector")
+            .and_then(JsonValue::as_object)
+            .is_some_and(|selector| !selector.is_empty());
+        if !has_selector || (namespace == "default" && name == "kubernetes") {
+            continue;
+        }
+        for port in item
+            .pointer("/spec/ports")
+            .and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let protocol = port
+                .get("protocol")
+                .and_then(JsonValue::as_str)
+                .unwrap_or("TCP");
+            let number = port
+                .get("port")
+                .and_then(JsonValue::as_u64)
+                .and_then(|p| u16::try_from(p).ok());
+            if let (Some(number), "TCP") = (number, protocol) {
+                ports.push((namespace.to_string(), name.to_string(), number));
+            }
+        }
+    }
+    ports
+}
+
+/// Pick a localhost port per service port: the service port itself when it
+/// is unprivileged and free, otherwise the next free port from the
+/// preferred one (privileged ports are offset by 10000 so no sudo is needed).
+fn allocate_local_ports(
+    ports: &[(String, String, u16)],
+    is_free: impl Fn(u16) -> bool,
+) -> Vec<Forward> {
+    let mut taken = HashSet::new();
+    ports
+        .iter()
+        .filter_map(|(namespace, service, port)| {
+            let preferred = if *port < 1024 {
+                port + PRIVILEGED_PORT_OFFSET
+            } else {
+                *port
+            };
+            let local_port = (preferred..=u16::MAX)
+                .find(|candidate| !taken.contains(candidate) && is_free(*candidate))?;
+            taken.insert(local_port);
+            Some(Forward {
+                namespace: namespace.clone(),
+                service: service.clone(),
+                port: *port,
+                local_port,
+            })
+        })
+        .collect()
+}
+
+fn port_is_free(port: u16) -> bool {
+    TcpListener::bind(("127.0.0.1", port)).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn forwardable_ports_skips_selectorless_and_udp() {
+        let services = json!({"items": [
+            {"metadata": {"namespace": "default", "name": "kubernetes"},
+             "spec": {"selector": {"x": "y"}, "ports": [{"port": 443}]}},
+            {"metadata": {"namespace": "apps", "name": "web"},
+             "spec": {"selector": {"app": "web"}, "ports": [
+                {"port": 80, "protocol": "TCP"}, {"port": 53, "protocol": "UDP"}]}},
+            {"metadata": {"namespace": "apps", "name": "external"},
+             "spec": {"ports": [{"port": 5432}]}}
+        ]});
+        assert_eq!(
+            forwardable_ports(&services),
+            vec![("apps".to_string(), "web".to_string(), 80)]
+        );
+    }
+
+    #[test]
+    fn allocate_local_ports_avoids_privileged_and_duplicate_ports() {
+        let ports = vec![
+            ("apps".to_string(), "web".to_string(), 80),
+            ("apps".to_string(), "api".to_string(), 8080),
+            ("other".to_string(), "api".to_string(), 8080),
+            ("other".to_string(), "busy".to_string(), 9000),
+        ];
+        let forwards = allocate_local_ports(&ports, |port| port != 9000);
+        let local: Vec<u16> = forwards.iter().map(|f| f.local_port).collect();
+        assert_eq!(local, vec![10080, 8080, 8081, 9001]);
+    }
+
+    #[test]
+    fn mdns_records_disambiguate_by_namespace() {
+        let ports = vec![
+            ("apps".to_string(), "web".to_string(), 80),
+            ("apps".to_string(), "web".to_string(), 443),
+            ("apps".to_string(), "api".to_string(), 8080),
+            ("other".to_string(), "api".to_string(), 8080),
+        ];
+        let records = mdns_records(&allocate_local_ports(&ports, |_| true));
+        let hostnames: Vec<&str> = records.iter().map(|r| r.hostname.as_str()).collect();
+        assert_eq!(
+            hostnames,
+            vec!["web.local", "api-apps.local", "api-other.local"]
+        );
+        assert_eq!(records[0].local_port, 10080);
+    }
+}
@@ -0,0 +1,427 @@
+use super::conditions::get_resource_json;
+use super::process::{read_state, state_path, write_state};
+use super::{kubectl_apply_stdin, registry_host, run_cmd, run_cmd_output};
+use crate::commands::render::{read_documents, SKIPPED_DIRS};
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::mpsc;
+use std::time::Duration;
+
+const NAMESPACE: &str = "crossplane-system";
+const FUNCTION_RESOURCE: &str = "function.pkg.crossplane.io";
+/// `~/.hops/local/function-dev.json`: Functions pointed at the host, with
+/// the runtime config to restore.
+const STATE_FILE: &str = "function-dev.json";
+/// `~/.hops/local/function-dev/<function>/tls/`: the Function's server
+/// certificates, so the host process serves the identity Crossplane expects.
+const CERTS_DIR: &str = "function-dev";
+/// Replaces the function image in the cluster and relays Crossplane's gRPC
+/// connections (still TLS end to end) to the host.
+const FORWARDER_IMAGE: &str = "alpine/socat:1.8.0.0";
+const RUNTIME_PORT: u16 = 9443;
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Args, Debug)]
+pub struct FunctionArgs {
+    #[command(subcommand)]
+    pub command: FunctionCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum FunctionCommands {
+    /// Run a function on the host and route the cluster's calls to it,
+    /// restarting it when its source changes
+    Dev(DevArgs),
+    /// Point Functions left in dev mode back at their packaged runtime
+    Restore(RestoreArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct DevArgs {
+    /// Path to the function source (defaults to current directory)
+    #[arg(long, default_value = ".")]
+    pub path: PathBuf,
+
+    /// Installed Function to replace (default: matched from the package
+    /// name in crossplane.yaml or the directory name)
+    #[arg(long)]
+    pub function: Option<String>,
+
+    /// Command that starts the function's gRPC server, run with `sh -c`
+    /// (default: `go run .` for Go, `python -m function.main` for Python).
+    /// It must serve on --port with the certificates in $TLS_SERVER_CERTS_DIR
+    #[arg(long)]
+    pub command: Option<String>,
+
+    /// Host port the function listens on
+    #[arg(long, default_value_t = RUNTIME_PORT)]
+    pub port: u16,
+}
+
+#[derive(Args, Debug)]
+pub struct RestoreArgs {
+    /// Function to restore (default: every Function in dev mode)
+    #[arg(long)]
+    pub function: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DevFunction {
+    /// `spec.runtimeConfigRef.name` before dev mode.
+    runtime_config: Option<String>,
+}
+
+pub fn run(args: &FunctionArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local function")?;
+    match &args.command {
+        FunctionCommands::Dev(dev_args) => dev(dev_args),
+        FunctionCommands::Restore(restore_args) => restore(restore_args.function.as_deref()),
+    }
+}
+
+fn dev(args: &DevArgs) -> Result<(), Box<dyn Error>> {
+    let dir = args.path.canonicalize()?;
+    let command = match &args.command {
+        Some(command) => command.clone(),
+        None => default_command(&dir, args.port)?,
+    };
+    let hint = match &args.function {
+        Some(function) => function.clone(),
+        None => package_name(&dir),
+    };
+    let function = resolve_function(&hint)?;
+    let certs = export_certificates(&function)?;
+
+    let mut state: BTreeMap<String, DevFunction> = read_state(STATE_FILE).unwrap_or_default();
+    if !state.contains_key(&function) {
+        let runtime_config =
+            get_resource_json(FUNCTION_RESOURCE, &function, None).and_then(|object| {
+                object
+                    .pointer("/spec/runtimeConfigRef/name")
+                    .and_then(JsonValue::as_str)
+                    .map(str::to_string)
+            });
+        state.insert(function.clone(), DevFunction { runtime_config });
+        write_state(STATE_FILE, &state)?;
+    }
+    let gateway = registry_host::gateway_ip()?;
+    log::info!(
+        "Routing Function {} to {}:{} on the host...",
+        function,
+        gateway,
+        args.port
+    );
+    kubectl_apply_stdin(&serde_yaml::to_string(&forwarder_config(
+        &function, &gateway, args.port,
+    ))?)?;
+    patch_runtime_config(&function, Some(&dev_config_name(&function)))?;
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if !event.paths.iter().all(|path| ignored(path)) {
+                let _ = tx.send(());
+            }
+        }
+    })?;
+    notify::Watcher::watch(&mut watcher, &dir, notify::RecursiveMode::Recursive)?;
+    log::info!(
+        "Running `{}` in {} (restarts on changes; Ctrl+C to stop, then `hops local function restore`)",
+        command,
+        dir.display()
+    );
+
+    let mut child = Some(spawn(&command, &dir, &certs)?);
+    loop {
+        match rx.recv_timeout(Duration::from_secs(1)) {
+            Ok(()) => {
+                while rx.recv_timeout(DEBOUNCE).is_ok() {}
+                log::info!("Change detected, restarting {}...", function);
+                if let Some(running) = child.take() {
+                    stop(running);
+                }
+                child = Some(spawn(&command, &dir, &certs)?);
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                if let Some(status) = child.as_mut().and_then(|c| c.try_wait().ok().flatten()) {
+                    log::warn!(
+                        "{} exited with {}; waiting for a change to restart it",
+                        function,
+                        status
+                    );
+                    child = None;
+                }
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err("file watcher stopped".into());
+            }
+        }
+    }
+}
+
+fn restore(function: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let mut state: BTreeMap<String, DevFunction> = read_state(STATE_FILE).unwrap_or_default();
+    let names: Vec<String> = match function {
+        Some(function) if state.contains_key(function) => vec![function.to_string()],
+        Some(function) => return Err(format!("Function {} is not in dev mode", function).into()),
+        None => state.keys().cloned().collect(),
+    };
+    if names.is_empty() {
+        crate::logging::success("No Functions in dev mode");
+        return Ok(());
+    }
+    for name in names {
+        let previous = state.remove(&name).and_then(|dev| dev.runtime_config);
+        log::info!("Restoring Function {}...", name);
+        if get_resource_json(FUNCTION_RESOURCE, &name, None).is_some() {
+            patch_runtime_config(&name, previous.as_deref())?;
+        }
+        run_cmd(
+            "kubectl",
+            &[
+                "delete",
+                "deploymentruntimeconfig.pkg.crossplane.io",
+                &dev_config_name(&name),
+                "--ignore-not-found",
+            ],
+        )?;
+        let certs = state_path(CERTS_DIR)?.join(&name);
+        if certs.exists() {
+            fs::remove_dir_all(certs)?;
+        }
+        write_state(STATE_FILE, &state)?;
+    }
+    crate::logging::success("Functions restored to their packaged runtime");
+    Ok(())
+}
+
+/// How to start the function's server from source, by language.
+fn default_command(dir: &Path, port: u16) -> Result<String, Box<dyn Error>> {
+    if dir.join("go.mod").exists() {
+        Ok(format!("go run . --debug --address=:{}", port))
+    } else if dir.join("function").join("main.py").exists() {
+        Ok(format!(
+            "python -m function.main --debug --address=0.0.0.0:{}",
+            port
+        ))
+    } else {
+        Err(format!(
+            "can't tell how to run the function in {}; pass --command",
+            dir.display()
+        )
+        .into())
+    }
+}
+
+/// `metadata.name` from the function's package metadata, else the
+/// directory name.
+fn package_name(dir: &Path) -> String {
+    ["package/crossplane.yaml", "crossplane.yaml"]
+        .iter()
+        .find_map(|file| {
+            read_documents(&dir.join(file))
+                .ok()?
+                .first()?
+                .pointer("/metadata/name")?
+                .as_str()
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| {
+            dir.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        })
+}
+
+/// The installed Function named `hint`, or the only one whose name ends
+/// with it (Functions installed from a registry path are prefixed with the
+/// org, e.g. `crossplane-contrib-function-auto-ready`).
+fn resolve_function(hint: &str) -> Result<String, Box<dyn Error>> {
+    let output = run_cmd_output(
+        "kubectl",
+        &["get", "functions.pkg.crossplane.io", "-o", "name"],
+    )?;
+    let installed: Vec<&str> = output
+        .lines()
+        .filter_map(|line| line.trim().rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .collect();
+    match_function(hint, &installed)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            format!(
+                "no single installed Function matches {}; pass --function (installed: {})",
+                hint,
+                installed.join(", ")
+            )
+            .into()
+        })
+}
+
+fn match_function<'a>(hint: &str, installed: &[&'a str]) -> Option<&'a str> {
+    if let Some(exact) = installed.iter().find(|name| **name == hint) {
+        return Some(exact);
+    }
+    let suffix = format!("-{}", hint);
+    let mut matches = installed.iter().filter(|name| name.ends_with(&suffix));
+    match (matches.next(), matches.next()) {
+        (Some(only), None) => Some(only),
+        _ => None,
+    }
+}
+
+/// Copy the active revision's server certificate Secret to disk for the
+/// host process ($TLS_SERVER_CERTS_DIR).
+fn export_certificates(function: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let revisions = run_cmd_output(
+        "kubectl",
+        &[
+            "get",
+            "functionrevisions.pkg.crossplane.io",
+            "-l",
+            &format!("pkg.crossplane.io/package={}", function),
+            "-o",
+            "json",
+        ],
+    )?;
+    let revisions: JsonValue = serde_json::from_str(&revisions)?;
+    let secret_name = revisions["items"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|revision| revision.pointer("/spec/desiredState") == Some(&json!("Active")))
+        .and_then(|revision| revision.pointer("/spec/tlsServerSecretName"))
+        .and_then(JsonValue::as_str)
+        .ok_or_else(|| format!("Function {} has no active revision with TLS", function))?;
+    let dir = state_path(CERTS_DIR)?.join(function).join("tls");
+    fs::create_dir_all(&dir)?;
+    for key in ["tls.crt", "tls.key", "ca.crt"] {
+        let pem = run_cmd_output(
+            "kubectl",
+            &[
+                "get",
+                "secret",
+                secret_name,
+                "-n",
+                NAMESPACE,
+                "-o",
+                &format!("go-template={{{{index .data \"{}\" | base64decode}}}}", key),
+            ],
+        )?;
+        if pem.trim().is_empty() {
+            return Err(format!("Secret {}/{} has no {}", NAMESPACE, secret_name, key).into());
+        }
+        fs::write(dir.join(key), pem)?;
+    }
+    Ok(dir)
+}
+
+fn dev_config_name(function: &str) -> String {
+    format!("hops-dev-{}", function)
+}
+
+/// A DeploymentRuntimeConfig that keeps the Function's Deployment and
+/// Service but swaps the runtime for a TCP relay to the host.
+fn forwarder_config(function: &str, gateway: &str, port: u16) -> JsonValue {
+    json!({
+        "apiVersion": "pkg.crossplane.io/v1beta1",
+        "kind": "DeploymentRuntimeConfig",
+        "metadata": {"name": dev_config_name(function)},
+        "spec": {
+            "deploymentTemplate": {
+                "spec": {
+                    "selector": {},
+                    "template": {
+                        "spec": {
+                            "containers": [{
+                                "name": "package-runtime",
+                                "image": FORWARDER_IMAGE,
+                                "args": [
+                                    format!("TCP-LISTEN:{},fork,reuseaddr", RUNTIME_PORT),
+                                    format!("TCP:{}:{}", gateway, port)
+                                ]
+                            }]
+                        }
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Set `spec.runtimeConfigRef`; `None` removes it, which Crossplane
+/// defaults back to the `default` config.
+fn patch_runtime_config(function: &str, config: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let patch = json!({"spec": {"runtimeConfigRef": config.map(|name| json!({"name": name}))}});
+    run_cmd(
+        "kubectl",
+        &[
+            "patch",
+            FUNCTION_RESOURCE,
+            function,
+            "--type",
+            "merge",
+            "-p",
+            &patch.to_string(),
+        ],
+    )
+}
+
+fn ignored(path: &Path) -> bool {
+    path.components().any(|component| {
+        let name = component.as_os_str().to_string_lossy();
+        name.starts_with('.') || name == "__pycache__" || SKIPPED_DIRS.contains(&name.as_ref())
+    })
+}
+
+/// Start the function in the foreground process group, so Ctrl+C stops it
+/// along with hops.
+fn spawn(command: &str, dir: &Path, certs: &Path) -> Result<Child, Box<dyn Error>> {
+    crate::logging::log_command("sh", &["-c", command]);
+    Ok(Command::new("sh")
+        .args(["-c", command])
+        .current_dir(dir)
+        .env("TLS_SERVER_CERTS_DIR", certs)
+        .spawn()?)
+}
+
+/// Stop the function and whatever it started (`go run` execs the built
+/// binary as a child).
+fn stop(mut child: Child) {
+    let pid = child.id().to_string();
+    let _ = Command::new("pkill").args(["-TERM", "-P", &pid]).status();
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_function_prefers_exact_then_unique_suffix() {
+        let installed = [
+            "crossplane-contrib-function-auto-ready",
+            "function-auto-ready",
+            "hops-ops-function-naming",
+            "acme-function-naming",
+        ];
+        assert_eq!(
+            match_function("function-auto-ready", &installed),
+            Some("function-auto-ready")
+        );
+        assert_eq!(match_function("function-naming", &installed), None);
+        assert_eq!(
+            match_function("ops-function-naming", &installed),
+            Some("hops-ops-function-naming")
+        );
+        assert_eq!(match_function("function-kcl", &installed), None);
+    }
+}
@@ -1,9 +1,9 @@
-use super::{command_exists, kubectl_apply_stdin, run_cmd, run_cmd_output};
+use super::conditions::{wait_for_all, ReadyTarget};
+use super::{command_exists, kubectl_apply_stdin, run_cmd, run_cmd_output, run_cmd_output_secret};
 use clap::Args;
 use serde_json::json;
 use std::error::Error;
-use std::io::{self, IsTerminal, Write};
-use std::thread;
+use std::io::{self, Write};
 use std::time::Duration;
 
 const DEFAULT_PROVIDER_PACKAGE: &str =
@@ -20,6 +20,11 @@ pub struct GithubArgs {
     #[arg(long, short = 'o')]
     pub owner: Option<String>,
 
+    /// How long to wait for the provider's ProviderConfig CRD, e.g. 10m
+    /// (default: `timeouts.providers` in ~/.hops/config.yaml, else 5m)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
+
     /// Namespace for the generated Secret and ProviderConfig
     #[arg(long, short = 'n', default_value = "default")]
     pub namespace: String,
@@ -46,6 +51,7 @@ pub struct GithubArgs {
 }
 
 pub fn run(args: &GithubArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local github")?;
     if !command_exists("gh") {
         return Err(
             "GitHub CLI (`gh`) is not installed or not in PATH. Install it first, then rerun `hops local github`."
@@ -88,7 +94,7 @@ pub fn run(args: &GithubArgs) -> Result<(), Box<dyn Error>> {
         &args.provider_package,
     ))?;
 
-    wait_for_crd(PROVIDER_CONFIG_CRD)?;
+    wait_for_provider(args)?;
 
     log::info!(
         "Applying secret '{}/{}' with generated credentials...",
@@ -150,7 +156,7 @@ fn select_owner(
 }
 
 fn prompt_for_owner(default_owner: Option<&str>) -> Result<String, Box<dyn Error>> {
-    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+    if !crate::interactive::prompts_allowed() {
         if let Some(owner) = default_owner
             .map(str::trim)
             .filter(|owner| !owner.is_empty())
@@ -198,9 +204,9 @@ fn export_token() -> Result<String, Box<dyn Error>> {
         Ok(output) => output,
         Err(initial_err) => {
             if gh_login_required(&initial_err) {
-                if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+                if !crate::interactive::prompts_allowed() {
                     return Err(format!(
-                        "failed to export GitHub token: {}\nGitHub CLI login is required, but no interactive terminal was detected. Run `{}` first.",
+                        "failed to export GitHub token: {}\nGitHub CLI login is required, but prompting is unavailable (no terminal or --yes). Run `{}` first.",
                         initial_err,
                         recommended_gh_login_command()
                     )
@@ -256,7 +262,7 @@ fn export_token() -> Result<String, Box<dyn Error>> {
 }
 
 fn run_gh_auth_token() -> Result<String, String> {
-    run_cmd_output("gh", &["auth", "token"]).map_err(|err| err.to_string())
+    run_cmd_output_secret("gh", &["auth", "token"]).map_err(|err| err.to_string())
 }
 
 fn recommended_gh_login_command() -> String {
@@ -279,16 +285,20 @@ fn authenticated_login() -> Result<String, Box<dyn Error>> {
     Ok(login.to_string())
 }
 
-fn wait_for_crd(crd: &str) -> Result<(), Box<dyn Error>> {
-    log::info!("Waiting for CRD {}...", crd);
-    for _ in 0..60 {
-        if run_cmd_output("kubectl", &["get", "crd", crd]).is_ok() {
-            return Ok(());
-        }
-        thread::sleep(Duration::from_secs(5));
-    }
-
-    Err(format!("Timed out waiting for CRD {}", crd).into())
+/// Wait for the provider package and its ProviderConfig CRD together.
+fn wait_for_provider(args: &GithubArgs) -> Result<(), Box<dyn Error>> {
+    log::info!(
+        "Waiting for provider '{}' and CRD {}...",
+        args.provider_name,
+        PROVIDER_CONFIG_CRD
+    );
+    wait_for_all(
+        &[
+            ReadyTarget::provider(&args.provider_name),
+            ReadyTarget::crd(PROVIDER_CONFIG_CRD),
+        ],
+        crate::settings::timeouts(args.timeout)?.providers,
+    )
 }
 
 fn build_credentials_json(owner: &str, token: &str) -> Result<String, Box<dyn Error>> {
@@ -0,0 +1,171 @@
+use super::process::{read_state, write_state};
+use super::OutputFormat;
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::SystemTime;
+
+/// `~/.hops/local/history.json`: `config install`/`uninstall`/`rollback` runs.
+const HISTORY_FILE: &str = "history.json";
+/// Oldest entries are dropped beyond this many.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Args, Debug)]
+pub struct HistoryArgs {
+    /// Only show entries for this Configuration (or `provider/<name>`, `function/<name>`)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Number of most recent entries to show
+    #[arg(long, short = 'n', default_value = "20")]
+    pub limit: usize,
+
+    /// Output format
+    #[arg(long, short = 'o', value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    pub entries: Vec<HistoryEntry>,
+}
+
+/// One applied or removed package.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// RFC 3339 UTC time of the operation.
+    pub timestamp: String,
+    /// `install`, `uninstall`, or `rollback`.
+    pub operation: String,
+    pub name: String,
+    /// Package reference the resource was pointed at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// Where it came from: a project path, `<org>/<repo>`, or a package file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    /// Images pushed to the local registry by this operation.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(operation: &str, name: &str) -> Self {
+        Self {
+            timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            operation: operation.to_string(),
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+}
+
+pub fn load() -> History {
+    read_state(HISTORY_FILE).unwrap_or_default()
+}
+
+/// Append entries to the history. Failures are logged, not returned: the
+/// history is a convenience and must not fail the operation it records.
+pub fn record(entries: Vec<HistoryEntry>) {
+    if entries.is_empty() {
+        return;
+    }
+    let mut history = load();
+    history.entries.extend(entries);
+    let excess = history.entries.len().saturating_sub(MAX_ENTRIES);
+    history.entries.drain(..excess);
+    if let Err(err) = write_state(HISTORY_FILE, &history) {
+        log::warn!("Failed to record history: {}", err);
+    }
+}
+
+impl History {
+    /// Package references `name` was installed with, newest first, without
+    /// consecutive repeats.
+    pub fn installed_packages(&self, name: &str) -> Vec<&str> {
+        let mut packages: Vec<&str> = Vec::new();
+        for entry in self.entries.iter().rev() {
+            if entry.name != name || entry.operation == "uninstall" {
+                continue;
+            }
+            if let Some(package) = entry.package.as_deref() {
+                if packages.last() != Some(&package) {
+                    packages.push(package);
+                }
+            }
+        }
+        packages
+    }
+}
+
+pub fn run(args: &HistoryArgs) -> Result<(), Box<dyn Error>> {
+    let history = load();
+    let mut entries: Vec<&HistoryEntry> = history
+        .entries
+        .iter()
+        .filter(|entry| args.name.as_ref().is_none_or(|name| &entry.name == name))
+        .collect();
+    let skip = entries.len().saturating_sub(args.limit);
+    entries.drain(..skip);
+
+    match args.output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+        OutputFormat::Text if entries.is_empty() => println!("No history recorded yet"),
+        OutputFormat::Text => {
+            let name_width = entries
+                .iter()
+                .map(|entry| entry.name.len())
+                .max()
+                .unwrap_or(0)
+                .max(4);
+            println!(
+                "{:<20} {:<9} {:<name_width$} {:<6} PACKAGE",
+                "TIME", "OPERATION", "NAME", "IMAGES"
+            );
+            for entry in entries {
+                println!(
+                    "{:<20} {:<9} {:<name_width$} {:<6} {}",
+                    entry.timestamp,
+                    entry.operation,
+                    entry.name,
+                    entry.images.len(),
+                    entry.package.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(operation: &str, name: &str, package: &str) -> HistoryEntry {
+        HistoryEntry {
+            package: Some(package.to_string()),
+            ..HistoryEntry::new(operation, name)
+        }
+    }
+
+    #[test]
+    fn installed_packages_are_newest_first_without_repeats() {
+        let history = History {
+            entries: vec![
+                entry("install", "cfg", "reg/cfg:dev-a"),
+                entry("install", "other", "reg/other:dev-x"),
+                entry("install", "cfg", "reg/cfg:dev-b"),
+                entry("install", "cfg", "reg/cfg:dev-b"),
+                entry("uninstall", "cfg", "reg/cfg:dev-b"),
+                entry("rollback", "cfg", "reg/cfg:dev-a"),
+            ],
+        };
+        assert_eq!(
+            history.installed_packages("cfg"),
+            vec!["reg/cfg:dev-a", "reg/cfg:dev-b", "reg/cfg:dev-a"]
+        );
+        assert!(HistoryEntry::new("install", "cfg").timestamp.ends_with('Z'));
+    }
+}
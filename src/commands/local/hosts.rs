@@ -0,0 +1,302 @@
+use super::run_cmd_output;
+use clap::{Args, Subcommand};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const BLOCK_BEGIN: &str = "# BEGIN hops managed block (hops local hosts sync)";
+const BLOCK_END: &str = "# END hops managed block";
+const DEFAULT_HOSTS_FILE: &str = "/etc/hosts";
+
+#[derive(Args, Debug)]
+pub struct HostsArgs {
+    /// Hosts file to manage
+    #[arg(long, global = true, default_value = DEFAULT_HOSTS_FILE)]
+    pub file: PathBuf,
+
+    #[command(subcommand)]
+    pub command: HostsCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum HostsCommands {
+    /// Write hosts entries for cluster services into a hops-managed block
+    Sync(HostsSyncArgs),
+    /// Remove the hops-managed block
+    Clean,
+    /// Print the entries currently in the hops-managed block
+    List,
+}
+
+#[derive(Args, Debug)]
+pub struct HostsSyncArgs {
+    /// Namespace whose services get entries (repeatable, default: default)
+    #[arg(long, short = 'n')]
+    pub namespace: Vec<String>,
+
+    /// Only add this service, as [namespace/]name (repeatable)
+    #[arg(long)]
+    pub service: Vec<String>,
+}
+
+/// One hosts-file line: an address and the names that resolve to it.
+#[derive(Debug, PartialEq, Eq)]
+struct HostEntry {
+    address: String,
+    names: Vec<String>,
+}
+
+pub fn run(args: &HostsArgs) -> Result<(), Box<dyn Error>> {
+    let current = fs::read_to_string(&args.file)
+        .map_err(|err| format!("failed to read {}: {}", args.file.display(), err))?;
+    match &args.command {
+        HostsCommands::Sync(sync_args) => {
+            let entries = collect_entries(sync_args)?;
+            if entries.is_empty() {
+                return Err("no matching services with a ClusterIP or NodePort".into());
+            }
+            write_hosts_file(&args.file, &with_managed_block(&current, &entries))?;
+            for entry in &entries {
+                println!("{:<16} {}", entry.address, entry.names.join(" "));
+            }
+            crate::logging::success(&format!(
+                "Wrote {} service entr{} to {}",
+                entries.len(),
+                if entries.len() == 1 { "y" } else { "ies" },
+                args.file.display()
+            ));
+        }
+        HostsCommands::Clean => {
+            if managed_block(&current).is_none() {
+                log::info!("No hops-managed block in {}", args.file.display());
+                return Ok(());
+            }
+            write_hosts_file(&args.file, &without_managed_block(&current))?;
+            crate::logging::success(&format!(
+                "Removed hops-managed block from {}",
+                args.file.display()
+            ));
+        }
+        HostsCommands::List => match managed_block(&current) {
+            Some(lines) => lines.iter().for_each(|line| println!("{}", line)),
+            None => println!("No hops-managed block in {}", args.file.display()),
+        },
+    }
+    Ok(())
+}
+
+fn collect_entries(args: &HostsSyncArgs) -> Result<Vec<HostEntry>, Box<dyn Error>> {
+    let namespaces = if args.namespace.is_empty() {
+        vec!["default".to_string()]
+    } else {
+        args.namespace.clone()
+    };
+    let wanted: Vec<(String, String)> = args
+        .service
+        .iter()
+        .map(|spec| match spec.split_once('/') {
+            Some((ns, name)) => (ns.to_string(), name.to_string()),
+            None => (namespaces[0].clone(), spec.to_string()),
+        })
+        .collect();
+
+    let mut lookup = namespaces.clone();
+    for (ns, _) in &wanted {
+        if !lookup.contains(ns) {
+            lookup.push(ns.clone());
+        }
+    }
+    let mut services = Vec::new();
+    for namespace in &lookup {
+        let output = run_cmd_output(
+            "kubectl",
+            &["get", "services", "-n", namespace, "-o", "json"],
+        )?;
+        let json: JsonValue = serde_json::from_str(&output)?;
+        services.extend(
+            service_addresses(&json)
+                .into_iter()
+                .filter(|(ns, name, _)| {
+                    if wanted.is_empty() {
+                        namespaces.contains(ns)
+                    } else {
+                        wanted
+                            .iter()
+                            .any(|(w_ns, w_name)| w_ns == ns && w_name == name)
+                    }
+                }),
+        );
+    }
+    Ok(host_entries(&services))
+}
+
+/// `(namespace, name, address)` per service: NodePort and LoadBalancer
+/// services are reachable on the host through Colima's port forwarding at
+/// 127.0.0.1; other services use their ClusterIP. Headless and
+/// ExternalName services are skipped.
+fn service_addresses(services: &JsonValue) -> Vec<(String, String, String)> {
+    services
+        .get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|item| {
+            let field = |pointer: &str| item.pointer(pointer).and_then(JsonValue::as_str);
+            let namespace = field("/metadata/namespace")?;
+            let name = field("/metadata/name")?;
+            let address = match field("/spec/type").unwrap_or("ClusterIP") {
+                "NodePort" | "LoadBalancer" => "127.0.0.1",
+                "ClusterIP" => field("/spec/clusterIP").filter(|ip| *ip != "None")?,
+                _ => return None,
+            };
+            Some((namespace.to_string(), name.to_string(), address.to_string()))
+        })
+        .collect()
+}
+
+/// Hosts entries named like kubefwd's: `<svc>.<ns>`, `<svc>.<ns>.svc`,
+/// `<svc>.<ns>.svc.cluster.local`, plus the bare `<svc>` when it is unique
+/// across the selection.
+fn host_entries(services: &[(String, String, String)]) -> Vec<HostEntry> {
+    let mut name_counts: HashMap<&str, usize> = HashMap::new();
+    for (_, name, _) in services {
+        *name_counts.entry(name.as_str()).or_default() += 1;
+    }
+    services
+        .iter()
+        .map(|(namespace, name, address)| {
+            let mut names = Vec::new();
+            if name_counts[name.as_str()] == 1 {
+                names.push(name.clone());
+            }
+            names.push(format!("{}.{}", name, namespace));
+            names.push(format!("{}.{}.svc", name, namespace));
+            names.push(format!("{}.{}.svc.cluster.local", name, namespace));
+            HostEntry {
+                address: address.clone(),
+                names,
+            }
+        })
+        .collect()
+}
+
+/// Lines between the managed block markers, if the block exists.
+fn managed_block(contents: &str) -> Option<Vec<&str>> {
+    let mut lines = contents
+        .lines()
+        .skip_while(|line| line.trim() != BLOCK_BEGIN);
+    lines.next()?;
+    Some(lines.take_while(|line| line.trim() != BLOCK_END).collect())
+}
+
+fn without_managed_block(contents: &str) -> String {
+    let mut out = String::new();
+    let mut inside = false;
+    for line in contents.lines() {
+        match line.trim() {
+            BLOCK_BEGIN => inside = true,
+            BLOCK_END if inside => inside = false,
+            _ if !inside => {
+                out.push_str(line);
+                out.push('\n');
+            }
+            _ => {}
+        }
+    }
+    out
+}
+
+/// Replace (or append) the managed block, leaving every other line as is.
+fn with_managed_block(contents: &str, entries: &[HostEntry]) -> String {
+    let mut out = without_managed_block(contents);
+    out.push_str(BLOCK_BEGIN);
+    out.push('\n');
+    for entry in entries {
+        out.push_str(&format!("{}\t{}\n", entry.address, entry.names.join(" ")));
+    }
+    out.push_str(BLOCK_END);
+    out.push('\n');
+    out
+}
+
+/// Write the hosts file directly when possible, otherwise through `sudo tee`.
+fn write_hosts_file(path: &Path, contents: &str) -> Result<(), Box<dyn Error>> {
+    if fs::write(path, contents).is_ok() {
+        return Ok(());
+    }
+    log::info!("Updating {} with sudo", path.display());
+    let path = path.display().to_string();
+    crate::logging::log_command("sudo", &["tee", &path]);
+    let mut child = Command::new("sudo")
+        .args(["tee", &path])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .ok_or("failed to open sudo tee stdin")?
+        .write_all(contents.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("sudo tee {} exited with {}", path, status).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn service_addresses_maps_types() {
+        let services = json!({"items": [
+            {"metadata": {"namespace": "apps", "name": "web"},
+             "spec": {"type": "ClusterIP", "clusterIP": "10.43.0.10"}},
+            {"metadata": {"namespace": "apps", "name": "registry"},
+             "spec": {"type": "NodePort", "clusterIP": "10.43.0.11"}},
+            {"metadata": {"namespace": "apps", "name": "headless"},
+             "spec": {"type": "ClusterIP", "clusterIP": "None"}},
+            {"metadata": {"namespace": "apps", "name": "ext"},
+             "spec": {"type": "ExternalName"}}
+        ]});
+        let addresses = service_addresses(&services);
+        assert_eq!(addresses.len(), 2);
+        assert_eq!(addresses[0].2, "10.43.0.10");
+        assert_eq!(addresses[1].2, "127.0.0.1");
+    }
+
+    #[test]
+    fn host_entries_skip_ambiguous_short_names() {
+        let services = vec![
+            ("a".to_string(), "api".to_string(), "10.0.0.1".to_string()),
+            ("b".to_string(), "api".to_string(), "10.0.0.2".to_string()),
+            ("a".to_string(), "web".to_string(), "10.0.0.3".to_string()),
+        ];
+        let entries = host_entries(&services);
+        assert_eq!(entries[0].names[0], "api.a");
+        assert_eq!(entries[2].names[0], "web");
+        assert_eq!(entries[2].names[3], "web.a.svc.cluster.local");
+    }
+
+    #[test]
+    fn managed_block_is_replaced_and_removed_cleanly() {
+        let original = "127.0.0.1\tlocalhost\n::1\tlocalhost\n";
+        let entries = vec![HostEntry {
+            address: "10.0.0.1".to_string(),
+            names: vec!["web".to_string()],
+        }];
+        let synced = with_managed_block(original, &entries);
+        assert_eq!(managed_block(&synced), Some(vec!["10.0.0.1\tweb"]));
+
+        let resynced = with_managed_block(&synced, &entries);
+        assert_eq!(resynced, synced);
+        assert_eq!(without_managed_block(&resynced), original);
+        assert_eq!(managed_block(original), None);
+    }
+}
@@ -0,0 +1,192 @@
+use super::process::{
+    process_alive, read_state, remove_state, spawn_detached, state_path, terminate_process_group,
+    write_state,
+};
+use super::stop::stop_forwarding;
+use super::{kubectl_command, lock, run_cmd, run_cmd_output};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::error::Error;
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const STATE_FILE: &str = "idle-watch.json";
+const LOG_FILE: &str = "idle-watch.log";
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The background watchdog started by `hops local start` when
+/// `start.idle-stop` is set.
+#[derive(Debug, Serialize, Deserialize)]
+struct IdleWatch {
+    pid: u32,
+    /// Stop the VM after this many seconds without activity.
+    after_secs: u64,
+}
+
+/// Start the watchdog unless one is already running. `after` comes from
+/// `start.idle-stop` in the config file (e.g. `4h`).
+pub fn start_watchdog(after: &str) -> Result<(), Box<dyn Error>> {
+    let after = humantime::parse_duration(after)
+        .map_err(|err| format!("invalid start.idle-stop {:?}: {}", after, err))?;
+    if let Some(watch) = running() {
+        log::info!("Idle watchdog is already running (pid {})", watch.pid);
+        return Ok(());
+    }
+    let exe = std::env::current_exe()?;
+    let log_path = state_path(LOG_FILE)?;
+    let pid = spawn_detached(&exe, &["local", "idle-watch"], &log_path)?;
+    write_state(
+        STATE_FILE,
+        &IdleWatch {
+            pid,
+            after_secs: after.as_secs(),
+        },
+    )?;
+    log::info!(
+        "Colima will stop after {} without kubectl or provider activity (watchdog pid {})",
+        humantime::format_duration(after),
+        pid
+    );
+    Ok(())
+}
+
+/// Stop the watchdog, if one is running.
+pub fn stop_watchdog() -> Result<(), Box<dyn Error>> {
+    if let Some(watch) = read_state::<IdleWatch>(STATE_FILE) {
+        remove_state(STATE_FILE)?;
+        terminate_process_group(watch.pid, false)?;
+    }
+    Ok(())
+}
+
+fn running() -> Option<IdleWatch> {
+    read_state::<IdleWatch>(STATE_FILE).filter(|watch| process_alive(watch.pid))
+}
+
+/// Run the watchdog in the foreground (used by `start_watchdog`). The
+/// cluster counts as active while a client holds a connection to the API
+/// server, or when it records a new Normal event (provider reconciles,
+/// rollouts, anything applied with kubectl).
+pub fn watch() -> Result<(), Box<dyn Error>> {
+    // `start_watchdog` writes the state right after spawning us.
+    thread::sleep(Duration::from_secs(1));
+    let after = read_state::<IdleWatch>(STATE_FILE)
+        .map(|watch| Duration::from_secs(watch.after_secs))
+        .ok_or("no idle watchdog state; start it with `hops local start`")?;
+    let api_port = api_server_port();
+    let mut last_active = SystemTime::now();
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        if read_state::<IdleWatch>(STATE_FILE).is_none() {
+            return Ok(());
+        }
+        if run_cmd_output("colima", &["status"]).is_err() {
+            log::info!("Colima is not running; idle watchdog exiting");
+            remove_state(STATE_FILE)?;
+            return Ok(());
+        }
+
+        let now = SystemTime::now();
+        if api_port.is_some_and(api_clients_connected) {
+            last_active = now;
+        } else if let Some(event) = newest_event_time() {
+            last_active = last_active.max(event.min(now));
+        }
+        let idle = now.duration_since(last_active).unwrap_or_default();
+        if idle >= after {
+            // A hops command holding the state lock counts as activity.
+            let Some(_lock) = lock::try_acquire()? else {
+                last_active = now;
+                continue;
+            };
+            log::info!(
+                "No activity for {}; stopping Colima",
+                humantime::format_duration(Duration::from_secs(idle.as_secs()))
+            );
+            // Drop the state first so stopping the forwards doesn't target us.
+            remove_state(STATE_FILE)?;
+            stop_forwarding();
+            return run_cmd("colima", &["stop"]);
+        }
+    }
+}
+
+/// Host port of the API server from the current kubeconfig context.
+fn api_server_port() -> Option<u16> {
+    let output = kubectl_command(&[
+        "config",
+        "view",
+        "--minify",
+        "-o",
+        "jsonpath={.clusters[0].cluster.server}",
+    ])
+    .output()
+    .ok()?;
+    let server = String::from_utf8_lossy(&output.stdout);
+    server
+        .trim()
+        .rsplit_once(':')?
+        .1
+        .trim_end_matches('/')
+        .parse()
+        .ok()
+}
+
+/// Whether any process on this machine holds an open connection to the
+/// API server (k9s, watches, port-forwards, kubefwd).
+fn api_clients_connected(port: u16) -> bool {
+    Command::new("lsof")
+        .args(["-nP", &format!("-iTCP:{}", port), "-sTCP:ESTABLISHED"])
+        .output()
+        .map(|output| output.status.success() && !output.stdout.is_empty())
+        .unwrap_or(false)
+}
+
+fn newest_event_time() -> Option<SystemTime> {
+    let output = kubectl_command(&["get", "events", "-A", "-o", "json"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    newest_normal_event(&serde_json::from_slice(&output.stdout).ok()?)
+}
+
+/// Latest timestamp of a Normal event. Warnings are left out: a failing
+/// resource retries forever and would keep a forgotten cluster awake.
+fn newest_normal_event(list: &JsonValue) -> Option<SystemTime> {
+    list["items"]
+        .as_array()?
+        .iter()
+        .filter(|event| event["type"] == "Normal")
+        .filter_map(|event| {
+            ["lastTimestamp", "eventTime"]
+                .iter()
+                .filter_map(|field| event[*field].as_str())
+                .chain(event["metadata"]["creationTimestamp"].as_str())
+                .find_map(|time| humantime::parse_rfc3339_weak(time).ok())
+        })
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn newest_normal_event_ignores_warnings() {
+        let list = json!({
+            "items": [
+                {"type": "Normal", "lastTimestamp": "2026-10-17T08:00:00Z", "metadata": {}},
+                {"type": "Normal", "lastTimestamp": null, "eventTime": "2026-10-17T09:30:00.123456Z", "metadata": {}},
+                {"type": "Warning", "lastTimestamp": "2026-10-17T11:00:00Z", "metadata": {}}
+            ]
+        });
+        assert_eq!(
+            newest_normal_event(&list),
+            Some(humantime::parse_rfc3339("2026-10-17T09:30:00.123456Z").unwrap())
+        );
+        assert_eq!(newest_normal_event(&json!({"items": []})), None);
+    }
+}
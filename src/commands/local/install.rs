@@ -4,6 +4,6 @@ use std::error::Error;
 pub fn run() -> Result<(), Box<dyn Error>> {
     log::info!("Installing Colima via Homebrew...");
     run_cmd("brew", &["install", "colima"])?;
-    log::info!("Colima installed successfully");
+    crate::logging::success("Colima installed successfully");
     Ok(())
 }
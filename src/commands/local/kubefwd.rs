@@ -0,0 +1,342 @@
+use super::process::{
+    process_alive, read_state, remove_state, rotate_log, spawn_detached, state_path,
+    terminate_process_group, write_state,
+};
+use super::{command_exists, kubectl_context_args, run_cmd};
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::path::Path;
+
+const STATE_FILE: &str = "kubefwd.json";
+const LOG_FILE: &str = "kubefwd.log";
+
+const DEFAULT_SESSION: &str = "default";
+
+#[derive(Args, Debug)]
+pub struct KubefwdArgs {
+    /// Session name, to run several kubefwd instances (e.g. one per namespace)
+    #[arg(long, global = true, default_value = DEFAULT_SESSION, value_parser = parse_session)]
+    pub session: String,
+
+    #[command(subcommand)]
+    pub command: KubefwdCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KubefwdCommands {
+    /// Start a kubefwd session in the background (prompts for sudo)
+    Start(KubefwdStartArgs),
+    /// Stop a kubefwd session started by hops
+    Stop(KubefwdStopArgs),
+    /// Restart a session with the namespaces and selectors it was started with
+    #[command(visible_alias = "restart")]
+    Refresh,
+    /// List kubefwd sessions
+    List,
+}
+
+#[derive(Args, Debug)]
+pub struct KubefwdStartArgs {
+    /// Only forward services in this namespace (repeatable, default: all namespaces)
+    #[arg(long, short = 'n')]
+    pub namespace: Vec<String>,
+
+    /// Only forward services matching this label selector, as key=value (repeatable)
+    #[arg(long, short = 'l', value_parser = parse_selector)]
+    pub selector: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct KubefwdStopArgs {
+    /// Stop every session
+    #[arg(long)]
+    pub all: bool,
+}
+
+/// Running kubefwd process and the options it was started with, so
+/// `refresh` reuses them.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KubefwdState {
+    /// PID (and process group) of the `sudo kubefwd` process.
+    pub pid: u32,
+    #[serde(default)]
+    pub namespaces: Vec<String>,
+    #[serde(default)]
+    pub selectors: Vec<String>,
+}
+
+/// Contents of `~/.hops/local/kubefwd.json`: sessions by name.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KubefwdSessions {
+    pub sessions: BTreeMap<String, KubefwdState>,
+}
+
+pub fn run(args: &KubefwdArgs) -> Result<(), Box<dyn Error>> {
+    let session = args.session.as_str();
+    match &args.command {
+        KubefwdCommands::Start(start_args) => start(
+            session,
+            KubefwdState {
+                pid: 0,
+                namespaces: start_args.namespace.clone(),
+                selectors: start_args.selector.clone(),
+            },
+        ),
+        KubefwdCommands::Stop(stop_args) if stop_args.all => stop_all(),
+        KubefwdCommands::Stop(_) => stop(session),
+        KubefwdCommands::Refresh => {
+            let previous = load_sessions()
+                .sessions
+                .remove(session)
+                .ok_or_else(|| format!("no kubefwd session '{}' to refresh", session))?;
+            stop(session)?;
+            start(session, previous)
+        }
+        KubefwdCommands::List => {
+            list();
+            Ok(())
+        }
+    }
+}
+
+fn load_sessions() -> KubefwdSessions {
+    read_state::<KubefwdSessions>(STATE_FILE).unwrap_or_default()
+}
+
+/// Sessions whose kubefwd process is still running.
+pub fn running_sessions() -> BTreeMap<String, KubefwdState> {
+    load_sessions()
+        .sessions
+        .into_iter()
+        .filter(|(_, state)| process_alive(state.pid))
+        .collect()
+}
+
+/// Start the default session from `hops local start`, using the namespaces
+/// and selectors from the config file. Already running is not an error.
+pub fn start_for_local_start() -> Result<(), Box<dyn Error>> {
+    if running_sessions().contains_key(DEFAULT_SESSION) {
+        log::info!("kubefwd is already running");
+        return Ok(());
+    }
+    let settings = crate::settings::load()?.kubefwd;
+    start(
+        DEFAULT_SESSION,
+        KubefwdState {
+            pid: 0,
+            namespaces: settings.namespaces,
+            selectors: settings.selectors,
+        },
+    )
+}
+
+/// Stop every session hops started (used by `local stop`/`destroy`).
+pub fn stop_all() -> Result<(), Box<dyn Error>> {
+    for name in load_sessions().sessions.keys() {
+        stop(name)?;
+    }
+    Ok(())
+}
+
+fn log_file(session: &str) -> String {
+    if session == DEFAULT_SESSION {
+        LOG_FILE.to_string()
+    } else {
+        format!("kubefwd-{}.log", session)
+    }
+}
+
+fn start(session: &str, mut state: KubefwdState) -> Result<(), Box<dyn Error>> {
+    if let Some(running) = running_sessions().get(session) {
+        return Err(format!(
+            "kubefwd session '{}' is already running (pid {}); run `hops local kubefwd stop --session {}` first",
+            session, running.pid, session
+        )
+        .into());
+    }
+    if !command_exists("kubefwd") {
+        return Err("kubefwd is not installed; run `brew install txn2/tap/kubefwd`".into());
+    }
+
+    // kubefwd edits /etc/hosts and binds privileged ports, so it runs as
+    // root. Prompt for the password now, while we still have a terminal.
+    run_cmd("sudo", &["-v"])?;
+
+    let args = kubefwd_args(&state);
+    let mut sudo_args = vec!["-E", "kubefwd"];
+    sudo_args.extend(args.iter().map(String::as_str));
+    let log_path = state_path(&log_file(session))?;
+    rotate_log(&log_path, &crate::settings::load()?.kubefwd.log_rotation)?;
+    state.pid = spawn_detached(Path::new("sudo"), &sudo_args, &log_path)?;
+
+    let mut sessions = load_sessions();
+    sessions.sessions.insert(session.to_string(), state.clone());
+    write_state(STATE_FILE, &sessions)?;
+
+    crate::logging::success(&format!(
+        "kubefwd session '{}' started for {} (pid {}, log {})",
+        session,
+        scope_label(&state),
+        state.pid,
+        log_path.display()
+    ));
+    Ok(())
+}
+
+fn stop(session: &str) -> Result<(), Box<dyn Error>> {
+    let mut sessions = load_sessions();
+    let Some(state) = sessions.sessions.remove(session) else {
+        log::info!("kubefwd session '{}' is not running", session);
+        return Ok(());
+    };
+    terminate_process_group(state.pid, true)?;
+    if sessions.sessions.is_empty() {
+        remove_state(STATE_FILE)?;
+    } else {
+        write_state(STATE_FILE, &sessions)?;
+    }
+    rotate_log(
+        &state_path(&log_file(session))?,
+        &crate::settings::load()?.kubefwd.log_rotation,
+    )?;
+    crate::logging::success(&format!(
+        "kubefwd session '{}' stopped (pid {})",
+        session, state.pid
+    ));
+    Ok(())
+}
+
+fn list() {
+    let sessions = load_sessions().sessions;
+    if sessions.is_empty() {
+        println!("No kubefwd sessions (start one with `hops local kubefwd start`)");
+        return;
+    }
+    println!("{:<20} {:<10} {:<8} SCOPE", "SESSION", "STATE", "PID");
+    for (name, state) in &sessions {
+        let running = if process_alive(state.pid) {
+            "running"
+        } else {
+            "exited"
+        };
+        println!(
+            "{:<20} {:<10} {:<8} {}",
+            name,
+            running,
+            state.pid,
+            scope_label(state)
+        );
+    }
+}
+
+/// `kubefwd svc` arguments for the given scope: every namespace unless
+/// namespaces are listed, narrowed by label selectors.
+fn kubefwd_args(state: &KubefwdState) -> Vec<String> {
+    let mut args = vec!["svc".to_string()];
+    if state.namespaces.is_empty() {
+        args.push("--all-namespaces".to_string());
+    }
+    for namespace in &state.namespaces {
+        args.push("-n".to_string());
+        args.push(namespace.clone());
+    }
+    if !state.selectors.is_empty() {
+        args.push("-l".to_string());
+        args.push(state.selectors.join(","));
+    }
+    args.extend(kubectl_context_args());
+    args
+}
+
+fn scope_label(state: &KubefwdState) -> String {
+    let mut label = if state.namespaces.is_empty() {
+        "all namespaces".to_string()
+    } else {
+        format!("namespace(s) {}", state.namespaces.join(", "))
+    };
+    if !state.selectors.is_empty() {
+        label.push_str(&format!(" matching {}", state.selectors.join(",")));
+    }
+    label
+}
+
+fn parse_session(value: &str) -> Result<String, String> {
+    let valid = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(format!(
+            "session names use lowercase letters, digits, and '-', got '{}'",
+            value
+        ))
+    }
+}
+
+fn parse_selector(value: &str) -> Result<String, String> {
+    match value.split_once('=') {
+        Some((key, _)) if !key.trim().is_empty() => Ok(value.trim().to_string()),
+        _ => Err(format!("expected key=value, got '{}'", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kubefwd_args_defaults_to_all_namespaces() {
+        let args = kubefwd_args(&KubefwdState::default());
+        assert_eq!(args[..2], ["svc", "--all-namespaces"]);
+    }
+
+    #[test]
+    fn kubefwd_args_uses_namespaces_and_selectors() {
+        let state = KubefwdState {
+            pid: 0,
+            namespaces: vec!["apps".to_string(), "data".to_string()],
+            selectors: vec!["app=web".to_string(), "tier=api".to_string()],
+        };
+        let args = kubefwd_args(&state);
+        assert_eq!(
+            args[..7],
+            ["svc", "-n", "apps", "-n", "data", "-l", "app=web,tier=api"]
+        );
+        assert_eq!(
+            scope_label(&state),
+            "namespace(s) apps, data matching app=web,tier=api"
+        );
+    }
+
+    #[test]
+    fn sessions_state_round_trips_and_names_logs() {
+        let mut sessions = KubefwdSessions::default();
+        sessions.sessions.insert(
+            "apps".to_string(),
+            KubefwdState {
+                pid: 42,
+                namespaces: vec!["apps".to_string()],
+                selectors: Vec::new(),
+            },
+        );
+        let json = serde_json::to_string(&sessions).unwrap();
+        let parsed: KubefwdSessions = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.sessions["apps"].pid, 42);
+
+        assert_eq!(log_file("default"), "kubefwd.log");
+        assert_eq!(log_file("apps"), "kubefwd-apps.log");
+        assert!(parse_session("team-a").is_ok());
+        assert!(parse_session("../etc").is_err());
+    }
+
+    #[test]
+    fn parse_selector_requires_key_value() {
+        assert_eq!(parse_selector("app=web").unwrap(), "app=web");
+        assert!(parse_selector("app").is_err());
+        assert!(parse_selector("=web").is_err());
+    }
+}
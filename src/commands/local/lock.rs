@@ -0,0 +1,120 @@
+use super::process::state_path;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::Path;
+
+/// `~/.hops/local/hops.lock`: held by commands that change the local cluster
+/// or the state files next to it, and records who holds it.
+const LOCK_FILE: &str = "hops.lock";
+/// Set while a hops process holds the lock, so the hops commands it runs
+/// (e.g. `config install --stack` running `local aws`) work under its lock
+/// instead of waiting on it. Background workers outlive the lock, so
+/// `spawn_detached` doesn't pass it on.
+pub const HELD_ENV: &str = "HOPS_STATE_LOCK_HELD";
+
+/// Exclusive hold on the hops state directory, released when dropped (or
+/// when the process exits, however it exits).
+#[derive(Debug)]
+pub struct StateLock {
+    /// `None` when a parent hops process holds the lock.
+    _file: Option<File>,
+}
+
+/// Take the state lock for this invocation, or fail right away when another
+/// hops command holds it.
+pub fn acquire() -> Result<StateLock, Box<dyn Error>> {
+    if std::env::var_os(HELD_ENV).is_some() {
+        return Ok(StateLock { _file: None });
+    }
+    let command: Vec<String> = std::env::args().skip(1).collect();
+    let lock = acquire_at(&state_path(LOCK_FILE)?, &command.join(" "))?;
+    // Only commands run from here on see it; nothing else reads the
+    // environment concurrently this early.
+    std::env::set_var(HELD_ENV, std::process::id().to_string());
+    Ok(lock)
+}
+
+/// Take the state lock for one change made by a background worker, or
+/// `None` while a hops command holds it (the worker tries again later).
+/// Unlike [`acquire`] it isn't passed on to child processes.
+pub fn try_acquire() -> Result<Option<StateLock>, Box<dyn Error>> {
+    let command: Vec<String> = std::env::args().skip(1).collect();
+    match try_acquire_at(&state_path(LOCK_FILE)?, &command.join(" "))? {
+        Ok(lock) => Ok(Some(lock)),
+        Err(holder) => {
+            log::debug!("State lock is held by {}; retrying later", holder);
+            Ok(None)
+        }
+    }
+}
+
+fn acquire_at(path: &Path, command: &str) -> Result<StateLock, Box<dyn Error>> {
+    try_acquire_at(path, command)?.map_err(|holder| {
+        format!(
+            "another hops command is running{}; wait for it to finish (or stop it) and retry",
+            if holder.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", holder)
+            }
+        )
+        .into()
+    })
+}
+
+/// The lock, or who holds it.
+fn try_acquire_at(path: &Path, command: &str) -> Result<Result<StateLock, String>, Box<dyn Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let holder = fs::read_to_string(path).unwrap_or_default();
+            return Ok(Err(holder.trim().to_string()));
+        }
+        Err(TryLockError::Error(err)) => {
+            return Err(format!("could not lock {}: {}", path.display(), err).into())
+        }
+    }
+    file.set_len(0)?;
+    writeln!(file, "`hops {}`, pid {}", command, std::process::id())?;
+    Ok(Ok(StateLock { _file: Some(file) }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_holder_is_told_who_has_the_lock() {
+        let dir = std::env::temp_dir().join(format!("hops-lock-{}", std::process::id()));
+        let path = dir.join(LOCK_FILE);
+
+        let lock = acquire_at(&path, "config install --watch").unwrap();
+        let err = acquire_at(&path, "config uninstall")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("another hops command is running"),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains("`hops config install --watch`, pid"),
+            "{}",
+            err
+        );
+
+        drop(lock);
+        acquire_at(&path, "config uninstall").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
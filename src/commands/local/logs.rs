@@ -0,0 +1,140 @@
+use super::{run_cmd, run_cmd_output};
+use clap::Args;
+use std::error::Error;
+
+const CROSSPLANE_NAMESPACE: &str = "crossplane-system";
+const CROSSPLANE_SELECTOR: &str = "app=crossplane";
+
+#[derive(Args, Debug)]
+pub struct LogsArgs {
+    /// crossplane, provider-helm, provider-kubernetes, or the name of an
+    /// installed Provider, Function, or Configuration
+    #[arg(long, short = 'c', default_value = "crossplane")]
+    pub component: String,
+
+    /// Stream new log lines until interrupted
+    #[arg(long, short = 'f')]
+    pub follow: bool,
+
+    /// Number of recent lines to show per container
+    #[arg(long, default_value = "200")]
+    pub tail: u32,
+}
+
+pub fn run(args: &LogsArgs) -> Result<(), Box<dyn Error>> {
+    let selector = resolve_selector(&args.component)?;
+    log::info!(
+        "Showing logs for '{}' ({} in {})...",
+        args.component,
+        selector,
+        CROSSPLANE_NAMESPACE
+    );
+
+    let tail = args.tail.to_string();
+    let mut kubectl_args = vec![
+        "logs",
+        "-n",
+        CROSSPLANE_NAMESPACE,
+        "-l",
+        &selector,
+        "--all-containers",
+        "--prefix",
+        "--tail",
+        &tail,
+        // Providers may run several replicas/revisions; the default of 5 is
+        // easy to hit while a package upgrade is rolling out.
+        "--max-log-requests",
+        "20",
+    ];
+    if args.follow {
+        kubectl_args.push("--follow");
+    }
+    run_cmd("kubectl", &kubectl_args)
+}
+
+/// Map a component name to a pod label selector in crossplane-system.
+fn resolve_selector(component: &str) -> Result<String, Box<dyn Error>> {
+    let component = component.trim();
+    if component.is_empty() || component == "crossplane" {
+        return Ok(CROSSPLANE_SELECTOR.to_string());
+    }
+
+    for (kind, name) in package_candidates(component) {
+        if let Some(revision) = current_revision(kind, &name) {
+            return Ok(format!("pkg.crossplane.io/revision={}", revision));
+        }
+    }
+
+    // Configurations don't run pods; their install and dependency errors are
+    // reported by the Crossplane package manager.
+    if current_revision("configuration.pkg.crossplane.io", component).is_some() {
+        log::info!(
+            "'{}' is a Configuration, which has no pods; showing Crossplane package manager logs",
+            component
+        );
+        return Ok(CROSSPLANE_SELECTOR.to_string());
+    }
+
+    Err(format!(
+        "no Provider, Function, or Configuration named '{}' has an active revision; see `kubectl get pkg`",
+        component
+    )
+    .into())
+}
+
+/// Package resources that may own pods for `component`. The bootstrap
+/// providers are also reachable by their short `provider-*` names.
+fn package_candidates(component: &str) -> Vec<(&'static str, String)> {
+    let mut names = vec![component.to_string()];
+    if component.starts_with("provider-") {
+        names.push(format!("crossplane-contrib-{}", component));
+    }
+
+    let mut candidates = Vec::new();
+    for name in names {
+        candidates.push(("provider.pkg.crossplane.io", name.clone()));
+        candidates.push(("function.pkg.crossplane.io", name));
+    }
+    candidates
+}
+
+fn current_revision(resource: &str, name: &str) -> Option<String> {
+    run_cmd_output(
+        "kubectl",
+        &[
+            "get",
+            resource,
+            name,
+            "-o",
+            "jsonpath={.status.currentRevision}",
+        ],
+    )
+    .ok()
+    .map(|revision| revision.trim().to_string())
+    .filter(|revision| !revision.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn package_candidates_expand_bootstrap_provider_names() {
+        assert_eq!(
+            package_candidates("provider-helm"),
+            vec![
+                ("provider.pkg.crossplane.io", "provider-helm".to_string()),
+                ("function.pkg.crossplane.io", "provider-helm".to_string()),
+                (
+                    "provider.pkg.crossplane.io",
+                    "crossplane-contrib-provider-helm".to_string()
+                ),
+                (
+                    "function.pkg.crossplane.io",
+                    "crossplane-contrib-provider-helm".to_string()
+                ),
+            ]
+        );
+        assert_eq!(package_candidates("function-auto-ready").len(), 2);
+    }
+}
@@ -0,0 +1,342 @@
+use super::process::{
+    process_alive, read_state, remove_state, spawn_detached, state_path, terminate_process_group,
+    write_state,
+};
+use super::trace::ObjectRef;
+use super::{kubectl_apply_stdin, lock, run_cmd, run_cmd_output};
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const STATE_FILE: &str = "mock.json";
+const LOG_FILE: &str = "mock-watch.log";
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Runtime config that scales a provider to zero replicas: its CRDs stay
+/// installed, but nothing talks to the cloud.
+const MOCK_RUNTIME_CONFIG: &str = "hops-mock";
+const PROVIDER_RESOURCE: &str = "provider.pkg.crossplane.io";
+
+#[derive(Args, Debug)]
+pub struct MockArgs {
+    #[command(subcommand)]
+    pub command: MockCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MockCommands {
+    /// Stop cloud providers and report every managed resource as Ready
+    On,
+    /// Run providers again (mocked resources are then created for real)
+    Off,
+}
+
+/// Mock mode, kept across `local stop`/`start` until `local mock off`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MockState {
+    /// Background watcher started by `local mock on`.
+    pid: u32,
+    /// Mocked providers and the runtime config each had before.
+    providers: BTreeMap<String, Option<String>>,
+}
+
+pub fn run(args: &MockArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local mock")?;
+    match &args.command {
+        MockCommands::On => enable(),
+        MockCommands::Off => disable(),
+    }
+}
+
+/// Turn on mock mode (also used by `config install --mock-providers`), or
+/// restart its watcher if it isn't running.
+pub fn enable() -> Result<(), Box<dyn Error>> {
+    let mut state = read_state::<MockState>(STATE_FILE).unwrap_or_default();
+    if process_alive(state.pid) {
+        log::info!("Mock providers are already on (watcher pid {})", state.pid);
+        return Ok(());
+    }
+    kubectl_apply_stdin(&serde_yaml::to_string(&mock_runtime_config())?)?;
+    let exe = std::env::current_exe()?;
+    state.pid = spawn_detached(&exe, &["local", "mock-watch"], &state_path(LOG_FILE)?)?;
+    write_state(STATE_FILE, &state)?;
+    crate::logging::success(&format!(
+        "Mock providers on: cloud providers are scaled to zero and their managed resources reported Ready (watcher pid {}, log {})",
+        state.pid,
+        state_path(LOG_FILE)?.display()
+    ));
+    Ok(())
+}
+
+/// Restart the watcher after `local start` when mock mode was left on.
+pub fn resume() -> Result<(), Box<dyn Error>> {
+    match read_state::<MockState>(STATE_FILE) {
+        Some(state) if !process_alive(state.pid) => enable(),
+        _ => Ok(()),
+    }
+}
+
+fn disable() -> Result<(), Box<dyn Error>> {
+    let Some(state) = read_state::<MockState>(STATE_FILE) else {
+        crate::logging::success("Mock providers are off");
+        return Ok(());
+    };
+    if !crate::interactive::confirm(
+        "Providers will reconcile the mocked managed resources and create them for real. Continue?",
+        false,
+    )? {
+        return Err("aborted".into());
+    }
+    remove_state(STATE_FILE)?;
+    terminate_process_group(state.pid, false)?;
+    for (provider, previous) in &state.providers {
+        log::info!("Restoring provider {}...", provider);
+        let patch = json!({"spec": {"runtimeConfigRef": previous.as_ref().map(|name| json!({"name": name}))}});
+        if let Err(err) = run_cmd(
+            "kubectl",
+            &[
+                "patch",
+                PROVIDER_RESOURCE,
+                provider,
+                "--type",
+                "merge",
+                "-p",
+                &patch.to_string(),
+            ],
+        ) {
+            log::warn!("Failed to restore provider {}: {}", provider, err);
+        }
+    }
+    run_cmd(
+        "kubectl",
+        &[
+            "delete",
+            "deploymentruntimeconfig.pkg.crossplane.io",
+            MOCK_RUNTIME_CONFIG,
+            "--ignore-not-found",
+        ],
+    )?;
+    crate::logging::success("Mock providers off");
+    Ok(())
+}
+
+/// Drop mock mode without touching the cluster, for when the cluster's
+/// providers are gone (`reset`, `teardown`, `destroy`).
+pub fn forget() -> Result<(), Box<dyn Error>> {
+    if let Some(state) = read_state::<MockState>(STATE_FILE) {
+        remove_state(STATE_FILE)?;
+        terminate_process_group(state.pid, false)?;
+    }
+    Ok(())
+}
+
+/// Run the watcher in the foreground (used by `enable`): point new cloud
+/// providers at the mock runtime config and mark their managed resources
+/// Synced and Ready.
+pub fn watch() -> Result<(), Box<dyn Error>> {
+    // `enable` writes the state right after spawning us.
+    thread::sleep(Duration::from_secs(1));
+    loop {
+        let Some(mut state) = read_state::<MockState>(STATE_FILE) else {
+            return Ok(());
+        };
+        if run_cmd_output("colima", &["status"]).is_err() {
+            log::info!("Colima is not running; mock watcher exiting until `hops local start`");
+            return Ok(());
+        }
+        // Change the cluster only between other hops commands.
+        let Some(guard) = lock::try_acquire()? else {
+            thread::sleep(POLL_INTERVAL);
+            continue;
+        };
+        if let Err(err) = mock_providers(&mut state) {
+            log::warn!("Failed to update providers: {}", err);
+        }
+        if let Err(err) = mark_ready() {
+            log::warn!("Failed to update managed resources: {}", err);
+        }
+        drop(guard);
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+fn mock_providers(state: &mut MockState) -> Result<(), Box<dyn Error>> {
+    let output = run_cmd_output("kubectl", &["get", PROVIDER_RESOURCE, "-o", "json"])?;
+    let providers: JsonValue = serde_json::from_str(&output)?;
+    let mut changed = false;
+    for provider in providers["items"].as_array().into_iter().flatten() {
+        let name = provider["metadata"]["name"].as_str().unwrap_or_default();
+        let package = provider["spec"]["package"].as_str().unwrap_or_default();
+        let runtime_config = provider
+            .pointer("/spec/runtimeConfigRef/name")
+            .and_then(JsonValue::as_str);
+        if runs_locally(package) || runtime_config == Some(MOCK_RUNTIME_CONFIG) {
+            continue;
+        }
+        log::info!("Mocking provider {}", name);
+        state
+            .providers
+            .entry(name.to_string())
+            .or_insert(runtime_config.map(str::to_string));
+        run_cmd(
+            "kubectl",
+            &[
+                "patch",
+                PROVIDER_RESOURCE,
+                name,
+                "--type",
+                "merge",
+                "-p",
+                &json!({"spec": {"runtimeConfigRef": {"name": MOCK_RUNTIME_CONFIG}}}).to_string(),
+            ],
+        )?;
+        changed = true;
+    }
+    // Skip the write if `mock off` removed the state meanwhile.
+    if changed && read_state::<MockState>(STATE_FILE).is_some() {
+        write_state(STATE_FILE, state)?;
+    }
+    Ok(())
+}
+
+fn mark_ready() -> Result<(), Box<dyn Error>> {
+    let output = run_cmd_output("kubectl", &["get", "managed", "-A", "-o", "json"])?;
+    let managed: JsonValue = serde_json::from_str(&output)?;
+    let now = humantime::format_rfc3339_seconds(SystemTime::now()).to_string();
+    for resource in managed["items"].as_array().into_iter().flatten() {
+        let Some(patch) = ready_patch(resource, &now) else {
+            continue;
+        };
+        let target = ObjectRef {
+            api_version: resource["apiVersion"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            kind: resource["kind"].as_str().unwrap_or_default().to_string(),
+            name: resource["metadata"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            namespace: resource["metadata"]["namespace"]
+                .as_str()
+                .map(str::to_string),
+        };
+        let resource_name = target.kubectl_resource();
+        let patch = patch.to_string();
+        let mut args = vec![
+            "patch",
+            &resource_name,
+            &target.name,
+            "--subresource=status",
+            "--type",
+            "merge",
+            "-p",
+            &patch,
+        ];
+        if let Some(namespace) = &target.namespace {
+            args.extend(["-n", namespace]);
+        }
+        log::info!("Marking {}/{} Ready", resource_name, target.name);
+        if let Err(err) = run_cmd_output("kubectl", &args) {
+            log::warn!("Failed to mark {}/{}: {}", resource_name, target.name, err);
+        }
+    }
+    Ok(())
+}
+
+/// Status patch marking a managed resource of a mocked provider Synced and
+/// Ready, or `None` when it already is or its provider runs for real.
+fn ready_patch(resource: &JsonValue, now: &str) -> Option<JsonValue> {
+    let group = resource["apiVersion"]
+        .as_str()?
+        .split_once('/')
+        .map_or("", |(group, _)| group);
+    if runs_locally_group(group) || resource.pointer("/metadata/deletionTimestamp").is_some() {
+        return None;
+    }
+    let conditions = resource
+        .pointer("/status/conditions")
+        .and_then(JsonValue::as_array);
+    let is_true = |condition_type: &str| {
+        conditions
+            .into_iter()
+            .flatten()
+            .any(|condition| condition["type"] == condition_type && condition["status"] == "True")
+    };
+    if is_true("Synced") && is_true("Ready") {
+        return None;
+    }
+    Some(json!({
+        "status": {
+            "conditions": [
+                {"type": "Synced", "status": "True", "reason": "ReconcileSuccess", "lastTransitionTime": now},
+                {"type": "Ready", "status": "True", "reason": "Available", "lastTransitionTime": now}
+            ]
+        }
+    }))
+}
+
+/// provider-kubernetes and provider-helm (installed by `local start`) work
+/// against the local cluster, so they keep running.
+fn runs_locally(package: &str) -> bool {
+    ["/provider-kubernetes:", "/provider-helm:"]
+        .iter()
+        .any(|path| package.contains(path))
+}
+
+fn runs_locally_group(group: &str) -> bool {
+    group.ends_with(".crossplane.io")
+        && (group.starts_with("kubernetes.") || group.starts_with("helm."))
+}
+
+fn mock_runtime_config() -> JsonValue {
+    json!({
+        "apiVersion": "pkg.crossplane.io/v1beta1",
+        "kind": "DeploymentRuntimeConfig",
+        "metadata": {"name": MOCK_RUNTIME_CONFIG},
+        "spec": {
+            "deploymentTemplate": {
+                "spec": {"replicas": 0, "selector": {}, "template": {}}
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ready_patch_skips_local_providers_and_ready_resources() {
+        let bucket = json!({
+            "apiVersion": "s3.aws.upbound.io/v1beta1",
+            "kind": "Bucket",
+            "metadata": {"name": "demo"},
+            "status": {"conditions": [{"type": "Synced", "status": "False"}]}
+        });
+        let patch = ready_patch(&bucket, "2026-01-01T00:00:00Z").unwrap();
+        assert_eq!(patch["status"]["conditions"][1]["type"], "Ready");
+        assert_eq!(patch["status"]["conditions"][1]["status"], "True");
+
+        let ready = json!({
+            "apiVersion": "s3.aws.upbound.io/v1beta1",
+            "status": {"conditions": [
+                {"type": "Synced", "status": "True"},
+                {"type": "Ready", "status": "True"}
+            ]}
+        });
+        assert!(ready_patch(&ready, "").is_none());
+
+        let object = json!({"apiVersion": "kubernetes.m.crossplane.io/v1alpha1"});
+        assert!(ready_patch(&object, "").is_none());
+        assert!(runs_locally(
+            "xpkg.crossplane.io/crossplane-contrib/provider-helm:v1.1.0"
+        ));
+        assert!(!runs_locally(
+            "xpkg.upbound.io/upbound/provider-aws-s3:v1.20.0"
+        ));
+    }
+}
@@ -1,13 +1,52 @@
+mod addon;
+mod autostart;
 mod aws;
+mod azure;
+pub mod backoff;
+mod charts;
+mod claim;
+mod clean;
+pub mod conditions;
+pub mod context;
 mod destroy;
+mod du;
+pub mod events;
+mod forward;
+mod function;
 mod github;
+pub mod history;
+mod hosts;
+mod idle;
 mod install;
+mod kubefwd;
+pub mod lock;
+mod logs;
+pub mod mock;
+mod preload;
+pub mod process;
+pub mod profile;
+mod record;
+pub mod registry;
+mod registry_auth;
+pub mod registry_forward;
+pub mod registry_host;
+mod registry_mirror;
+mod registry_tls;
 mod reset;
+pub mod retry;
+mod snapshot;
 mod start;
+mod status;
 mod stop;
+mod teardown;
+pub mod trace;
 mod uninstall;
+mod upgrade;
+mod vm_file;
+mod wait;
+mod watch;
 
-use clap::{Args, Subcommand};
+use clap::{Args, Subcommand, ValueEnum};
 use std::error::Error;
 use std::io::Write;
 use std::path::{Path, PathBuf};
@@ -38,13 +77,38 @@ fn with_kube_context(args: &[&str]) -> Vec<String> {
 /// Build a `Command` for kubectl with `--context` injected when configured.
 pub fn kubectl_command(args: &[&str]) -> Command {
     let full = with_kube_context(args);
+    crate::logging::log_command("kubectl", &full);
     let mut cmd = Command::new("kubectl");
     cmd.args(&full);
     cmd
 }
 
+/// Output format for reporting subcommands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// A single JSON document on stdout
+    Json,
+}
+
+/// Build a `Command` for colima with `--profile` injected when configured.
+pub fn colima_command(args: &[&str]) -> Command {
+    let full = profile::with_profile(args);
+    crate::logging::log_command("colima", &full);
+    let mut cmd = Command::new("colima");
+    cmd.args(&full);
+    cmd
+}
+
 #[derive(Args, Debug)]
 pub struct LocalArgs {
+    /// Colima profile to run the cluster in, e.g. `hops` to keep it apart
+    /// from your other Colima VMs (saved by `local start` for later commands)
+    #[arg(long, global = true)]
+    pub colima_profile: Option<String>,
+
     #[command(subcommand)]
     pub command: LocalCommands,
 }
@@ -53,14 +117,75 @@ pub struct LocalArgs {
 pub enum LocalCommands {
     /// Install Colima via Homebrew
     Install,
-    /// Reset local Colima Kubernetes state
-    Reset,
+    /// Reset local Colima Kubernetes state (pushed packages are kept)
+    Reset(reset::ResetArgs),
     /// Start local k8s cluster with Crossplane and providers
-    Start,
+    Start(start::StartArgs),
+    /// Upgrade the Crossplane Helm release and check package health afterward
+    Upgrade(upgrade::UpgradeArgs),
     /// Configure crossplane-contrib provider-family-aws and AWS ProviderConfig
     Aws(aws::AwsArgs),
+    /// Configure crossplane-contrib provider-family-azure and Azure ProviderConfig
+    Azure(azure::AzureArgs),
     /// Configure crossplane-contrib provider-upjet-github and GitHub ProviderConfig
     Github(github::GithubArgs),
+    /// Deploy optional development tools into the local cluster
+    Addon(addon::AddonArgs),
+    /// Maintain the in-cluster package registry
+    Registry(registry::RegistryArgs),
+    /// Create pull Secrets for private package registries
+    RegistryAuth(registry_auth::RegistryAuthArgs),
+    /// Show VM, Crossplane, package, registry, and kubefwd status
+    Status(status::StatusArgs),
+    /// Show logs for Crossplane, a provider, or a function
+    Logs(logs::LogsArgs),
+    /// Stream Kubernetes events for Crossplane packages and composite resources
+    Events(events::EventsArgs),
+    /// Wait until packages are healthy or XRs/claims are ready
+    Wait(wait::WaitArgs),
+    /// Apply a project's example XRs and claims as a smoke test
+    Claim(claim::ClaimArgs),
+    /// Develop a function against the cluster by running it on the host
+    Function(function::FunctionArgs),
+    /// Exercise compositions without cloud credentials by mocking providers
+    Mock(mock::MockArgs),
+    /// Keep mocked providers scaled down and their resources Ready (used by `mock on`)
+    #[command(hide = true)]
+    MockWatch,
+    /// Pull provider and function images into the VM ahead of installs
+    Preload(preload::PreloadArgs),
+    /// Print the tree of resources composed by an XR or claim with their conditions
+    Trace(trace::TraceArgs),
+    /// Follow an XR or claim and its composed resources, printing condition
+    /// changes and events as they happen
+    Watch(watch::WatchArgs),
+    /// Save the specs of the resources composed by an XR or claim
+    Record(record::RecordArgs),
+    /// Compare the resources composed by an XR or claim with its last recording
+    Diff(record::RecordArgs),
+    /// Report disk used by the VM, the package registry, and hops Docker images
+    Du(du::DuArgs),
+    /// Remove hops Docker images, leftover build dirs, and stale repo clones from the host
+    Clean(clean::CleanArgs),
+    /// Report or delete Providers, Functions, and Configurations nothing depends on
+    Prune(crate::commands::config::PruneArgs),
+    /// Show config install/uninstall/rollback history recorded in ~/.hops/local
+    History(history::HistoryArgs),
+    /// Forward cluster services to localhost ports without sudo or hosts-file edits
+    Forward(forward::ForwardArgs),
+    /// Run kubefwd in the background to reach cluster services by name
+    Kubefwd(kubefwd::KubefwdArgs),
+    /// Manage hosts-file entries for cluster services (lighter alternative to kubefwd)
+    Hosts(hosts::HostsArgs),
+    /// Save and restore snapshots of the VM
+    Snapshot(snapshot::SnapshotArgs),
+    /// Start the local environment at login
+    Autostart(autostart::AutostartArgs),
+    /// Stop Colima once the cluster has been idle (used by `start`)
+    #[command(hide = true)]
+    IdleWatch,
+    /// Remove Crossplane, packages, and the registry but keep the VM running
+    Teardown(teardown::TeardownArgs),
     /// Stop the local cluster
     Stop,
     /// Destroy the local cluster VM
@@ -69,13 +194,135 @@ pub enum LocalCommands {
     Uninstall,
 }
 
+impl LocalCommands {
+    /// Whether the command changes the cluster or the state in ~/.hops/local,
+    /// and so must not run alongside another such hops command.
+    fn changes_state(&self) -> bool {
+        match self {
+            Self::Registry(registry_args) => {
+                !matches!(registry_args.command, registry::RegistryCommands::Ls(_))
+            }
+            // Background workers outlive any lock taken at startup: the
+            // refresh loop locks each refresh, the forward supervisor only
+            // reads state.
+            Self::Aws(aws_args) if aws_args.daemon_loop => false,
+            Self::Forward(forward_args)
+                if matches!(forward_args.command, forward::ForwardCommands::Supervise) =>
+            {
+                false
+            }
+            Self::Reset(_)
+            | Self::Start(_)
+            | Self::Upgrade(_)
+            | Self::Aws(_)
+            | Self::Azure(_)
+            | Self::Github(_)
+            | Self::Addon(_)
+            | Self::RegistryAuth(_)
+            | Self::Claim(_)
+            | Self::Mock(_)
+            | Self::Clean(_)
+            | Self::Prune(_)
+            | Self::Forward(_)
+            | Self::Kubefwd(_)
+            | Self::Hosts(_)
+            | Self::Snapshot(_)
+            | Self::Autostart(_)
+            | Self::Teardown(_)
+            | Self::Stop
+            | Self::Destroy
+            | Self::Uninstall
+            | Self::Function(_)
+            // Loads images into the VM's Docker daemon.
+            | Self::Preload(_)
+            // Writes the recording under ~/.hops/local.
+            | Self::Record(_) => true,
+            // Read-only, or (the watchers) running for as long as the cluster
+            // does, where holding the lock would block every other command.
+            Self::Install
+            | Self::Status(_)
+            | Self::Logs(_)
+            | Self::Events(_)
+            | Self::Wait(_)
+            | Self::MockWatch
+            | Self::Trace(_)
+            | Self::Watch(_)
+            | Self::Diff(_)
+            | Self::Du(_)
+            | Self::History(_)
+            | Self::IdleWatch => false,
+        }
+    }
+
+    /// Whether kubectl and helm calls should fail at once instead of
+    /// retrying transient errors: `status` reports a stopped or restarting
+    /// cluster as it is, and the idle watchdog just checks again next poll.
+    fn fails_fast(&self) -> bool {
+        matches!(self, Self::Status(_) | Self::IdleWatch)
+    }
+}
+
 pub fn run(args: &LocalArgs) -> Result<(), Box<dyn Error>> {
+    retry::set_enabled(!args.command.fails_fast());
+    let _lock = if args.command.changes_state() {
+        Some(lock::acquire()?)
+    } else {
+        None
+    };
+    if let Some(colima_profile) = &args.colima_profile {
+        profile::select(
+            colima_profile,
+            matches!(args.command, LocalCommands::Start(_)),
+        )?;
+    }
+    // kubectl follows the current context, which may belong to another
+    // profile; pin it to this profile's cluster.
+    let colima_profile = profile::current();
+    if colima_profile != profile::DEFAULT_PROFILE
+        && std::env::var_os(HOPS_KUBE_CONTEXT_ENV).is_none()
+    {
+        std::env::set_var(
+            HOPS_KUBE_CONTEXT_ENV,
+            profile::instance_name(&colima_profile),
+        );
+    }
     match &args.command {
         LocalCommands::Install => install::run(),
-        LocalCommands::Reset => reset::run(),
-        LocalCommands::Start => start::run(),
+        LocalCommands::Reset(reset_args) => reset::run(reset_args),
+        LocalCommands::Start(start_args) => {
+            crate::notification::notify_result("local start", start::run(start_args))
+        }
+        LocalCommands::Upgrade(upgrade_args) => upgrade::run(upgrade_args),
         LocalCommands::Aws(aws_args) => aws::run(aws_args),
+        LocalCommands::Azure(azure_args) => azure::run(azure_args),
         LocalCommands::Github(github_args) => github::run(github_args),
+        LocalCommands::Addon(addon_args) => addon::run(addon_args),
+        LocalCommands::Registry(registry_args) => registry::run(registry_args),
+        LocalCommands::RegistryAuth(registry_auth_args) => registry_auth::run(registry_auth_args),
+        LocalCommands::Status(status_args) => status::run(status_args),
+        LocalCommands::Logs(logs_args) => logs::run(logs_args),
+        LocalCommands::Events(events_args) => events::run(events_args),
+        LocalCommands::Wait(wait_args) => wait::run(wait_args),
+        LocalCommands::Claim(claim_args) => claim::run(claim_args),
+        LocalCommands::Function(function_args) => function::run(function_args),
+        LocalCommands::Mock(mock_args) => mock::run(mock_args),
+        LocalCommands::MockWatch => mock::watch(),
+        LocalCommands::Preload(preload_args) => preload::run(preload_args),
+        LocalCommands::Trace(trace_args) => trace::run(trace_args),
+        LocalCommands::Watch(watch_args) => watch::run(watch_args),
+        LocalCommands::Record(record_args) => record::record(record_args),
+        LocalCommands::Diff(record_args) => record::diff(record_args),
+        LocalCommands::Du(du_args) => du::run(du_args),
+        LocalCommands::Clean(clean_args) => clean::run(clean_args),
+        LocalCommands::Prune(prune_args) => crate::commands::config::prune(prune_args),
+        LocalCommands::History(history_args) => history::run(history_args),
+        LocalCommands::Forward(forward_args) => forward::run(forward_args),
+        LocalCommands::Kubefwd(kubefwd_args) => kubefwd::run(kubefwd_args),
+        LocalCommands::Hosts(hosts_args) => hosts::run(hosts_args),
+        LocalCommands::Snapshot(snapshot_args) => snapshot::run(snapshot_args),
+        LocalCommands::Autostart(autostart_args) => autostart::run(autostart_args),
+        LocalCommands::IdleWatch => idle::watch(),
+        LocalCommands::Teardown(teardown_args) => teardown::run(teardown_args),
         LocalCommands::Stop => stop::run(),
         LocalCommands::Destroy => destroy::run(),
         LocalCommands::Uninstall => uninstall::run(),
@@ -83,10 +330,15 @@ pub fn run(args: &LocalArgs) -> Result<(), Box<dyn Error>> {
 }
 
 /// Run an external command with inherited stdio. Fails on non-zero exit.
-/// For kubectl commands, automatically injects `--context` when configured.
+/// For kubectl commands, automatically injects `--context` when configured,
+/// and for colima `--profile`.
 pub fn run_cmd(program: &str, args: &[&str]) -> Result<(), Box<dyn Error>> {
-    if program == "kubectl" {
-        let full = with_kube_context(args);
+    if program == "kubectl" || program == "colima" {
+        let full = if program == "kubectl" {
+            with_kube_context(args)
+        } else {
+            profile::with_profile(args)
+        };
         let refs: Vec<&str> = full.iter().map(|s| s.as_str()).collect();
         return run_cmd_with_logged_args(program, &refs, &refs);
     }
@@ -94,12 +346,45 @@ pub fn run_cmd(program: &str, args: &[&str]) -> Result<(), Box<dyn Error>> {
 }
 
 /// Run an external command and capture stdout.
-/// For kubectl commands, automatically injects `--context` when configured.
+/// For kubectl commands, automatically injects `--context` when configured,
+/// and for colima `--profile`.
+/// kubectl and helm are retried when they fail with a transient cluster
+/// error (see [`retry`]).
 pub fn run_cmd_output(program: &str, args: &[&str]) -> Result<String, Box<dyn Error>> {
-    if program == "kubectl" {
-        let full = with_kube_context(args);
-        log::debug!("Running: {} {}", program, full.join(" "));
-        let output = Command::new(program).args(&full).output()?;
+    if program == "kubectl" || program == "helm" {
+        let what = format!("{} {}", program, args.first().unwrap_or(&""));
+        return retry::retry(&what, || run_cmd_output_once(program, args, false));
+    }
+    run_cmd_output_once(program, args, false)
+}
+
+/// [`run_cmd_output`] for commands that print credentials: `--debug-commands`
+/// shows only the size of their stdout.
+pub fn run_cmd_output_secret(program: &str, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    run_cmd_output_once(program, args, true)
+}
+
+fn run_cmd_output_once(
+    program: &str,
+    args: &[&str],
+    secret: bool,
+) -> Result<String, Box<dyn Error>> {
+    let log_output = |output: &std::process::Output| {
+        if secret {
+            crate::logging::log_secret_command_output(program, output);
+        } else {
+            crate::logging::log_command_output(program, output);
+        }
+    };
+    if program == "kubectl" || program == "colima" {
+        let full = if program == "kubectl" {
+            with_kube_context(args)
+        } else {
+            profile::with_profile(args)
+        };
+        crate::logging::log_command(program, &full);
+        let output = crate::interrupt::output(Command::new(program).args(&full))?;
+        log_output(&output);
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(format!("{} exited with {}: {}", program, output.status, stderr).into());
@@ -107,8 +392,9 @@ pub fn run_cmd_output(program: &str, args: &[&str]) -> Result<String, Box<dyn Er
         return Ok(String::from_utf8_lossy(&output.stdout).to_string());
     }
 
-    log::debug!("Running: {} {}", program, args.join(" "));
-    let output = Command::new(program).args(args).output()?;
+    crate::logging::log_command(program, args);
+    let output = crate::interrupt::output(Command::new(program).args(args))?;
+    log_output(&output);
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(format!("{} exited with {}: {}", program, output.status, stderr).into());
@@ -121,13 +407,14 @@ fn run_cmd_with_logged_args(
     args: &[&str],
     logged_args: &[&str],
 ) -> Result<(), Box<dyn Error>> {
-    log::debug!("Running: {} {}", program, logged_args.join(" "));
-    let status = Command::new(program)
-        .args(args)
-        .stdin(Stdio::inherit())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status()?;
+    crate::logging::log_command(program, logged_args);
+    let status = crate::interrupt::status(
+        Command::new(program)
+            .args(args)
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit()),
+    )?;
 
     if !status.success() {
         return Err(format!("{} exited with {}", program, status).into());
@@ -145,7 +432,7 @@ fn local_state_dir() -> Result<PathBuf, Box<dyn Error>> {
     Ok(Path::new(&home).join(LOCAL_STATE_DIR))
 }
 
-fn command_exists(program: &str) -> bool {
+pub fn command_exists(program: &str) -> bool {
     Command::new("sh")
         .args(["-c", &format!("command -v {} >/dev/null 2>&1", program)])
         .status()
@@ -153,6 +440,22 @@ fn command_exists(program: &str) -> bool {
         .unwrap_or(false)
 }
 
+/// Used and available space, in KiB, from POSIX `df -Pk` output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiskUsage {
+    pub used_kib: u64,
+    pub available_kib: u64,
+}
+
+/// Parse the first filesystem row of POSIX `df -Pk` output.
+pub fn parse_df_kib(output: &str) -> Option<DiskUsage> {
+    let mut columns = output.lines().nth(1)?.split_whitespace().skip(2);
+    Some(DiskUsage {
+        used_kib: columns.next()?.parse().ok()?,
+        available_kib: columns.next()?.parse().ok()?,
+    })
+}
+
 /// Ensure Colima's /etc/hosts maps a service hostname to the current ClusterIP.
 pub fn sync_registry_hosts_entry(
     namespace: &str,
@@ -176,70 +479,47 @@ pub fn sync_registry_hosts_entry(
         return Err(format!("Service {}/{} has no ClusterIP", namespace, service).into());
     }
 
-    let current_ip = run_cmd_output(
-        "colima",
-        &[
-            "ssh",
-            "--",
-            "sh",
-            "-c",
-            &format!("awk '$2 == \"{}\" {{print $1; exit}}' /etc/hosts", hostname),
-        ],
-    )
-    .unwrap_or_default();
-    if current_ip.trim() == cluster_ip {
+    let hosts = vm_file::read(vm_file::HOSTS_FILE)?;
+    let Some(updated) = vm_file::with_hosts_entry(&hosts, hostname, cluster_ip) else {
         return Ok(());
-    }
-
+    };
     log::info!("Updating hosts entry: {} -> {}", hostname, cluster_ip);
-
-    let escaped_host = hostname.replace('.', "\\.");
-    run_cmd(
-        "colima",
-        &[
-            "ssh",
-            "--",
-            "sudo",
-            "sed",
-            "-i",
-            &format!("/{}/d", escaped_host),
-            "/etc/hosts",
-        ],
-    )?;
-    run_cmd(
-        "colima",
-        &[
-            "ssh",
-            "--",
-            "sudo",
-            "sh",
-            "-c",
-            &format!("echo '{} {}' >> /etc/hosts", cluster_ip, hostname),
-        ],
-    )?;
-
-    Ok(())
+    vm_file::write(vm_file::HOSTS_FILE, &updated)
 }
 
 /// Pipe a YAML string into `kubectl apply -f -`.
 /// Automatically injects `--context` when configured.
 pub fn kubectl_apply_stdin(yaml: &str) -> Result<(), Box<dyn Error>> {
+    retry::retry("kubectl apply", || kubectl_apply_stdin_once(yaml))
+}
+
+fn kubectl_apply_stdin_once(yaml: &str) -> Result<(), Box<dyn Error>> {
     let full = with_kube_context(&["apply", "-f", "-"]);
+    crate::logging::log_command("kubectl", &full);
+    // stderr is captured to tell transient failures apart, and shown after.
     let mut child = Command::new("kubectl")
         .args(&full)
         .stdin(Stdio::piped())
         .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
+        .stderr(Stdio::piped())
         .spawn()?;
+    let _guard = crate::interrupt::child(child.id());
 
     if let Some(ref mut stdin) = child.stdin {
         stdin.write_all(yaml.as_bytes())?;
     }
 
-    let status = child.wait()?;
-    if !status.success() {
-        return Err(format!("kubectl apply exited with {}", status).into());
+    let output = child.wait_with_output()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(format!(
+            "kubectl apply exited with {}: {}",
+            output.status,
+            stderr.trim()
+        )
+        .into());
     }
+    eprint!("{}", stderr);
     Ok(())
 }
 
@@ -255,7 +535,15 @@ pub fn kubectl_patch_merge(
         "patch", resource, name, "-n", namespace, "--type", "merge", "-p", patch_json,
     ];
     let base_logged = [
-        "patch", resource, name, "-n", namespace, "--type", "merge", "-p", "<REDACTED>",
+        "patch",
+        resource,
+        name,
+        "-n",
+        namespace,
+        "--type",
+        "merge",
+        "-p",
+        "<REDACTED>",
     ];
     let full_args = with_kube_context(&base_args);
     let full_logged = with_kube_context(&base_logged);
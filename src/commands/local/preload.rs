@@ -0,0 +1,166 @@
+use super::{run_cmd, run_cmd_output};
+use crate::commands::render::{is_exact_version, project_dependencies};
+use clap::Args;
+use std::error::Error;
+use std::path::PathBuf;
+use std::thread;
+
+#[derive(Args, Debug)]
+pub struct PreloadArgs {
+    /// Provider or function packages (or any image) to pull, e.g.
+    /// xpkg.crossplane.io/crossplane-contrib/provider-aws-s3:v2.1.0. Without
+    /// images, --path, or --import: the providers `local start` installs plus
+    /// `start.preload` from ~/.hops/config.yaml
+    pub images: Vec<String>,
+
+    /// Also pull the dependencies of this project that are pinned to an
+    /// exact version or digest (upbound.yaml or crossplane.yaml)
+    #[arg(long)]
+    pub path: Option<PathBuf>,
+
+    /// Load images from a tarball written by `docker save` or `--save`
+    /// (repeatable)
+    #[arg(long = "import", value_name = "FILE")]
+    pub imports: Vec<PathBuf>,
+
+    /// Write the preloaded images to this tarball, for `--import` offline or
+    /// on another machine
+    #[arg(long, value_name = "FILE")]
+    pub save: Option<PathBuf>,
+
+    /// Pull images again even if the VM already has them
+    #[arg(long)]
+    pub force: bool,
+}
+
+pub fn run(args: &PreloadArgs) -> Result<(), Box<dyn Error>> {
+    for tarball in &args.imports {
+        log::info!("Loading images from {}...", tarball.display());
+        run_cmd("docker", &["load", "-i", &tarball.display().to_string()])?;
+    }
+
+    let mut images = args.images.clone();
+    if let Some(path) = &args.path {
+        for (package, version) in project_dependencies(path) {
+            match pinned_image(&package, &version) {
+                Some(image) => images.push(image),
+                None => log::warn!(
+                    "Skipping {}: {:?} is a constraint, not an exact version",
+                    package,
+                    version
+                ),
+            }
+        }
+    }
+    if images.is_empty() && args.path.is_none() && args.imports.is_empty() {
+        images = default_images()?;
+    }
+    images.sort();
+    images.dedup();
+    if images.is_empty() {
+        if args.imports.is_empty() {
+            log::info!("Nothing to preload");
+        }
+        return Ok(());
+    }
+
+    pull(&images, args.force)?;
+    if let Some(file) = &args.save {
+        log::info!("Saving {} image(s) to {}...", images.len(), file.display());
+        let file = file.display().to_string();
+        let mut save_args = vec!["save", "-o", file.as_str()];
+        save_args.extend(images.iter().map(String::as_str));
+        run_cmd("docker", &save_args)?;
+    }
+    Ok(())
+}
+
+/// The providers `local start` installs plus `start.preload` from the
+/// config file.
+pub fn default_images() -> Result<Vec<String>, Box<dyn Error>> {
+    let mut images = super::start::bootstrap_packages();
+    images.extend(crate::settings::load()?.start.preload);
+    Ok(images)
+}
+
+/// Pull `images` into the Colima Docker daemon (which the kubelet pulls
+/// through), several at a time, skipping those already present unless
+/// `force`.
+pub fn pull(images: &[String], force: bool) -> Result<(), Box<dyn Error>> {
+    let (present, missing): (Vec<&String>, Vec<&String>) = images
+        .iter()
+        .partition(|image| !force && image_present(image));
+    for image in &present {
+        log::debug!("{} is already in the VM", image);
+    }
+    if !missing.is_empty() {
+        log::info!("Pulling {} image(s) into the VM...", missing.len());
+    }
+    let errors: Vec<String> = thread::scope(|scope| {
+        let handles: Vec<_> = missing
+            .iter()
+            .map(|image| {
+                scope.spawn(move || {
+                    run_cmd_output("docker", &["pull", "--quiet", image])
+                        .map_err(|err| format!("{}: {}", image, err))
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|handle| match handle.join() {
+                Ok(result) => result.err(),
+                Err(_) => Some("an image pull panicked".to_string()),
+            })
+            .collect()
+    });
+    if !errors.is_empty() {
+        return Err(format!("failed to pull:\n  {}", errors.join("\n  ")).into());
+    }
+    crate::logging::success(&format!(
+        "Preloaded {} image(s) ({} already present)",
+        images.len(),
+        present.len()
+    ));
+    Ok(())
+}
+
+fn image_present(image: &str) -> bool {
+    run_cmd_output(
+        "docker",
+        &["image", "inspect", "--format", "{{.Id}}", image],
+    )
+    .is_ok()
+}
+
+/// `package:version` or `package@digest` for a dependency pinned exactly.
+fn pinned_image(package: &str, version: &str) -> Option<String> {
+    let version = version.trim();
+    if version.starts_with("sha256:") {
+        Some(format!("{}@{}", package, version))
+    } else if is_exact_version(version) {
+        Some(format!("{}:{}", package, version))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pinned_image_accepts_exact_versions_and_digests() {
+        let package = "xpkg.crossplane.io/crossplane-contrib/function-auto-ready";
+        assert_eq!(
+            pinned_image(package, "v0.6.0").as_deref(),
+            Some("xpkg.crossplane.io/crossplane-contrib/function-auto-ready:v0.6.0")
+        );
+        assert_eq!(
+            pinned_image(package, "sha256:0123abcd").as_deref(),
+            Some("xpkg.crossplane.io/crossplane-contrib/function-auto-ready@sha256:0123abcd")
+        );
+        assert_eq!(pinned_image(package, ">=v0.2.0"), None);
+        assert_eq!(pinned_image(package, "*"), None);
+    }
+}
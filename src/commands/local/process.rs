@@ -0,0 +1,201 @@
+use super::local_state_dir;
+use crate::settings::LogRotation;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, SystemTime};
+
+/// Path of a file in the local state directory (`~/.hops/local`).
+pub fn state_path(file_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    Ok(local_state_dir()?.join(file_name))
+}
+
+/// Read a JSON state file. Missing or unreadable files yield `None`.
+pub fn read_state<T: DeserializeOwned>(file_name: &str) -> Option<T> {
+    let contents = fs::read_to_string(state_path(file_name).ok()?).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Write a JSON state file, creating the state directory if needed.
+pub fn write_state<T: Serialize>(file_name: &str, state: &T) -> Result<(), Box<dyn Error>> {
+    let path = state_path(file_name)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // Write then rename so a concurrent reader never sees a partial file.
+    let tmp = path.with_extension("json.tmp");
+    let _guard = crate::interrupt::temp_path(&tmp);
+    fs::write(&tmp, serde_json::to_string_pretty(state)?)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+pub fn remove_state(file_name: &str) -> Result<(), Box<dyn Error>> {
+    match fs::remove_file(state_path(file_name)?) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Whether a process with this PID exists. Uses `ps` rather than `kill -0`
+/// so processes owned by root (e.g. kubefwd under sudo) are seen too.
+pub fn process_alive(pid: u32) -> bool {
+    pid != 0
+        && Command::new("ps")
+            .args(["-p", &pid.to_string()])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+}
+
+/// Start a background process in its own process group, detached from the
+/// terminal, with stdout and stderr appended to `log_path`. Returns its PID,
+/// which is also the process group ID.
+pub fn spawn_detached(
+    program: &Path,
+    args: &[&str],
+    log_path: &Path,
+) -> Result<u32, Box<dyn Error>> {
+    if let Some(parent) = log_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log_path)?;
+    crate::logging::log_command(&program.display().to_string(), args);
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .env_remove(super::lock::HELD_ENV)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Own process group: Ctrl-C in the launching shell doesn't reach it,
+        // and stopping the group also stops its children.
+        command.process_group(0);
+    }
+    Ok(command.spawn()?.id())
+}
+
+/// Send SIGTERM to a process group started by [`spawn_detached`]. Groups
+/// running as root (started through sudo) need `as_root`.
+pub fn terminate_process_group(pid: u32, as_root: bool) -> Result<(), Box<dyn Error>> {
+    if !process_alive(pid) {
+        return Ok(());
+    }
+    let group = format!("-{}", pid);
+    let mut command = if as_root {
+        let mut command = Command::new("sudo");
+        command.arg("kill");
+        command
+    } else {
+        Command::new("kill")
+    };
+    let status = command
+        .args(["-TERM", "--", &group])
+        .stderr(Stdio::null())
+        .status()?;
+    if !status.success() {
+        return Err(format!("failed to stop process group {}", pid).into());
+    }
+    Ok(())
+}
+
+/// Rotate `log_path` to `<log>.1` (shifting older files up to `keep`) when it
+/// is larger than the configured size or older than the configured age.
+/// Only call this while no process has the log open.
+pub fn rotate_log(log_path: &Path, rotation: &LogRotation) -> Result<(), Box<dyn Error>> {
+    let Ok(metadata) = fs::metadata(log_path) else {
+        return Ok(());
+    };
+    let too_big = metadata.len() > rotation.max_size_mb * 1024 * 1024;
+    let too_old = rotation.max_age_days > 0
+        && metadata
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > Duration::from_secs(rotation.max_age_days * 24 * 60 * 60));
+    if !too_big && !too_old {
+        return Ok(());
+    }
+
+    let rotated = |index: usize| PathBuf::from(format!("{}.{}", log_path.display(), index));
+    if rotation.keep == 0 {
+        fs::remove_file(log_path)?;
+        return Ok(());
+    }
+    let _ = fs::remove_file(rotated(rotation.keep));
+    for index in (1..rotation.keep).rev() {
+        let from = rotated(index);
+        if from.exists() {
+            fs::rename(&from, rotated(index + 1))?;
+        }
+    }
+    fs::rename(log_path, rotated(1))?;
+    log::debug!("Rotated {}", log_path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detached_children_do_not_inherit_the_state_lock() {
+        let dir = std::env::temp_dir().join(format!("hops-detached-test-{}", std::process::id()));
+        let log = dir.join("child.log");
+        std::env::set_var(super::super::lock::HELD_ENV, "1");
+        let pid = spawn_detached(
+            Path::new("sh"),
+            &["-c", "echo \"held=${HOPS_STATE_LOCK_HELD:-no}\""],
+            &log,
+        )
+        .unwrap();
+        std::env::remove_var(super::super::lock::HELD_ENV);
+        assert!(pid > 0);
+        // The child is never reaped here, so wait for its output instead.
+        let mut output = String::new();
+        for _ in 0..250 {
+            output = fs::read_to_string(&log).unwrap_or_default();
+            if output.ends_with('\n') {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(output.trim(), "held=no");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rotate_log_shifts_and_caps_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("hops-rotate-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let log = dir.join("kubefwd.log");
+        let rotation = LogRotation {
+            max_size_mb: 0,
+            max_age_days: 0,
+            keep: 2,
+        };
+        for round in 0..3 {
+            fs::write(&log, format!("round {}", round)).unwrap();
+            rotate_log(&log, &rotation).unwrap();
+        }
+
+        assert!(!log.exists());
+        let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("kubefwd.log.1"), "round 2");
+        assert_eq!(read("kubefwd.log.2"), "round 1");
+        assert!(!dir.join("kubefwd.log.3").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
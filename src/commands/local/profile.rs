@@ -0,0 +1,150 @@
+use super::process::{read_state, write_state};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+/// Colima's profile when none is given.
+pub const DEFAULT_PROFILE: &str = "default";
+/// Carries `--colima-profile` to the hops processes this one starts (the idle
+/// and mock watchers, `local aws` run by a stack install).
+const PROFILE_ENV: &str = "HOPS_COLIMA_PROFILE";
+/// `~/.hops/local/colima.json`
+const STATE_FILE: &str = "colima.json";
+
+/// The profile hops manages, and the ones `local start` has started: only
+/// those may be stopped or deleted by hops.
+#[derive(Debug, Serialize, Deserialize)]
+struct ProfileState {
+    profile: String,
+    owned: Vec<String>,
+}
+
+impl Default for ProfileState {
+    /// Before profiles were configurable, hops always ran the default one.
+    fn default() -> Self {
+        Self {
+            profile: DEFAULT_PROFILE.to_string(),
+            owned: vec![DEFAULT_PROFILE.to_string()],
+        }
+    }
+}
+
+impl ProfileState {
+    fn load() -> Self {
+        read_state(STATE_FILE).unwrap_or_default()
+    }
+}
+
+/// Use `profile` (from `--colima-profile`) for this invocation and the hops
+/// processes it starts; `local start` also saves it for later commands.
+pub fn select(profile: &str, persist: bool) -> Result<(), Box<dyn Error>> {
+    validate(profile)?;
+    if persist {
+        let mut state = ProfileState::load();
+        state.profile = profile.to_string();
+        write_state(STATE_FILE, &state)?;
+    }
+    // Set before any threads or child processes start.
+    std::env::set_var(PROFILE_ENV, profile);
+    Ok(())
+}
+
+/// The Colima profile to act on: `--colima-profile`, else the one the last
+/// `local start --colima-profile` saved, else Colima's default.
+pub fn current() -> String {
+    match std::env::var(PROFILE_ENV) {
+        Ok(profile) if !profile.is_empty() => profile,
+        _ => ProfileState::load().profile,
+    }
+}
+
+/// Prepend `--profile` to a colima arg slice unless the default profile is
+/// in use.
+pub fn with_profile(args: &[&str]) -> Vec<String> {
+    let profile = current();
+    let mut out = Vec::new();
+    if profile != DEFAULT_PROFILE {
+        out.extend(["--profile".to_string(), profile]);
+    }
+    out.extend(args.iter().map(|s| s.to_string()));
+    out
+}
+
+/// Name Colima gives the profile's kube context, Docker context, and Lima
+/// instance: `colima`, or `colima-<profile>`.
+pub fn instance_name(profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        "colima".to_string()
+    } else {
+        format!("colima-{}", profile)
+    }
+}
+
+/// Record that `local start` started the current profile, so hops may stop
+/// and delete it later.
+pub fn mark_owned() -> Result<(), Box<dyn Error>> {
+    let profile = current();
+    let mut state = ProfileState::load();
+    if !state.owned.contains(&profile) {
+        state.owned.push(profile);
+        write_state(STATE_FILE, &state)?;
+    }
+    Ok(())
+}
+
+/// Forget the current profile after `local destroy` deleted it.
+pub fn forget_owned() -> Result<(), Box<dyn Error>> {
+    let profile = current();
+    let mut state = ProfileState::load();
+    state.owned.retain(|owned| *owned != profile);
+    write_state(STATE_FILE, &state)
+}
+
+/// Refuse to go on unless `local start` started the current profile, so
+/// `command` can't stop or delete a VM the user runs for something else.
+pub fn ensure_owned(command: &str) -> Result<(), Box<dyn Error>> {
+    check_owned(command, &current(), &ProfileState::load().owned)?;
+    Ok(())
+}
+
+fn check_owned(command: &str, profile: &str, owned: &[String]) -> Result<(), String> {
+    if owned.iter().any(|o| o == profile) {
+        return Ok(());
+    }
+    Err(format!(
+        "refusing to run `{}` on Colima profile '{}': hops didn't start it. Use `colima --profile {}` directly, or adopt it with `hops local start --colima-profile {}`",
+        command, profile, profile, profile
+    ))
+}
+
+fn validate(profile: &str) -> Result<(), Box<dyn Error>> {
+    let valid = !profile.is_empty()
+        && profile
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if !valid {
+        return Err(format!(
+            "invalid Colima profile '{}': use letters, digits, '-' and '_'",
+            profile
+        )
+        .into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_profiles_hops_started_can_be_stopped() {
+        assert_eq!(instance_name(DEFAULT_PROFILE), "colima");
+        assert_eq!(instance_name("hops"), "colima-hops");
+        assert!(validate("hops-dev_2").is_ok());
+        assert!(validate("../work").is_err());
+
+        let owned = ProfileState::default().owned;
+        assert!(check_owned("local stop", DEFAULT_PROFILE, &owned).is_ok());
+        let err = check_owned("local destroy", "work", &owned).unwrap_err();
+        assert!(err.starts_with("refusing to run `local destroy` on Colima profile 'work'"));
+    }
+}
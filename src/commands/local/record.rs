@@ -0,0 +1,195 @@
+use super::conditions::get_resource_json;
+use super::process::state_path;
+use super::trace::composed_resources;
+use crate::commands::test::compare_documents;
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// `~/.hops/local/records/<file>.json`, one recording per XR or claim.
+const RECORDS_DIR: &str = "records";
+const COMPOSITION_RESOURCE_NAME: &str = "crossplane.io/composition-resource-name";
+/// Spec fields Crossplane maintains itself, which change without the
+/// Composition changing.
+const VOLATILE_SPEC_FIELDS: &[&str] = &[
+    "claimRef",
+    "compositionRevisionRef",
+    "resourceRef",
+    "resourceRefs",
+];
+
+#[derive(Args, Debug)]
+pub struct RecordArgs {
+    /// XR or claim as <kind>/<name> (kind may include the group)
+    pub target: String,
+
+    /// Namespace of the XR or claim
+    #[arg(long, short = 'n')]
+    pub namespace: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+    recorded: String,
+    resources: Vec<JsonValue>,
+}
+
+/// Save the specs of everything composed under the target, replacing the
+/// previous recording.
+pub fn record(args: &RecordArgs) -> Result<(), Box<dyn Error>> {
+    let resources = composed_specs(args)?;
+    let path = recording_path(args)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let recording = Recording {
+        recorded: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+        resources,
+    };
+    fs::write(&path, serde_json::to_string_pretty(&recording)?)?;
+    crate::logging::success(&format!(
+        "Recorded {} composed resource(s) of {}",
+        recording.resources.len(),
+        args.target
+    ));
+    Ok(())
+}
+
+/// Compare the live composed resources with the last recording.
+pub fn diff(args: &RecordArgs) -> Result<(), Box<dyn Error>> {
+    let path = recording_path(args)?;
+    let recording: Recording = serde_json::from_str(&fs::read_to_string(&path).map_err(|_| {
+        format!(
+            "no recording of {}; run `hops local record {}` first",
+            args.target, args.target
+        )
+    })?)?;
+    let live = composed_specs(args)?;
+    let differences = compare_documents(&recording.resources, &live);
+    if differences.is_empty() {
+        crate::logging::success(&format!(
+            "No changes to {} since {}",
+            args.target, recording.recorded
+        ));
+        return Ok(());
+    }
+    println!(
+        "Changes to {} since {} (expected = recorded, got = live):",
+        args.target, recording.recorded
+    );
+    for difference in differences {
+        println!("  {}", difference);
+    }
+    Ok(())
+}
+
+fn composed_specs(args: &RecordArgs) -> Result<Vec<JsonValue>, Box<dyn Error>> {
+    let (kind, name) = args
+        .target
+        .split_once('/')
+        .map(|(kind, name)| (kind.trim(), name.trim()))
+        .filter(|(kind, name)| !kind.is_empty() && !name.is_empty())
+        .ok_or_else(|| format!("expected <kind>/<name>, got '{}'", args.target))?;
+    let root = get_resource_json(kind, name, args.namespace.as_deref())
+        .ok_or_else(|| format!("could not get {}", args.target))?;
+    Ok(composed_resources(&root)
+        .into_iter()
+        .filter_map(|(_, object)| object)
+        .map(|object| spec_document(&object))
+        .collect())
+}
+
+/// The identifying metadata and spec of a composed resource.
+fn spec_document(object: &JsonValue) -> JsonValue {
+    let mut spec = object["spec"].clone();
+    if let Some(fields) = spec.as_object_mut() {
+        for field in VOLATILE_SPEC_FIELDS {
+            fields.remove(*field);
+        }
+        if let Some(crossplane) = fields
+            .get_mut("crossplane")
+            .and_then(JsonValue::as_object_mut)
+        {
+            for field in VOLATILE_SPEC_FIELDS {
+                crossplane.remove(*field);
+            }
+        }
+    }
+    let mut metadata = json!({"name": object["metadata"]["name"]});
+    if let Some(namespace) = object.pointer("/metadata/namespace") {
+        metadata["namespace"] = namespace.clone();
+    }
+    if let Some(resource_name) = object
+        .pointer("/metadata/annotations")
+        .and_then(|annotations| annotations.get(COMPOSITION_RESOURCE_NAME))
+    {
+        metadata["annotations"] = json!({COMPOSITION_RESOURCE_NAME: resource_name});
+    }
+    json!({
+        "apiVersion": object["apiVersion"],
+        "kind": object["kind"],
+        "metadata": metadata,
+        "spec": spec
+    })
+}
+
+fn recording_path(args: &RecordArgs) -> Result<PathBuf, Box<dyn Error>> {
+    let target = match &args.namespace {
+        Some(namespace) => format!("{}_{}", namespace, args.target),
+        None => args.target.clone(),
+    };
+    let file: String = target
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_') {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(state_path(RECORDS_DIR)?.join(format!("{}.json", file)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spec_document_keeps_identity_and_drops_volatile_fields() {
+        let object = json!({
+            "apiVersion": "example.org/v1",
+            "kind": "XSubnet",
+            "metadata": {
+                "name": "demo-x7k2p",
+                "uid": "1234",
+                "annotations": {COMPOSITION_RESOURCE_NAME: "subnet", "other": "x"}
+            },
+            "spec": {
+                "cidr": "10.0.0.0/24",
+                "resourceRefs": [{"kind": "Subnet"}],
+                "crossplane": {"resourceRefs": [], "compositionRef": {"name": "default"}}
+            },
+            "status": {"ready": true}
+        });
+        assert_eq!(
+            spec_document(&object),
+            json!({
+                "apiVersion": "example.org/v1",
+                "kind": "XSubnet",
+                "metadata": {
+                    "name": "demo-x7k2p",
+                    "annotations": {COMPOSITION_RESOURCE_NAME: "subnet"}
+                },
+                "spec": {
+                    "cidr": "10.0.0.0/24",
+                    "crossplane": {"compositionRef": {"name": "default"}}
+                }
+            })
+        );
+    }
+}
@@ -0,0 +1,865 @@
+use super::backoff::Backoff;
+use super::process::read_state;
+use super::{registry_host, registry_tls, run_cmd, run_cmd_output, OutputFormat};
+use crate::settings::RegistryMode;
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::io::{Read, Seek, SeekFrom};
+use std::time::Duration;
+
+/// NodePort of the bundled registry manifest.
+const DEFAULT_NODE_PORT: u16 = 30500;
+
+/// Cluster-internal address used in Crossplane package references.
+pub const REGISTRY_PULL: &str = "registry.crossplane-system.svc.cluster.local:5000";
+
+/// Storage path of the `registry:2` image.
+pub const REGISTRY_STORAGE: &str = "/var/lib/registry";
+/// Colima VM directory backing [`REGISTRY_STORAGE`] (see bootstrap/registry).
+pub const REGISTRY_HOST_PATH: &str = "/var/lib/hops/registry";
+const REGISTRY_CONFIG: &str = "/etc/docker/registry/config.yml";
+
+const REGISTRY_MANIFEST: &str = include_str!("../../../bootstrap/registry/registry.yaml");
+
+/// `~/.hops/local/registry-auth.json`: credentials of an htpasswd-protected
+/// registry (`hops local registry-auth local`).
+pub const AUTH_STATE_FILE: &str = "registry-auth.json";
+/// Secret in crossplane-system holding the registry's htpasswd file.
+pub const HTPASSWD_SECRET: &str = "registry-htpasswd";
+const HTPASSWD_DIR: &str = "/auth";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+    /// bcrypt htpasswd line for `username`, as the registry reads it.
+    pub htpasswd: String,
+}
+
+impl Credentials {
+    /// `Authorization` header value for the registry API.
+    pub fn basic_auth(&self) -> String {
+        format!(
+            "Basic {}",
+            base64(format!("{}:{}", self.username, self.password).as_bytes())
+        )
+    }
+}
+
+/// Credentials for the local registry, when it requires authentication.
+pub fn credentials() -> Option<Credentials> {
+    read_state(AUTH_STATE_FILE)
+}
+
+/// Host port the registry is pushed to: its NodePort (`registry.node-port`
+/// in ~/.hops/config.yaml), or a `kubectl port-forward` on the same port
+/// when the NodePort is unreachable (see [`super::registry_forward`]).
+pub fn node_port() -> u16 {
+    crate::settings::load()
+        .map(|settings| settings.registry.node_port)
+        .unwrap_or(DEFAULT_NODE_PORT)
+}
+
+/// Host address of the in-cluster registry, used for `docker push`.
+pub fn push_host() -> String {
+    format!("localhost:{}", node_port())
+}
+
+/// Where the registry runs (`registry.mode` in ~/.hops/config.yaml).
+pub fn mode() -> RegistryMode {
+    crate::settings::load()
+        .map(|settings| settings.registry.mode)
+        .unwrap_or_default()
+}
+
+/// Program and arguments running `command` inside the registry container.
+fn exec_command(command: &[&str]) -> (&'static str, Vec<String>) {
+    let (program, prefix): (&str, &[&str]) = match mode() {
+        RegistryMode::InCluster => (
+            "kubectl",
+            &["exec", "-n", "crossplane-system", "deploy/registry", "--"],
+        ),
+        RegistryMode::Host => ("docker", &["exec", registry_host::CONTAINER]),
+    };
+    let args = prefix
+        .iter()
+        .chain(command)
+        .map(|arg| arg.to_string())
+        .collect();
+    (program, args)
+}
+
+/// The registry Deployment and Service, with htpasswd auth and the TLS
+/// proxy when enabled.
+pub fn manifest() -> Result<String, Box<dyn Error>> {
+    let auth = credentials().is_some();
+    let tls = registry_tls::enabled();
+    let node_port = node_port();
+    if !auth && !tls && node_port == DEFAULT_NODE_PORT {
+        return Ok(REGISTRY_MANIFEST.to_string());
+    }
+    customize(REGISTRY_MANIFEST, auth, tls, node_port)
+}
+
+fn customize(
+    manifest: &str,
+    auth: bool,
+    tls: bool,
+    node_port: u16,
+) -> Result<String, Box<dyn Error>> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(manifest) {
+        let mut object = JsonValue::deserialize(document)?;
+        if object.get("kind").and_then(JsonValue::as_str) == Some("Service") {
+            object["spec"]["ports"][0]["nodePort"] = json!(node_port);
+        }
+        if tls {
+            registry_tls::add_tls_proxy(&mut object, node_port)?;
+        }
+        if auth && object.get("kind").and_then(JsonValue::as_str) == Some("Deployment") {
+            let pod = object
+                .pointer_mut("/spec/template/spec")
+                .ok_or("registry manifest has no pod spec")?;
+            pod["volumes"]
+                .as_array_mut()
+                .ok_or("registry manifest has no volumes")?
+                .push(json!({"name": "htpasswd", "secret": {"secretName": HTPASSWD_SECRET}}));
+            let container = pod
+                .pointer_mut("/containers/0")
+                .ok_or("registry manifest has no container")?;
+            container["env"]
+                .as_array_mut()
+                .ok_or("registry container has no env")?
+                .extend([
+                    json!({"name": "REGISTRY_AUTH", "value": "htpasswd"}),
+                    json!({"name": "REGISTRY_AUTH_HTPASSWD_REALM", "value": "hops local registry"}),
+                    json!({"name": "REGISTRY_AUTH_HTPASSWD_PATH", "value": format!("{}/htpasswd", HTPASSWD_DIR)}),
+                ]);
+            container["volumeMounts"]
+                .as_array_mut()
+                .ok_or("registry container has no volumeMounts")?
+                .push(json!({"name": "htpasswd", "mountPath": HTPASSWD_DIR, "readOnly": true}));
+        }
+        documents.push(serde_yaml::to_string(&object)?);
+    }
+    Ok(documents.join("---\n"))
+}
+
+/// Package resources whose references keep a registry manifest alive.
+const PACKAGE_RESOURCES: &str = "configurations.pkg.crossplane.io,providers.pkg.crossplane.io,functions.pkg.crossplane.io,configurationrevisions.pkg.crossplane.io,providerrevisions.pkg.crossplane.io,functionrevisions.pkg.crossplane.io";
+
+#[derive(Args, Debug)]
+pub struct RegistryArgs {
+    #[command(subcommand)]
+    pub command: RegistryCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RegistryCommands {
+    /// List repositories, or one repository's tags, with digests and sizes
+    Ls(LsArgs),
+    /// Delete tags (with every other tag on the same manifest) from the registry
+    Rm(RmArgs),
+    /// Copy upstream packages into the registry and rewrite Crossplane pulls to it
+    Mirror(super::registry_mirror::MirrorArgs),
+    /// Delete manifests no Crossplane package references, then garbage-collect blobs
+    Gc(GcArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct LsArgs {
+    /// Only list this repository (e.g. hops-ops/network)
+    pub repository: Option<String>,
+
+    /// Output format
+    #[arg(long, short = 'o', value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+/// A tag in the registry, as listed by `local registry ls`.
+#[derive(Debug, Serialize)]
+struct TagEntry {
+    repository: String,
+    tag: String,
+    digest: Option<String>,
+    /// Config plus layer bytes; `None` for an index or unreadable manifest.
+    size_bytes: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct RmArgs {
+    /// Tags to delete, as <repository>:<tag> (e.g. hops-ops/network:dev-1a2b3c4d5e6f)
+    #[arg(required = true)]
+    pub references: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct GcArgs {
+    /// Only list the tags that would be deleted
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Blobs are uploaded in chunks of this size, so a failed request resends
+/// one chunk rather than the whole layer.
+const BLOB_CHUNK_SIZE: u64 = 8 * 1024 * 1024;
+const BLOB_CHUNK_TIMEOUT: Duration = Duration::from_secs(120);
+/// Consecutive failed chunk uploads before giving up on a blob.
+const BLOB_CHUNK_RETRIES: u32 = 5;
+
+#[derive(Debug, Deserialize)]
+struct Catalog {
+    repositories: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TagList {
+    tags: Option<Vec<String>>,
+}
+
+fn registry_url(path: &str) -> String {
+    format!("http://{}/v2/{}", push_host(), path.trim_start_matches('/'))
+}
+
+/// A registry API request, authenticated when the registry requires it.
+fn request(method: &str, url: &str) -> ureq::Request {
+    log::debug!("Requesting {} {}", method, url);
+    let request = ureq::request(method, url).timeout(REQUEST_TIMEOUT);
+    match credentials() {
+        Some(credentials) => request.set("Authorization", &credentials.basic_auth()),
+        None => request,
+    }
+}
+
+fn base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn get(path: &str) -> Result<ureq::Response, Box<dyn Error>> {
+    let url = registry_url(path);
+    request("GET", &url)
+        .call()
+        .map_err(|err| format!("registry request {} failed: {}", url, err).into())
+}
+
+pub fn run(args: &RegistryArgs) -> Result<(), Box<dyn Error>> {
+    super::registry_forward::ensure_reachable()?;
+    match &args.command {
+        RegistryCommands::Ls(ls_args) => ls(ls_args),
+        RegistryCommands::Rm(rm_args) => rm(rm_args),
+        RegistryCommands::Mirror(mirror_args) => super::registry_mirror::run(mirror_args),
+        RegistryCommands::Gc(gc_args) => gc(gc_args),
+    }
+}
+
+fn ls(args: &LsArgs) -> Result<(), Box<dyn Error>> {
+    let repositories = match &args.repository {
+        Some(repository) => vec![repository.trim_matches('/').to_string()],
+        None => catalog()?,
+    };
+    let mut entries = Vec::new();
+    for repository in repositories {
+        for tag in tags(&repository)? {
+            let manifest = get_manifest(&repository, &tag).ok().flatten();
+            entries.push(TagEntry {
+                repository: repository.clone(),
+                tag,
+                digest: manifest.as_ref().and_then(|m| m.digest.clone()),
+                size_bytes: manifest.as_ref().and_then(|m| image_size(&m.body)),
+            });
+        }
+    }
+
+    match args.output {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+        OutputFormat::Text if entries.is_empty() => println!("The registry is empty"),
+        OutputFormat::Text => {
+            let name_width = entries
+                .iter()
+                .map(|entry| entry.repository.len() + entry.tag.len() + 1)
+                .max()
+                .unwrap_or(0)
+                .max(10);
+            println!("{:<name_width$} {:<71} SIZE", "REPOSITORY", "DIGEST");
+            for entry in &entries {
+                println!(
+                    "{:<name_width$} {:<71} {}",
+                    format!("{}:{}", entry.repository, entry.tag),
+                    entry.digest.as_deref().unwrap_or("-"),
+                    entry
+                        .size_bytes
+                        .map(super::du::format_bytes)
+                        .unwrap_or_else(|| "-".to_string())
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Total config and layer bytes of an image manifest (compressed, as stored).
+fn image_size(manifest: &JsonValue) -> Option<u64> {
+    let config = manifest.pointer("/config/size")?.as_u64()?;
+    let layers = manifest.get("layers")?.as_array()?;
+    Some(
+        config
+            + layers
+                .iter()
+                .filter_map(|l| l.get("size")?.as_u64())
+                .sum::<u64>(),
+    )
+}
+
+fn rm(args: &RmArgs) -> Result<(), Box<dyn Error>> {
+    for reference in &args.references {
+        let (repository, tag) = parse_tag_reference(reference)?;
+        let digest = manifest_digest(repository, tag)?
+            .ok_or_else(|| format!("{} is not in the registry", reference))?;
+        // The API deletes manifests, not tags: every tag on this digest goes.
+        let shared: Vec<String> = tags(repository)?
+            .into_iter()
+            .filter(|other| other != tag)
+            .filter(|other| {
+                manifest_digest(repository, other).ok().flatten().as_ref() == Some(&digest)
+            })
+            .collect();
+        delete_manifest(repository, &digest)?;
+        if shared.is_empty() {
+            println!("Deleted {} ({})", reference, digest);
+        } else {
+            println!(
+                "Deleted {} ({}), along with tag(s) {} on the same manifest",
+                reference,
+                digest,
+                shared.join(", ")
+            );
+        }
+    }
+    crate::logging::success(&format!(
+        "Deleted {} tag(s); run `hops local registry gc` to reclaim their blobs",
+        args.references.len()
+    ));
+    Ok(())
+}
+
+/// Split `<repository>:<tag>`, accepting a leading registry host.
+fn parse_tag_reference(reference: &str) -> Result<(&str, &str), Box<dyn Error>> {
+    let reference = reference
+        .strip_prefix(&format!("{}/", push_host()))
+        .or_else(|| reference.strip_prefix(&format!("{}/", REGISTRY_PULL)))
+        .unwrap_or(reference);
+    match reference.rsplit_once(':') {
+        Some((repository, tag))
+            if !repository.is_empty() && !tag.is_empty() && !tag.contains('/') =>
+        {
+            Ok((repository, tag))
+        }
+        _ => Err(format!("{}: expected <repository>:<tag>", reference).into()),
+    }
+}
+
+fn gc(args: &GcArgs) -> Result<(), Box<dyn Error>> {
+    // Another cluster's packages would leave the local ones unreferenced.
+    super::context::ensure_local("local registry gc")?;
+    // Fail before deleting anything if the cluster can't be read, rather
+    // than treating every manifest as unreferenced.
+    let raw = run_cmd_output("kubectl", &["get", PACKAGE_RESOURCES, "-o", "json"])?;
+    let referenced = referenced_digests(&serde_json::from_str(&raw)?)?;
+    let before_kib = storage_kib();
+
+    let mut deleted = 0usize;
+    for repository in catalog()? {
+        let tagged = tags(&repository)?
+            .into_iter()
+            .filter_map(|tag| {
+                let digest = manifest_digest(&repository, &tag).ok().flatten()?;
+                Some((tag, digest))
+            })
+            .collect::<Vec<_>>();
+        let keep = referenced.get(&repository).cloned().unwrap_or_default();
+        for (digest, tags) in unreferenced(&tagged, &keep) {
+            println!("  {}:{} ({})", repository, tags.join(","), digest);
+            if !args.dry_run {
+                delete_manifest(&repository, &digest)?;
+            }
+            deleted += 1;
+        }
+    }
+
+    if args.dry_run {
+        crate::logging::success(&format!(
+            "Would delete {} unreferenced manifest(s)",
+            deleted
+        ));
+        return Ok(());
+    }
+    log::info!("Running registry garbage-collect...");
+    let (program, args) = exec_command(&[
+        "registry",
+        "garbage-collect",
+        "--delete-untagged",
+        REGISTRY_CONFIG,
+    ]);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_cmd(program, &args)?;
+    let freed = match (before_kib, storage_kib()) {
+        (Some(before), Some(after)) => format!(
+            ", freed {}",
+            super::du::format_bytes(before.saturating_sub(after) * 1024)
+        ),
+        _ => String::new(),
+    };
+    crate::logging::success(&format!(
+        "Deleted {} unreferenced manifest(s){}",
+        deleted, freed
+    ));
+    Ok(())
+}
+
+/// Manifest digests each local-registry repository must keep, resolved from
+/// the package references of `list`.
+fn referenced_digests(
+    list: &JsonValue,
+) -> Result<BTreeMap<String, HashSet<String>>, Box<dyn Error>> {
+    let mut referenced: BTreeMap<String, HashSet<String>> = BTreeMap::new();
+    let items = list.get("items").and_then(JsonValue::as_array);
+    for item in items.into_iter().flatten() {
+        for pointer in ["/spec/package", "/spec/image", "/status/resolvedPackage"] {
+            let Some(package) = item.pointer(pointer).and_then(JsonValue::as_str) else {
+                continue;
+            };
+            let Some((repository, reference)) = local_reference(package) else {
+                continue;
+            };
+            let digest = if reference.starts_with("sha256:") {
+                Some(reference.to_string())
+            } else {
+                manifest_digest(repository, reference)?
+            };
+            if let Some(digest) = digest {
+                referenced
+                    .entry(repository.to_string())
+                    .or_default()
+                    .insert(digest);
+            }
+        }
+    }
+    Ok(referenced)
+}
+
+/// `(repository, tag or digest)` of a package pulled from the local registry.
+fn local_reference(package: &str) -> Option<(&str, &str)> {
+    let path = package.strip_prefix(REGISTRY_PULL)?.strip_prefix('/')?;
+    path.split_once('@').or_else(|| path.rsplit_once(':'))
+}
+
+/// Digests in `tagged` (tag, digest) that are not in `keep`, with their tags.
+fn unreferenced(
+    tagged: &[(String, String)],
+    keep: &HashSet<String>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut unreferenced: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (tag, digest) in tagged {
+        if !keep.contains(digest) {
+            unreferenced
+                .entry(digest.clone())
+                .or_default()
+                .push(tag.clone());
+        }
+    }
+    unreferenced
+}
+
+/// Registry storage usage in KiB, read inside the registry pod or container.
+pub fn storage_kib() -> Option<u64> {
+    let (program, args) = exec_command(&["du", "-sk", REGISTRY_STORAGE]);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_cmd_output(program, &args)
+        .ok()
+        .and_then(|output| output.split_whitespace().next()?.parse::<u64>().ok())
+}
+
+/// List repositories known to the registry, sorted by name.
+pub fn catalog() -> Result<Vec<String>, Box<dyn Error>> {
+    let catalog: Catalog = get("_catalog?n=1000")?.into_json()?;
+    let mut repositories = catalog.repositories.unwrap_or_default();
+    repositories.sort();
+    Ok(repositories)
+}
+
+/// List tags for a repository, sorted by name.
+pub fn tags(repository: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let list: TagList = get(&format!("{}/tags/list", repository))?.into_json()?;
+    let mut tags = list.tags.unwrap_or_default();
+    tags.sort();
+    Ok(tags)
+}
+
+/// Manifest types accepted when resolving a tag, so the registry reports the
+/// same digest `docker push` printed.
+const MANIFEST_ACCEPT: &str = "application/vnd.docker.distribution.manifest.v2+json, \
+application/vnd.docker.distribution.manifest.list.v2+json, \
+application/vnd.oci.image.manifest.v1+json, \
+application/vnd.oci.image.index.v1+json";
+
+/// Digest of `repository:reference`, or `None` when the registry doesn't
+/// have it.
+pub fn manifest_digest(
+    repository: &str,
+    reference: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let url = registry_url(&format!("{}/manifests/{}", repository, reference));
+    match request("HEAD", &url).set("Accept", MANIFEST_ACCEPT).call() {
+        Ok(response) => Ok(response.header("Docker-Content-Digest").map(str::to_string)),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(err) => Err(format!("registry request {} failed: {}", url, err).into()),
+    }
+}
+
+/// Whether the registry already has blob `digest` in `repository`.
+pub fn blob_exists(repository: &str, digest: &str) -> Result<bool, Box<dyn Error>> {
+    let url = registry_url(&format!("{}/blobs/{}", repository, digest));
+    match request("HEAD", &url).call() {
+        Ok(_) => Ok(true),
+        Err(ureq::Error::Status(404, _)) => Ok(false),
+        Err(err) => Err(format!("registry request {} failed: {}", url, err).into()),
+    }
+}
+
+/// Upload the `size` bytes at `data`'s current position as blob `digest`,
+/// in chunks. A failed chunk is retried from the offset the registry reports
+/// it has, so an interrupted upload resumes instead of starting over.
+pub fn upload_blob<R: Read + Seek>(
+    repository: &str,
+    digest: &str,
+    size: u64,
+    mut data: R,
+) -> Result<(), Box<dyn Error>> {
+    let start = data.stream_position()?;
+    let url = registry_url(&format!("{}/blobs/uploads/", repository));
+    let response = request("POST", &url)
+        .call()
+        .map_err(|err| format!("registry request {} failed: {}", url, err))?;
+    let mut location = upload_location(&response, &url)?;
+
+    let mut offset = 0;
+    let mut failures = 0;
+    let mut backoff = None;
+    while offset < size {
+        let len = BLOB_CHUNK_SIZE.min(size - offset);
+        let mut chunk = vec![0; len as usize];
+        data.seek(SeekFrom::Start(start + offset))?;
+        data.read_exact(&mut chunk)?;
+        let result = request("PATCH", &location)
+            .timeout(BLOB_CHUNK_TIMEOUT)
+            .set("Content-Type", "application/octet-stream")
+            .set("Content-Range", &format!("{}-{}", offset, offset + len - 1))
+            .send_bytes(&chunk);
+        match result {
+            Ok(response) => {
+                location = upload_location(&response, &location)?;
+                offset += len;
+                failures = 0;
+                backoff = None;
+            }
+            Err(err) => {
+                failures += 1;
+                let backoff = backoff.get_or_insert_with(|| Backoff::new(BLOB_CHUNK_TIMEOUT));
+                if failures > BLOB_CHUNK_RETRIES || !backoff.wait() {
+                    return Err(format!(
+                        "uploading {} to {} failed at byte {}: {}",
+                        digest, repository, offset, err
+                    )
+                    .into());
+                }
+                log::debug!(
+                    "Upload of {} failed at byte {} ({}); resuming",
+                    digest,
+                    offset,
+                    err
+                );
+                (location, offset) = upload_status(&location)?;
+            }
+        }
+    }
+
+    let separator = if location.contains('?') { '&' } else { '?' };
+    let url = format!("{}{}digest={}", location, separator, digest);
+    request("PUT", &url)
+        .timeout(BLOB_CHUNK_TIMEOUT)
+        .call()
+        .map_err(|err| format!("completing upload of {} failed: {}", digest, err))?;
+    Ok(())
+}
+
+/// Where an upload continues, and how many bytes the registry has, per
+/// `GET` on the upload URL.
+fn upload_status(location: &str) -> Result<(String, u64), Box<dyn Error>> {
+    let response = request("GET", location)
+        .call()
+        .map_err(|err| format!("registry request {} failed: {}", location, err))?;
+    let received = response
+        .header("Range")
+        .and_then(|range| range.split_once('-'))
+        .and_then(|(_, last)| last.parse::<u64>().ok())
+        .map_or(0, |last| last + 1);
+    Ok((upload_location(&response, location)?, received))
+}
+
+/// The absolute upload URL from a response's `Location` header.
+fn upload_location(response: &ureq::Response, url: &str) -> Result<String, Box<dyn Error>> {
+    let location = response
+        .header("Location")
+        .ok_or_else(|| format!("registry response to {} has no upload location", url))?;
+    Ok(
+        if location.starts_with("http://") || location.starts_with("https://") {
+            location.to_string()
+        } else {
+            format!("http://{}{}", push_host(), location)
+        },
+    )
+}
+
+/// Store `body` as the manifest of `repository:reference`.
+pub fn put_manifest(
+    repository: &str,
+    reference: &str,
+    media_type: &str,
+    body: &[u8],
+) -> Result<(), Box<dyn Error>> {
+    let url = registry_url(&format!("{}/manifests/{}", repository, reference));
+    request("PUT", &url)
+        .set("Content-Type", media_type)
+        .send_bytes(body)
+        .map_err(|err| format!("registry request {} failed: {}", url, err))?;
+    Ok(())
+}
+
+/// Largest manifest `verify_manifest` reads; package manifests are a few KiB.
+const MAX_MANIFEST_BYTES: u64 = 4 << 20;
+
+/// Check that the registry serves `repository:reference` as `expected`, the
+/// digest computed locally when it was pushed: both the digest the registry
+/// reports and the digest of the bytes it returns must match.
+pub fn verify_manifest(
+    repository: &str,
+    reference: &str,
+    expected: &str,
+) -> Result<(), Box<dyn Error>> {
+    let url = registry_url(&format!("{}/manifests/{}", repository, reference));
+    let response = match request("GET", &url).set("Accept", MANIFEST_ACCEPT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(404, _)) => {
+            return Err(format!(
+                "{}:{} was pushed as {} but the registry doesn't have it",
+                repository, reference, expected
+            )
+            .into())
+        }
+        Err(err) => return Err(format!("registry request {} failed: {}", url, err).into()),
+    };
+    let reported = response.header("Docker-Content-Digest").map(str::to_string);
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_MANIFEST_BYTES)
+        .read_to_end(&mut body)?;
+    let served = format!("sha256:{:x}", Sha256::digest(&body));
+    match digest_mismatch(expected, reported.as_deref(), &served) {
+        Some(mismatch) => Err(format!(
+            "digest mismatch for {}:{}: {}; the push was corrupted or another push overwrote the tag; rerun the install to push it again",
+            repository, reference, mismatch
+        )
+        .into()),
+        None => Ok(()),
+    }
+}
+
+/// How the digest the registry reports (if any) or the digest of the
+/// manifest it served differs from the `expected` one.
+fn digest_mismatch(expected: &str, reported: Option<&str>, served: &str) -> Option<String> {
+    match reported {
+        Some(reported) if reported != expected => Some(format!(
+            "pushed {} but the registry reports {}",
+            expected, reported
+        )),
+        _ if served != expected => Some(format!(
+            "pushed {} but the registry serves a manifest with digest {}",
+            expected, served
+        )),
+        _ => None,
+    }
+}
+
+/// A manifest as served by the registry.
+struct Manifest {
+    digest: Option<String>,
+    body: JsonValue,
+}
+
+/// `repository:reference`'s manifest, or `None` when the registry doesn't
+/// have it.
+fn get_manifest(repository: &str, reference: &str) -> Result<Option<Manifest>, Box<dyn Error>> {
+    let url = registry_url(&format!("{}/manifests/{}", repository, reference));
+    match request("GET", &url).set("Accept", MANIFEST_ACCEPT).call() {
+        Ok(response) => {
+            let digest = response.header("Docker-Content-Digest").map(str::to_string);
+            Ok(Some(Manifest {
+                digest,
+                body: response.into_json()?,
+            }))
+        }
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(err) => Err(format!("registry request {} failed: {}", url, err).into()),
+    }
+}
+
+/// Delete a manifest (and every tag pointing at it) by digest.
+fn delete_manifest(repository: &str, digest: &str) -> Result<(), Box<dyn Error>> {
+    let url = registry_url(&format!("{}/manifests/{}", repository, digest));
+    match request("DELETE", &url).call() {
+        Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+        Err(ureq::Error::Status(405, _)) => Err(
+            "the registry does not allow deletes; it predates REGISTRY_STORAGE_DELETE_ENABLED (re-run `hops local start` to redeploy it; registries deployed before persistent storage are emptied once)".into(),
+        ),
+        Err(err) => Err(format!("registry request {} failed: {}", url, err).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_url_joins_api_paths() {
+        assert_eq!(
+            registry_url("_catalog"),
+            "http://localhost:30500/v2/_catalog"
+        );
+        assert_eq!(
+            registry_url("/hops-ops/test/tags/list"),
+            "http://localhost:30500/v2/hops-ops/test/tags/list"
+        );
+    }
+
+    #[test]
+    fn pushed_digest_must_match_reported_and_served() {
+        assert_eq!(
+            digest_mismatch("sha256:a", Some("sha256:a"), "sha256:a"),
+            None
+        );
+        assert_eq!(digest_mismatch("sha256:a", None, "sha256:a"), None);
+        assert_eq!(
+            digest_mismatch("sha256:a", Some("sha256:b"), "sha256:a").unwrap(),
+            "pushed sha256:a but the registry reports sha256:b"
+        );
+        assert!(digest_mismatch("sha256:a", Some("sha256:a"), "sha256:c")
+            .unwrap()
+            .ends_with("serves a manifest with digest sha256:c"));
+    }
+
+    #[test]
+    fn htpasswd_auth_mounts_secret_and_sets_env() {
+        let manifest = customize(REGISTRY_MANIFEST, true, false, DEFAULT_NODE_PORT).unwrap();
+        let deployment: JsonValue =
+            serde_yaml::from_str(manifest.split("---\n").next().unwrap()).unwrap();
+        let container = &deployment["spec"]["template"]["spec"]["containers"][0];
+        assert!(container["env"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|env| env["name"] == "REGISTRY_AUTH" && env["value"] == "htpasswd"));
+        assert_eq!(container["volumeMounts"][1]["mountPath"], "/auth");
+        assert_eq!(
+            deployment["spec"]["template"]["spec"]["volumes"][1]["secret"]["secretName"],
+            HTPASSWD_SECRET
+        );
+        assert_eq!(manifest.matches("kind:").count(), 2);
+
+        assert_eq!(base64(b"hops:secret"), "aG9wczpzZWNyZXQ=");
+        assert_eq!(base64(b"ab"), "YWI=");
+        assert_eq!(base64(b"abc"), "YWJj");
+    }
+
+    #[test]
+    fn custom_node_port_is_set_on_service() {
+        let manifest = customize(REGISTRY_MANIFEST, false, false, 30600).unwrap();
+        let service: JsonValue =
+            serde_yaml::from_str(manifest.split("---\n").nth(1).unwrap()).unwrap();
+        assert_eq!(service["kind"], "Service");
+        assert_eq!(service["spec"]["ports"][0]["nodePort"], 30600);
+    }
+
+    #[test]
+    fn parse_tag_reference_strips_registry_hosts() {
+        assert_eq!(
+            parse_tag_reference("hops-ops/network:dev-1").unwrap(),
+            ("hops-ops/network", "dev-1")
+        );
+        assert_eq!(
+            parse_tag_reference(&format!("{}/hops-ops/network:dev-1", REGISTRY_PULL)).unwrap(),
+            ("hops-ops/network", "dev-1")
+        );
+        assert!(parse_tag_reference("hops-ops/network").is_err());
+    }
+
+    #[test]
+    fn image_size_sums_config_and_layers() {
+        let manifest = json!({
+            "config": {"size": 100},
+            "layers": [{"size": 1000}, {"size": 24}]
+        });
+        assert_eq!(image_size(&manifest), Some(1124));
+        assert_eq!(image_size(&json!({"manifests": [{"size": 500}]})), None);
+    }
+
+    #[test]
+    fn unreferenced_groups_tags_by_digest() {
+        assert_eq!(
+            local_reference(
+                "registry.crossplane-system.svc.cluster.local:5000/hops-ops/network:dev-abc"
+            ),
+            Some(("hops-ops/network", "dev-abc"))
+        );
+        assert_eq!(
+            local_reference(
+                "registry.crossplane-system.svc.cluster.local:5000/hops-ops/network_render@sha256:1"
+            ),
+            Some(("hops-ops/network_render", "sha256:1"))
+        );
+        assert_eq!(local_reference("ghcr.io/hops-ops/network:v1"), None);
+
+        let tagged = [
+            ("dev-a".to_string(), "sha256:a".to_string()),
+            ("dev-b".to_string(), "sha256:b".to_string()),
+            ("arm64".to_string(), "sha256:b".to_string()),
+            ("dev-c".to_string(), "sha256:c".to_string()),
+        ];
+        let keep = HashSet::from(["sha256:c".to_string()]);
+        let unreferenced = unreferenced(&tagged, &keep);
+        assert_eq!(unreferenced.len(), 2);
+        assert_eq!(unreferenced["sha256:b"], ["dev-b", "arm64"]);
+    }
+}
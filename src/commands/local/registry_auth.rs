@@ -0,0 +1,499 @@
+use super::process::{remove_state, write_state};
+use super::registry::{self, Credentials, AUTH_STATE_FILE, HTPASSWD_SECRET};
+use super::registry_host;
+use super::{command_exists, kubectl_apply_stdin, run_cmd, run_cmd_output};
+use crate::settings::RegistryMode;
+use clap::{Args, Subcommand};
+use serde_json::{json, Value as JsonValue};
+use std::error::Error;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+const DEFAULT_NAMESPACE: &str = "crossplane-system";
+const DEFAULT_SECRET_NAME: &str = "ecr-pull";
+/// ECR registry tokens are always issued for this user.
+const ECR_USERNAME: &str = "AWS";
+/// Cluster-internal address of the local registry, as packages reference it.
+const LOCAL_REGISTRY: &str = "registry.crossplane-system.svc.cluster.local:5000";
+const LOCAL_SECRET_NAME: &str = "local-registry-pull";
+/// Image providing the `htpasswd` tool used to hash the password.
+const HTPASSWD_IMAGE: &str = "httpd:2-alpine";
+/// Package kinds whose `spec.packagePullSecrets` get the pull Secret.
+const PACKAGE_KINDS: &[&str] = &[
+    "configurations.pkg.crossplane.io",
+    "providers.pkg.crossplane.io",
+    "functions.pkg.crossplane.io",
+];
+
+#[derive(Args, Debug)]
+pub struct RegistryAuthArgs {
+    #[command(subcommand)]
+    pub command: RegistryAuthCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RegistryAuthCommands {
+    /// Create or refresh a pull Secret for a private ECR registry
+    Ecr(EcrArgs),
+    /// Require a password for the in-cluster registry and log Docker and Crossplane in to it
+    Local(LocalRegistryArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct LocalRegistryArgs {
+    /// Registry user name
+    #[arg(long, default_value = "hops")]
+    pub username: String,
+
+    /// Turn authentication off again (the registry becomes anonymous-writable)
+    #[arg(long, conflicts_with = "username")]
+    pub disable: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct EcrArgs {
+    /// AWS CLI profile used to request the ECR token (falls back to
+    /// AWS_PROFILE/AWS_DEFAULT_PROFILE, then prompts)
+    #[arg(long, short = 'p')]
+    pub profile: Option<String>,
+
+    /// Registry region (default: the profile's configured region)
+    #[arg(long)]
+    pub region: Option<String>,
+
+    /// Registry host (default: <account>.dkr.ecr.<region>.amazonaws.com for
+    /// the profile's account)
+    #[arg(long)]
+    pub registry: Option<String>,
+
+    /// Namespace for the pull Secret (Crossplane's namespace)
+    #[arg(long, short = 'n', default_value = DEFAULT_NAMESPACE)]
+    pub namespace: String,
+
+    /// Pull Secret name
+    #[arg(long, default_value = DEFAULT_SECRET_NAME)]
+    pub secret_name: String,
+}
+
+pub fn run(args: &RegistryAuthArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local registry-auth")?;
+    match &args.command {
+        RegistryAuthCommands::Ecr(ecr_args) => run_ecr(ecr_args),
+        RegistryAuthCommands::Local(local_args) => run_local(local_args),
+    }
+}
+
+fn run_local(args: &LocalRegistryArgs) -> Result<(), Box<dyn Error>> {
+    if args.disable {
+        remove_state(AUTH_STATE_FILE)?;
+        log::info!("Redeploying the local registry without authentication...");
+        redeploy_registry()?;
+        run_cmd(
+            "kubectl",
+            &[
+                "delete",
+                "secret",
+                HTPASSWD_SECRET,
+                "-n",
+                DEFAULT_NAMESPACE,
+                "--ignore-not-found",
+            ],
+        )?;
+        run_cmd("docker", &["logout", &registry::push_host()])?;
+        crate::logging::success("Local registry authentication disabled");
+        return Ok(());
+    }
+
+    let credentials = match registry::credentials() {
+        Some(credentials) if credentials.username == args.username => credentials,
+        _ => {
+            let password = uuid::Uuid::new_v4().simple().to_string();
+            log::info!(
+                "Hashing the registry password with htpasswd ({})...",
+                HTPASSWD_IMAGE
+            );
+            let htpasswd = htpasswd(&args.username, &password)?;
+            let credentials = Credentials {
+                username: args.username.clone(),
+                password,
+                htpasswd,
+            };
+            write_state(AUTH_STATE_FILE, &credentials)?;
+            credentials
+        }
+    };
+
+    apply_local_auth(&credentials)?;
+    log::info!("Redeploying the local registry with htpasswd authentication...");
+    redeploy_registry()?;
+
+    super::registry_forward::ensure_reachable()?;
+    let push_host = registry::push_host();
+    log::info!("Logging Docker in to {}...", push_host);
+    run_with_stdin(
+        "docker",
+        &[
+            "login",
+            &push_host,
+            "--username",
+            &credentials.username,
+            "--password-stdin",
+        ],
+        &credentials.password,
+    )?;
+    let wired = wire_packages(LOCAL_REGISTRY, LOCAL_SECRET_NAME)?;
+
+    crate::logging::success(&format!(
+        "Local registry now requires user '{}' ({} existing package(s) updated); credentials are in {}",
+        credentials.username,
+        wired,
+        super::process::state_path(AUTH_STATE_FILE)?.display()
+    ));
+    Ok(())
+}
+
+/// Apply the htpasswd Secret the registry reads and the pull Secret and
+/// ImageConfig Crossplane uses for it. `hops local start` calls this again
+/// after a reset.
+pub fn apply_local_auth(credentials: &Credentials) -> Result<(), Box<dyn Error>> {
+    let htpasswd_secret = json!({
+        "apiVersion": "v1",
+        "kind": "Secret",
+        "metadata": {"name": HTPASSWD_SECRET, "namespace": DEFAULT_NAMESPACE},
+        "type": "Opaque",
+        "stringData": {"htpasswd": credentials.htpasswd}
+    });
+    kubectl_apply_stdin(&serde_yaml::to_string(&htpasswd_secret)?)?;
+    kubectl_apply_stdin(&build_pull_secret_yaml(
+        DEFAULT_NAMESPACE,
+        LOCAL_SECRET_NAME,
+        LOCAL_REGISTRY,
+        &credentials.username,
+        &credentials.password,
+        "local",
+    )?)?;
+    kubectl_apply_stdin(&build_image_config_yaml(LOCAL_REGISTRY, LOCAL_SECRET_NAME))
+}
+
+fn redeploy_registry() -> Result<(), Box<dyn Error>> {
+    if registry::mode() == RegistryMode::Host {
+        return registry_host::recreate();
+    }
+    kubectl_apply_stdin(&registry::manifest()?)?;
+    run_cmd(
+        "kubectl",
+        &[
+            "rollout",
+            "status",
+            "deployment/registry",
+            "-n",
+            DEFAULT_NAMESPACE,
+            "--timeout=120s",
+        ],
+    )
+}
+
+/// A bcrypt htpasswd line (the only hash the registry accepts).
+fn htpasswd(username: &str, password: &str) -> Result<String, Box<dyn Error>> {
+    let line = run_with_stdin(
+        "docker",
+        &[
+            "run",
+            "--rm",
+            "-i",
+            "--entrypoint",
+            "htpasswd",
+            HTPASSWD_IMAGE,
+            "-niB",
+            username,
+        ],
+        password,
+    )?;
+    let line = line.trim();
+    if !line.starts_with(&format!("{}:$2", username)) {
+        return Err(format!("unexpected htpasswd output: {}", line).into());
+    }
+    Ok(line.to_string())
+}
+
+/// Run a command with a secret on stdin (kept out of the logged arguments).
+fn run_with_stdin(program: &str, args: &[&str], input: &str) -> Result<String, Box<dyn Error>> {
+    crate::logging::log_command(program, args);
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}: {}",
+            program,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn run_ecr(args: &EcrArgs) -> Result<(), Box<dyn Error>> {
+    if !command_exists("aws") {
+        return Err("AWS CLI (`aws`) is not installed or not in PATH".into());
+    }
+    let profile = super::aws::resolve_profile(args.profile.as_deref())?;
+    let region = match &args.region {
+        Some(region) => region.clone(),
+        None => profile_region(&profile)?,
+    };
+    let registry = match &args.registry {
+        Some(registry) => registry.trim_end_matches('/').to_string(),
+        None => ecr_registry(&account_id(&profile)?, &region),
+    };
+
+    log::info!("Requesting ECR token for {}...", registry);
+    let token = run_cmd_output(
+        "aws",
+        &[
+            "ecr",
+            "get-login-password",
+            "--profile",
+            &profile,
+            "--region",
+            &region,
+        ],
+    )
+    .map_err(|err| {
+        format!(
+            "failed to get an ECR token with profile '{}': {}\nIf this is an SSO profile, run `aws sso login --profile {}` first.",
+            profile, err, profile
+        )
+    })?;
+    let token = token.trim();
+    if token.is_empty() {
+        return Err("aws ecr get-login-password returned an empty token".into());
+    }
+
+    log::info!(
+        "Applying pull secret '{}/{}'...",
+        args.namespace,
+        args.secret_name
+    );
+    kubectl_apply_stdin(&build_pull_secret_yaml(
+        &args.namespace,
+        &args.secret_name,
+        &registry,
+        ECR_USERNAME,
+        token,
+        "ecr",
+    )?)?;
+
+    // The ImageConfig covers package dependencies, which Crossplane resolves
+    // without the parent package's packagePullSecrets.
+    log::info!("Applying ImageConfig for {}...", registry);
+    kubectl_apply_stdin(&build_image_config_yaml(&registry, &args.secret_name))?;
+
+    let wired = wire_packages(&registry, &args.secret_name)?;
+
+    crate::logging::success(&format!(
+        "ECR pull secret {}/{} refreshed for {} ({} existing package(s) updated); tokens expire after 12 hours, rerun to refresh",
+        args.namespace, args.secret_name, registry, wired
+    ));
+    Ok(())
+}
+
+/// Add the pull Secret to installed packages pulled from `registry`.
+fn wire_packages(registry: &str, secret_name: &str) -> Result<usize, Box<dyn Error>> {
+    let mut wired = 0;
+    for kind in PACKAGE_KINDS {
+        let raw = run_cmd_output("kubectl", &["get", kind, "-o", "json"])?;
+        let list: JsonValue = serde_json::from_str(&raw)?;
+        for name in packages_from_registry(&list, registry, secret_name) {
+            log::info!("Adding packagePullSecrets to {} '{}'...", kind, name);
+            let patch = json!({"spec": {"packagePullSecrets": [{"name": secret_name}]}});
+            run_cmd(
+                "kubectl",
+                &[
+                    "patch",
+                    kind,
+                    &name,
+                    "--type",
+                    "merge",
+                    "-p",
+                    &patch.to_string(),
+                ],
+            )?;
+            wired += 1;
+        }
+    }
+    Ok(wired)
+}
+
+fn profile_region(profile: &str) -> Result<String, Box<dyn Error>> {
+    let region = run_cmd_output("aws", &["configure", "get", "region", "--profile", profile])
+        .unwrap_or_default();
+    let region = region.trim();
+    if region.is_empty() {
+        return Err(format!(
+            "profile '{}' has no region configured; pass `--region`",
+            profile
+        )
+        .into());
+    }
+    Ok(region.to_string())
+}
+
+fn account_id(profile: &str) -> Result<String, Box<dyn Error>> {
+    let account = run_cmd_output(
+        "aws",
+        &[
+            "sts",
+            "get-caller-identity",
+            "--profile",
+            profile,
+            "--query",
+            "Account",
+            "--output",
+            "text",
+        ],
+    )
+    .map_err(|err| {
+        format!(
+            "failed to look up the account for profile '{}': {}",
+            profile, err
+        )
+    })?;
+    Ok(account.trim().to_string())
+}
+
+fn ecr_registry(account: &str, region: &str) -> String {
+    format!("{}.dkr.ecr.{}.amazonaws.com", account, region)
+}
+
+/// A `kubernetes.io/dockerconfigjson` Secret for `registry`.
+fn build_pull_secret_yaml(
+    namespace: &str,
+    secret_name: &str,
+    registry: &str,
+    username: &str,
+    password: &str,
+    auth_label: &str,
+) -> Result<String, Box<dyn Error>> {
+    let docker_config = json!({
+        "auths": {
+            registry: {"username": username, "password": password}
+        }
+    });
+    let secret = json!({
+        "apiVersion": "v1",
+        "kind": "Secret",
+        "metadata": {
+            "name": secret_name,
+            "namespace": namespace,
+            "labels": {"hops.ops.com.ai/registry-auth": auth_label}
+        },
+        "type": "kubernetes.io/dockerconfigjson",
+        "stringData": {".dockerconfigjson": docker_config.to_string()}
+    });
+    Ok(serde_yaml::to_string(&secret)?)
+}
+
+fn build_image_config_yaml(registry: &str, secret_name: &str) -> String {
+    format!(
+        "apiVersion: pkg.crossplane.io/v1beta1\nkind: ImageConfig\nmetadata:\n  name: {}\nspec:\n  matchImages:\n    - type: Prefix\n      prefix: {}\n  registry:\n    authentication:\n      pullSecretRef:\n        name: {}\n",
+        image_config_name(registry),
+        registry,
+        secret_name
+    )
+}
+
+fn image_config_name(registry: &str) -> String {
+    let host: String = registry
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("hops-auth-{}", host.trim_matches('-'))
+}
+
+/// Names of packages pulled from `registry` that don't list the Secret yet.
+fn packages_from_registry(list: &JsonValue, registry: &str, secret_name: &str) -> Vec<String> {
+    let prefix = format!("{}/", registry);
+    list.get("items")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|item| {
+            item.pointer("/spec/package")
+                .and_then(JsonValue::as_str)
+                .is_some_and(|package| package.starts_with(&prefix))
+        })
+        .filter(|item| {
+            !item
+                .pointer("/spec/packagePullSecrets")
+                .and_then(JsonValue::as_array)
+                .into_iter()
+                .flatten()
+                .any(|secret| secret.get("name").and_then(JsonValue::as_str) == Some(secret_name))
+        })
+        .filter_map(|item| item.pointer("/metadata/name").and_then(JsonValue::as_str))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pull_secret_holds_docker_config_for_registry() {
+        let registry = ecr_registry("123456789012", "us-east-1");
+        assert_eq!(registry, "123456789012.dkr.ecr.us-east-1.amazonaws.com");
+        let yaml = build_pull_secret_yaml(
+            "crossplane-system",
+            "ecr-pull",
+            &registry,
+            "AWS",
+            "tok",
+            "ecr",
+        )
+        .unwrap();
+        let secret: JsonValue = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(secret["type"], "kubernetes.io/dockerconfigjson");
+        let config: JsonValue =
+            serde_json::from_str(secret["stringData"][".dockerconfigjson"].as_str().unwrap())
+                .unwrap();
+        assert_eq!(config["auths"][registry.as_str()]["username"], "AWS");
+        assert_eq!(config["auths"][registry.as_str()]["password"], "tok");
+        assert_eq!(
+            image_config_name(&registry),
+            "hops-auth-123456789012-dkr-ecr-us-east-1-amazonaws-com"
+        );
+    }
+
+    #[test]
+    fn packages_from_registry_skips_other_registries_and_wired_packages() {
+        let list = json!({"items": [
+            {"metadata": {"name": "private"},
+             "spec": {"package": "1.dkr.ecr.eu-west-1.amazonaws.com/stack:v1"}},
+            {"metadata": {"name": "wired"},
+             "spec": {"package": "1.dkr.ecr.eu-west-1.amazonaws.com/other:v1",
+                      "packagePullSecrets": [{"name": "ecr-pull"}]}},
+            {"metadata": {"name": "public"},
+             "spec": {"package": "xpkg.crossplane.io/crossplane-contrib/provider-aws:v1"}}
+        ]});
+        assert_eq!(
+            packages_from_registry(&list, "1.dkr.ecr.eu-west-1.amazonaws.com", "ecr-pull"),
+            vec!["private"]
+        );
+    }
+}
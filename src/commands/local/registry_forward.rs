@@ -0,0 +1,113 @@
+use super::process::{
+    process_alive, read_state, remove_state, spawn_detached, state_path, terminate_process_group,
+    write_state,
+};
+use super::{registry, registry_host, registry_tls, with_kube_context};
+use crate::settings::RegistryMode;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const STATE_FILE: &str = "registry-forward.json";
+const LOG_FILE: &str = "registry-forward.log";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+const READY_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// A `kubectl port-forward` standing in for an unreachable registry NodePort.
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistryForward {
+    pid: u32,
+    port: u16,
+}
+
+/// Make the registry answer on `localhost:<node port>`. Colima forwards
+/// NodePorts to the host; where that doesn't happen (some kind/k3d network
+/// setups), fall back to a background `kubectl port-forward` on the same
+/// port so push references don't change.
+pub fn ensure_reachable() -> Result<(), Box<dyn Error>> {
+    let port = registry::node_port();
+    if port_reachable(port) {
+        return Ok(());
+    }
+    if registry::mode() == RegistryMode::Host {
+        // The container publishes the port itself; it only needs to run.
+        registry_host::ensure_running()?;
+        return if wait_for_port(port, || true) {
+            Ok(())
+        } else {
+            Err(format!(
+                "the {} container is not answering on localhost:{} (see `docker logs {}`)",
+                registry_host::CONTAINER,
+                port,
+                registry_host::CONTAINER
+            )
+            .into())
+        };
+    }
+    // A forward that isn't answering (e.g. its pod was replaced) is stale.
+    stop()?;
+
+    log::info!(
+        "Registry NodePort {} is unreachable; forwarding it with kubectl port-forward...",
+        port
+    );
+    // The port-forward must reach the plain-HTTP registry, like the NodePort.
+    let service_port = if registry_tls::enabled() {
+        registry_tls::HTTP_PORT
+    } else {
+        5000
+    };
+    let args = with_kube_context(&[
+        "port-forward",
+        "-n",
+        "crossplane-system",
+        "svc/registry",
+        &format!("{}:{}", port, service_port),
+    ]);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let log_path = state_path(LOG_FILE)?;
+    let pid = spawn_detached(Path::new("kubectl"), &args, &log_path)?;
+    write_state(STATE_FILE, &RegistryForward { pid, port })?;
+
+    if wait_for_port(port, || process_alive(pid)) {
+        return Ok(());
+    }
+    stop()?;
+    Err(format!(
+        "the registry is unreachable on localhost:{} and `kubectl port-forward` did not come up (see {}); is `hops local start` done and the port free?",
+        port,
+        log_path.display()
+    )
+    .into())
+}
+
+/// Stop the fallback port-forward, if one is running.
+pub fn stop() -> Result<(), Box<dyn Error>> {
+    if let Some(forward) = read_state::<RegistryForward>(STATE_FILE) {
+        terminate_process_group(forward.pid, false)?;
+        remove_state(STATE_FILE)?;
+    }
+    Ok(())
+}
+
+/// Poll until `port` answers, giving up early once `alive` turns false.
+fn wait_for_port(port: u16, alive: impl Fn() -> bool) -> bool {
+    let deadline = Instant::now() + READY_TIMEOUT;
+    while Instant::now() < deadline {
+        if port_reachable(port) {
+            return true;
+        }
+        if !alive() {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+    false
+}
+
+fn port_reachable(port: u16) -> bool {
+    TcpStream::connect_timeout(&SocketAddr::from(([127, 0, 0, 1], port)), CONNECT_TIMEOUT).is_ok()
+}
@@ -0,0 +1,165 @@
+use super::process::state_path;
+use super::registry::{self, REGISTRY_STORAGE};
+use super::{kubectl_apply_stdin, run_cmd, run_cmd_output};
+use serde_json::json;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// Docker container running the registry in `registry.mode: host`.
+pub const CONTAINER: &str = "hops-registry";
+const IMAGE: &str = "registry:2";
+/// `~/.hops/local/registry-data/`: registry storage, bind-mounted into the
+/// container so it survives `reset`, `destroy`, and container recreation.
+const DATA_DIR: &str = "registry-data";
+/// `~/.hops/local/registry-htpasswd/`: htpasswd file when auth is enabled.
+const HTPASSWD_DIR: &str = "registry-htpasswd";
+/// How the VM (and so the cluster) reaches the host, as set up by Lima.
+const GATEWAY_HOSTNAME: &str = "host.lima.internal";
+const NAMESPACE: &str = "crossplane-system";
+
+pub fn data_dir() -> Result<PathBuf, Box<dyn Error>> {
+    state_path(DATA_DIR)
+}
+
+/// Replace the in-cluster registry with the host container: (re)create the
+/// container, remove the registry Deployment, and point the `registry`
+/// Service at the VM gateway so the cluster-internal name keeps working.
+pub fn deploy() -> Result<(), Box<dyn Error>> {
+    recreate()?;
+    let gateway = gateway_ip()?;
+    run_cmd(
+        "kubectl",
+        &[
+            "delete",
+            "deployment/registry",
+            "service/registry",
+            "-n",
+            NAMESPACE,
+            "--ignore-not-found",
+        ],
+    )?;
+    kubectl_apply_stdin(&service_yaml(&gateway, registry::node_port())?)
+}
+
+/// Start the container if it exists but is stopped, or create it.
+pub fn ensure_running() -> Result<(), Box<dyn Error>> {
+    match run_cmd_output(
+        "docker",
+        &["inspect", "--format", "{{.State.Running}}", CONTAINER],
+    ) {
+        Ok(running) if running.trim() == "true" => Ok(()),
+        Ok(_) => run_cmd("docker", &["start", CONTAINER]),
+        Err(_) => recreate(),
+    }
+}
+
+/// Recreate the container so it picks up the current port and credentials.
+pub fn recreate() -> Result<(), Box<dyn Error>> {
+    let data_dir = data_dir()?;
+    fs::create_dir_all(&data_dir)?;
+    let _ = run_cmd_output("docker", &["rm", "-f", CONTAINER]);
+
+    log::info!("Starting registry container {}...", CONTAINER);
+    let mut args = vec![
+        "run".to_string(),
+        "-d".to_string(),
+        "--name".to_string(),
+        CONTAINER.to_string(),
+        "--restart".to_string(),
+        "unless-stopped".to_string(),
+        "-p".to_string(),
+        // Loopback only; the VM reaches it through the gateway.
+        format!("127.0.0.1:{}:5000", registry::node_port()),
+        "-v".to_string(),
+        format!("{}:{}", data_dir.display(), REGISTRY_STORAGE),
+        "-e".to_string(),
+        "REGISTRY_STORAGE_DELETE_ENABLED=true".to_string(),
+    ];
+    if let Some(credentials) = registry::credentials() {
+        let htpasswd_dir = state_path(HTPASSWD_DIR)?;
+        fs::create_dir_all(&htpasswd_dir)?;
+        fs::write(
+            htpasswd_dir.join("htpasswd"),
+            format!("{}\n", credentials.htpasswd),
+        )?;
+        args.extend([
+            "-v".to_string(),
+            format!("{}:/auth:ro", htpasswd_dir.display()),
+            "-e".to_string(),
+            "REGISTRY_AUTH=htpasswd".to_string(),
+            "-e".to_string(),
+            "REGISTRY_AUTH_HTPASSWD_REALM=hops local registry".to_string(),
+            "-e".to_string(),
+            "REGISTRY_AUTH_HTPASSWD_PATH=/auth/htpasswd".to_string(),
+        ]);
+    }
+    args.push(IMAGE.to_string());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    run_cmd("docker", &args)
+}
+
+/// Remove the container and its data.
+pub fn wipe() -> Result<(), Box<dyn Error>> {
+    let _ = run_cmd_output("docker", &["rm", "-f", CONTAINER]);
+    let data_dir = data_dir()?;
+    if data_dir.exists() {
+        fs::remove_dir_all(&data_dir)?;
+    }
+    Ok(())
+}
+
+/// The host's address as seen from inside the Colima VM.
+pub(super) fn gateway_ip() -> Result<String, Box<dyn Error>> {
+    let output = run_cmd_output(
+        "colima",
+        &["ssh", "--", "getent", "hosts", GATEWAY_HOSTNAME],
+    )?;
+    output
+        .split_whitespace()
+        .next()
+        .map(str::to_string)
+        .ok_or_else(|| format!("could not resolve {} inside the VM", GATEWAY_HOSTNAME).into())
+}
+
+/// A selector-less `registry` Service whose Endpoints are the host registry.
+fn service_yaml(gateway: &str, port: u16) -> Result<String, Box<dyn Error>> {
+    let service = json!({
+        "apiVersion": "v1",
+        "kind": "Service",
+        "metadata": {"name": "registry", "namespace": NAMESPACE},
+        "spec": {"ports": [{"port": 5000, "targetPort": port}]}
+    });
+    let endpoints = json!({
+        "apiVersion": "v1",
+        "kind": "Endpoints",
+        "metadata": {"name": "registry", "namespace": NAMESPACE},
+        "subsets": [{"addresses": [{"ip": gateway}], "ports": [{"port": port}]}]
+    });
+    Ok(format!(
+        "{}---\n{}",
+        serde_yaml::to_string(&service)?,
+        serde_yaml::to_string(&endpoints)?
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn service_targets_gateway_on_registry_port() {
+        let yaml = service_yaml("192.168.5.2", 30500).unwrap();
+        let documents: Vec<serde_json::Value> = yaml
+            .split("---\n")
+            .map(|document| serde_yaml::from_str(document).unwrap())
+            .collect();
+        assert!(documents[0]["spec"].get("selector").is_none());
+        assert_eq!(documents[0]["spec"]["ports"][0]["port"], 5000);
+        assert_eq!(
+            documents[1]["subsets"][0]["addresses"][0]["ip"],
+            "192.168.5.2"
+        );
+        assert_eq!(documents[1]["subsets"][0]["ports"][0]["port"], 30500);
+    }
+}
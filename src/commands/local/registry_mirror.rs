@@ -0,0 +1,120 @@
+use super::registry::{self, REGISTRY_PULL};
+use super::{kubectl_apply_stdin, registry_forward, run_cmd};
+use crate::commands::config::{image_config_name, strip_registry};
+use clap::Args;
+use std::error::Error;
+
+#[derive(Args, Debug)]
+pub struct MirrorArgs {
+    /// Package references to mirror, with a tag
+    /// (e.g. xpkg.upbound.io/crossplane-contrib/provider-family-aws:v1.20.0)
+    #[arg(required = true)]
+    pub packages: Vec<String>,
+
+    /// Only push the packages; don't create ImageConfig rewrites for them
+    #[arg(long)]
+    pub no_rewrite: bool,
+}
+
+pub fn run(args: &MirrorArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local registry mirror")?;
+    let packages = args
+        .packages
+        .iter()
+        .map(|package| parse_package_ref(package))
+        .collect::<Result<Vec<_>, _>>()?;
+    registry_forward::ensure_reachable()?;
+    let push_host = registry::push_host();
+
+    for (path, tag) in &packages {
+        let source = format!("{}:{}", path, tag);
+        let local_path = strip_registry(path);
+        let push_ref = format!("{}/{}:{}", push_host, local_path, tag);
+        log::info!("Mirroring {}...", source);
+        // The daemon runs in the VM, so this pulls the cluster's platform.
+        run_cmd("docker", &["pull", &source])?;
+        run_cmd("docker", &["tag", &source, &push_ref])?;
+        run_cmd("docker", &["push", &push_ref])?;
+
+        if args.no_rewrite {
+            continue;
+        }
+        // Same rewrite `config install` uses for render functions, so
+        // Crossplane keeps the upstream reference in spec.package.
+        let target_prefix = format!("{}/{}", REGISTRY_PULL, local_path);
+        log::info!(
+            "Applying ImageConfig rewrite for {} -> {}...",
+            path,
+            target_prefix
+        );
+        kubectl_apply_stdin(&format!(
+            "apiVersion: pkg.crossplane.io/v1beta1
+kind: ImageConfig
+metadata:
+  name: {}
+spec:
+  matchImages:
+    - type: Prefix
+      prefix: {}
+  rewriteImage:
+    prefix: {}
+",
+            image_config_name(path),
+            path,
+            target_prefix
+        ))?;
+    }
+
+    crate::logging::success(&format!(
+        "Mirrored {} package(s) into {}",
+        packages.len(),
+        push_host
+    ));
+    Ok(())
+}
+
+/// Split `<registry>/<path>:<tag>` into path and tag. Digest references
+/// can't be mirrored: pushing a single-platform image changes the digest.
+fn parse_package_ref(package: &str) -> Result<(String, String), Box<dyn Error>> {
+    if package.contains('@') {
+        return Err(format!(
+            "{}: mirror needs a tag, not a digest (the mirrored image gets a new digest)",
+            package
+        )
+        .into());
+    }
+    let name_start = package.rfind('/').map(|i| i + 1).unwrap_or(0);
+    match package[name_start..].rsplit_once(':') {
+        Some((name, tag)) if !tag.is_empty() && !name.is_empty() => Ok((
+            format!("{}{}", &package[..name_start], name),
+            tag.to_string(),
+        )),
+        _ => Err(format!(
+            "{}: give a tag, e.g. {}:v1.0.0 (mirrors are pinned to a version)",
+            package, package
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_package_ref_requires_a_tag() {
+        assert_eq!(
+            parse_package_ref("xpkg.upbound.io/crossplane-contrib/provider-family-aws:v1.20.0")
+                .unwrap(),
+            (
+                "xpkg.upbound.io/crossplane-contrib/provider-family-aws".to_string(),
+                "v1.20.0".to_string()
+            )
+        );
+        assert!(parse_package_ref("localhost:5000/provider-helm").is_err());
+        assert!(
+            parse_package_ref("xpkg.upbound.io/crossplane-contrib/provider-helm@sha256:ab")
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use super::process::state_path;
+use super::run_cmd;
+use serde_json::{json, Value as JsonValue};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// `~/.hops/local/registry-tls/`: generated CA and registry certificate.
+/// TLS mode stays on while it exists.
+const TLS_DIR: &str = "registry-tls";
+const REGISTRY_HOSTNAME: &str = "registry.crossplane-system.svc.cluster.local";
+
+/// Secret with the registry certificate, ConfigMap with the CA (read by
+/// Crossplane through `registryCaBundleConfig`), and the nginx config.
+pub const TLS_SECRET: &str = "registry-tls";
+pub const CA_CONFIGMAP: &str = "registry-ca";
+pub const CA_KEY: &str = "ca.crt";
+const PROXY_CONFIGMAP: &str = "registry-tls-proxy";
+/// Port the TLS proxy listens on; the Service's cluster port 5000 targets it.
+const PROXY_PORT: u16 = 5443;
+/// Service port that still reaches the registry over plain HTTP (NodePort).
+pub const HTTP_PORT: u16 = 5001;
+
+const NGINX_CONF: &str = r#"events {}
+http {
+  server {
+    listen 5443 ssl;
+    ssl_certificate /certs/tls.crt;
+    ssl_certificate_key /certs/tls.key;
+    # Package layers can be large; stream them straight through.
+    client_max_body_size 0;
+    proxy_request_buffering off;
+    location / {
+      proxy_pass http://127.0.0.1:5000;
+      proxy_set_header Host $http_host;
+      proxy_set_header X-Forwarded-Proto https;
+      proxy_read_timeout 900;
+    }
+  }
+}
+"#;
+
+/// Whether the registry is served over TLS (never for a host registry).
+pub fn enabled() -> bool {
+    ca_path().is_ok_and(|path| path.exists())
+        && super::registry::mode() == crate::settings::RegistryMode::InCluster
+}
+
+fn tls_dir() -> Result<PathBuf, Box<dyn Error>> {
+    state_path(TLS_DIR)
+}
+
+fn ca_path() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(tls_dir()?.join("ca.crt"))
+}
+
+/// Generate the CA and a certificate for the registry's cluster-internal
+/// name with `openssl`, unless they already exist.
+pub fn ensure_certs() -> Result<(), Box<dyn Error>> {
+    let dir = tls_dir()?;
+    if dir.join("tls.crt").exists() && dir.join("ca.crt").exists() {
+        return Ok(());
+    }
+    fs::create_dir_all(&dir)?;
+    log::info!(
+        "Generating registry CA and certificate in {}...",
+        dir.display()
+    );
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    fs::write(
+        path("san.ext"),
+        format!("subjectAltName=DNS:{},DNS:registry\n", REGISTRY_HOSTNAME),
+    )?;
+    run_cmd(
+        "openssl",
+        &[
+            "req",
+            "-x509",
+            "-newkey",
+            "rsa:2048",
+            "-nodes",
+            "-days",
+            "3650",
+            "-subj",
+            "/CN=hops local registry CA",
+            "-keyout",
+            &path("ca.key"),
+            "-out",
+            &path("ca.crt.tmp"),
+        ],
+    )?;
+    run_cmd(
+        "openssl",
+        &[
+            "req",
+            "-newkey",
+            "rsa:2048",
+            "-nodes",
+            "-subj",
+            &format!("/CN={}", REGISTRY_HOSTNAME),
+            "-keyout",
+            &path("tls.key"),
+            "-out",
+            &path("tls.csr"),
+        ],
+    )?;
+    run_cmd(
+        "openssl",
+        &[
+            "x509",
+            "-req",
+            "-in",
+            &path("tls.csr"),
+            "-CA",
+            &path("ca.crt.tmp"),
+            "-CAkey",
+            &path("ca.key"),
+            "-CAcreateserial",
+            "-days",
+            "825",
+            "-extfile",
+            &path("san.ext"),
+            "-out",
+            &path("tls.crt"),
+        ],
+    )?;
+    // ca.crt last: its presence is what turns TLS mode on.
+    fs::rename(path("ca.crt.tmp"), path("ca.crt"))?;
+    Ok(())
+}
+
+pub fn ca_pem() -> Result<String, Box<dyn Error>> {
+    Ok(fs::read_to_string(ca_path()?)?)
+}
+
+/// The certificate Secret, CA ConfigMap, and proxy config, as one YAML stream.
+pub fn resources_yaml(namespace: &str) -> Result<String, Box<dyn Error>> {
+    let dir = tls_dir()?;
+    let resources = [
+        json!({
+            "apiVersion": "v1",
+            "kind": "Secret",
+            "metadata": {"name": TLS_SECRET, "namespace": namespace},
+            "type": "kubernetes.io/tls",
+            "stringData": {
+                "tls.crt": fs::read_to_string(dir.join("tls.crt"))?,
+                "tls.key": fs::read_to_string(dir.join("tls.key"))?,
+            }
+        }),
+        ca_configmap(namespace)?,
+        json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": PROXY_CONFIGMAP, "namespace": namespace},
+            "data": {"nginx.conf": NGINX_CONF}
+        }),
+    ];
+    let documents = resources
+        .iter()
+        .map(serde_yaml::to_string)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(documents.join("---\n"))
+}
+
+/// The CA ConfigMap alone; it must exist before Crossplane is installed.
+pub fn ca_configmap(namespace: &str) -> Result<JsonValue, Box<dyn Error>> {
+    Ok(json!({
+        "apiVersion": "v1",
+        "kind": "ConfigMap",
+        "metadata": {"name": CA_CONFIGMAP, "namespace": namespace},
+        "data": {CA_KEY: ca_pem()?}
+    }))
+}
+
+/// Add the TLS proxy sidecar to the registry Deployment and point the
+/// Service's cluster port at it. The NodePort keeps serving plain HTTP on
+/// localhost, which Docker allows without an insecure-registries entry.
+pub fn add_tls_proxy(object: &mut JsonValue, node_port: u16) -> Result<(), Box<dyn Error>> {
+    match object.get("kind").and_then(JsonValue::as_str) {
+        Some("Deployment") => {
+            let pod = object
+                .pointer_mut("/spec/template/spec")
+                .ok_or("registry manifest has no pod spec")?;
+            pod["containers"]
+                .as_array_mut()
+                .ok_or("registry manifest has no containers")?
+                .push(json!({
+                    "name": "tls-proxy",
+                    "image": "nginx:1-alpine",
+                    "ports": [{"containerPort": PROXY_PORT}],
+                    "volumeMounts": [
+                        {"name": "tls", "mountPath": "/certs", "readOnly": true},
+                        {"name": "tls-proxy", "mountPath": "/etc/nginx/nginx.conf", "subPath": "nginx.conf", "readOnly": true}
+                    ]
+                }));
+            pod["volumes"]
+                .as_array_mut()
+                .ok_or("registry manifest has no volumes")?
+                .extend([
+                    json!({"name": "tls", "secret": {"secretName": TLS_SECRET}}),
+                    json!({"name": "tls-proxy", "configMap": {"name": PROXY_CONFIGMAP}}),
+                ]);
+        }
+        Some("Service") => {
+            object["spec"]["ports"] = json!([
+                {"name": "https", "port": 5000, "targetPort": PROXY_PORT},
+                {"name": "http", "port": HTTP_PORT, "targetPort": 5000, "nodePort": node_port}
+            ]);
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tls_proxy_fronts_cluster_port_and_keeps_node_port_plain() {
+        let mut deployment = json!({"kind": "Deployment", "spec": {"template": {"spec": {
+            "containers": [{"name": "registry"}],
+            "volumes": [{"name": "storage"}]
+        }}}});
+        add_tls_proxy(&mut deployment, 30500).unwrap();
+        let pod = &deployment["spec"]["template"]["spec"];
+        assert_eq!(pod["containers"][1]["name"], "tls-proxy");
+        assert_eq!(pod["volumes"][1]["secret"]["secretName"], TLS_SECRET);
+
+        let mut service = json!({"kind": "Service", "spec": {"ports": [
+            {"port": 5000, "targetPort": 5000, "nodePort": 30500}
+        ]}});
+        add_tls_proxy(&mut service, 30600).unwrap();
+        assert_eq!(service["spec"]["ports"][0]["targetPort"], PROXY_PORT);
+        assert_eq!(service["spec"]["ports"][1]["nodePort"], 30600);
+        assert!(NGINX_CONF.contains(&format!("listen {} ssl", PROXY_PORT)));
+    }
+}
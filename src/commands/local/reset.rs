@@ -1,9 +1,36 @@
-use super::run_cmd;
+use super::registry::{self, REGISTRY_HOST_PATH};
+use super::start::StartState;
+use super::{mock, registry_host, run_cmd};
+use crate::settings::RegistryMode;
+use clap::Args;
 use std::error::Error;
 
-pub fn run() -> Result<(), Box<dyn Error>> {
+#[derive(Args, Debug)]
+pub struct ResetArgs {
+    /// Also delete the packages pushed to the local registry (kept across resets by default)
+    #[arg(long)]
+    pub wipe_registry: bool,
+}
+
+pub fn run(args: &ResetArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local reset")?;
     log::info!("Resetting Colima Kubernetes...");
     run_cmd("colima", &["kubernetes", "reset"])?;
-    log::info!("Colima Kubernetes reset complete");
+    StartState::reset_cluster()?;
+    mock::forget()?;
+    if args.wipe_registry && registry::mode() == RegistryMode::Host {
+        log::info!(
+            "Wiping host registry storage ({})...",
+            registry_host::data_dir()?.display()
+        );
+        registry_host::wipe()?;
+    } else if args.wipe_registry {
+        log::info!("Wiping local registry storage ({})...", REGISTRY_HOST_PATH);
+        run_cmd(
+            "colima",
+            &["ssh", "--", "sudo", "rm", "-rf", REGISTRY_HOST_PATH],
+        )?;
+    }
+    crate::logging::success("Colima Kubernetes reset complete");
     Ok(())
 }
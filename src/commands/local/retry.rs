@@ -0,0 +1,128 @@
+use super::backoff::Backoff;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Attempts, including the first, before a transient failure is returned.
+const MAX_ATTEMPTS: u32 = 4;
+const FIRST_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(8);
+/// Longest time spent retrying one operation.
+const RETRY_WINDOW: Duration = Duration::from_secs(60);
+
+/// Error text from kubectl, helm, or client-go meaning the API server was
+/// briefly unreachable or overloaded (e.g. while Docker restarts in the VM or
+/// a webhook's pod comes up), not that the request itself was wrong.
+const TRANSIENT_ERRORS: &[&str] = &[
+    "connection refused",
+    "connection reset by peer",
+    "i/o timeout",
+    "tls handshake timeout",
+    "net/http: request canceled",
+    "unexpected eof",
+    "http2: client connection lost",
+    "unable to connect to the server",
+    "kubernetes cluster unreachable",
+    "the server is currently unable to handle the request",
+    "the server was unable to return a response in the time allotted",
+    "the server has received too many requests",
+    "etcdserver: request timed out",
+    "etcdserver: leader changed",
+    "failed calling webhook",
+    "no endpoints available for service",
+];
+
+/// Retry transient failures from here on. `local` and `config` commands turn
+/// this on, except those meant to fail fast (e.g. `local status` against a
+/// stopped cluster).
+pub fn set_enabled(value: bool) {
+    ENABLED.store(value, Ordering::Relaxed);
+}
+
+/// Whether `message` reports a failure that is worth retrying as is.
+pub fn is_transient(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    TRANSIENT_ERRORS
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+/// Run `op`, running it again with backoff while it fails with a transient
+/// error, up to `MAX_ATTEMPTS` times within `RETRY_WINDOW`. Runs it once
+/// unless retries are enabled.
+pub fn retry<T>(
+    what: &str,
+    mut op: impl FnMut() -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return op();
+    }
+    retry_transient(what, op)
+}
+
+fn retry_transient<T>(
+    what: &str,
+    mut op: impl FnMut() -> Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    let mut backoff = Backoff::with_delays(RETRY_WINDOW, FIRST_DELAY, MAX_DELAY);
+    let mut attempt = 1;
+    loop {
+        match op() {
+            Err(err) if attempt < MAX_ATTEMPTS && is_transient(&err.to_string()) => {
+                let message = err.to_string();
+                log::warn!(
+                    "{} failed ({}); retrying ({}/{})",
+                    what,
+                    message.lines().last().unwrap_or_default().trim(),
+                    attempt,
+                    MAX_ATTEMPTS - 1
+                );
+                if !backoff.wait() {
+                    return Err(err);
+                }
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_transient_errors_are_retried() {
+        assert!(is_transient(
+            "kubectl exited with exit status: 1: The connection to the server 127.0.0.1:6443 was refused - did you specify the right host or port?\ndial tcp 127.0.0.1:6443: connect: connection refused"
+        ));
+        assert!(is_transient(
+            "Error from server (InternalError): Internal error occurred: failed calling webhook \"compositeresourcedefinitions.apiextensions.crossplane.io\""
+        ));
+        assert!(!is_transient(
+            "Error from server (NotFound): configurations.pkg.crossplane.io \"demo\" not found"
+        ));
+        assert!(!is_transient("error: timed out waiting for the condition"));
+
+        let mut calls = 0;
+        let result = retry_transient("kubectl get", || {
+            calls += 1;
+            if calls == 1 {
+                Err("Unable to connect to the server: EOF".into())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 2);
+
+        let mut calls = 0;
+        let result: Result<(), _> = retry_transient("kubectl get", || {
+            calls += 1;
+            Err("NotFound".into())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+}
@@ -0,0 +1,335 @@
+use super::du::{dir_size, format_bytes};
+use super::process::state_path;
+use super::registry::REGISTRY_HOST_PATH;
+use super::{colima_command, profile, run_cmd, run_cmd_output};
+use clap::{Args, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::SystemTime;
+
+/// `~/.hops/local/snapshots/<name>/`
+const SNAPSHOTS_DIR: &str = "snapshots";
+const META_FILE: &str = "snapshot.json";
+/// Disk images of the Lima instance: the root disk, and the data disk newer
+/// Colima versions keep containers and volumes on.
+const ROOT_DISK: &str = "diffdisk";
+const DATA_DISK: &str = "datadisk";
+/// Archive of the cluster state when the disks can't be copied.
+const CLUSTER_ARCHIVE: &str = "cluster.tar.gz";
+/// k3s datastore, certificates, and tokens inside the VM.
+const K3S_SERVER_DIR: &str = "/var/lib/rancher/k3s/server";
+
+#[derive(Args, Debug)]
+pub struct SnapshotArgs {
+    #[command(subcommand)]
+    pub command: SnapshotCommands,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommands {
+    /// Save the VM disks (stopping Colima briefly) or, where they can't be
+    /// found, the cluster state
+    Save(SaveArgs),
+    /// Restore a snapshot, replacing the current VM or cluster state
+    Restore(NameArgs),
+    /// List saved snapshots
+    Ls,
+    /// Delete a snapshot
+    Rm(NameArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct SaveArgs {
+    /// Snapshot name
+    pub name: String,
+
+    /// Overwrite an existing snapshot with the same name
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct NameArgs {
+    /// Snapshot name
+    pub name: String,
+}
+
+/// What a snapshot holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum SnapshotKind {
+    /// Copies of the Lima disk images.
+    Disk,
+    /// A tarball of the k3s server directory and registry storage.
+    Cluster,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotMeta {
+    kind: SnapshotKind,
+    created: String,
+}
+
+pub fn run(args: &SnapshotArgs) -> Result<(), Box<dyn Error>> {
+    match &args.command {
+        SnapshotCommands::Save(save_args) => save(save_args),
+        SnapshotCommands::Restore(name_args) => restore(&name_args.name),
+        SnapshotCommands::Ls => list(),
+        SnapshotCommands::Rm(name_args) => remove(&name_args.name),
+    }
+}
+
+fn save(args: &SaveArgs) -> Result<(), Box<dyn Error>> {
+    // Saving stops the VM or its Kubernetes for a moment.
+    profile::ensure_owned("local snapshot save")?;
+    let dir = snapshot_dir(&args.name)?;
+    if dir.exists() {
+        if !args.force {
+            return Err(format!(
+                "snapshot {} already exists; pass --force to overwrite it",
+                args.name
+            )
+            .into());
+        }
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+
+    let result = match lima_disks()? {
+        Some(disks) => save_disks(&disks, &dir).map(|_| SnapshotKind::Disk),
+        None => {
+            log::warn!("Colima's disk images were not found; saving the cluster state instead");
+            save_cluster(&dir).map(|_| SnapshotKind::Cluster)
+        }
+    };
+    let kind = match result {
+        Ok(kind) => kind,
+        Err(err) => {
+            let _ = fs::remove_dir_all(&dir);
+            return Err(err);
+        }
+    };
+    let meta = SnapshotMeta {
+        kind,
+        created: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+    };
+    fs::write(dir.join(META_FILE), serde_json::to_string_pretty(&meta)?)?;
+    crate::logging::success(&format!(
+        "Saved snapshot {} ({})",
+        args.name,
+        format_bytes(dir_size(&dir))
+    ));
+    Ok(())
+}
+
+fn restore(name: &str) -> Result<(), Box<dyn Error>> {
+    profile::ensure_owned("local snapshot restore")?;
+    let dir = snapshot_dir(name)?;
+    let meta = read_meta(&dir).ok_or_else(|| format!("no snapshot named {}", name))?;
+    match meta.kind {
+        SnapshotKind::Disk => {
+            let disks =
+                lima_disks()?.ok_or("Colima's disk images were not found; is the VM created?")?;
+            log::info!("Stopping Colima...");
+            run_cmd("colima", &["stop"])?;
+            for disk in &disks {
+                copy_file(&dir.join(disk_file_name(disk)?), disk)?;
+            }
+            log::info!("Starting Colima...");
+            run_cmd("colima", &["start"])?;
+        }
+        SnapshotKind::Cluster => {
+            log::info!("Stopping Kubernetes...");
+            run_cmd("colima", &["kubernetes", "stop"])?;
+            let archive = File::open(dir.join(CLUSTER_ARCHIVE))?;
+            let script = format!(
+                "rm -rf {} {} && tar -xzf - -C /",
+                K3S_SERVER_DIR, REGISTRY_HOST_PATH
+            );
+            run_with_stdio(
+                &["ssh", "--", "sudo", "sh", "-c", &script],
+                Stdio::from(archive),
+                Stdio::inherit(),
+            )?;
+            log::info!("Starting Kubernetes...");
+            run_cmd("colima", &["kubernetes", "start"])?;
+        }
+    }
+    crate::logging::success(&format!("Restored snapshot {} from {}", name, meta.created));
+    Ok(())
+}
+
+fn list() -> Result<(), Box<dyn Error>> {
+    let root = state_path(SNAPSHOTS_DIR)?;
+    let mut names: Vec<String> = match fs::read_dir(&root) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().join(META_FILE).exists())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    if names.is_empty() {
+        println!("No snapshots (save one with `hops local snapshot save <name>`)");
+        return Ok(());
+    }
+    names.sort();
+    for name in names {
+        let dir = root.join(&name);
+        if let Some(meta) = read_meta(&dir) {
+            println!(
+                "{}  {}  {}  {}",
+                name,
+                match meta.kind {
+                    SnapshotKind::Disk => "disk",
+                    SnapshotKind::Cluster => "cluster",
+                },
+                meta.created,
+                format_bytes(dir_size(&dir))
+            );
+        }
+    }
+    Ok(())
+}
+
+fn remove(name: &str) -> Result<(), Box<dyn Error>> {
+    let dir = snapshot_dir(name)?;
+    if read_meta(&dir).is_none() {
+        return Err(format!("no snapshot named {}", name).into());
+    }
+    fs::remove_dir_all(&dir)?;
+    crate::logging::success(&format!("Deleted snapshot {}", name));
+    Ok(())
+}
+
+fn snapshot_dir(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    validate_name(name)?;
+    Ok(state_path(SNAPSHOTS_DIR)?.join(name))
+}
+
+fn validate_name(name: &str) -> Result<(), Box<dyn Error>> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid snapshot name {:?}: use letters, digits, '-', '_', and '.'",
+            name
+        )
+        .into())
+    }
+}
+
+fn read_meta(dir: &Path) -> Option<SnapshotMeta> {
+    serde_json::from_str(&fs::read_to_string(dir.join(META_FILE)).ok()?).ok()
+}
+
+/// Disk images of the Colima VM, or `None` when the root disk isn't where
+/// Lima keeps it (e.g. a Colima build with a different layout).
+fn lima_disks() -> Result<Option<Vec<PathBuf>>, Box<dyn Error>> {
+    let lima_home = match std::env::var("COLIMA_HOME") {
+        Ok(home) if !home.is_empty() => PathBuf::from(home),
+        _ => PathBuf::from(std::env::var("HOME").map_err(|_| "HOME is not set")?).join(".colima"),
+    }
+    .join("_lima");
+    // The Lima instance backing the Colima profile.
+    let instance = profile::instance_name(&profile::current());
+    let root_disk = lima_home.join(&instance).join(ROOT_DISK);
+    if !root_disk.exists() {
+        return Ok(None);
+    }
+    let mut disks = vec![root_disk];
+    let data_disk = lima_home.join("_disks").join(&instance).join(DATA_DISK);
+    if data_disk.exists() {
+        disks.push(data_disk);
+    }
+    Ok(Some(disks))
+}
+
+fn disk_file_name(disk: &Path) -> Result<&str, Box<dyn Error>> {
+    disk.file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("unexpected disk path {}", disk.display()).into())
+}
+
+/// Copy the disks with Colima stopped so they're consistent, then start it
+/// again if it was running.
+fn save_disks(disks: &[PathBuf], dir: &Path) -> Result<(), Box<dyn Error>> {
+    let was_running = run_cmd_output("colima", &["status"]).is_ok();
+    if was_running {
+        log::info!("Stopping Colima to copy its disks...");
+        run_cmd("colima", &["stop"])?;
+    }
+    let copied = disks
+        .iter()
+        .try_for_each(|disk| copy_file(disk, &dir.join(disk_file_name(disk)?)));
+    if was_running {
+        log::info!("Starting Colima...");
+        run_cmd("colima", &["start"])?;
+    }
+    copied
+}
+
+/// Copy a disk image, cloning it where the filesystem supports that (APFS,
+/// btrfs, XFS) so multi-GiB images take seconds and little space.
+fn copy_file(from: &Path, to: &Path) -> Result<(), Box<dyn Error>> {
+    log::info!("Copying {}...", from.display());
+    let from = from.display().to_string();
+    let to = to.display().to_string();
+    if cfg!(target_os = "macos") {
+        run_cmd("cp", &["-c", &from, &to])
+    } else {
+        run_cmd("cp", &["--reflink=auto", "--sparse=always", &from, &to])
+    }
+}
+
+/// Archive the k3s server directory and registry storage from inside the
+/// VM, with Kubernetes stopped so the datastore is consistent.
+fn save_cluster(dir: &Path) -> Result<(), Box<dyn Error>> {
+    log::info!("Stopping Kubernetes to archive its state...");
+    run_cmd("colima", &["kubernetes", "stop"])?;
+    let archive = File::create(dir.join(CLUSTER_ARCHIVE))?;
+    let script = format!(
+        "cd / && tar -czf - $(ls -d {} {} 2>/dev/null)",
+        K3S_SERVER_DIR.trim_start_matches('/'),
+        REGISTRY_HOST_PATH.trim_start_matches('/')
+    );
+    let archived = run_with_stdio(
+        &["ssh", "--", "sudo", "sh", "-c", &script],
+        Stdio::null(),
+        Stdio::from(archive),
+    );
+    log::info!("Starting Kubernetes...");
+    run_cmd("colima", &["kubernetes", "start"])?;
+    archived
+}
+
+fn run_with_stdio(args: &[&str], stdin: Stdio, stdout: Stdio) -> Result<(), Box<dyn Error>> {
+    let status = colima_command(args).stdin(stdin).stdout(stdout).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("colima {} failed with {}", args.join(" "), status).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_name_rejects_paths() {
+        assert!(validate_name("bootstrapped-1.0").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("..").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("with space").is_err());
+    }
+}
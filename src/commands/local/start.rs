@@ -1,7 +1,16 @@
-use super::{kubectl_apply_stdin, run_cmd, run_cmd_output, sync_registry_hosts_entry};
+use super::backoff::poll_until;
+use super::conditions::{timed_out, wait_for_all, wait_for_deployment, ReadyTarget};
+use super::process::{read_state, remove_state, write_state};
+use super::{
+    kubectl_apply_stdin, parse_df_kib, registry, registry_auth, registry_host, registry_tls,
+    run_cmd, run_cmd_output, sync_registry_hosts_entry, vm_file,
+};
+use crate::settings::RegistryMode;
+use crate::settings::Timeouts;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::io::Write;
-use std::process::{Command, Stdio};
+use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 
@@ -10,14 +19,510 @@ const PROVIDER_HELM: &str = include_str!("../../../bootstrap/providers/provider-
 const PROVIDER_K8S: &str = include_str!("../../../bootstrap/providers/provider-kubernetes.yaml");
 const PC_HELM: &str = include_str!("../../../bootstrap/helm/pc.yaml");
 const PC_K8S: &str = include_str!("../../../bootstrap/k8s/pc.yaml");
-const REGISTRY: &str = include_str!("../../../bootstrap/registry/registry.yaml");
+const STATE_FILE: &str = "state.json";
 
 /// Cluster-internal hostname for the package registry.
 const REGISTRY_HOST: &str = "registry.crossplane-system.svc.cluster.local:5000";
 const REGISTRY_HOSTNAME: &str = "registry.crossplane-system.svc.cluster.local";
 
-pub fn run() -> Result<(), Box<dyn Error>> {
-    // 1. Start Colima with Kubernetes
+/// Colima VM size when neither flags nor ~/.hops/config.yaml set it and the
+/// host can't be measured. Otherwise CPU and memory are a share of the host
+/// (half the CPUs, a third of the memory) within these bounds.
+const DEFAULT_CPU: u32 = 8;
+const DEFAULT_MEMORY_GIB: u32 = 16;
+const DEFAULT_DISK_GIB: u32 = 60;
+const AUTO_CPU: (u32, u32) = (2, 8);
+const AUTO_MEMORY_GIB: (u32, u32) = (4, 16);
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// NodePorts of the optional ingress controller, forwarded to the host by Colima.
+const INGRESS_HTTP_NODE_PORT: u16 = 30080;
+const INGRESS_HTTPS_NODE_PORT: u16 = 30443;
+
+#[derive(Args, Debug, Default)]
+pub struct StartArgs {
+    /// Also install ingress-nginx so Ingresses are reachable at
+    /// http://<host>.<ingress-domain>:30080 without kubefwd
+    #[arg(long)]
+    pub ingress: bool,
+
+    /// Wildcard domain that resolves to 127.0.0.1 (nip.io needs internet
+    /// access; `localhost` works offline in browsers)
+    #[arg(long, default_value = "127.0.0.1.nip.io", requires = "ingress")]
+    pub ingress_domain: String,
+
+    /// Start kubefwd once the cluster is ready, on full runs (default from
+    /// `start.with-kubefwd` in ~/.hops/config.yaml)
+    #[arg(long)]
+    pub with_kubefwd: bool,
+
+    /// Serve the registry over TLS with a generated CA trusted by the VM,
+    /// instead of adding it to Docker's insecure-registries (kept for later starts)
+    #[arg(long)]
+    pub registry_tls: bool,
+
+    /// VM CPUs (default from `start.cpu` in ~/.hops/config.yaml, else half
+    /// the host's, 2-8)
+    #[arg(long)]
+    pub cpu: Option<u32>,
+
+    /// VM memory in GiB (default from `start.memory`, else a third of the
+    /// host's, 4-16)
+    #[arg(long)]
+    pub memory: Option<u32>,
+
+    /// VM disk size in GiB (default from `start.disk`, else 60); Colima
+    /// cannot shrink an existing disk
+    #[arg(long)]
+    pub disk: Option<u32>,
+
+    /// Skip starting Colima and configuring its Docker daemon
+    #[arg(long, conflicts_with = "only")]
+    pub skip_vm: bool,
+
+    /// Skip installing Crossplane
+    #[arg(long, conflicts_with = "only")]
+    pub skip_crossplane: bool,
+
+    /// Skip the DeploymentRuntimeConfig, bootstrap providers, and ProviderConfigs
+    #[arg(long, conflicts_with = "only")]
+    pub skip_providers: bool,
+
+    /// Skip deploying the package registry
+    #[arg(long, conflicts_with = "only")]
+    pub skip_registry: bool,
+
+    /// Skip the VM hosts entry for the registry
+    #[arg(long, conflicts_with = "only")]
+    pub skip_hosts: bool,
+
+    /// Install Crossplane from the newest chart cached in ~/.hops/cache
+    /// instead of fetching it (a failed fetch falls back to the cache too)
+    #[arg(long)]
+    pub offline: bool,
+
+    /// Pull the bootstrap providers and `start.preload` images into the VM
+    /// while the cluster comes up (see `hops local preload`)
+    #[arg(long)]
+    pub preload: bool,
+
+    /// Limit for every wait in this run, e.g. 10m (default: the `timeouts:`
+    /// keys in ~/.hops/config.yaml, else 5m per wait)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
+
+    /// Run only these phases against the existing cluster (repeatable)
+    #[arg(long, value_enum)]
+    pub only: Vec<StartPhase>,
+
+    /// Skip the phases a previous run completed and continue from the one
+    /// that failed (as recorded in ~/.hops/local/state.json)
+    #[arg(long)]
+    pub resume: bool,
+}
+
+/// Steps of `hops local start`, in the order they run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StartPhase {
+    /// Start Colima and let its Docker daemon pull from the registry
+    Vm,
+    /// Install Crossplane with Helm
+    Crossplane,
+    /// DeploymentRuntimeConfig, provider-helm, provider-kubernetes, ProviderConfigs
+    Providers,
+    /// Deploy the package registry
+    Registry,
+    /// Map the registry hostname inside the VM
+    Hosts,
+}
+
+impl StartPhase {
+    const ALL: [StartPhase; 5] = [
+        StartPhase::Vm,
+        StartPhase::Crossplane,
+        StartPhase::Providers,
+        StartPhase::Registry,
+        StartPhase::Hosts,
+    ];
+}
+
+/// `~/.hops/local/state.json`: which start phases have completed, so a
+/// failed `hops local start --resume` can pick up where it stopped.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StartState {
+    pub completed_phases: Vec<StartPhase>,
+    pub failed_phase: Option<StartPhase>,
+}
+
+impl StartState {
+    /// The recorded progress, or `None` if `hops local start` never ran.
+    pub fn load() -> Option<StartState> {
+        read_state(STATE_FILE)
+    }
+
+    /// The first phase that hasn't completed, if any.
+    pub fn incomplete_phase(&self) -> Option<StartPhase> {
+        StartPhase::ALL
+            .into_iter()
+            .find(|phase| !self.completed_phases.contains(phase))
+    }
+
+    /// Forget everything past the VM, e.g. after `colima kubernetes reset`.
+    pub fn reset_cluster() -> Result<(), Box<dyn Error>> {
+        if let Some(mut state) = StartState::load() {
+            state
+                .completed_phases
+                .retain(|phase| *phase == StartPhase::Vm);
+            state.failed_phase = None;
+            write_state(STATE_FILE, &state)?;
+        }
+        Ok(())
+    }
+
+    pub fn remove() -> Result<(), Box<dyn Error>> {
+        remove_state(STATE_FILE)
+    }
+
+    fn mark(&mut self, phase: StartPhase, succeeded: bool) {
+        self.completed_phases
+            .retain(|completed| *completed != phase);
+        if succeeded {
+            self.completed_phases.push(phase);
+            self.completed_phases
+                .sort_by_key(|phase| StartPhase::ALL.iter().position(|p| p == phase));
+            self.failed_phase = None;
+        } else {
+            self.failed_phase = Some(phase);
+        }
+    }
+
+    /// Record the outcome of `phase` and pass it through.
+    fn record(
+        &mut self,
+        phase: StartPhase,
+        result: Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        self.mark(phase, result.is_ok());
+        if let Err(err) = write_state(STATE_FILE, self) {
+            log::warn!("Could not record start progress: {}", err);
+        }
+        result
+    }
+}
+
+/// Reported as left incomplete if `local start` is interrupted during
+/// `phase`.
+fn pending_phase(phase: StartPhase) -> crate::interrupt::Guard {
+    crate::interrupt::pending(&format!(
+        "the {} phase of `local start` (continue with --resume)",
+        phase
+    ))
+}
+
+impl std::fmt::Display for StartPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        f.write_str(value.get_name())
+    }
+}
+
+impl StartArgs {
+    /// Phases to run, in order: `--only`, or all of them minus `--skip-*`.
+    fn phases(&self) -> Vec<StartPhase> {
+        StartPhase::ALL
+            .into_iter()
+            .filter(|phase| {
+                if !self.only.is_empty() {
+                    return self.only.contains(phase);
+                }
+                !match phase {
+                    StartPhase::Vm => self.skip_vm,
+                    StartPhase::Crossplane => self.skip_crossplane,
+                    StartPhase::Providers => self.skip_providers,
+                    StartPhase::Registry => self.skip_registry,
+                    StartPhase::Hosts => self.skip_hosts,
+                }
+            })
+            .collect()
+    }
+}
+
+/// CPU, memory, and disk of the Colima VM.
+#[derive(Debug, PartialEq, Eq)]
+struct VmResources {
+    cpu: u32,
+    memory_gib: u32,
+    disk_gib: u32,
+}
+
+/// What the host can give the VM; `None` where it couldn't be read.
+struct HostCapacity {
+    cpus: Option<u32>,
+    memory_gib: Option<u64>,
+    free_disk_gib: Option<u64>,
+}
+
+impl VmResources {
+    fn resolve(
+        args: &StartArgs,
+        settings: &crate::settings::StartSettings,
+        host: &HostCapacity,
+    ) -> Self {
+        let cpu = args.cpu.or(settings.cpu).unwrap_or_else(|| {
+            host.cpus.map_or(DEFAULT_CPU, |cpus| {
+                let cpu = (cpus / 2).clamp(AUTO_CPU.0, AUTO_CPU.1);
+                log::info!("Sizing VM CPUs from the host: {} of {}", cpu, cpus);
+                cpu
+            })
+        });
+        let memory_gib = args.memory.or(settings.memory).unwrap_or_else(|| {
+            host.memory_gib.map_or(DEFAULT_MEMORY_GIB, |memory| {
+                let gib = u32::try_from(memory / 3)
+                    .unwrap_or(u32::MAX)
+                    .clamp(AUTO_MEMORY_GIB.0, AUTO_MEMORY_GIB.1);
+                log::info!("Sizing VM memory from the host: {} of {} GiB", gib, memory);
+                gib
+            })
+        });
+        Self {
+            cpu,
+            memory_gib,
+            disk_gib: args.disk.or(settings.disk).unwrap_or(DEFAULT_DISK_GIB),
+        }
+    }
+
+    /// Reject sizes the host can't provide; return warnings for tight ones.
+    fn validate(&self, host: &HostCapacity) -> Result<Vec<String>, Box<dyn Error>> {
+        if self.cpu == 0 || self.memory_gib == 0 || self.disk_gib == 0 {
+            return Err("--cpu, --memory, and --disk must be greater than 0".into());
+        }
+        if let Some(cpus) = host.cpus.filter(|&cpus| self.cpu > cpus) {
+            return Err(format!("--cpu {} exceeds the host's {} CPUs", self.cpu, cpus).into());
+        }
+        let mut warnings = Vec::new();
+        if let Some(memory) = host.memory_gib {
+            if u64::from(self.memory_gib) > memory {
+                return Err(format!(
+                    "--memory {} GiB exceeds the host's {} GiB",
+                    self.memory_gib, memory
+                )
+                .into());
+            }
+            if u64::from(self.memory_gib) * 4 > memory * 3 {
+                warnings.push(format!(
+                    "the VM gets {} of the host's {} GiB of memory; the host may start swapping",
+                    self.memory_gib, memory
+                ));
+            }
+        }
+        if let Some(free) = host
+            .free_disk_gib
+            .filter(|&free| u64::from(self.disk_gib) > free)
+        {
+            warnings.push(format!(
+                "the VM disk can grow to {} GiB but only {} GiB is free in $HOME",
+                self.disk_gib, free
+            ));
+        }
+        Ok(warnings)
+    }
+}
+
+fn host_capacity() -> HostCapacity {
+    let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
+    HostCapacity {
+        cpus: thread::available_parallelism()
+            .ok()
+            .and_then(|cpus| u32::try_from(cpus.get()).ok()),
+        memory_gib: host_memory_bytes().map(|bytes| bytes / GIB),
+        free_disk_gib: run_cmd_output("df", &["-Pk", &home])
+            .ok()
+            .and_then(|output| parse_df_kib(&output))
+            .map(|usage| usage.available_kib / (1024 * 1024)),
+    }
+}
+
+/// Physical memory: `hw.memsize` on macOS, `MemTotal` on Linux.
+fn host_memory_bytes() -> Option<u64> {
+    if let Ok(output) = run_cmd_output("sysctl", &["-n", "hw.memsize"]) {
+        if let Ok(bytes) = output.trim().parse() {
+            return Some(bytes);
+        }
+    }
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+pub fn run(args: &StartArgs) -> Result<(), Box<dyn Error>> {
+    let settings = crate::settings::load()?;
+    let timeouts = settings.timeouts.resolve(args.timeout);
+    let mut phases = args.phases();
+    let full_run = phases.len() == StartPhase::ALL.len();
+    // kubefwd, the idle watchdog, and mock mode belong to the whole
+    // environment: partial runs leave them alone unless they run the phase
+    // the watcher depends on.
+    let with_kubefwd = full_run && (args.with_kubefwd || settings.start.with_kubefwd);
+    if args.with_kubefwd && !full_run {
+        log::warn!(
+            "--with-kubefwd only applies to full runs; start it with `hops local kubefwd start`"
+        );
+    }
+    let host_registry = registry::mode() == RegistryMode::Host;
+    if args.registry_tls && host_registry {
+        return Err("--registry-tls is not supported with `registry.mode: host`".into());
+    }
+    if args.registry_tls {
+        registry_tls::ensure_certs()?;
+    }
+    let registry_tls = registry_tls::enabled();
+
+    let mut state = StartState::load().unwrap_or_default();
+    if args.resume {
+        // After a reboot or `local stop` the VM phase has to run again even
+        // though it completed, and the VM's /etc/hosts may have been reset;
+        // the rest of the cluster survives the restart.
+        let vm_running = run_cmd_output("colima", &["status"]).is_ok();
+        phases.retain(|phase| {
+            !state.completed_phases.contains(phase)
+                || (!vm_running && matches!(phase, StartPhase::Vm | StartPhase::Hosts))
+        });
+        if phases.is_empty() {
+            log::info!("All start phases are already complete");
+        } else {
+            log::info!("Resuming start at the {} phase", phases[0]);
+        }
+    } else if !full_run {
+        let names: Vec<String> = phases.iter().map(ToString::to_string).collect();
+        log::info!("Running start phases: {}", names.join(", "));
+    }
+
+    if phases.contains(&StartPhase::Vm) {
+        let host = host_capacity();
+        let resources = VmResources::resolve(args, &settings.start, &host);
+        for warning in resources.validate(&host)? {
+            log::warn!("{}", warning);
+        }
+        let _pending = pending_phase(StartPhase::Vm);
+        state.record(
+            StartPhase::Vm,
+            vm_phase(&resources, registry_tls, &timeouts),
+        )?;
+    } else {
+        // Later phases need the API server of the existing cluster.
+        crate::logging::set_step("kubernetes");
+        wait_for_kubernetes(timeouts.kubernetes)?;
+    }
+    // Once the API is up, the cluster components and the registry don't
+    // depend on each other: run them (and ingress-nginx) side by side.
+    if phases.contains(&StartPhase::Registry) && !host_registry {
+        // Both sides deploy into crossplane-system.
+        apply_namespace("crossplane-system")?;
+    }
+    let state = Mutex::new(state);
+    let run_phase = |phase: StartPhase, f: &dyn Fn() -> Result<(), Box<dyn Error>>| {
+        if !phases.contains(&phase) {
+            return Ok(());
+        }
+        let _pending = pending_phase(phase);
+        let result = f();
+        state
+            .lock()
+            .expect("start state lock")
+            .record(phase, result)
+            .map_err(|err| format!("{} phase failed: {}", phase, err))
+    };
+    let errors: Vec<String> = thread::scope(|scope| {
+        let cluster = scope.spawn(|| {
+            run_phase(StartPhase::Crossplane, &|| {
+                install_crossplane(registry_tls, None, args.offline, timeouts.crossplane)
+            })?;
+            run_phase(StartPhase::Providers, &|| {
+                providers_phase(timeouts.providers)
+            })
+        });
+        let registry = scope.spawn(|| {
+            run_phase(StartPhase::Registry, &|| {
+                registry_phase(host_registry, registry_tls, timeouts.registry)
+            })?;
+            // Map the registry's cluster-internal hostname to its ClusterIP
+            // inside the VM so the kubelet can resolve it.
+            run_phase(StartPhase::Hosts, &|| {
+                crate::logging::set_step("registry-hosts");
+                sync_registry_hosts_entry("crossplane-system", "registry", REGISTRY_HOSTNAME)
+            })
+        });
+        // Optional ingress controller for stable URLs on a wildcard domain.
+        let ingress = args.ingress.then(|| {
+            scope.spawn(|| {
+                crate::logging::set_step("ingress");
+                install_ingress(&args.ingress_domain, timeouts.ingress)
+                    .map_err(|err| err.to_string())
+            })
+        });
+        // Warm the Docker daemon's image cache; a failed pull only costs
+        // time later, so it doesn't fail the start.
+        if args.preload {
+            scope.spawn(|| {
+                crate::logging::set_step("preload");
+                if let Err(err) = super::preload::default_images()
+                    .and_then(|images| super::preload::pull(&images, false))
+                {
+                    log::warn!("Preloading images failed: {}", err);
+                }
+            });
+        }
+        [Some(cluster), Some(registry), ingress]
+            .into_iter()
+            .flatten()
+            .filter_map(|handle| match handle.join() {
+                Ok(result) => result.err(),
+                Err(_) => Some("a start step panicked".to_string()),
+            })
+            .collect()
+    });
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into());
+    }
+
+    // Optional service forwarding so the environment is usable in one command.
+    if with_kubefwd {
+        crate::logging::set_step("kubefwd");
+        super::kubefwd::start_for_local_start()?;
+    }
+
+    // The watchdog stops the VM, so it follows the VM phase.
+    if let Some(after) = &settings.start.idle_stop {
+        if full_run || phases.contains(&StartPhase::Vm) {
+            crate::logging::set_step("idle-watch");
+            super::idle::start_watchdog(after)?;
+        }
+    }
+
+    // Mock mode stays on across stop/start until `local mock off`; it acts
+    // on the providers, so it follows the providers phase.
+    if full_run || phases.contains(&StartPhase::Providers) {
+        super::mock::resume()?;
+    }
+
+    crate::logging::success(if full_run {
+        "Local environment is ready"
+    } else {
+        "Selected start phases are done"
+    });
+    Ok(())
+}
+
+/// Start Colima with Kubernetes and let its Docker daemon pull from the
+/// cluster-internal registry.
+fn vm_phase(
+    resources: &VmResources,
+    registry_tls: bool,
+    timeouts: &Timeouts,
+) -> Result<(), Box<dyn Error>> {
+    crate::logging::set_step("colima");
     log::info!("Starting Colima with Kubernetes...");
     run_cmd(
         "colima",
@@ -25,128 +530,267 @@ pub fn run() -> Result<(), Box<dyn Error>> {
             "start",
             "--kubernetes",
             "--cpu",
-            "8",
+            &resources.cpu.to_string(),
             "--memory",
-            "16",
+            &resources.memory_gib.to_string(),
             "--disk",
-            "60",
+            &resources.disk_gib.to_string(),
         ],
     )?;
+    super::profile::mark_owned()?;
 
-    // 2. Wait for the Kubernetes API to become reachable.
-    //    Colima may return immediately ("already running") before the
-    //    API server is ready, or a fresh start needs time to initialise.
-    wait_for_kubernetes()?;
+    // Colima may return immediately ("already running") before the API
+    // server is ready, or a fresh start needs time to initialise.
+    crate::logging::set_step("kubernetes");
+    wait_for_kubernetes(timeouts.kubernetes)?;
 
-    // 3. Configure Docker in the VM to allow HTTP pulls from the
-    //    cluster-internal registry. Without this the kubelet's Docker
-    //    daemon defaults to HTTPS and fails.
-    configure_docker_insecure_registry()?;
+    // Trust the registry's CA in TLS mode, otherwise allow plain HTTP.
+    // Without either the kubelet's Docker daemon fails the HTTPS pull.
+    if registry_tls {
+        crate::logging::set_step("registry-ca");
+        trust_registry_ca(timeouts)
+    } else {
+        crate::logging::set_step("docker-insecure-registry");
+        configure_docker_insecure_registry(timeouts)
+    }
+}
 
-    // 4. Add Crossplane Helm repo
-    log::info!("Adding Crossplane Helm repo...");
-    run_cmd(
-        "helm",
-        &[
-            "repo",
-            "add",
-            "crossplane-stable",
-            "https://charts.crossplane.io/stable",
-        ],
-    )?;
-    run_cmd("helm", &["repo", "update"])?;
+/// The packages of the providers `providers_phase` installs.
+pub(super) fn bootstrap_packages() -> Vec<String> {
+    [PROVIDER_HELM, PROVIDER_K8S]
+        .iter()
+        .filter_map(|manifest| serde_yaml::from_str::<serde_yaml::Value>(manifest).ok())
+        .filter_map(|provider| provider["spec"]["package"].as_str().map(str::to_string))
+        .collect()
+}
+
+/// `helm upgrade --install` the Crossplane release, at `version` or the
+/// chart's latest (from the chart cache when `offline`), and wait for the
+/// deployment, each within `timeout`. Also used by `local upgrade`.
+pub(super) fn install_crossplane(
+    registry_tls: bool,
+    version: Option<&str>,
+    offline: bool,
+    timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+    crate::logging::set_step("crossplane-chart");
+    let chart = super::charts::crossplane_chart(version, offline)?;
 
-    // 5. Install Crossplane
+    crate::logging::set_step("crossplane");
     log::info!("Installing Crossplane...");
-    run_cmd(
-        "helm",
-        &[
-            "upgrade",
-            "--install",
-            "crossplane",
-            "crossplane-stable/crossplane",
-            "-n",
-            "crossplane-system",
-            "--create-namespace",
-            "--wait",
-            "--timeout",
-            "5m",
-        ],
-    )?;
+    let mut helm_args = vec![
+        "upgrade".to_string(),
+        "--install".to_string(),
+        "crossplane".to_string(),
+        chart.display().to_string(),
+        "-n".to_string(),
+        "crossplane-system".to_string(),
+        "--create-namespace".to_string(),
+        "--wait".to_string(),
+        "--timeout".to_string(),
+        format!("{}s", timeout.as_secs().max(1)),
+    ];
+    if registry_tls {
+        // Crossplane pulls packages itself and needs the CA to verify them.
+        apply_registry_ca_bundle()?;
+        helm_args.extend([
+            "--set".to_string(),
+            format!("registryCaBundleConfig.name={}", registry_tls::CA_CONFIGMAP),
+            "--set".to_string(),
+            format!("registryCaBundleConfig.key={}", registry_tls::CA_KEY),
+        ]);
+    }
+    let helm_args: Vec<&str> = helm_args.iter().map(String::as_str).collect();
+    run_cmd("helm", &helm_args)?;
 
-    // 6. Wait for Crossplane deployment
+    crate::logging::set_step("crossplane-ready");
     log::info!("Waiting for Crossplane to be ready...");
-    wait_for_deployment("crossplane-system", "crossplane")?;
+    wait_for_deployment("crossplane-system", "crossplane", timeout)
+}
 
-    // 7. Deploy DRC (cluster-admin SA for provider pods)
+/// The DeploymentRuntimeConfig, provider-helm and provider-kubernetes, and
+/// their ProviderConfigs.
+fn providers_phase(timeout: Duration) -> Result<(), Box<dyn Error>> {
+    // cluster-admin SA for provider pods
+    crate::logging::set_step("deployment-runtime-config");
     log::info!("Applying DeploymentRuntimeConfig...");
     kubectl_apply_stdin(DRC)?;
 
-    // 8. Install providers
+    crate::logging::set_step("providers");
     log::info!("Installing providers...");
     kubectl_apply_stdin(PROVIDER_HELM)?;
     kubectl_apply_stdin(PROVIDER_K8S)?;
 
-    // 9. Wait for provider CRDs
+    crate::logging::set_step("provider-crds");
     log::info!("Waiting for provider CRDs...");
-    wait_for_crd("providerconfigs.helm.m.crossplane.io")?;
-    wait_for_crd("providerconfigs.kubernetes.m.crossplane.io")?;
+    wait_for_all(
+        &[
+            ReadyTarget::crd("providerconfigs.helm.m.crossplane.io"),
+            ReadyTarget::crd("providerconfigs.kubernetes.m.crossplane.io"),
+        ],
+        timeout,
+    )?;
 
-    // 10. Apply ProviderConfigs
+    crate::logging::set_step("provider-configs");
     log::info!("Applying ProviderConfigs...");
     kubectl_apply_stdin(PC_HELM)?;
-    kubectl_apply_stdin(PC_K8S)?;
+    kubectl_apply_stdin(PC_K8S)
+}
 
-    // 11. Deploy local OCI registry for Crossplane packages
+/// Deploy the local OCI registry for Crossplane packages, in the cluster or
+/// as a host container the cluster reaches through the VM gateway.
+fn registry_phase(
+    host_registry: bool,
+    registry_tls: bool,
+    timeout: Duration,
+) -> Result<(), Box<dyn Error>> {
+    crate::logging::set_step("registry");
     log::info!("Deploying local package registry...");
-    kubectl_apply_stdin(REGISTRY)?;
-    wait_for_deployment("crossplane-system", "registry")?;
-
-    // 12. Map the registry's cluster-internal hostname to its ClusterIP
-    //     inside the VM so the kubelet can resolve it.
-    sync_registry_hosts_entry("crossplane-system", "registry", REGISTRY_HOSTNAME)?;
+    if let Some(credentials) = registry::credentials() {
+        registry_auth::apply_local_auth(&credentials)?;
+    }
+    if host_registry {
+        return registry_host::deploy();
+    }
+    if registry_tls {
+        kubectl_apply_stdin(&registry_tls::resources_yaml("crossplane-system")?)?;
+    }
+    kubectl_apply_stdin(&registry::manifest()?)?;
+    wait_for_deployment("crossplane-system", "registry", timeout)
+}
 
-    log::info!("Local environment is ready");
+/// Install ingress-nginx as the default IngressClass, exposed on fixed
+/// NodePorts so any Ingress host under `domain` reaches it from the host.
+fn install_ingress(domain: &str, timeout: Duration) -> Result<(), Box<dyn Error>> {
+    log::info!("Installing ingress-nginx...");
+    run_cmd(
+        "helm",
+        &[
+            "repo",
+            "add",
+            "ingress-nginx",
+            "https://kubernetes.github.io/ingress-nginx",
+        ],
+    )?;
+    run_cmd("helm", &["repo", "update", "ingress-nginx"])?;
+    let http_port = format!(
+        "controller.service.nodePorts.http={}",
+        INGRESS_HTTP_NODE_PORT
+    );
+    let https_port = format!(
+        "controller.service.nodePorts.https={}",
+        INGRESS_HTTPS_NODE_PORT
+    );
+    let timeout = format!("{}s", timeout.as_secs().max(1));
+    run_cmd(
+        "helm",
+        &[
+            "upgrade",
+            "--install",
+            "ingress-nginx",
+            "ingress-nginx/ingress-nginx",
+            "-n",
+            "ingress-nginx",
+            "--create-namespace",
+            "--set",
+            "controller.service.type=NodePort",
+            "--set",
+            &http_port,
+            "--set",
+            &https_port,
+            "--set",
+            "controller.ingressClassResource.default=true",
+            "--wait",
+            "--timeout",
+            &timeout,
+        ],
+    )?;
+    log::info!(
+        "Ingress ready: Ingresses with host <name>.{} are served at http://<name>.{}:{} (https on {})",
+        domain,
+        domain,
+        INGRESS_HTTP_NODE_PORT,
+        INGRESS_HTTPS_NODE_PORT
+    );
     Ok(())
 }
 
 /// Add the cluster-internal registry to Docker's insecure-registries list
 /// inside the Colima VM. Docker defaults to HTTPS for non-localhost registries;
 /// our in-cluster registry speaks plain HTTP.
-fn configure_docker_insecure_registry() -> Result<(), Box<dyn Error>> {
-    let config = run_cmd_output("colima", &["ssh", "--", "cat", "/etc/docker/daemon.json"])?;
+fn configure_docker_insecure_registry(timeouts: &Timeouts) -> Result<(), Box<dyn Error>> {
+    let config = vm_file::read_if_exists(vm_file::DOCKER_DAEMON_CONFIG)?.unwrap_or_else(|| {
+        log::info!("Creating {} in the VM", vm_file::DOCKER_DAEMON_CONFIG);
+        String::new()
+    });
+    let updated = match vm_file::with_insecure_registry(&config, REGISTRY_HOST) {
+        Ok(updated) => updated,
+        Err(err) => {
+            // Docker can't start with it as it is either; keep a copy for
+            // whoever edited it and start over.
+            let backup = format!("{}.hops-backup", vm_file::DOCKER_DAEMON_CONFIG);
+            log::warn!("{}; saving it as {} and writing a new one", err, backup);
+            vm_file::write(&backup, &config)?;
+            vm_file::with_insecure_registry("", REGISTRY_HOST)?
+        }
+    };
+    let Some(updated) = updated else {
+        return Ok(());
+    };
+
+    log::info!("Configuring Docker for insecure local registry...");
+    vm_file::write(vm_file::DOCKER_DAEMON_CONFIG, &updated)?;
+    restart_docker(timeouts)
+}
 
-    if config.contains("insecure-registries") {
+/// Trust the generated registry CA inside the Colima VM: per-registry for
+/// Docker, and in the system store for containerd and anything else.
+fn trust_registry_ca(timeouts: &Timeouts) -> Result<(), Box<dyn Error>> {
+    let ca = registry_tls::ca_pem()?;
+    let docker_ca = format!("/etc/docker/certs.d/{}/ca.crt", REGISTRY_HOST);
+    let trusted = run_cmd_output("colima", &["ssh", "--", "sudo", "cat", &docker_ca])
+        .is_ok_and(|current| current == ca);
+    if trusted {
         return Ok(());
     }
 
-    log::info!("Configuring Docker for insecure local registry...");
+    log::info!("Trusting local registry CA in the Colima VM...");
+    run_cmd(
+        "colima",
+        &[
+            "ssh",
+            "--",
+            "sudo",
+            "mkdir",
+            "-p",
+            &format!("/etc/docker/certs.d/{}", REGISTRY_HOST),
+        ],
+    )?;
+    vm_file::write(&docker_ca, &ca)?;
+    vm_file::write("/usr/local/share/ca-certificates/hops-registry.crt", &ca)?;
+    run_cmd("colima", &["ssh", "--", "sudo", "update-ca-certificates"])?;
+    restart_docker(timeouts)
+}
 
-    // Insert the insecure-registries key before the final closing brace.
-    let new_config = if let Some(pos) = config.rfind('}') {
-        let prefix = config[..pos].trim_end();
-        format!(
-            "{},\n  \"insecure-registries\": [\"{}\"]\n}}\n",
-            prefix, REGISTRY_HOST
-        )
-    } else {
-        return Err("Invalid daemon.json: no closing brace".into());
-    };
+/// Create crossplane-system and the CA ConfigMap Crossplane's Helm values
+/// point at, ahead of `helm install`.
+fn apply_registry_ca_bundle() -> Result<(), Box<dyn Error>> {
+    apply_namespace("crossplane-system")?;
+    kubectl_apply_stdin(&serde_yaml::to_string(&registry_tls::ca_configmap(
+        "crossplane-system",
+    )?)?)
+}
 
-    let mut child = Command::new("colima")
-        .args(["ssh", "--", "sudo", "tee", "/etc/docker/daemon.json"])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .stderr(Stdio::inherit())
-        .spawn()?;
-    if let Some(ref mut stdin) = child.stdin {
-        stdin.write_all(new_config.as_bytes())?;
-    }
-    let status = child.wait()?;
-    if !status.success() {
-        return Err("Failed to write Docker daemon.json".into());
-    }
+fn apply_namespace(name: &str) -> Result<(), Box<dyn Error>> {
+    let namespace = serde_json::json!({
+        "apiVersion": "v1",
+        "kind": "Namespace",
+        "metadata": {"name": name}
+    });
+    kubectl_apply_stdin(&serde_yaml::to_string(&namespace)?)
+}
 
+fn restart_docker(timeouts: &Timeouts) -> Result<(), Box<dyn Error>> {
     log::info!("Restarting Docker daemon...");
     run_cmd(
         "colima",
@@ -154,66 +798,145 @@ fn configure_docker_insecure_registry() -> Result<(), Box<dyn Error>> {
     )?;
 
     // Wait for Docker to come back.
-    for _ in 0..30 {
-        if run_cmd_output("docker", &["info"]).is_ok() {
-            // Docker restart can temporarily disrupt the Kubernetes API.
-            wait_for_kubernetes()?;
-            return Ok(());
-        }
-        thread::sleep(Duration::from_secs(2));
+    if !poll_until(timeouts.docker, || {
+        run_cmd_output("docker", &["info"]).is_ok()
+    }) {
+        return Err(timed_out("Docker to come back after restart", timeouts.docker).into());
     }
-    Err("Docker did not come back after restart".into())
+    // Docker restart can temporarily disrupt the Kubernetes API.
+    wait_for_kubernetes(timeouts.kubernetes)
 }
 
 /// Poll until the Kubernetes API server is reachable.
-fn wait_for_kubernetes() -> Result<(), Box<dyn Error>> {
+fn wait_for_kubernetes(timeout: Duration) -> Result<(), Box<dyn Error>> {
     log::info!("Waiting for Kubernetes API...");
-    for _ in 0..60 {
-        let result = run_cmd_output("kubectl", &["cluster-info"]);
-        if result.is_ok() {
-            return Ok(());
-        }
-        thread::sleep(Duration::from_secs(5));
-    }
-    Err("Timed out waiting for Kubernetes API".into())
-}
-
-/// Poll until a deployment's Available condition is True.
-fn wait_for_deployment(namespace: &str, name: &str) -> Result<(), Box<dyn Error>> {
-    for _ in 0..60 {
-        let output = run_cmd_output(
-            "kubectl",
-            &[
-                "get",
-                "deployment",
-                name,
-                "-n",
-                namespace,
-                "-o",
-                "jsonpath={.status.conditions[?(@.type==\"Available\")].status}",
-            ],
-        );
+    if poll_until(timeout, || {
+        run_cmd_output("kubectl", &["cluster-info"]).is_ok()
+    }) {
+        Ok(())
+    } else {
+        Err(timed_out("the Kubernetes API", timeout).into())
+    }
+}
 
-        if let Ok(status) = output {
-            if status.trim() == "True" {
-                return Ok(());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vm_resources_prefer_flags_then_settings_and_fit_the_host() {
+        let args = StartArgs {
+            cpu: Some(4),
+            ..StartArgs::default()
+        };
+        let settings = crate::settings::StartSettings {
+            memory: Some(8),
+            cpu: Some(6),
+            ..Default::default()
+        };
+        let host = HostCapacity {
+            cpus: Some(8),
+            memory_gib: Some(10),
+            free_disk_gib: Some(20),
+        };
+        let resources = VmResources::resolve(&args, &settings, &host);
+        assert_eq!(
+            resources,
+            VmResources {
+                cpu: 4,
+                memory_gib: 8,
+                disk_gib: DEFAULT_DISK_GIB
             }
-        }
+        );
+        let warnings = resources.validate(&host).unwrap();
+        assert_eq!(warnings.len(), 2);
 
-        thread::sleep(Duration::from_secs(5));
+        let too_many_cpus = VmResources {
+            cpu: 12,
+            ..resources
+        };
+        assert!(too_many_cpus.validate(&host).is_err());
+        let unknown_host = HostCapacity {
+            cpus: None,
+            memory_gib: None,
+            free_disk_gib: None,
+        };
+        assert!(too_many_cpus.validate(&unknown_host).unwrap().is_empty());
     }
-    Err(format!("Timed out waiting for deployment {}/{}", namespace, name).into())
-}
 
-/// Poll until a CRD exists in the cluster.
-fn wait_for_crd(crd: &str) -> Result<(), Box<dyn Error>> {
-    log::info!("Waiting for CRD {}...", crd);
-    for _ in 0..60 {
-        let result = run_cmd_output("kubectl", &["get", "crd", crd]);
-        if result.is_ok() {
-            return Ok(());
-        }
-        thread::sleep(Duration::from_secs(5));
+    #[test]
+    fn phases_follow_only_or_skip_flags_in_order() {
+        assert_eq!(StartArgs::default().phases(), StartPhase::ALL);
+        let args = StartArgs {
+            skip_vm: true,
+            skip_hosts: true,
+            ..StartArgs::default()
+        };
+        assert_eq!(
+            args.phases(),
+            [
+                StartPhase::Crossplane,
+                StartPhase::Providers,
+                StartPhase::Registry
+            ]
+        );
+        let args = StartArgs {
+            only: vec![StartPhase::Hosts, StartPhase::Registry],
+            ..StartArgs::default()
+        };
+        assert_eq!(args.phases(), [StartPhase::Registry, StartPhase::Hosts]);
+        assert_eq!(StartPhase::Crossplane.to_string(), "crossplane");
+    }
+
+    #[test]
+    fn start_state_records_phase_outcomes_in_order() {
+        let mut state = StartState::default();
+        assert_eq!(state.incomplete_phase(), Some(StartPhase::Vm));
+        state.mark(StartPhase::Crossplane, true);
+        state.mark(StartPhase::Vm, false);
+        assert_eq!(state.failed_phase, Some(StartPhase::Vm));
+        state.mark(StartPhase::Vm, true);
+        assert_eq!(state.failed_phase, None);
+        assert_eq!(state.incomplete_phase(), Some(StartPhase::Providers));
+        state.mark(StartPhase::Crossplane, false);
+        assert_eq!(state.completed_phases, [StartPhase::Vm]);
+        state.mark(StartPhase::Crossplane, true);
+
+        let json = serde_json::to_string(&state).unwrap();
+        assert!(json.contains(r#""completed_phases":["vm","crossplane"]"#));
+        assert_eq!(serde_json::from_str::<StartState>(&json).unwrap(), state);
+    }
+
+    #[test]
+    fn vm_resources_default_to_a_share_of_the_host() {
+        let args = StartArgs::default();
+        let settings = crate::settings::StartSettings::default();
+        let small = HostCapacity {
+            cpus: Some(4),
+            memory_gib: Some(8),
+            free_disk_gib: None,
+        };
+        let resources = VmResources::resolve(&args, &settings, &small);
+        assert_eq!((resources.cpu, resources.memory_gib), (2, 4));
+        assert!(resources.validate(&small).unwrap().is_empty());
+
+        let large = HostCapacity {
+            cpus: Some(24),
+            memory_gib: Some(128),
+            free_disk_gib: None,
+        };
+        let resources = VmResources::resolve(&args, &settings, &large);
+        assert_eq!((resources.cpu, resources.memory_gib), (8, 16));
+
+        let unknown = HostCapacity {
+            cpus: None,
+            memory_gib: None,
+            free_disk_gib: None,
+        };
+        let resources = VmResources::resolve(&args, &settings, &unknown);
+        assert_eq!(
+            (resources.cpu, resources.memory_gib),
+            (DEFAULT_CPU, DEFAULT_MEMORY_GIB)
+        );
     }
-    Err(format!("Timed out waiting for CRD {}", crd).into())
 }
@@ -0,0 +1,686 @@
+use super::start::StartState;
+use super::{command_exists, profile, registry, run_cmd_output, OutputFormat};
+use clap::Args;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::error::Error;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Args, Debug)]
+pub struct StatusArgs {
+    /// Show a live dashboard that refreshes until `q` is pressed
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Refresh interval for --watch in seconds
+    #[arg(long, requires = "watch", default_value = "5")]
+    pub interval: u64,
+
+    /// Output format
+    #[arg(long, short = 'o', value_enum, default_value_t = OutputFormat::Text, conflicts_with = "watch")]
+    pub output: OutputFormat,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+struct LocalStatus {
+    vm: VmStatus,
+    kubernetes: ComponentHealth,
+    crossplane: ComponentHealth,
+    providers: Vec<PackageHealth>,
+    configurations: Vec<PackageHealth>,
+    registry: RegistryStatus,
+    kubefwd_pids: Vec<String>,
+    /// Phase progress recorded by `hops local start`; `None` if it never ran.
+    start: Option<StartState>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+struct VmStatus {
+    state: String,
+    cpus: Option<u64>,
+    memory_gib: Option<u64>,
+    disk_gib: Option<u64>,
+    runtime: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+struct ComponentHealth {
+    available: bool,
+    detail: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+struct PackageHealth {
+    name: String,
+    package: String,
+    version: Option<String>,
+    installed: Option<bool>,
+    healthy: Option<bool>,
+    revision: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+struct RegistryStatus {
+    reachable: bool,
+    repositories: Vec<RegistryRepository>,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+struct RegistryRepository {
+    name: String,
+    tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ColimaListEntry {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    status: String,
+    cpus: Option<u64>,
+    memory: Option<u64>,
+    disk: Option<u64>,
+    runtime: Option<String>,
+}
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+pub fn run(args: &StatusArgs) -> Result<(), Box<dyn Error>> {
+    if args.watch {
+        return run_dashboard(Duration::from_secs(args.interval.max(1)));
+    }
+
+    let status = collect_status();
+    match args.output {
+        OutputFormat::Text => print_status(&status),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&status)?),
+    }
+    Ok(())
+}
+
+fn collect_status() -> LocalStatus {
+    let vm = collect_vm_status();
+    let kubernetes = if vm.state.eq_ignore_ascii_case("running") {
+        collect_api_health()
+    } else {
+        ComponentHealth {
+            available: false,
+            detail: "VM not running".to_string(),
+        }
+    };
+    let cluster_up = kubernetes.available;
+
+    LocalStatus {
+        crossplane: if cluster_up {
+            collect_deployment_health("crossplane-system", "crossplane")
+        } else {
+            ComponentHealth {
+                available: false,
+                detail: "Kubernetes API unreachable".to_string(),
+            }
+        },
+        providers: if cluster_up {
+            collect_packages("provider.pkg.crossplane.io")
+        } else {
+            Vec::new()
+        },
+        configurations: if cluster_up {
+            collect_packages("configuration.pkg.crossplane.io")
+        } else {
+            Vec::new()
+        },
+        registry: collect_registry_status(),
+        kubefwd_pids: collect_kubefwd_pids(),
+        start: StartState::load(),
+        kubernetes,
+        vm,
+    }
+}
+
+fn collect_vm_status() -> VmStatus {
+    if !command_exists("colima") {
+        return VmStatus {
+            state: "colima not installed".to_string(),
+            ..Default::default()
+        };
+    }
+
+    match run_cmd_output("colima", &["list", "--json"]) {
+        Ok(output) => parse_colima_list(&output, &profile::current()).unwrap_or(VmStatus {
+            state: "not created".to_string(),
+            ..Default::default()
+        }),
+        Err(err) => VmStatus {
+            state: format!("unknown ({})", first_line(&err.to_string())),
+            ..Default::default()
+        },
+    }
+}
+
+fn parse_colima_list(output: &str, profile: &str) -> Option<VmStatus> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<ColimaListEntry>(line.trim()).ok())
+        .find(|entry| entry.name == profile)
+        .map(|entry| VmStatus {
+            state: entry.status,
+            cpus: entry.cpus,
+            memory_gib: entry.memory.map(|bytes| bytes / GIB),
+            disk_gib: entry.disk.map(|bytes| bytes / GIB),
+            runtime: entry.runtime,
+        })
+}
+
+fn collect_api_health() -> ComponentHealth {
+    match run_cmd_output("kubectl", &["get", "--raw", "/readyz"]) {
+        Ok(raw) => ComponentHealth {
+            available: raw.trim() == "ok",
+            detail: format!("readyz: {}", first_line(&raw)),
+        },
+        Err(err) => ComponentHealth {
+            available: false,
+            detail: first_line(&err.to_string()),
+        },
+    }
+}
+
+fn collect_deployment_health(namespace: &str, name: &str) -> ComponentHealth {
+    let output = run_cmd_output(
+        "kubectl",
+        &[
+            "get",
+            "deployment",
+            name,
+            "-n",
+            namespace,
+            "-o",
+            "jsonpath={.status.readyReplicas}/{.status.replicas}|{.status.conditions[?(@.type==\"Available\")].status}",
+        ],
+    );
+
+    match output {
+        Ok(raw) => {
+            let (ready, available) = raw.trim().split_once('|').unwrap_or((raw.trim(), ""));
+            ComponentHealth {
+                available: available == "True",
+                detail: format!("{} ready", ready),
+            }
+        }
+        Err(err) => ComponentHealth {
+            available: false,
+            detail: first_line(&err.to_string()),
+        },
+    }
+}
+
+fn collect_packages(resource: &str) -> Vec<PackageHealth> {
+    run_cmd_output("kubectl", &["get", resource, "-o", "json"])
+        .ok()
+        .and_then(|raw| serde_json::from_str::<JsonValue>(&raw).ok())
+        .map(|list| parse_package_list(&list))
+        .unwrap_or_default()
+}
+
+fn parse_package_list(list: &JsonValue) -> Vec<PackageHealth> {
+    let mut packages: Vec<PackageHealth> = list
+        .get("items")
+        .and_then(JsonValue::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| {
+                    let package = item
+                        .pointer("/spec/package")
+                        .and_then(JsonValue::as_str)
+                        .unwrap_or_default();
+                    PackageHealth {
+                        name: item
+                            .pointer("/metadata/name")
+                            .and_then(JsonValue::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        version: package_version(package),
+                        package: package.to_string(),
+                        installed: condition_status(item, "Installed"),
+                        healthy: condition_status(item, "Healthy"),
+                        revision: item
+                            .pointer("/status/currentRevision")
+                            .and_then(JsonValue::as_str)
+                            .map(str::to_string),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    packages.sort_by(|a, b| a.name.cmp(&b.name));
+    packages
+}
+
+/// Tag or digest of a package reference, e.g. `v1.2.0` for
+/// `ghcr.io/org/pkg:v1.2.0`.
+fn package_version(package: &str) -> Option<String> {
+    if let Some((_, digest)) = package.split_once('@') {
+        return Some(digest.to_string());
+    }
+    let name = package.rsplit('/').next()?;
+    name.split_once(':').map(|(_, tag)| tag.to_string())
+}
+
+fn condition_status(item: &JsonValue, condition_type: &str) -> Option<bool> {
+    item.pointer("/status/conditions")
+        .and_then(JsonValue::as_array)?
+        .iter()
+        .find(|c| c.get("type").and_then(JsonValue::as_str) == Some(condition_type))
+        .and_then(|c| c.get("status").and_then(JsonValue::as_str))
+        .map(|status| status == "True")
+}
+
+fn collect_registry_status() -> RegistryStatus {
+    let Ok(repositories) = registry::catalog() else {
+        return RegistryStatus::default();
+    };
+
+    RegistryStatus {
+        reachable: true,
+        repositories: repositories
+            .into_iter()
+            .map(|name| RegistryRepository {
+                tags: registry::tags(&name).unwrap_or_default(),
+                name,
+            })
+            .collect(),
+    }
+}
+
+fn collect_kubefwd_pids() -> Vec<String> {
+    run_cmd_output("pgrep", &["-x", "kubefwd"])
+        .map(|out| out.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn first_line(text: &str) -> String {
+    text.lines().next().unwrap_or_default().trim().to_string()
+}
+
+fn health_label(value: Option<bool>) -> &'static str {
+    match value {
+        Some(true) => "True",
+        Some(false) => "False",
+        None => "Unknown",
+    }
+}
+
+fn vm_summary(vm: &VmStatus) -> String {
+    let mut summary = vm.state.clone();
+    if let (Some(cpus), Some(memory), Some(disk)) = (vm.cpus, vm.memory_gib, vm.disk_gib) {
+        summary.push_str(&format!(
+            " ({} CPU, {} GiB memory, {} GiB disk)",
+            cpus, memory, disk
+        ));
+    }
+    if let Some(runtime) = vm.runtime.as_deref().filter(|r| !r.is_empty()) {
+        summary.push_str(&format!(" [{}]", runtime));
+    }
+    summary
+}
+
+fn start_summary(state: Option<&StartState>) -> String {
+    let Some(state) = state else {
+        return "no record".to_string();
+    };
+    match (state.incomplete_phase(), state.failed_phase) {
+        (None, _) => "complete".to_string(),
+        (Some(_), Some(failed)) => format!(
+            "failed at the {} phase (run `hops local start --resume`)",
+            failed
+        ),
+        (Some(phase), None) => format!(
+            "incomplete from the {} phase (run `hops local start --resume`)",
+            phase
+        ),
+    }
+}
+
+fn kubefwd_summary(pids: &[String]) -> String {
+    if pids.is_empty() {
+        "not running".to_string()
+    } else {
+        format!("running (pid {})", pids.join(", "))
+    }
+}
+
+fn print_status(status: &LocalStatus) {
+    println!("VM:         {}", vm_summary(&status.vm));
+    println!("Start:      {}", start_summary(status.start.as_ref()));
+    println!(
+        "Kubernetes: {} ({})",
+        if status.kubernetes.available {
+            "Reachable"
+        } else {
+            "Unreachable"
+        },
+        status.kubernetes.detail
+    );
+    println!(
+        "Crossplane: {} ({})",
+        if status.crossplane.available {
+            "Available"
+        } else {
+            "Unavailable"
+        },
+        status.crossplane.detail
+    );
+
+    println!("Providers:");
+    print_packages(&status.providers);
+    println!("Configurations:");
+    print_packages(&status.configurations);
+
+    if status.registry.reachable {
+        println!(
+            "Registry:   reachable at {} ({} repositories)",
+            registry::push_host(),
+            status.registry.repositories.len()
+        );
+        for repo in &status.registry.repositories {
+            println!("  {} [{}]", repo.name, repo.tags.join(", "));
+        }
+    } else {
+        println!("Registry:   unreachable at {}", registry::push_host());
+    }
+
+    println!("kubefwd:    {}", kubefwd_summary(&status.kubefwd_pids));
+}
+
+fn print_packages(packages: &[PackageHealth]) {
+    if packages.is_empty() {
+        println!("  (none)");
+        return;
+    }
+    for pkg in packages {
+        println!(
+            "  {} version={} installed={} healthy={} revision={} package={}",
+            pkg.name,
+            pkg.version.as_deref().unwrap_or("-"),
+            health_label(pkg.installed),
+            health_label(pkg.healthy),
+            pkg.revision.as_deref().unwrap_or("-"),
+            pkg.package
+        );
+    }
+}
+
+fn run_dashboard(interval: Duration) -> Result<(), Box<dyn Error>> {
+    // Collection shells out to colima/kubectl and can take seconds, so it runs
+    // on a background thread and the UI only redraws from the latest snapshot.
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || loop {
+        if tx.send(collect_status()).is_err() {
+            return;
+        }
+        thread::sleep(interval);
+    });
+
+    let mut terminal = ratatui::init();
+    let result = dashboard_loop(&mut terminal, &rx);
+    ratatui::restore();
+    result
+}
+
+fn dashboard_loop(
+    terminal: &mut DefaultTerminal,
+    rx: &mpsc::Receiver<LocalStatus>,
+) -> Result<(), Box<dyn Error>> {
+    let mut latest: Option<(LocalStatus, Instant)> = None;
+
+    loop {
+        while let Ok(status) = rx.try_recv() {
+            latest = Some((status, Instant::now()));
+        }
+
+        terminal.draw(|frame| draw_dashboard(frame, latest.as_ref()))?;
+
+        if event::poll(Duration::from_millis(250))? {
+            if let Event::Key(key) = event::read()? {
+                let ctrl_c =
+                    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                if key.kind == KeyEventKind::Press
+                    && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c)
+                {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn draw_dashboard(frame: &mut Frame, latest: Option<&(LocalStatus, Instant)>) {
+    let Some((status, refreshed)) = latest else {
+        frame.render_widget(
+            Paragraph::new("Collecting status...").block(Block::bordered().title(" hops local ")),
+            frame.area(),
+        );
+        return;
+    };
+
+    let [header, packages, bottom, footer] = Layout::vertical([
+        Constraint::Length(7),
+        Constraint::Min(6),
+        Constraint::Min(4),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [providers_area, configurations_area] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .areas(packages);
+
+    draw_overview(frame, header, status);
+    draw_packages(frame, providers_area, " Providers ", &status.providers);
+    draw_packages(
+        frame,
+        configurations_area,
+        " Configurations ",
+        &status.configurations,
+    );
+    draw_registry(frame, bottom, &status.registry);
+
+    frame.render_widget(
+        Line::from(format!(
+            " q: quit · refreshed {}s ago",
+            refreshed.elapsed().as_secs()
+        ))
+        .dim(),
+        footer,
+    );
+}
+
+fn health_style(ok: bool) -> Style {
+    if ok {
+        Style::default().fg(Color::Green)
+    } else {
+        Style::default().fg(Color::Red)
+    }
+}
+
+fn draw_overview(frame: &mut Frame, area: Rect, status: &LocalStatus) {
+    let vm_running = status.vm.state.eq_ignore_ascii_case("running");
+    let lines = vec![
+        Line::from(vec![
+            "VM:         ".bold(),
+            Span::styled(vm_summary(&status.vm), health_style(vm_running)),
+        ]),
+        Line::from(vec![
+            "Start:      ".bold(),
+            Span::styled(
+                start_summary(status.start.as_ref()),
+                health_style(
+                    status
+                        .start
+                        .as_ref()
+                        .is_some_and(|state| state.incomplete_phase().is_none()),
+                ),
+            ),
+        ]),
+        Line::from(vec![
+            "Kubernetes: ".bold(),
+            Span::styled(
+                status.kubernetes.detail.clone(),
+                health_style(status.kubernetes.available),
+            ),
+        ]),
+        Line::from(vec![
+            "Crossplane: ".bold(),
+            Span::styled(
+                status.crossplane.detail.clone(),
+                health_style(status.crossplane.available),
+            ),
+        ]),
+        Line::from(vec![
+            "kubefwd:    ".bold(),
+            kubefwd_summary(&status.kubefwd_pids).into(),
+        ]),
+    ];
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(" hops local ")),
+        area,
+    );
+}
+
+fn draw_packages(frame: &mut Frame, area: Rect, title: &str, packages: &[PackageHealth]) {
+    let rows = packages.iter().map(|pkg| {
+        let healthy = pkg.healthy == Some(true);
+        Row::new(vec![
+            pkg.name.clone(),
+            pkg.version.clone().unwrap_or_else(|| "-".to_string()),
+            health_label(pkg.installed).to_string(),
+            health_label(pkg.healthy).to_string(),
+            pkg.revision.clone().unwrap_or_else(|| "-".to_string()),
+        ])
+        .style(health_style(healthy))
+    });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Percentage(30),
+            Constraint::Percentage(15),
+            Constraint::Length(9),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(vec!["NAME", "VERSION", "INSTALLED", "HEALTHY", "REVISION"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title(title.to_string()));
+    frame.render_widget(table, area);
+}
+
+fn draw_registry(frame: &mut Frame, area: Rect, registry_status: &RegistryStatus) {
+    let title = if registry_status.reachable {
+        format!(
+            " Registry {} ({} repositories) ",
+            registry::push_host(),
+            registry_status.repositories.len()
+        )
+    } else {
+        format!(" Registry {} (unreachable) ", registry::push_host())
+    };
+    let rows = registry_status
+        .repositories
+        .iter()
+        .map(|repo| Row::new(vec![repo.name.clone(), repo.tags.join(", ")]));
+    let table = Table::new(rows, [Constraint::Percentage(50), Constraint::Fill(1)])
+        .header(
+            Row::new(vec!["REPOSITORY", "TAGS"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .block(Block::bordered().title(title));
+    frame.render_widget(table, area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::local::start::StartPhase;
+    use serde_json::json;
+
+    #[test]
+    fn parse_colima_list_selects_profile() {
+        let output = r#"{"name":"other","status":"Stopped"}
+{"name":"default","status":"Running","arch":"aarch64","cpus":8,"memory":17179869184,"disk":64424509440,"runtime":"docker+k3s"}
+"#;
+        let vm = parse_colima_list(output, "default").expect("default profile");
+        assert_eq!(vm.state, "Running");
+        assert_eq!(vm.cpus, Some(8));
+        assert_eq!(vm.memory_gib, Some(16));
+        assert_eq!(vm.disk_gib, Some(60));
+        assert!(parse_colima_list(output, "hops").is_none());
+    }
+
+    #[test]
+    fn start_summary_points_at_resume() {
+        assert_eq!(start_summary(None), "no record");
+        let state = StartState {
+            completed_phases: vec![StartPhase::Vm, StartPhase::Crossplane],
+            failed_phase: Some(StartPhase::Providers),
+        };
+        assert_eq!(
+            start_summary(Some(&state)),
+            "failed at the providers phase (run `hops local start --resume`)"
+        );
+    }
+
+    #[test]
+    fn parse_package_list_reads_conditions_and_revision() {
+        let list = json!({
+            "items": [
+                {
+                    "metadata": {"name": "hops-ops-test"},
+                    "spec": {"package": "ghcr.io/hops-ops/test:v1"},
+                    "status": {
+                        "currentRevision": "hops-ops-test-abc123",
+                        "conditions": [
+                            {"type": "Installed", "status": "True"},
+                            {"type": "Healthy", "status": "False"}
+                        ]
+                    }
+                },
+                {"metadata": {"name": "a-pending"}, "spec": {"package": "x"}}
+            ]
+        });
+
+        let packages = parse_package_list(&list);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(packages[0].name, "a-pending");
+        assert_eq!(packages[0].healthy, None);
+        assert_eq!(packages[1].installed, Some(true));
+        assert_eq!(packages[1].healthy, Some(false));
+        assert_eq!(
+            packages[1].revision.as_deref(),
+            Some("hops-ops-test-abc123")
+        );
+        assert_eq!(packages[1].version.as_deref(), Some("v1"));
+    }
+
+    #[test]
+    fn package_version_reads_tag_or_digest() {
+        assert_eq!(
+            package_version("ghcr.io/hops-ops/test:v1.2.0").as_deref(),
+            Some("v1.2.0")
+        );
+        assert_eq!(
+            package_version("localhost:5000/hops-ops/test@sha256:abc").as_deref(),
+            Some("sha256:abc")
+        );
+        assert_eq!(package_version("localhost:5000/hops-ops/test"), None);
+    }
+}
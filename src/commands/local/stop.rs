@@ -1,9 +1,28 @@
-use super::run_cmd;
+use super::{forward, idle, kubefwd, profile, registry_forward, run_cmd};
 use std::error::Error;
 
 pub fn run() -> Result<(), Box<dyn Error>> {
+    profile::ensure_owned("local stop")?;
+    stop_forwarding();
+    if let Err(err) = idle::stop_watchdog() {
+        log::warn!("Failed to stop the idle watchdog: {}", err);
+    }
     log::info!("Stopping Colima...");
     run_cmd("colima", &["stop"])?;
     log::info!("Colima stopped");
     Ok(())
 }
+
+/// Stop kubefwd sessions and forwards hops started; they only produce errors
+/// once the cluster is gone. Failures are logged, not fatal.
+pub(super) fn stop_forwarding() {
+    if let Err(err) = kubefwd::stop_all() {
+        log::warn!("Failed to stop kubefwd: {}", err);
+    }
+    if let Err(err) = forward::stop() {
+        log::warn!("Failed to stop forwards: {}", err);
+    }
+    if let Err(err) = registry_forward::stop() {
+        log::warn!("Failed to stop the registry port-forward: {}", err);
+    }
+}
@@ -0,0 +1,147 @@
+use super::start::StartState;
+use super::{mock, registry, registry_forward, registry_host, run_cmd, run_cmd_output, vm_file};
+use crate::settings::RegistryMode;
+use clap::Args;
+use std::error::Error;
+use std::time::Duration;
+
+const NAMESPACE: &str = "crossplane-system";
+const REGISTRY_HOSTNAME: &str = "registry.crossplane-system.svc.cluster.local";
+
+/// Crossplane objects removed while Crossplane is still running, so its
+/// finalizers clean up what they own: XRDs take their XRs, claims, and
+/// CRDs with them, packages their revisions and CRDs.
+const CROSSPLANE_RESOURCES: &[&str] = &[
+    "compositeresourcedefinitions.apiextensions.crossplane.io",
+    "compositions.apiextensions.crossplane.io",
+    "configurations.pkg.crossplane.io",
+    "providers.pkg.crossplane.io",
+    "functions.pkg.crossplane.io",
+    "deploymentruntimeconfigs.pkg.crossplane.io",
+    "imageconfigs.pkg.crossplane.io",
+];
+
+#[derive(Args, Debug)]
+pub struct TeardownArgs {
+    /// How long each deletion may take, e.g. 10m (default: `timeouts.teardown`
+    /// in ~/.hops/config.yaml, else 5m)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
+}
+
+/// Remove Crossplane, its packages, the registry, and everything hops
+/// applied, leaving Colima and Kubernetes running.
+pub fn run(args: &TeardownArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local teardown")?;
+    let timeout = crate::settings::timeouts(args.timeout)?.teardown;
+    let timeout = format!("--timeout={}s", timeout.as_secs().max(1));
+    if !crate::interactive::confirm(
+        "Remove Crossplane, all XRs and packages, and the local registry from the cluster?",
+        false,
+    )? {
+        return Err("aborted".into());
+    }
+
+    for resource in CROSSPLANE_RESOURCES {
+        delete_all(resource, &timeout)?;
+    }
+
+    log::info!("Uninstalling the Crossplane Helm release...");
+    let releases = run_cmd_output("helm", &["list", "-n", NAMESPACE, "-q"]).unwrap_or_default();
+    if releases
+        .lines()
+        .any(|release| release.trim() == "crossplane")
+    {
+        run_cmd(
+            "helm",
+            &[
+                "uninstall",
+                "crossplane",
+                "-n",
+                NAMESPACE,
+                "--wait",
+                &timeout,
+            ],
+        )?;
+    }
+    // Helm leaves the chart's CRDs behind.
+    let crds = run_cmd_output("kubectl", &["get", "crd", "-o", "name"])?;
+    let crossplane_crds = crossplane_crds(&crds);
+    if !crossplane_crds.is_empty() {
+        log::info!("Deleting {} Crossplane CRDs...", crossplane_crds.len());
+        let mut args = vec!["delete", "--ignore-not-found", &timeout];
+        args.extend(crossplane_crds);
+        run_cmd("kubectl", &args)?;
+    }
+
+    // The registry, provider credentials, and TLS/auth Secrets all live here.
+    log::info!("Deleting namespace {}...", NAMESPACE);
+    run_cmd(
+        "kubectl",
+        &[
+            "delete",
+            "namespace",
+            NAMESPACE,
+            "--ignore-not-found",
+            &timeout,
+        ],
+    )?;
+    registry_forward::stop()?;
+    if registry::mode() == RegistryMode::Host {
+        // Keeps the pushed packages in ~/.hops/local/registry-data.
+        let _ = run_cmd_output("docker", &["rm", "-f", registry_host::CONTAINER]);
+    }
+    let hosts = vm_file::read(vm_file::HOSTS_FILE)?;
+    let updated = vm_file::without_hosts_entry(&hosts, REGISTRY_HOSTNAME);
+    if updated != hosts {
+        vm_file::write(vm_file::HOSTS_FILE, &updated)?;
+    }
+
+    StartState::reset_cluster()?;
+    mock::forget()?;
+    crate::logging::success(
+        "Crossplane removed; run `hops local start --resume` to bootstrap it again",
+    );
+    Ok(())
+}
+
+/// Crossplane's own CRDs in `kubectl get crd -o name` output.
+fn crossplane_crds(output: &str) -> Vec<&str> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|crd| crd.ends_with(".crossplane.io"))
+        .collect()
+}
+
+/// `kubectl delete <resource> --all`, skipping types that aren't installed.
+fn delete_all(resource: &str, timeout: &str) -> Result<(), Box<dyn Error>> {
+    if run_cmd_output("kubectl", &["get", "crd", resource]).is_err() {
+        return Ok(());
+    }
+    log::info!("Deleting all {}...", resource);
+    run_cmd(
+        "kubectl",
+        &["delete", resource, "--all", "--all-namespaces", timeout],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossplane_crds_keeps_only_crossplane_groups() {
+        let output = "customresourcedefinition.apiextensions.k8s.io/providers.pkg.crossplane.io
+customresourcedefinition.apiextensions.k8s.io/certificates.cert-manager.io
+customresourcedefinition.apiextensions.k8s.io/usages.protection.crossplane.io
+";
+        assert_eq!(
+            crossplane_crds(output),
+            [
+                "customresourcedefinition.apiextensions.k8s.io/providers.pkg.crossplane.io",
+                "customresourcedefinition.apiextensions.k8s.io/usages.protection.crossplane.io"
+            ]
+        );
+    }
+}
@@ -0,0 +1,331 @@
+use super::conditions::get_resource_json;
+use clap::Args;
+use colored::Colorize;
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+use std::error::Error;
+
+/// Guard against pathological compositions (or reference cycles).
+const MAX_DEPTH: usize = 16;
+
+#[derive(Args, Debug)]
+pub struct TraceArgs {
+    /// XR or claim to trace, as <kind>/<name> (kind may include the group,
+    /// e.g. network.aws.hops.ops.com.ai/my-net)
+    pub target: String,
+
+    /// Namespace of the XR or claim
+    #[arg(long, short = 'n')]
+    pub namespace: Option<String>,
+}
+
+/// Reference to a Kubernetes object as found in `resourceRefs`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ObjectRef {
+    pub api_version: String,
+    pub kind: String,
+    pub name: String,
+    pub namespace: Option<String>,
+}
+
+impl ObjectRef {
+    /// `kind.version.group` form accepted by kubectl for any resource.
+    pub fn kubectl_resource(&self) -> String {
+        match self.api_version.split_once('/') {
+            Some((group, version)) => {
+                format!("{}.{}.{}", self.kind.to_ascii_lowercase(), version, group)
+            }
+            None => self.kind.to_ascii_lowercase(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TraceNode {
+    label: String,
+    synced: String,
+    ready: String,
+    status: String,
+    children: Vec<TraceNode>,
+}
+
+pub fn run(args: &TraceArgs) -> Result<(), Box<dyn Error>> {
+    let (kind, name) = args
+        .target
+        .split_once('/')
+        .filter(|(kind, name)| !kind.trim().is_empty() && !name.trim().is_empty())
+        .ok_or_else(|| format!("expected <kind>/<name>, got '{}'", args.target))?;
+
+    let root = get_resource_json(kind.trim(), name.trim(), args.namespace.as_deref())
+        .ok_or_else(|| format!("could not get {}", args.target))?;
+    let mut visited = HashSet::new();
+    let tree = build_node(&root, args.namespace.as_deref(), &mut visited, 0);
+
+    println!(
+        "{}",
+        format!("{:<60} {:<7} {:<7} {}", "NAME", "SYNCED", "READY", "STATUS").bold()
+    );
+    print_node(&tree, "", "");
+    Ok(())
+}
+
+fn build_node(
+    object: &JsonValue,
+    parent_namespace: Option<&str>,
+    visited: &mut HashSet<ObjectRef>,
+    depth: usize,
+) -> TraceNode {
+    let namespace = object
+        .pointer("/metadata/namespace")
+        .and_then(JsonValue::as_str)
+        .or(parent_namespace)
+        .map(str::to_string);
+    let (synced, synced_message) = condition(object, "Synced");
+    let (ready, ready_message) = condition(object, "Ready");
+    let status = if !synced_message.is_empty() && synced != "True" {
+        synced_message
+    } else {
+        ready_message
+    };
+
+    let mut children = Vec::new();
+    if depth < MAX_DEPTH {
+        for child_ref in child_refs(object, namespace.as_deref()) {
+            if !visited.insert(child_ref.clone()) {
+                continue;
+            }
+            let child = match get_resource_json(
+                &child_ref.kubectl_resource(),
+                &child_ref.name,
+                child_ref.namespace.as_deref(),
+            ) {
+                Some(child) => {
+                    build_node(&child, child_ref.namespace.as_deref(), visited, depth + 1)
+                }
+                None => TraceNode {
+                    label: format!("{}/{}", child_ref.kind, child_ref.name),
+                    synced: "-".to_string(),
+                    ready: "-".to_string(),
+                    status: "not found".to_string(),
+                    children: Vec::new(),
+                },
+            };
+            children.push(child);
+        }
+    }
+
+    let kind = object
+        .get("kind")
+        .and_then(JsonValue::as_str)
+        .unwrap_or("?");
+    let name = object
+        .pointer("/metadata/name")
+        .and_then(JsonValue::as_str)
+        .unwrap_or("?");
+    TraceNode {
+        label: match &namespace {
+            Some(ns) if object.pointer("/metadata/namespace").is_some() => {
+                format!("{}/{} ({})", kind, name, ns)
+            }
+            _ => format!("{}/{}", kind, name),
+        },
+        synced,
+        ready,
+        status,
+        children,
+    }
+}
+
+/// Every object composed (transitively) under an XR or claim.
+pub fn composed_objects(object: &JsonValue) -> Vec<ObjectRef> {
+    composed_resources(object)
+        .into_iter()
+        .map(|(object_ref, _)| object_ref)
+        .collect()
+}
+
+/// Like [`composed_objects`], with each object as fetched while walking the
+/// tree (`None` when it could not be read).
+pub fn composed_resources(object: &JsonValue) -> Vec<(ObjectRef, Option<JsonValue>)> {
+    let mut visited = HashSet::new();
+    let mut found = Vec::new();
+    collect_composed(object, None, &mut visited, &mut found, 0);
+    found
+}
+
+fn collect_composed(
+    object: &JsonValue,
+    parent_namespace: Option<&str>,
+    visited: &mut HashSet<ObjectRef>,
+    found: &mut Vec<(ObjectRef, Option<JsonValue>)>,
+    depth: usize,
+) {
+    if depth >= MAX_DEPTH {
+        return;
+    }
+    let namespace = object
+        .pointer("/metadata/namespace")
+        .and_then(JsonValue::as_str)
+        .or(parent_namespace);
+    for child_ref in child_refs(object, namespace) {
+        if !visited.insert(child_ref.clone()) {
+            continue;
+        }
+        let child = get_resource_json(
+            &child_ref.kubectl_resource(),
+            &child_ref.name,
+            child_ref.namespace.as_deref(),
+        );
+        if let Some(child) = &child {
+            collect_composed(
+                child,
+                child_ref.namespace.as_deref(),
+                visited,
+                found,
+                depth + 1,
+            );
+        }
+        found.push((child_ref, child));
+    }
+}
+
+/// Children of an XR or claim: composed resources from `resourceRefs`
+/// (Crossplane v1 `spec.resourceRefs`, v2 `spec.crossplane.resourceRefs`)
+/// and the XR bound to a claim (`spec.resourceRef`).
+fn child_refs(object: &JsonValue, namespace: Option<&str>) -> Vec<ObjectRef> {
+    let mut refs: Vec<&JsonValue> = Vec::new();
+    for pointer in ["/spec/resourceRefs", "/spec/crossplane/resourceRefs"] {
+        if let Some(items) = object.pointer(pointer).and_then(JsonValue::as_array) {
+            refs.extend(items);
+        }
+    }
+    if let Some(resource_ref) = object.pointer("/spec/resourceRef") {
+        refs.push(resource_ref);
+    }
+
+    refs.into_iter()
+        .filter_map(|r| {
+            let field = |key: &str| r.get(key).and_then(JsonValue::as_str).map(str::to_string);
+            Some(ObjectRef {
+                api_version: field("apiVersion")?,
+                kind: field("kind")?,
+                name: field("name")?,
+                namespace: field("namespace").or_else(|| namespace.map(str::to_string)),
+            })
+        })
+        .collect()
+}
+
+/// Status and message of a condition, or "-" when it is not reported.
+fn condition(object: &JsonValue, condition_type: &str) -> (String, String) {
+    object
+        .pointer("/status/conditions")
+        .and_then(JsonValue::as_array)
+        .and_then(|conditions| {
+            conditions
+                .iter()
+                .find(|c| c.get("type").and_then(JsonValue::as_str) == Some(condition_type))
+        })
+        .map(|c| {
+            let field = |key: &str| {
+                c.get(key)
+                    .and_then(JsonValue::as_str)
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            let message = match (field("reason"), field("message")) {
+                (reason, message) if message.is_empty() => reason,
+                (reason, message) => format!("{}: {}", reason, message),
+            };
+            (field("status"), message)
+        })
+        .unwrap_or_else(|| ("-".to_string(), String::new()))
+}
+
+fn print_node(node: &TraceNode, prefix: &str, child_prefix: &str) {
+    // Pad before coloring so escape codes don't break column alignment.
+    let colorize = |value: &str| {
+        let padded = format!("{:<7}", value);
+        match value {
+            "True" => padded.green(),
+            "False" => padded.red(),
+            _ => padded.normal(),
+        }
+    };
+    println!(
+        "{:<60} {} {} {}",
+        format!("{}{}", prefix, node.label),
+        colorize(&node.synced),
+        colorize(&node.ready),
+        first_line(&node.status)
+    );
+    for (index, child) in node.children.iter().enumerate() {
+        let last = index + 1 == node.children.len();
+        let (branch, continuation) = if last {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+        print_node(
+            child,
+            &format!("{}{}", child_prefix, branch),
+            &format!("{}{}", child_prefix, continuation),
+        );
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn child_refs_reads_v1_v2_and_claim_refs() {
+        let xr = json!({
+            "metadata": {"name": "net", "namespace": "team-a"},
+            "spec": {
+                "crossplane": {
+                    "resourceRefs": [
+                        {"apiVersion": "ec2.aws.m.upbound.io/v1beta1", "kind": "VPC", "name": "net-vpc"}
+                    ]
+                },
+                "resourceRefs": [
+                    {"apiVersion": "v1", "kind": "ConfigMap", "name": "cm", "namespace": "other"}
+                ],
+                "resourceRef": {"apiVersion": "aws.hops.ops.com.ai/v1alpha1", "kind": "XNetwork", "name": "net-x"}
+            }
+        });
+
+        let refs = child_refs(&xr, Some("team-a"));
+        assert_eq!(refs.len(), 3);
+        assert_eq!(refs[0].kubectl_resource(), "configmap");
+        assert_eq!(refs[0].namespace.as_deref(), Some("other"));
+        assert_eq!(
+            refs[1].kubectl_resource(),
+            "vpc.v1beta1.ec2.aws.m.upbound.io"
+        );
+        assert_eq!(refs[1].namespace.as_deref(), Some("team-a"));
+        assert_eq!(refs[2].kind, "XNetwork");
+    }
+
+    #[test]
+    fn condition_combines_reason_and_message() {
+        let object = json!({"status": {"conditions": [
+            {"type": "Ready", "status": "False", "reason": "Creating", "message": "waiting for VPC"},
+            {"type": "Synced", "status": "True", "reason": "ReconcileSuccess"}
+        ]}});
+        assert_eq!(
+            condition(&object, "Ready"),
+            ("False".to_string(), "Creating: waiting for VPC".to_string())
+        );
+        assert_eq!(
+            condition(&object, "Synced"),
+            ("True".to_string(), "ReconcileSuccess".to_string())
+        );
+        assert_eq!(condition(&object, "Healthy").0, "-");
+    }
+}
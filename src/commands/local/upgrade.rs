@@ -0,0 +1,152 @@
+use super::conditions::{pending_conditions, wait_until, PendingCondition};
+use super::{registry_tls, run_cmd_output};
+use clap::Args;
+use serde_json::Value as JsonValue;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::time::Duration;
+
+const PACKAGE_RESOURCES: &[&str] = &[
+    "providers.pkg.crossplane.io",
+    "functions.pkg.crossplane.io",
+    "configurations.pkg.crossplane.io",
+];
+const PACKAGE_CONDITIONS: &[&str] = &["Installed", "Healthy"];
+
+#[derive(Args, Debug)]
+pub struct UpgradeArgs {
+    /// Crossplane chart version to upgrade to (default: the latest in
+    /// charts.crossplane.io/stable)
+    #[arg(long)]
+    pub version: Option<String>,
+
+    /// How long to wait for the Crossplane rollout and then for packages to
+    /// become healthy, e.g. 10m (default: `timeouts.crossplane` and
+    /// `timeouts.providers` in ~/.hops/config.yaml, else 5m)
+    #[arg(long, value_parser = humantime::parse_duration)]
+    pub timeout: Option<Duration>,
+}
+
+pub fn run(args: &UpgradeArgs) -> Result<(), Box<dyn Error>> {
+    super::context::ensure_local("local upgrade")?;
+    let timeouts = crate::settings::timeouts(args.timeout)?;
+    let current =
+        installed_version()?.ok_or("Crossplane is not installed; run `hops local start` first")?;
+    let target = match &args.version {
+        Some(version) => version.trim_start_matches('v').to_string(),
+        None => super::charts::latest_crossplane_version()?,
+    };
+    match compare_versions(&target, &current) {
+        Ordering::Equal => {
+            crate::logging::success(&format!("Crossplane is already at {}", current));
+            return Ok(());
+        }
+        Ordering::Less => {
+            return Err(format!(
+                "{} is older than the installed Crossplane {}; Crossplane doesn't support downgrades (use `hops local snapshot restore` or `destroy`/`start`)",
+                target, current
+            )
+            .into())
+        }
+        Ordering::Greater => {}
+    }
+
+    log::info!("Upgrading Crossplane from {} to {}...", current, target);
+    super::start::install_crossplane(
+        registry_tls::enabled(),
+        Some(&target),
+        false,
+        timeouts.crossplane,
+    )?;
+
+    crate::logging::set_step("package-health");
+    log::info!("Checking package health...");
+    wait_until(timeouts.providers, pending_packages).map_err(|pending| {
+        let mut lines = vec![format!(
+            "Crossplane is at {}, but packages are unhealthy after {}:",
+            target,
+            humantime::format_duration(timeouts.providers)
+        )];
+        lines.extend(pending.iter().map(|condition| format!("  {}", condition)));
+        lines.join("\n")
+    })?;
+
+    crate::logging::success(&format!(
+        "Crossplane upgraded from {} to {}",
+        current, target
+    ));
+    Ok(())
+}
+
+/// App version of the `crossplane` Helm release, if installed.
+fn installed_version() -> Result<Option<String>, Box<dyn Error>> {
+    let output = run_cmd_output(
+        "helm",
+        &[
+            "list",
+            "-n",
+            "crossplane-system",
+            "--filter",
+            "^crossplane$",
+            "-o",
+            "json",
+        ],
+    )?;
+    let releases: JsonValue = serde_json::from_str(&output)?;
+    Ok(releases
+        .as_array()
+        .and_then(|releases| releases.first())
+        .and_then(|release| release["app_version"].as_str())
+        .map(|version| version.trim_start_matches('v').to_string()))
+}
+
+/// Compare dotted release versions numerically; pre-release suffixes
+/// (`-rc.1`) are ignored.
+pub(super) fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<u64> {
+        version
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    };
+    parts(a).cmp(&parts(b))
+}
+
+fn pending_packages() -> Vec<PendingCondition> {
+    let mut pending = Vec::new();
+    for resource in PACKAGE_RESOURCES {
+        let list = run_cmd_output("kubectl", &["get", resource, "-o", "json"])
+            .ok()
+            .and_then(|raw| serde_json::from_str::<JsonValue>(&raw).ok());
+        let Some(items) = list.as_ref().and_then(|list| list["items"].as_array()) else {
+            pending.push(PendingCondition::not_found(resource));
+            continue;
+        };
+        for item in items {
+            let kind = resource.split('.').next().unwrap_or(resource);
+            let label = format!(
+                "{}/{}",
+                kind.trim_end_matches('s'),
+                item["metadata"]["name"].as_str().unwrap_or_default()
+            );
+            pending.extend(pending_conditions(&label, item, PACKAGE_CONDITIONS));
+        }
+    }
+    pending
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_versions_is_numeric() {
+        assert_eq!(compare_versions("1.20.0", "1.9.3"), Ordering::Greater);
+        assert_eq!(compare_versions("1.17.1", "1.17.1"), Ordering::Equal);
+        assert_eq!(compare_versions("1.17.0-rc.1", "1.17.1"), Ordering::Less);
+        assert_eq!(compare_versions("2.0", "1.20.1"), Ordering::Greater);
+    }
+}
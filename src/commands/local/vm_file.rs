@@ -0,0 +1,254 @@
+use super::{colima_command, run_cmd_output};
+use serde_json::{json, Value as JsonValue};
+use std::error::Error;
+use std::io::Write;
+use std::process::Stdio;
+
+pub const HOSTS_FILE: &str = "/etc/hosts";
+pub const DOCKER_DAEMON_CONFIG: &str = "/etc/docker/daemon.json";
+
+/// Writes stdin to a temp file next to the target, with the target's mode,
+/// then renames it over the target, so readers (and a hops run interrupted
+/// halfway) only ever see the old or the new file.
+const ATOMIC_WRITE_SCRIPT: &str = r#"set -e
+mkdir -p "$(dirname "$1")"
+tmp=$(mktemp "$1.hops.XXXXXX")
+trap 'rm -f "$tmp"' EXIT
+cat > "$tmp"
+if [ -e "$1" ]; then chmod --reference="$1" "$tmp"; else chmod 644 "$tmp"; fi
+mv -f "$tmp" "$1""#;
+
+/// Read `path` inside the Colima VM as root.
+pub fn read(path: &str) -> Result<String, Box<dyn Error>> {
+    run_cmd_output("colima", &["ssh", "--", "sudo", "cat", path])
+}
+
+/// Prints a marker line before the file, so a missing file (no output) can't
+/// be mistaken for an empty one or for a failed `colima ssh`.
+const READ_IF_EXISTS_SCRIPT: &str = r#"if [ -e "$1" ]; then echo present; cat "$1"; fi"#;
+
+/// Read `path` inside the Colima VM as root, or `None` when it doesn't exist.
+pub fn read_if_exists(path: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let output = run_cmd_output(
+        "colima",
+        &[
+            "ssh",
+            "--",
+            "sudo",
+            "sh",
+            "-c",
+            READ_IF_EXISTS_SCRIPT,
+            "sh",
+            path,
+        ],
+    )?;
+    Ok(output
+        .strip_prefix("present\n")
+        .or_else(|| output.strip_prefix("present\r\n"))
+        .map(str::to_string))
+}
+
+/// Replace `path` inside the Colima VM with `contents` as root, atomically.
+pub fn write(path: &str, contents: &str) -> Result<(), Box<dyn Error>> {
+    let mut child = colima_command(&[
+        "ssh",
+        "--",
+        "sudo",
+        "sh",
+        "-c",
+        ATOMIC_WRITE_SCRIPT,
+        "sh",
+        path,
+    ])
+    .stdin(Stdio::piped())
+    .stdout(Stdio::null())
+    .stderr(Stdio::inherit())
+    .spawn()?;
+    let _guard = crate::interrupt::child(child.id());
+    if let Some(ref mut stdin) = child.stdin {
+        stdin.write_all(contents.as_bytes())?;
+    }
+    // Close stdin so `cat` sees EOF.
+    drop(child.stdin.take());
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("Failed to write {} in the Colima VM", path).into());
+    }
+    Ok(())
+}
+
+/// `hosts` with `hostname` mapped to `address` alone: the name is taken off
+/// any other line (dropping lines left without names) and appended, while
+/// comments and unrelated entries stay as they are. `None` when it already
+/// resolves only to `address`.
+pub fn with_hosts_entry(hosts: &str, hostname: &str, address: &str) -> Option<String> {
+    let current: Vec<&str> = hosts
+        .lines()
+        .filter_map(|line| {
+            let (address, names) = parse_hosts_line(line)?;
+            names.contains(&hostname).then_some(address)
+        })
+        .collect();
+    if current == [address] {
+        return None;
+    }
+    let mut out = without_hosts_entry(hosts, hostname);
+    out.push_str(&format!("{} {}\n", address, hostname));
+    Some(out)
+}
+
+/// `hosts` with `hostname` taken off every line. Only that name (and the
+/// whitespace before it) is removed; everything else, comments and
+/// alignment included, is kept byte-for-byte.
+pub fn without_hosts_entry(hosts: &str, hostname: &str) -> String {
+    let mut out: String = hosts
+        .split_inclusive('\n')
+        .map(|line| without_hostname(line, hostname))
+        .collect();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// `line` without its `hostname` names, each taken out with the whitespace
+/// after it (before it, for the last one); empty when that leaves the entry
+/// without names.
+fn without_hostname(line: &str, hostname: &str) -> String {
+    let (entry, comment) = line.split_at(line.find('#').unwrap_or(line.len()));
+    // Byte ranges of the entry's tokens; the first is the address.
+    let mut tokens = Vec::new();
+    for token in entry.split_whitespace() {
+        let from = tokens.last().map_or(0, |&(_, end)| end);
+        let start = from + entry[from..].find(token).unwrap_or_default();
+        tokens.push((start, start + token.len()));
+    }
+    let is_hostname = |&(start, end): &(usize, usize)| &entry[start..end] == hostname;
+    if !tokens.iter().skip(1).any(is_hostname) {
+        return line.to_string();
+    }
+    if tokens.iter().skip(1).all(is_hostname) {
+        return String::new();
+    }
+    let mut out = String::new();
+    let mut copied = 0;
+    for (index, token) in tokens.iter().enumerate().skip(1) {
+        if !is_hostname(token) {
+            continue;
+        }
+        let (start, end) = match tokens.get(index + 1) {
+            Some(&(next, _)) => (token.0, next),
+            None => (tokens[index - 1].1, token.1),
+        };
+        out.push_str(&entry[copied..start.max(copied)]);
+        copied = copied.max(end);
+    }
+    out.push_str(&entry[copied..]);
+    out.push_str(comment);
+    out
+}
+
+/// The address and names of an entry line; `None` for comments and blanks.
+fn parse_hosts_line(line: &str) -> Option<(&str, Vec<&str>)> {
+    let entry = line.split('#').next().unwrap_or_default();
+    let mut fields = entry.split_whitespace();
+    let address = fields.next()?;
+    Some((address, fields.collect()))
+}
+
+/// Docker's daemon.json with `registry` added to `insecure-registries`,
+/// keeping every other setting; `None` when it's already there. An empty
+/// file counts as `{}`; JSON that isn't an object is an error.
+pub fn with_insecure_registry(
+    config: &str,
+    registry: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let mut config: JsonValue = if config.trim().is_empty() {
+        json!({})
+    } else {
+        serde_json::from_str(config).map_err(|err| format!("Invalid daemon.json: {}", err))?
+    };
+    let settings = config
+        .as_object_mut()
+        .ok_or("Invalid daemon.json: not a JSON object")?;
+    let registries = settings
+        .entry("insecure-registries")
+        .or_insert_with(|| json!([]));
+    // Docker only accepts a list, but a lone string is easy to write by hand.
+    if let Some(single) = registries.as_str() {
+        *registries = json!([single]);
+    }
+    let registries = registries
+        .as_array_mut()
+        .ok_or("Invalid daemon.json: insecure-registries is not a list")?;
+    if registries.iter().any(|r| r == registry) {
+        return Ok(None);
+    }
+    registries.push(json!(registry));
+    Ok(Some(serde_json::to_string_pretty(&config)? + "\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_keep_unrelated_lines_and_settings() {
+        let host = "registry.crossplane-system.svc.cluster.local";
+        let hosts = format!(
+            "127.0.0.1 localhost\n# {} used to be here\n10.43.0.5 {} old-alias\n",
+            host, host
+        );
+        let updated = with_hosts_entry(&hosts, host, "10.43.0.9").unwrap();
+        assert_eq!(
+            updated,
+            format!(
+                "127.0.0.1 localhost\n# {} used to be here\n10.43.0.5 old-alias\n10.43.0.9 {}\n",
+                host, host
+            )
+        );
+        assert_eq!(with_hosts_entry(&updated, host, "10.43.0.9"), None);
+        assert!(!without_hosts_entry(&updated, host).contains("10.43.0.9"));
+        assert_eq!(
+            without_hosts_entry(
+                &format!(
+                    "10.43.0.5\t{}   old-alias  # pinned by hand\r\n10.43.0.6 {} # gone\n",
+                    host, host
+                ),
+                host
+            ),
+            "10.43.0.5\told-alias  # pinned by hand\r\n"
+        );
+
+        let config = r#"{"features": {"buildkit": true}, "insecure-registries": ["other:5000"]}"#;
+        let updated = with_insecure_registry(config, "registry:5000")
+            .unwrap()
+            .unwrap();
+        let parsed: JsonValue = serde_json::from_str(&updated).unwrap();
+        assert_eq!(parsed["features"]["buildkit"], true);
+        assert_eq!(
+            parsed["insecure-registries"],
+            json!(["other:5000", "registry:5000"])
+        );
+        assert_eq!(
+            with_insecure_registry(&updated, "registry:5000").unwrap(),
+            None
+        );
+        assert!(with_insecure_registry("{", "registry:5000").is_err());
+        let created = with_insecure_registry("", "registry:5000")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(&created).unwrap(),
+            json!({"insecure-registries": ["registry:5000"]})
+        );
+        let fixed =
+            with_insecure_registry(r#"{"insecure-registries": "other:5000"}"#, "registry:5000")
+                .unwrap()
+                .unwrap();
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(&fixed).unwrap()["insecure-registries"],
+            json!(["other:5000", "registry:5000"])
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use super::conditions::{get_resource_json, pending_conditions, wait_until, PendingCondition};
+use clap::Args;
+use std::error::Error;
+use std::time::Duration;
+
+#[derive(Args, Debug)]
+pub struct WaitArgs {
+    /// Object to wait for: configuration/<name>, provider/<name>,
+    /// function/<name>, or <xr-kind>/<name> for composite resources and
+    /// claims (repeatable)
+    #[arg(long = "for", value_name = "KIND/NAME", required = true)]
+    pub targets: Vec<String>,
+
+    /// How long to wait, e.g. 90s, 5m, 1h
+    #[arg(long, default_value = "5m", value_parser = humantime::parse_duration)]
+    pub timeout: Duration,
+
+    /// Namespace for namespaced XRs and claims
+    #[arg(long, short = 'n')]
+    pub namespace: Option<String>,
+}
+
+/// What to poll and which conditions must be True.
+#[derive(Debug, PartialEq, Eq)]
+struct WaitTarget {
+    resource: String,
+    name: String,
+    namespaced: bool,
+    conditions: &'static [&'static str],
+}
+
+const PACKAGE_CONDITIONS: &[&str] = &["Installed", "Healthy"];
+pub(super) const XR_CONDITIONS: &[&str] = &["Synced", "Ready"];
+
+pub fn run(args: &WaitArgs) -> Result<(), Box<dyn Error>> {
+    let targets = args
+        .targets
+        .iter()
+        .map(|spec| parse_target(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    let labels = args.targets.join(", ");
+    log::info!(
+        "Waiting up to {} for {}...",
+        humantime::format_duration(args.timeout),
+        labels
+    );
+
+    wait_until(args.timeout, || {
+        targets
+            .iter()
+            .flat_map(|target| target_pending_conditions(target, args.namespace.as_deref()))
+            .collect()
+    })
+    .map_err(|pending| {
+        let mut lines = vec![format!(
+            "timed out after {} waiting for {}:",
+            humantime::format_duration(args.timeout),
+            labels
+        )];
+        lines.extend(pending.iter().map(|condition| format!("  {}", condition)));
+        lines.join("\n")
+    })?;
+
+    crate::logging::success(&format!("{} ready", labels));
+    Ok(())
+}
+
+fn parse_target(spec: &str) -> Result<WaitTarget, Box<dyn Error>> {
+    let (kind, name) = spec
+        .split_once('/')
+        .map(|(kind, name)| (kind.trim(), name.trim()))
+        .filter(|(kind, name)| !kind.is_empty() && !name.is_empty())
+        .ok_or_else(|| format!("`--for` must be <kind>/<name>, got '{}'", spec))?;
+
+    let package_resource = match kind.to_ascii_lowercase().as_str() {
+        "configuration" | "configurations" | "configuration.pkg.crossplane.io" => {
+            Some("configuration.pkg.crossplane.io")
+        }
+        "provider" | "providers" | "provider.pkg.crossplane.io" => {
+            Some("provider.pkg.crossplane.io")
+        }
+        "function" | "functions" | "function.pkg.crossplane.io" => {
+            Some("function.pkg.crossplane.io")
+        }
+        _ => None,
+    };
+
+    Ok(match package_resource {
+        Some(resource) => WaitTarget {
+            resource: resource.to_string(),
+            name: name.to_string(),
+            namespaced: false,
+            conditions: PACKAGE_CONDITIONS,
+        },
+        // Anything else is resolved by kubectl (kind, plural, or
+        // kind.group) and treated as an XR or claim.
+        None => WaitTarget {
+            resource: kind.to_string(),
+            name: name.to_string(),
+            namespaced: true,
+            conditions: XR_CONDITIONS,
+        },
+    })
+}
+
+fn target_pending_conditions(
+    target: &WaitTarget,
+    namespace: Option<&str>,
+) -> Vec<PendingCondition> {
+    let label = format!("{}/{}", target.resource, target.name);
+    let namespace = namespace.filter(|_| target.namespaced);
+    match get_resource_json(&target.resource, &target.name, namespace) {
+        Some(object) => pending_conditions(&label, &object, target.conditions),
+        None => vec![PendingCondition::not_found(&label)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_target_maps_packages_and_xrs() {
+        let target = parse_target("configuration/hops-ops-aws").unwrap();
+        assert_eq!(target.resource, "configuration.pkg.crossplane.io");
+        assert_eq!(target.conditions, PACKAGE_CONDITIONS);
+        assert!(!target.namespaced);
+
+        let target = parse_target("Providers/provider-helm").unwrap();
+        assert_eq!(target.resource, "provider.pkg.crossplane.io");
+
+        let target = parse_target("network.aws.hops.ops.com.ai/my-net").unwrap();
+        assert_eq!(target.resource, "network.aws.hops.ops.com.ai");
+        assert_eq!(target.name, "my-net");
+        assert_eq!(target.conditions, XR_CONDITIONS);
+        assert!(target.namespaced);
+
+        assert!(parse_target("configuration/").is_err());
+        assert!(parse_target("hops-ops-aws").is_err());
+    }
+}
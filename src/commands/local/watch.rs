@@ -0,0 +1,270 @@
+use super::conditions::get_resource_json;
+use super::events::events_for;
+use super::trace::composed_resources;
+use clap::Args;
+use colored::Colorize;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+#[derive(Args, Debug)]
+pub struct WatchArgs {
+    /// XR or claim to watch, as <kind>/<name> (kind may include the group)
+    pub target: String,
+
+    /// Namespace of the XR or claim
+    #[arg(long, short = 'n')]
+    pub namespace: Option<String>,
+
+    /// How often to poll the cluster, e.g. 2s
+    #[arg(long, default_value = "2s", value_parser = humantime::parse_duration)]
+    pub interval: Duration,
+
+    /// Exit once the XR or claim is Ready
+    #[arg(long)]
+    pub until_ready: bool,
+}
+
+/// Condition status, reason, and message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Condition {
+    status: String,
+    reason: String,
+    message: String,
+}
+
+/// Conditions by type for every watched object, keyed by `Kind/name`.
+type Snapshot = BTreeMap<String, BTreeMap<String, Condition>>;
+
+pub fn run(args: &WatchArgs) -> Result<(), Box<dyn Error>> {
+    let (kind, name) = args
+        .target
+        .split_once('/')
+        .map(|(kind, name)| (kind.trim(), name.trim()))
+        .filter(|(kind, name)| !kind.is_empty() && !name.is_empty())
+        .ok_or_else(|| format!("expected <kind>/<name>, got '{}'", args.target))?;
+    log::info!("Watching {} (Ctrl+C to stop)...", args.target);
+
+    let mut previous = Snapshot::new();
+    let mut seen_events = HashSet::new();
+    let mut waiting = false;
+    loop {
+        let Some(root) = get_resource_json(kind, name, args.namespace.as_deref()) else {
+            if !waiting {
+                println!("{} {} not found; waiting for it", now(), args.target);
+                waiting = true;
+            }
+            thread::sleep(args.interval);
+            continue;
+        };
+        waiting = false;
+
+        let mut objects = vec![root.clone()];
+        objects.extend(
+            composed_resources(&root)
+                .into_iter()
+                .filter_map(|(_, object)| object),
+        );
+        let snapshot: Snapshot = objects
+            .iter()
+            .map(|object| (label(object), conditions(object)))
+            .collect();
+        for line in transitions(&previous, &snapshot) {
+            println!("{} {}", now(), line);
+        }
+
+        let involved: HashSet<(String, String)> = objects
+            .iter()
+            .map(|object| (field(object, "/kind"), field(object, "/metadata/name")))
+            .collect();
+        for (key, line) in events_for(&involved) {
+            if seen_events.insert(key) {
+                println!("{}", line);
+            }
+        }
+
+        if args.until_ready
+            && snapshot[&label(&root)]
+                .get("Ready")
+                .map(|c| c.status.as_str())
+                == Some("True")
+        {
+            crate::logging::success(&format!("{} is Ready", args.target));
+            return Ok(());
+        }
+        previous = snapshot;
+        thread::sleep(args.interval);
+    }
+}
+
+/// Lines describing what changed between two snapshots: objects that
+/// appeared or went away and conditions whose status, reason, or message
+/// changed.
+fn transitions(previous: &Snapshot, current: &Snapshot) -> Vec<String> {
+    let mut lines = Vec::new();
+    for (object, conditions) in current {
+        let before = previous.get(object);
+        if before.is_none() {
+            let summary: Vec<String> = conditions
+                .iter()
+                .map(|(condition_type, condition)| {
+                    format!("{}={}", condition_type, colorize(&condition.status))
+                })
+                .collect();
+            lines.push(format!(
+                "{} {} {}",
+                "+".green(),
+                object,
+                if summary.is_empty() {
+                    "(no conditions yet)".to_string()
+                } else {
+                    summary.join(" ")
+                }
+            ));
+        }
+        for (condition_type, condition) in conditions {
+            let old = before.and_then(|before| before.get(condition_type));
+            if before.is_some() && old == Some(condition) {
+                continue;
+            }
+            if before.is_none() && condition.message.is_empty() {
+                continue;
+            }
+            let change = match old {
+                Some(old) if old.status != condition.status => format!(
+                    "{} -> {}",
+                    colorize(&old.status),
+                    colorize(&condition.status)
+                ),
+                _ => colorize(&condition.status).to_string(),
+            };
+            let detail: Vec<&str> = [condition.reason.as_str(), first_line(&condition.message)]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect();
+            let detail = if detail.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", detail.join(": "))
+            };
+            lines.push(format!(
+                "  {} {}: {}{}",
+                object, condition_type, change, detail
+            ));
+        }
+    }
+    for object in previous
+        .keys()
+        .filter(|object| !current.contains_key(*object))
+    {
+        lines.push(format!("{} {} gone", "-".red(), object));
+    }
+    lines
+}
+
+fn conditions(object: &JsonValue) -> BTreeMap<String, Condition> {
+    object
+        .pointer("/status/conditions")
+        .and_then(JsonValue::as_array)
+        .into_iter()
+        .flatten()
+        .map(|condition| {
+            (
+                field(condition, "/type"),
+                Condition {
+                    status: field(condition, "/status"),
+                    reason: field(condition, "/reason"),
+                    message: field(condition, "/message"),
+                },
+            )
+        })
+        .collect()
+}
+
+fn label(object: &JsonValue) -> String {
+    format!(
+        "{}/{}",
+        field(object, "/kind"),
+        field(object, "/metadata/name")
+    )
+}
+
+fn field(object: &JsonValue, pointer: &str) -> String {
+    object
+        .pointer(pointer)
+        .and_then(JsonValue::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn colorize(status: &str) -> colored::ColoredString {
+    match status {
+        "True" => status.green(),
+        "False" => status.red(),
+        _ => status.normal(),
+    }
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or_default().trim()
+}
+
+fn now() -> String {
+    humantime::format_rfc3339_seconds(SystemTime::now()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(
+        name: &str,
+        conditions: &[(&str, &str, &str)],
+    ) -> (String, BTreeMap<String, Condition>) {
+        let conditions = conditions
+            .iter()
+            .map(|(condition_type, status, message)| {
+                let condition = Condition {
+                    status: status.to_string(),
+                    reason: String::new(),
+                    message: message.to_string(),
+                };
+                (condition_type.to_string(), condition)
+            })
+            .collect();
+        (name.to_string(), conditions)
+    }
+
+    #[test]
+    fn transitions_report_new_objects_changes_and_removals() {
+        colored::control::set_override(false);
+        let before = Snapshot::from([
+            object("XNetwork/demo", &[("Ready", "False", "")]),
+            object("VPC/demo", &[("Ready", "False", "creating")]),
+        ]);
+        let after = Snapshot::from([
+            object("XNetwork/demo", &[("Ready", "False", "")]),
+            object(
+                "Subnet/demo-a",
+                &[("Synced", "False", "cannot resolve VPC")],
+            ),
+        ]);
+        assert_eq!(
+            transitions(&before, &after),
+            [
+                "+ Subnet/demo-a Synced=False",
+                "  Subnet/demo-a Synced: False (cannot resolve VPC)",
+                "- VPC/demo gone",
+            ]
+        );
+        assert!(transitions(&after, &after).is_empty());
+
+        let ready = Snapshot::from([object("XNetwork/demo", &[("Ready", "True", "")])]);
+        assert_eq!(
+            transitions(&before, &ready)[0],
+            "  XNetwork/demo Ready: False -> True"
+        );
+    }
+}
@@ -1,6 +1,11 @@
 pub mod ai;
 pub mod config;
+pub mod doctor;
+pub mod generate;
 pub mod local;
+pub mod new;
+pub mod render;
 pub mod secrets;
+pub mod test;
 pub mod validate;
 pub mod xr;
@@ -0,0 +1,182 @@
+mod templates;
+
+use clap::{Args, ValueEnum};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Args, Debug)]
+pub struct NewArgs {
+    /// Project name, e.g. helm-airflow (also the repository and package name)
+    pub name: String,
+
+    /// What the composition manages
+    #[arg(long, value_enum)]
+    pub template: Template,
+
+    /// GitHub and ghcr.io organization the package is published under
+    #[arg(long, default_value = "hops-ops")]
+    pub org: String,
+
+    /// API group of the XRD (defaults to <template>.hops.ops.com.ai)
+    #[arg(long)]
+    pub group: Option<String>,
+
+    /// Directory to create the project in (defaults to ./<name>)
+    #[arg(long)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Template {
+    /// Installs a Helm chart with provider-helm
+    Helm,
+    /// Manages Kubernetes objects with provider-kubernetes
+    K8s,
+    /// Manages AWS resources (an S3 bucket) with provider-aws
+    Aws,
+}
+
+impl Template {
+    fn name(self) -> &'static str {
+        match self {
+            Template::Helm => "helm",
+            Template::K8s => "k8s",
+            Template::Aws => "aws",
+        }
+    }
+}
+
+pub fn run(args: &NewArgs) -> Result<(), Box<dyn Error>> {
+    for (flag, value) in [("name", &args.name), ("--org", &args.org)] {
+        if !is_dns_label(value) {
+            return Err(format!(
+                "{} must be lowercase letters, digits, and dashes, got '{}'",
+                flag, value
+            )
+            .into());
+        }
+    }
+    let template = args.template.name();
+    let group = args
+        .group
+        .clone()
+        .unwrap_or_else(|| format!("{}.hops.ops.com.ai", template));
+    if !group.contains('.') || !group.split('.').all(is_dns_label) {
+        return Err(format!(
+            "--group must be a DNS name such as example.org, got '{}'",
+            group
+        )
+        .into());
+    }
+    let kind = kind_name(&args.name, template);
+    if kind.is_empty() {
+        return Err(format!(
+            "name '{}' leaves no kind after the '{}-' prefix",
+            args.name, template
+        )
+        .into());
+    }
+    let plural = plural(&kind.to_lowercase());
+
+    let dir = args
+        .path
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(&args.name));
+    if fs::read_dir(&dir).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(format!("{} already exists and is not empty", dir.display()).into());
+    }
+
+    let fill = |text: &str| {
+        text.replace("{{org}}", &args.org)
+            .replace("{{name}}", &args.name)
+            .replace("{{group}}", &group)
+            .replace("{{kind}}", &kind)
+            .replace("{{plural}}", &plural)
+            .replace("{{template}}", template)
+    };
+    for (path, contents) in templates::files(args.template) {
+        let path = dir.join(fill(path));
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, fill(&contents))?;
+        log::debug!("Wrote {}", path.display());
+    }
+
+    crate::logging::success(&format!(
+        "Created {} ({} in {}/v1alpha1, {} template) in {}",
+        args.name,
+        kind,
+        group,
+        template,
+        dir.display()
+    ));
+    log::info!(
+        "Next: `hops validate --path {0}`, then `hops local start` and `hops config install --path {0}`",
+        dir.display()
+    );
+    Ok(())
+}
+
+/// The XR kind for a project name: the template prefix dropped and the rest
+/// in PascalCase (`helm-airflow` -> `Airflow`).
+fn kind_name(name: &str, template: &str) -> String {
+    let name = name
+        .strip_prefix(template)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .unwrap_or(name);
+    name.split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn plural(singular: &str) -> String {
+    if singular.ends_with('s') || singular.ends_with('x') || singular.ends_with("ch") {
+        format!("{}es", singular)
+    } else if let Some(stem) = singular
+        .strip_suffix('y')
+        .filter(|stem| !stem.ends_with(['a', 'e', 'i', 'o', 'u']))
+    {
+        format!("{}ies", stem)
+    } else {
+        format!("{}s", singular)
+    }
+}
+
+fn is_dns_label(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 63
+        && !value.starts_with('-')
+        && !value.ends_with('-')
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_and_plural_come_from_the_project_name() {
+        assert_eq!(kind_name("helm-airflow", "helm"), "Airflow");
+        assert_eq!(kind_name("aws-auto-eks-cluster", "aws"), "AutoEksCluster");
+        assert_eq!(kind_name("k8s-namespace", "aws"), "K8sNamespace");
+        assert_eq!(kind_name("helm", "helm"), "Helm");
+        assert_eq!(plural("airflow"), "airflows");
+        assert_eq!(plural("autoekscluster"), "autoeksclusters");
+        assert_eq!(plural("ingress"), "ingresses");
+        assert_eq!(plural("registry"), "registries");
+        assert_eq!(plural("gateway"), "gateways");
+        assert!(is_dns_label("hops-ops"));
+        assert!(!is_dns_label("Hops"));
+        assert!(!is_dns_label("-ops"));
+    }
+}
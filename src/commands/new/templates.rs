@@ -0,0 +1,296 @@
+use super::Template;
+
+const UPBOUND_YAML: &str = r#"apiVersion: meta.dev.upbound.io/v1alpha1
+kind: Project
+metadata:
+  name: {{name}}
+spec:
+  repository: ghcr.io/{{org}}/{{name}}
+  source: github.com/{{org}}/{{name}}
+  license: Apache-2.0
+  description: {{kind}} composition ({{template}})
+  dependsOn:
+    - apiVersion: pkg.crossplane.io/v1
+      kind: Provider
+      package: {{provider}}
+      version: "{{provider_version}}"
+    - apiVersion: pkg.crossplane.io/v1beta1
+      kind: Function
+      package: xpkg.crossplane.io/crossplane-contrib/function-auto-ready
+      version: v0.6.0
+"#;
+
+const COMPOSITION_YAML: &str = r#"apiVersion: apiextensions.crossplane.io/v1
+kind: Composition
+metadata:
+  name: {{plural}}.{{group}}
+spec:
+  compositeTypeRef:
+    apiVersion: {{group}}/v1alpha1
+    kind: {{kind}}
+  mode: Pipeline
+  pipeline:
+    - step: render
+      functionRef:
+        name: {{org}}-{{name}}-render
+    - step: auto-ready
+      functionRef:
+        name: crossplane-contrib-function-auto-ready
+"#;
+
+const DEFINITION_YAML: &str = r#"apiVersion: apiextensions.crossplane.io/v2
+kind: CompositeResourceDefinition
+metadata:
+  name: {{plural}}.{{group}}
+spec:
+  scope: Namespaced
+  group: {{group}}
+  names:
+    kind: {{kind}}
+    plural: {{plural}}
+  versions:
+    - name: v1alpha1
+      served: true
+      referenceable: true
+      schema:
+        openAPIV3Schema:
+          type: object
+          properties:
+            spec:
+              type: object
+              properties:
+{{spec_properties}}
+                providerConfigName:
+                  type: string
+                  description: ProviderConfig (in the XR's namespace) for the composed resources
+                  default: default
+"#;
+
+const KCL_MOD: &str = r#"[package]
+name = "render"
+version = "0.0.1"
+edition = "v0.11.0"
+"#;
+
+const GITIGNORE: &str = "_output/\napis/**/configuration.yaml\n";
+
+const README_MD: &str = r#"# {{name}}
+
+`{{kind}}` ({{group}}/v1alpha1), composed by the KCL function in
+`functions/render`.
+
+```bash
+hops validate --path .            # check the XRD, Composition, and examples
+hops test --update                # record tests/basic/expected.yaml
+hops test                         # compare renders with the recorded output
+hops local start
+hops config install --path .      # build and install into the local cluster
+hops local claim apply            # apply examples/ and wait until Ready
+```
+"#;
+
+const HELM_SPEC_PROPERTIES: &str = r#"                chart:
+                  type: object
+                  required: [name, repository, version]
+                  properties:
+                    name:
+                      type: string
+                    repository:
+                      type: string
+                    version:
+                      type: string
+                namespace:
+                  type: string
+                  description: Namespace to install the release into (defaults to the XR's)
+                values:
+                  type: object
+                  description: Helm values
+                  x-kubernetes-preserve-unknown-fields: true"#;
+
+const HELM_MAIN_K: &str = r#"oxr = option("params").oxr
+_spec = oxr.spec
+
+_metadata = lambda name: str -> any {
+    {annotations = {"krm.kcl.dev/composition-resource-name" = name}}
+}
+
+items = [
+    {
+        apiVersion = "helm.m.crossplane.io/v1beta1"
+        kind = "Release"
+        metadata = _metadata("release")
+        spec = {
+            forProvider = {
+                chart = {
+                    name = _spec.chart.name
+                    repository = _spec.chart.repository
+                    version = _spec.chart.version
+                }
+                namespace = _spec?.namespace or oxr.metadata.namespace
+                values = _spec?.values or {}
+            }
+            providerConfigRef = {
+                kind = "ProviderConfig"
+                name = _spec?.providerConfigName or "default"
+            }
+        }
+    }
+]
+"#;
+
+const HELM_EXAMPLE: &str = r#"apiVersion: {{group}}/v1alpha1
+kind: {{kind}}
+metadata:
+  name: example
+  namespace: default
+spec:
+  chart:
+    name: podinfo
+    repository: https://stefanprodan.github.io/podinfo
+    version: 6.9.2
+  values:
+    replicaCount: 1
+"#;
+
+const K8S_SPEC_PROPERTIES: &str = r#"                data:
+                  type: object
+                  description: Entries of the composed ConfigMap
+                  additionalProperties:
+                    type: string"#;
+
+const K8S_MAIN_K: &str = r#"oxr = option("params").oxr
+_spec = oxr.spec
+
+_metadata = lambda name: str -> any {
+    {annotations = {"krm.kcl.dev/composition-resource-name" = name}}
+}
+
+items = [
+    {
+        apiVersion = "kubernetes.m.crossplane.io/v1alpha1"
+        kind = "Object"
+        metadata = _metadata("config")
+        spec = {
+            forProvider.manifest = {
+                apiVersion = "v1"
+                kind = "ConfigMap"
+                metadata = {
+                    name = oxr.metadata.name
+                    namespace = oxr.metadata.namespace
+                }
+                data = _spec?.data or {}
+            }
+            providerConfigRef = {
+                kind = "ProviderConfig"
+                name = _spec?.providerConfigName or "default"
+            }
+        }
+    }
+]
+"#;
+
+const K8S_EXAMPLE: &str = r#"apiVersion: {{group}}/v1alpha1
+kind: {{kind}}
+metadata:
+  name: example
+  namespace: default
+spec:
+  data:
+    greeting: hello
+"#;
+
+const AWS_SPEC_PROPERTIES: &str = r#"                region:
+                  type: string
+                  default: us-east-1
+                tags:
+                  type: object
+                  additionalProperties:
+                    type: string"#;
+
+const AWS_MAIN_K: &str = r#"oxr = option("params").oxr
+_spec = oxr.spec
+
+_metadata = lambda name: str -> any {
+    {annotations = {"krm.kcl.dev/composition-resource-name" = name}}
+}
+
+items = [
+    {
+        apiVersion = "s3.aws.m.upbound.io/v1beta1"
+        kind = "Bucket"
+        metadata = _metadata("bucket")
+        spec = {
+            forProvider = {
+                region = _spec?.region or "us-east-1"
+                tags = _spec?.tags or {}
+            }
+            providerConfigRef = {
+                kind = "ProviderConfig"
+                name = _spec?.providerConfigName or "default"
+            }
+        }
+    }
+]
+"#;
+
+const AWS_EXAMPLE: &str = r#"apiVersion: {{group}}/v1alpha1
+kind: {{kind}}
+metadata:
+  name: example
+  namespace: default
+spec:
+  region: us-east-1
+  tags:
+    owner: platform
+"#;
+
+/// `(path, contents)` of every file of a new project. Both still contain the
+/// `{{org}}`, `{{name}}`, `{{group}}`, `{{kind}}`, `{{plural}}`, and
+/// `{{template}}` placeholders.
+pub fn files(template: Template) -> Vec<(&'static str, String)> {
+    let (provider, provider_version, spec_properties, main_k, example) = match template {
+        Template::Helm => (
+            "xpkg.crossplane.io/crossplane-contrib/provider-helm",
+            ">=v1.0.0",
+            HELM_SPEC_PROPERTIES,
+            HELM_MAIN_K,
+            HELM_EXAMPLE,
+        ),
+        Template::K8s => (
+            "xpkg.crossplane.io/crossplane-contrib/provider-kubernetes",
+            ">=v1.0.0",
+            K8S_SPEC_PROPERTIES,
+            K8S_MAIN_K,
+            K8S_EXAMPLE,
+        ),
+        Template::Aws => (
+            "xpkg.crossplane.io/crossplane-contrib/provider-aws-s3",
+            ">=v2.0.0",
+            AWS_SPEC_PROPERTIES,
+            AWS_MAIN_K,
+            AWS_EXAMPLE,
+        ),
+    };
+    vec![
+        (
+            "upbound.yaml",
+            UPBOUND_YAML
+                .replace("{{provider}}", provider)
+                .replace("{{provider_version}}", provider_version),
+        ),
+        (
+            "apis/{{plural}}/definition.yaml",
+            DEFINITION_YAML.replace("{{spec_properties}}", spec_properties),
+        ),
+        (
+            "apis/{{plural}}/composition.yaml",
+            COMPOSITION_YAML.to_string(),
+        ),
+        ("functions/render/main.k", main_k.to_string()),
+        ("functions/render/kcl.mod", KCL_MOD.to_string()),
+        ("examples/{{plural}}/example.yaml", example.to_string()),
+        ("tests/basic/xr.yaml", example.to_string()),
+        (".gitignore", GITIGNORE.to_string()),
+        ("README.md", README_MD.to_string()),
+    ]
+}
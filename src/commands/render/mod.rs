@@ -0,0 +1,424 @@
+use crate::commands::config::{
+    build_render_functions, package_dependencies, strip_registry, Builder,
+};
+use clap::Args;
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Directories never searched for Compositions.
+pub const SKIPPED_DIRS: &[&str] = &[".git", ".up", "_output", "node_modules", "target"];
+
+#[derive(Args, Debug)]
+pub struct RenderArgs {
+    /// Path to the XRD project directory (defaults to current directory)
+    #[arg(long, default_value = ".")]
+    pub path: PathBuf,
+
+    /// Composite resource (XR) manifest to render, relative to the current
+    /// directory or the project (e.g. examples/xr.yaml)
+    #[arg(long)]
+    pub xr: PathBuf,
+
+    /// Composition to render with (default: the project's Composition whose
+    /// compositeTypeRef matches the XR)
+    #[arg(long)]
+    pub composition: Option<PathBuf>,
+
+    /// Function manifests to use instead of the resolved ones, e.g. for
+    /// dependencies without an exact version
+    #[arg(long)]
+    pub functions: Option<PathBuf>,
+
+    /// Observed composed resources (`crossplane render --observed-resources`)
+    #[arg(long)]
+    pub observed_resources: Option<PathBuf>,
+
+    /// Extra resources functions can request (`crossplane render --extra-resources`)
+    #[arg(long)]
+    pub extra_resources: Option<PathBuf>,
+
+    /// Tool used to build the project's embedded functions (auto: `up` when
+    /// upbound.yaml exists; crossplane.yaml projects are not built)
+    #[arg(long, value_enum, default_value_t = Builder::Auto)]
+    pub builder: Builder,
+}
+
+/// Render an XR locally with `crossplane render`, running the project's
+/// embedded functions from freshly built images and its other function
+/// dependencies from their pinned versions. Nothing touches the cluster.
+pub fn run(args: &RenderArgs) -> Result<(), Box<dyn Error>> {
+    let context = RenderContext::prepare(&args.path, args.builder, args.functions.as_deref())?;
+    let xr = project_file(&args.path, &args.xr)?;
+    let composition = args
+        .composition
+        .as_deref()
+        .map(|path| project_file(&args.path, path))
+        .transpose()?;
+    let output = context.render(&RenderInput {
+        xr: &xr,
+        composition: composition.as_deref(),
+        observed_resources: args.observed_resources.as_deref(),
+        extra_resources: args.extra_resources.as_deref(),
+    })?;
+    print!("{}", output);
+    Ok(())
+}
+
+/// A project with its functions built, ready to render XRs. Shared with
+/// `hops test`, which renders many XRs from one build.
+pub struct RenderContext {
+    dir: PathBuf,
+    overrides: Vec<JsonValue>,
+    local_images: Vec<String>,
+    dependencies: BTreeMap<String, String>,
+}
+
+/// Files for one `crossplane render` run.
+pub struct RenderInput<'a> {
+    pub xr: &'a Path,
+    /// Default: the project's Composition for the XR's type.
+    pub composition: Option<&'a Path>,
+    pub observed_resources: Option<&'a Path>,
+    pub extra_resources: Option<&'a Path>,
+}
+
+impl RenderContext {
+    /// Build the project's embedded functions and read its dependencies.
+    pub fn prepare(
+        dir: &Path,
+        builder: Builder,
+        functions: Option<&Path>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if !dir.is_dir() {
+            return Err(format!("{} is not a directory", dir.display()).into());
+        }
+        let overrides = match functions {
+            Some(path) => read_documents(&project_file(dir, path)?)?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            overrides,
+            local_images: build_render_functions(dir, builder)?,
+            dependencies: project_dependencies(dir),
+        })
+    }
+
+    /// Run `crossplane render` and return its YAML output.
+    pub fn render(&self, input: &RenderInput) -> Result<String, Box<dyn Error>> {
+        let xr = read_documents(input.xr)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("{} is empty", input.xr.display()))?;
+        let (api_version, kind) = (field(&xr, "apiVersion"), field(&xr, "kind"));
+
+        let composition_path = match input.composition {
+            Some(path) => path.to_path_buf(),
+            None => find_composition(&self.dir, &api_version, &kind)?.ok_or_else(|| {
+                format!(
+                    "no Composition for {} {} in {}; pass --composition",
+                    api_version,
+                    kind,
+                    self.dir.display()
+                )
+            })?,
+        };
+        let composition = read_documents(&composition_path)?
+            .into_iter()
+            .find(|document| field(document, "kind") == "Composition")
+            .ok_or_else(|| format!("{} has no Composition", composition_path.display()))?;
+        let function_refs: Vec<String> = composition
+            .pointer("/spec/pipeline")
+            .and_then(JsonValue::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|step| step.pointer("/functionRef/name")?.as_str())
+            .map(str::to_string)
+            .collect();
+        let functions = resolve_functions(
+            &function_refs,
+            &self.overrides,
+            &self.local_images,
+            &self.dependencies,
+        )?;
+
+        let functions_path =
+            std::env::temp_dir().join(format!("hops-render-functions-{}.yaml", std::process::id()));
+        let functions_yaml = functions
+            .iter()
+            .map(serde_yaml::to_string)
+            .collect::<Result<Vec<_>, _>>()?
+            .join("---\n");
+        let _functions_file = crate::interrupt::temp_path(&functions_path);
+        fs::write(&functions_path, functions_yaml)?;
+
+        let mut render_args = vec![
+            "render".to_string(),
+            input.xr.display().to_string(),
+            composition_path.display().to_string(),
+            functions_path.display().to_string(),
+        ];
+        if let Some(path) = input.observed_resources {
+            render_args.push(format!("--observed-resources={}", path.display()));
+        }
+        if let Some(path) = input.extra_resources {
+            render_args.push(format!("--extra-resources={}", path.display()));
+        }
+        log::info!(
+            "Rendering {} with {}...",
+            input.xr.display(),
+            composition_path.display()
+        );
+        crate::logging::log_command("crossplane", &render_args);
+        // stderr stays on the terminal for function logs and errors.
+        let output = Command::new("crossplane")
+            .args(&render_args)
+            .stderr(Stdio::inherit())
+            .output();
+        let _ = fs::remove_file(&functions_path);
+        let output = output?;
+        if !output.status.success() {
+            return Err(format!("crossplane render exited with {}", output.status).into());
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+/// `path` as given if it exists, else relative to the project directory.
+pub fn project_file(dir: &Path, path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    if path.exists() {
+        return Ok(path.to_path_buf());
+    }
+    let in_project = dir.join(path);
+    if in_project.exists() {
+        return Ok(in_project);
+    }
+    Err(format!("{} not found", path.display()).into())
+}
+
+pub fn read_documents(path: &Path) -> Result<Vec<JsonValue>, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(&contents) {
+        let value = JsonValue::deserialize(document)
+            .map_err(|err| format!("invalid YAML in {}: {}", path.display(), err))?;
+        if !value.is_null() {
+            documents.push(value);
+        }
+    }
+    Ok(documents)
+}
+
+/// YAML files under `dir`, skipping build output and hidden directories.
+pub fn yaml_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let Ok(entries) = fs::read_dir(dir) else {
+        return files;
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    for path in paths {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_ref()) {
+                files.extend(yaml_files(&path));
+            }
+        } else if name.ends_with(".yaml") || name.ends_with(".yml") {
+            files.push(path);
+        }
+    }
+    files
+}
+
+fn field(object: &JsonValue, name: &str) -> String {
+    object[name].as_str().unwrap_or_default().to_string()
+}
+
+/// The first YAML file under `dir` holding a Composition for the XR type.
+fn find_composition(
+    dir: &Path,
+    api_version: &str,
+    kind: &str,
+) -> Result<Option<PathBuf>, Box<dyn Error>> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        if path.is_dir() {
+            if SKIPPED_DIRS.contains(&name) {
+                continue;
+            }
+            if let Some(found) = find_composition(&path, api_version, kind)? {
+                return Ok(Some(found));
+            }
+        } else if name.ends_with(".yaml") || name.ends_with(".yml") {
+            let matches = read_documents(&path).unwrap_or_default().iter().any(|doc| {
+                field(doc, "kind") == "Composition"
+                    && doc.pointer("/spec/compositeTypeRef/apiVersion") == Some(&json!(api_version))
+                    && doc.pointer("/spec/compositeTypeRef/kind") == Some(&json!(kind))
+            });
+            if matches {
+                return Ok(Some(path));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// `spec.dependsOn` of upbound.yaml or crossplane.yaml.
+pub fn project_dependencies(dir: &Path) -> BTreeMap<String, String> {
+    ["upbound.yaml", "crossplane.yaml", "package/crossplane.yaml"]
+        .iter()
+        .find_map(|file| fs::read_to_string(dir.join(file)).ok())
+        .map(|contents| package_dependencies(&contents))
+        .unwrap_or_default()
+}
+
+/// Function objects for `crossplane render`, one per functionRef: an
+/// override with that name, else an embedded function image built from the
+/// project, else a dependency pinned to an exact version. Names are matched
+/// on their letters and digits, since Crossplane derives Function names from
+/// package paths (`org/project_render` -> `org-project-render`).
+fn resolve_functions(
+    function_refs: &[String],
+    overrides: &[JsonValue],
+    local_images: &[String],
+    dependencies: &BTreeMap<String, String>,
+) -> Result<Vec<JsonValue>, Box<dyn Error>> {
+    let mut functions = Vec::new();
+    let mut unresolved = Vec::new();
+    for name in function_refs {
+        if let Some(function) = overrides
+            .iter()
+            .find(|function| function.pointer("/metadata/name") == Some(&json!(name)))
+        {
+            functions.push(function.clone());
+            continue;
+        }
+        let key = normalize(name);
+        let image_path = |image: &str| {
+            strip_registry(image.rsplit_once(':').map_or(image, |(path, _)| path)).to_string()
+        };
+        if let Some(image) = local_images
+            .iter()
+            .find(|image| normalize(&image_path(image)) == key)
+        {
+            functions.push(function_object(name, image, true));
+            continue;
+        }
+        match dependencies
+            .iter()
+            .find(|(package, _)| normalize(strip_registry(package)) == key)
+        {
+            Some((package, version)) if is_exact_version(version) => {
+                functions.push(function_object(
+                    name,
+                    &format!("{}:{}", package, version),
+                    false,
+                ));
+            }
+            Some((package, version)) => unresolved.push(format!(
+                "{} ({} is constrained to {:?}, not an exact version)",
+                name, package, version
+            )),
+            None => unresolved.push(format!("{} (no embedded function or dependency)", name)),
+        }
+    }
+    if !unresolved.is_empty() {
+        return Err(format!(
+            "could not resolve these functions; list them in a file passed with --functions:\n  {}",
+            unresolved.join("\n  ")
+        )
+        .into());
+    }
+    Ok(functions)
+}
+
+fn function_object(name: &str, package: &str, local: bool) -> JsonValue {
+    let mut function = json!({
+        "apiVersion": "pkg.crossplane.io/v1beta1",
+        "kind": "Function",
+        "metadata": {"name": name},
+        "spec": {"package": package}
+    });
+    if local {
+        // Only exists in the local Docker daemon.
+        function["metadata"]["annotations"] =
+            json!({"render.crossplane.io/runtime-docker-pull-policy": "Never"});
+    }
+    function
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// A single version (`v0.2.1`), not a constraint (`>=v0.2.0`, `^1`, `*`).
+pub fn is_exact_version(version: &str) -> bool {
+    let version = version.trim();
+    version.starts_with(|c: char| c == 'v' || c.is_ascii_digit())
+        && !version.contains(|c: char| "<>=~^*, |".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_functions_prefers_overrides_then_local_images_then_pins() {
+        let refs = vec![
+            "hops-ops-aws-auto-eks-cluster-render".to_string(),
+            "crossplane-contrib-function-auto-ready".to_string(),
+            "crossplane-contrib-function-go-templating".to_string(),
+        ];
+        let overrides = vec![function_object(
+            "crossplane-contrib-function-go-templating",
+            "xpkg.crossplane.io/crossplane-contrib/function-go-templating:v0.9.0",
+            false,
+        )];
+        let local =
+            vec!["xpkg.upbound.io/hops-ops/aws-auto-eks-cluster_render:hops-render".to_string()];
+        let mut dependencies = BTreeMap::new();
+        dependencies.insert(
+            "xpkg.crossplane.io/crossplane-contrib/function-auto-ready".to_string(),
+            "v0.5.0".to_string(),
+        );
+
+        let functions = resolve_functions(&refs, &overrides, &local, &dependencies).unwrap();
+        assert_eq!(functions[0]["spec"]["package"], json!(local[0]));
+        assert_eq!(
+            functions[0]["metadata"]["annotations"]
+                ["render.crossplane.io/runtime-docker-pull-policy"],
+            "Never"
+        );
+        assert_eq!(
+            functions[1]["spec"]["package"],
+            "xpkg.crossplane.io/crossplane-contrib/function-auto-ready:v0.5.0"
+        );
+        assert_eq!(functions[2], overrides[0]);
+
+        dependencies.insert(
+            "xpkg.crossplane.io/crossplane-contrib/function-auto-ready".to_string(),
+            ">=v0.2.0".to_string(),
+        );
+        assert!(resolve_functions(&refs, &overrides, &local, &dependencies).is_err());
+    }
+}
@@ -74,7 +74,7 @@ pub fn run(args: &InitArgs) -> Result<(), Box<dyn Error>> {
     ensure_secret_tags(args)?;
     configure_kms(args)?;
     maybe_create_examples(args)?;
-    log::info!("Secrets initialization complete");
+    crate::logging::success("Secrets initialization complete");
     Ok(())
 }
 
@@ -495,7 +495,7 @@ fn wait_for_kms_key_ready(
     namespace: &str,
     wait_seconds: u64,
 ) -> Result<JsonValue, Box<dyn Error>> {
-    let attempts = std::cmp::max(1, (wait_seconds + 4) / 5);
+    let attempts = std::cmp::max(1, wait_seconds.div_ceil(5));
     let mut last_summary = None;
 
     for i in 0..attempts {
@@ -1,6 +1,7 @@
 use super::{
     aws_clients, collect_local_secret_names, configured_aws_settings, configured_github_settings,
-    configured_secret_paths, require_command, run_command_output_string,
+    configured_secret_paths, require_command, run_command_output_secret_string,
+    run_command_output_string, GithubSecret,
 };
 use rusoto_secretsmanager::{ListSecretsRequest, SecretsManager, SecretsManagerClient};
 use serde::Deserialize;
@@ -457,7 +458,7 @@ fn print_github_section() -> Result<(), Box<dyn Error>> {
 }
 
 fn ensure_gh_auth_for_list() -> Result<(), Box<dyn Error>> {
-    let token = run_command_output_string("gh", &["auth", "token"]).map_err(|err| {
+    let token = run_command_output_secret_string("gh", &["auth", "token"]).map_err(|err| {
         format!(
             "failed to read GitHub auth token: {}\nRun `gh auth login` first.",
             err
@@ -525,7 +526,7 @@ fn resolve_github_repos_for_list(
 fn collect_github_repo_secret_names(
     source_root: &Path,
     shared_root: &Path,
-    shared_secrets: &[(String, String, String)],
+    shared_secrets: &[GithubSecret],
     repo: &str,
 ) -> Result<HashSet<String>, Box<dyn Error>> {
     let repo_dir = source_root.join(repo);
@@ -552,7 +553,7 @@ fn collect_github_repo_secret_names(
 
 fn collect_github_target_secrets_for_list(
     target: &Path,
-) -> Result<Vec<(String, String, String)>, Box<dyn Error>> {
+) -> Result<Vec<GithubSecret>, Box<dyn Error>> {
     if !target.exists() {
         return Ok(Vec::new());
     }
@@ -568,7 +569,7 @@ fn collect_github_target_secrets_for_list(
 fn collect_github_dir_secrets_for_list(
     root: &Path,
     current: &Path,
-    out: &mut Vec<(String, String, String)>,
+    out: &mut Vec<GithubSecret>,
 ) -> Result<(), Box<dyn Error>> {
     for entry in fs::read_dir(current)? {
         let path = entry?.path();
@@ -584,7 +585,7 @@ fn collect_github_dir_secrets_for_list(
 fn collect_github_file_secrets_for_list(
     root: &Path,
     path: &Path,
-) -> Result<Vec<(String, String, String)>, Box<dyn Error>> {
+) -> Result<Vec<GithubSecret>, Box<dyn Error>> {
     if path.extension().and_then(|value| value.to_str()) == Some("json") {
         let contents = fs::read_to_string(path)?;
         let secrets = parse_github_secret_map_for_list(&contents, path)?;
@@ -101,9 +101,31 @@ pub fn run(args: &SecretsArgs) -> Result<(), Box<dyn Error>> {
     }
 }
 
+/// A GitHub secret read from disk: its name, value, and the file it came from.
+type GithubSecret = (String, String, String);
+
 fn run_command_output(program: &str, args: &[&str]) -> Result<Vec<u8>, Box<dyn Error>> {
-    log::debug!("Running: {} {}", program, args.join(" "));
+    run_command_capture(program, args, false)
+}
+
+/// [`run_command_output`] for commands that print secrets: `--debug-commands`
+/// shows only the size of their stdout.
+fn run_command_output_secret(program: &str, args: &[&str]) -> Result<Vec<u8>, Box<dyn Error>> {
+    run_command_capture(program, args, true)
+}
+
+fn run_command_capture(
+    program: &str,
+    args: &[&str],
+    secret: bool,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    crate::logging::log_command(program, args);
     let output = Command::new(program).args(args).output()?;
+    if secret {
+        crate::logging::log_secret_command_output(program, &output);
+    } else {
+        crate::logging::log_command_output(program, &output);
+    }
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(format!("{} exited with {}: {}", program, output.status, stderr).into());
@@ -115,6 +137,15 @@ fn run_command_output_string(program: &str, args: &[&str]) -> Result<String, Box
     Ok(String::from_utf8(run_command_output(program, args)?)?)
 }
 
+fn run_command_output_secret_string(
+    program: &str,
+    args: &[&str],
+) -> Result<String, Box<dyn Error>> {
+    Ok(String::from_utf8(run_command_output_secret(
+        program, args,
+    )?)?)
+}
+
 fn require_command(program: &str) -> Result<(), Box<dyn Error>> {
     let status = Command::new("sh")
         .args(["-c", &format!("command -v {} >/dev/null 2>&1", program)])
@@ -228,7 +259,7 @@ fn selected_aws_profile() -> Option<String> {
 }
 
 fn run_aws_export_credentials(profile: &str) -> Result<String, String> {
-    run_command_output_string(
+    run_command_output_secret_string(
         "aws",
         &[
             "configure",
@@ -455,6 +486,13 @@ fn process_tree(
 
     let relative = current.strip_prefix(source_root)?;
     let destination = dest_root.join(relative);
+    if destination.exists() && !force {
+        log::warn!(
+            "Skipping {}: it already exists (pass --force to overwrite)",
+            destination.display()
+        );
+        return Ok(());
+    }
 
     if let Some(parent) = destination.parent() {
         fs::create_dir_all(parent)?;
@@ -468,7 +506,7 @@ fn process_tree(
             "sops",
             &["--encrypt", "--input-type=raw", "--output-type=raw", source],
         )?,
-        "decrypt" => run_command_output(
+        "decrypt" => run_command_output_secret(
             "sops",
             &["--decrypt", "--input-type=raw", "--output-type=raw", source],
         )?,
@@ -512,8 +550,9 @@ fn default_secret_paths() -> Result<(PathBuf, PathBuf), Box<dyn Error>> {
 
 #[cfg(test)]
 mod tests {
-    use super::{derive_secret_name, sort_value};
+    use super::{derive_secret_name, process_tree, sort_value};
     use serde_yaml::Value;
+    use std::fs;
     use std::path::Path;
 
     #[test]
@@ -531,6 +570,20 @@ mod tests {
         assert_eq!(name.as_deref(), Some("github"));
     }
 
+    #[test]
+    fn process_tree_keeps_existing_files_without_force() {
+        let dir = std::env::temp_dir().join(format!("hops-secrets-tree-{}", std::process::id()));
+        let (source, dest) = (dir.join("plain"), dir.join("encrypted"));
+        fs::create_dir_all(&source).unwrap();
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(source.join("token"), "new").unwrap();
+        fs::write(dest.join("token"), "old").unwrap();
+
+        process_tree(&source, &source, &dest, "encrypt", false).unwrap();
+        assert_eq!(fs::read_to_string(dest.join("token")).unwrap(), "old");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn sort_value_orders_mapping_keys() {
         let mut value: Value = serde_yaml::from_str("b: 2\na: 1\n").expect("yaml");
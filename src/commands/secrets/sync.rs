@@ -1,6 +1,7 @@
 use super::{
     aws_clients, collect_local_secret_names, configured_aws_settings, configured_github_settings,
-    configured_secret_paths, derive_secret_name, require_command, run_command_output_string,
+    configured_secret_paths, derive_secret_name, require_command, run_command_output_secret_string,
+    GithubSecret,
 };
 use clap::{Args, Subcommand};
 use dialoguer::Confirm;
@@ -43,10 +44,6 @@ pub struct AwsSyncArgs {
     #[arg(long, value_parser = parse_key_value)]
     pub tags: Vec<(String, String)>,
 
-    /// Skip confirmation prompts
-    #[arg(short, long)]
-    pub yes: bool,
-
     /// Check for remote repo-owned secrets that no longer exist locally and delete them
     #[arg(long)]
     pub cleanup: bool,
@@ -118,26 +115,32 @@ fn run_aws(args: &AwsSyncArgs) -> Result<(), Box<dyn Error>> {
     let mut final_tags = final_tags_map.into_iter().collect::<Vec<_>>();
     final_tags.sort();
 
-    confirm_target_account(&runtime, &sts_client, args.yes)?;
+    confirm_target_account(&runtime, &sts_client, crate::interactive::assume_yes())?;
 
-    let mut synced = 0usize;
-    process_aws_path(
-        &runtime,
-        &client,
-        &final_tags,
-        &naming_root,
-        &secret_source,
-        &mut synced,
-        args.yes,
-        args.tags_only,
-    );
+    let mut sync = AwsSync {
+        runtime: &runtime,
+        client: &client,
+        tags: &final_tags,
+        yes: crate::interactive::assume_yes(),
+        tags_only: args.tags_only,
+        synced: 0,
+    };
+    process_aws_path(&mut sync, &naming_root, &secret_source);
 
     if args.cleanup {
         let local_names = collect_local_secret_names(&naming_root);
-        delete_missing_secrets(&runtime, &client, &local_names, args.yes);
+        delete_missing_secrets(
+            &runtime,
+            &client,
+            &local_names,
+            crate::interactive::assume_yes(),
+        );
     }
 
-    log::info!("AWS sync complete - {} secrets processed", synced);
+    crate::logging::success(&format!(
+        "AWS sync complete - {} secrets processed",
+        sync.synced
+    ));
     Ok(())
 }
 
@@ -166,16 +169,18 @@ fn normalized_absolute_path(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
     Ok(normalized)
 }
 
-fn process_aws_path(
-    runtime: &tokio::runtime::Runtime,
-    client: &SecretsManagerClient,
-    tags: &[(String, String)],
-    root: &Path,
-    path: &Path,
-    synced: &mut usize,
+/// Where `sync aws` writes secrets and with which tags, and how many it has
+/// processed so far.
+struct AwsSync<'a> {
+    runtime: &'a tokio::runtime::Runtime,
+    client: &'a SecretsManagerClient,
+    tags: &'a [(String, String)],
     yes: bool,
     tags_only: bool,
-) {
+    synced: usize,
+}
+
+fn process_aws_path(sync: &mut AwsSync, root: &Path, path: &Path) {
     if path.is_dir() {
         let entries = match fs::read_dir(path) {
             Ok(entries) => entries,
@@ -203,7 +208,7 @@ fn process_aws_path(
         }
 
         for dir in subdirs {
-            process_aws_path(runtime, client, tags, root, &dir, synced, yes, tags_only);
+            process_aws_path(sync, root, &dir);
         }
 
         for file in &json_files {
@@ -219,15 +224,10 @@ fn process_aws_path(
                 continue;
             };
             sync_aws_secret(
-                runtime,
-                client,
-                tags,
+                sync,
                 &secret_name,
                 &secret_string,
                 &file.display().to_string(),
-                synced,
-                yes,
-                tags_only,
             );
         }
 
@@ -269,15 +269,10 @@ fn process_aws_path(
                     return;
                 };
                 sync_aws_secret(
-                    runtime,
-                    client,
-                    tags,
+                    sync,
                     &secret_name,
                     &secret_string,
                     &path.display().to_string(),
-                    synced,
-                    yes,
-                    tags_only,
                 );
             }
         }
@@ -316,29 +311,22 @@ fn process_aws_path(
         return;
     };
     sync_aws_secret(
-        runtime,
-        client,
-        tags,
+        sync,
         &secret_name,
         &secret_string,
         &path.display().to_string(),
-        synced,
-        yes,
-        tags_only,
     );
 }
 
-fn sync_aws_secret(
-    runtime: &tokio::runtime::Runtime,
-    client: &SecretsManagerClient,
-    tags: &[(String, String)],
-    secret_name: &str,
-    secret_string: &str,
-    source_label: &str,
-    synced: &mut usize,
-    yes: bool,
-    tags_only: bool,
-) {
+fn sync_aws_secret(sync: &mut AwsSync, secret_name: &str, secret_string: &str, source_label: &str) {
+    let AwsSync {
+        runtime,
+        client,
+        tags,
+        yes,
+        tags_only,
+        ..
+    } = *sync;
     let exists = remote_secret_exists(runtime, client, secret_name);
     if tags_only {
         if !exists {
@@ -359,7 +347,7 @@ fn sync_aws_secret(
             log::error!("Failed applying tags to {}: {}", secret_name, err);
             return;
         }
-        *synced += 1;
+        sync.synced += 1;
         return;
     }
 
@@ -433,7 +421,7 @@ fn sync_aws_secret(
         log::error!("Failed applying tags to {}: {}", secret_name, err);
         return;
     }
-    *synced += 1;
+    sync.synced += 1;
 }
 
 fn run_github(args: &GithubSyncArgs) -> Result<(), Box<dyn Error>> {
@@ -467,17 +455,20 @@ fn run_github(args: &GithubSyncArgs) -> Result<(), Box<dyn Error>> {
             &source_root,
             &shared_root,
             &shared_secrets,
-            args.yes,
+            crate::interactive::assume_yes(),
             &mut synced,
         )?;
     }
 
-    log::info!("GitHub sync complete - {} secrets processed", synced);
+    crate::logging::success(&format!(
+        "GitHub sync complete - {} secrets processed",
+        synced
+    ));
     Ok(())
 }
 
 fn ensure_gh_auth() -> Result<(), Box<dyn Error>> {
-    let token = run_command_output_string("gh", &["auth", "token"]).map_err(|err| {
+    let token = run_command_output_secret_string("gh", &["auth", "token"]).map_err(|err| {
         format!(
             "failed to read GitHub auth token: {}\nRun `gh auth login` first.",
             err
@@ -554,7 +545,7 @@ fn sync_github_repo(
     repo: &str,
     source_root: &Path,
     shared_root: &Path,
-    shared_secrets: &[(String, String, String)],
+    shared_secrets: &[GithubSecret],
     yes: bool,
     synced: &mut usize,
 ) -> Result<(), Box<dyn Error>> {
@@ -600,9 +591,7 @@ fn sync_github_repo(
     Ok(())
 }
 
-fn collect_github_target_secrets(
-    target: &Path,
-) -> Result<Vec<(String, String, String)>, Box<dyn Error>> {
+fn collect_github_target_secrets(target: &Path) -> Result<Vec<GithubSecret>, Box<dyn Error>> {
     if !target.exists() {
         return Ok(Vec::new());
     }
@@ -618,7 +607,7 @@ fn collect_github_target_secrets(
 fn collect_github_dir_secrets(
     root: &Path,
     current: &Path,
-    out: &mut Vec<(String, String, String)>,
+    out: &mut Vec<GithubSecret>,
 ) -> Result<(), Box<dyn Error>> {
     for entry in fs::read_dir(current)? {
         let path = entry?.path();
@@ -634,7 +623,7 @@ fn collect_github_dir_secrets(
 fn collect_github_file_secrets(
     root: &Path,
     path: &Path,
-) -> Result<Vec<(String, String, String)>, Box<dyn Error>> {
+) -> Result<Vec<GithubSecret>, Box<dyn Error>> {
     let contents = fs::read_to_string(path)?;
     if path.extension().and_then(|value| value.to_str()) == Some("json") {
         let secrets = parse_github_secret_map(&contents, path)?;
@@ -797,14 +786,11 @@ fn set_github_secret(
         return Ok(());
     }
 
+    let repo_slug = format!("{}/{}", owner, repo);
+    let gh_args = ["secret", "set", secret_name, "--repo", &repo_slug];
+    crate::logging::log_command("gh", &gh_args);
     let mut child = Command::new("gh")
-        .args([
-            "secret",
-            "set",
-            secret_name,
-            "--repo",
-            &format!("{}/{}", owner, repo),
-        ])
+        .args(gh_args)
         .stdin(Stdio::piped())
         .spawn()?;
     if let Some(mut stdin) = child.stdin.take() {
@@ -871,7 +857,6 @@ fn check_tags_need_update(
         filters: Some(vec![Filter {
             key: Some("name".to_string()),
             values: Some(vec![secret_name.to_string()]),
-            ..Default::default()
         }]),
         ..Default::default()
     };
@@ -938,7 +923,6 @@ fn delete_missing_secrets(
             filters: Some(vec![Filter {
                 key: Some("tag-key".to_string()),
                 values: Some(vec!["hops.ops.com.ai/secret".to_string()]),
-                ..Default::default()
             }]),
             ..Default::default()
         })) {
@@ -0,0 +1,295 @@
+use crate::commands::config::Builder;
+use crate::commands::render::{read_documents, RenderContext, RenderInput};
+use clap::Args;
+use colored::Colorize;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const XR_FILE: &str = "xr.yaml";
+const EXPECTED_FILE: &str = "expected.yaml";
+const OBSERVED_FILE: &str = "observed.yaml";
+const EXTRA_RESOURCES_FILE: &str = "extra-resources.yaml";
+const COMPOSITION_RESOURCE_NAME: &str = "crossplane.io/composition-resource-name";
+
+#[derive(Args, Debug)]
+pub struct TestArgs {
+    /// Path to the XRD project directory (defaults to current directory)
+    #[arg(long, default_value = ".")]
+    pub path: PathBuf,
+
+    /// Directory of test cases, relative to the project: one subdirectory per
+    /// case with xr.yaml, expected.yaml, and optionally observed.yaml and
+    /// extra-resources.yaml
+    #[arg(long, default_value = "tests")]
+    pub tests_dir: PathBuf,
+
+    /// Only run cases whose name contains this string
+    #[arg(long)]
+    pub filter: Option<String>,
+
+    /// Write the rendered output to expected.yaml instead of comparing
+    #[arg(long)]
+    pub update: bool,
+
+    /// Function manifests to use instead of the resolved ones (see `hops render`)
+    #[arg(long)]
+    pub functions: Option<PathBuf>,
+
+    /// Tool used to build the project's embedded functions
+    #[arg(long, value_enum, default_value_t = Builder::Auto)]
+    pub builder: Builder,
+}
+
+pub fn run(args: &TestArgs) -> Result<(), Box<dyn Error>> {
+    let tests_dir = args.path.join(&args.tests_dir);
+    let cases = discover_cases(&tests_dir, args.filter.as_deref())?;
+    if cases.is_empty() {
+        return Err(format!(
+            "no test cases in {} (each case is a directory with {})",
+            tests_dir.display(),
+            XR_FILE
+        )
+        .into());
+    }
+    let context = RenderContext::prepare(&args.path, args.builder, args.functions.as_deref())?;
+
+    let mut failed = Vec::new();
+    for case in &cases {
+        let name = case.file_name().unwrap_or_default().to_string_lossy();
+        let optional = |file: &str| Some(case.join(file)).filter(|path| path.is_file());
+        let observed = optional(OBSERVED_FILE);
+        let extra = optional(EXTRA_RESOURCES_FILE);
+        let rendered = context.render(&RenderInput {
+            xr: &case.join(XR_FILE),
+            composition: None,
+            observed_resources: observed.as_deref(),
+            extra_resources: extra.as_deref(),
+        });
+        let rendered = match rendered {
+            Ok(rendered) => rendered,
+            Err(err) => {
+                println!("{} {}: {}", "FAIL".red().bold(), name, err);
+                failed.push(name.to_string());
+                continue;
+            }
+        };
+
+        let expected_path = case.join(EXPECTED_FILE);
+        if args.update {
+            fs::write(&expected_path, &rendered)?;
+            println!("{} {}", "UPDATED".yellow().bold(), name);
+            continue;
+        }
+        if !expected_path.is_file() {
+            println!(
+                "{} {}: no {} (run with --update to record it)",
+                "FAIL".red().bold(),
+                name,
+                EXPECTED_FILE
+            );
+            failed.push(name.to_string());
+            continue;
+        }
+        let expected = read_documents(&expected_path)?;
+        let actual = parse_documents(&rendered)?;
+        let differences = compare_documents(&expected, &actual);
+        if differences.is_empty() {
+            println!("{} {}", "PASS".green().bold(), name);
+        } else {
+            println!("{} {}", "FAIL".red().bold(), name);
+            for difference in &differences {
+                println!("  {}", difference);
+            }
+            failed.push(name.to_string());
+        }
+    }
+
+    if args.update {
+        crate::logging::success(&format!("Updated {} golden file(s)", cases.len()));
+        return Ok(());
+    }
+    if failed.is_empty() {
+        crate::logging::success(&format!("{} test case(s) passed", cases.len()));
+        Ok(())
+    } else {
+        Err(format!(
+            "{} of {} test case(s) failed: {}",
+            failed.len(),
+            cases.len(),
+            failed.join(", ")
+        )
+        .into())
+    }
+}
+
+/// Case directories (those holding xr.yaml), sorted by name.
+fn discover_cases(tests_dir: &Path, filter: Option<&str>) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let entries = fs::read_dir(tests_dir)
+        .map_err(|err| format!("failed to read {}: {}", tests_dir.display(), err))?;
+    let mut cases: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.join(XR_FILE).is_file())
+        .filter(|path| {
+            filter.is_none_or(|filter| {
+                path.file_name()
+                    .is_some_and(|name| name.to_string_lossy().contains(filter))
+            })
+        })
+        .collect();
+    cases.sort();
+    Ok(cases)
+}
+
+fn parse_documents(yaml: &str) -> Result<Vec<JsonValue>, Box<dyn Error>> {
+    let mut documents = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(yaml) {
+        let value = JsonValue::deserialize(document)?;
+        if !value.is_null() {
+            documents.push(value);
+        }
+    }
+    Ok(documents)
+}
+
+/// `apiVersion kind name`, naming composed resources by their composition
+/// resource name since their metadata.name is generated.
+fn document_key(document: &JsonValue) -> String {
+    let name = document
+        .pointer("/metadata/annotations")
+        .and_then(|annotations| annotations.get(COMPOSITION_RESOURCE_NAME))
+        .or_else(|| document.pointer("/metadata/name"))
+        .and_then(JsonValue::as_str)
+        .unwrap_or("<unnamed>");
+    format!(
+        "{} {} {}",
+        document["apiVersion"].as_str().unwrap_or_default(),
+        document["kind"].as_str().unwrap_or_default(),
+        name
+    )
+}
+
+/// Human-readable differences between the golden and rendered documents.
+pub fn compare_documents(expected: &[JsonValue], actual: &[JsonValue]) -> Vec<String> {
+    let mut differences = Vec::new();
+    for document in expected {
+        let key = document_key(document);
+        match actual
+            .iter()
+            .find(|candidate| document_key(candidate) == key)
+        {
+            None => differences.push(format!("missing    {}", key)),
+            Some(rendered) => {
+                let mut fields = Vec::new();
+                diff_values("", document, rendered, &mut fields);
+                if !fields.is_empty() {
+                    differences.push(format!("changed    {}", key));
+                    differences.extend(fields.into_iter().map(|field| format!("  {}", field)));
+                }
+            }
+        }
+    }
+    for document in actual {
+        let key = document_key(document);
+        if !expected
+            .iter()
+            .any(|candidate| document_key(candidate) == key)
+        {
+            differences.push(format!("unexpected {}", key));
+        }
+    }
+    differences
+}
+
+/// Field paths where `actual` differs from `expected`, e.g.
+/// `spec.forProvider.region: expected "us-east-1", got "us-west-2"`.
+fn diff_values(path: &str, expected: &JsonValue, actual: &JsonValue, out: &mut Vec<String>) {
+    let child = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    match (expected, actual) {
+        (JsonValue::Object(expected), JsonValue::Object(actual)) => {
+            for (key, value) in expected {
+                diff_values(
+                    &child(key),
+                    value,
+                    actual.get(key).unwrap_or(&JsonValue::Null),
+                    out,
+                );
+            }
+            for (key, value) in actual {
+                if !expected.contains_key(key) {
+                    diff_values(&child(key), &JsonValue::Null, value, out);
+                }
+            }
+        }
+        (JsonValue::Array(expected), JsonValue::Array(actual))
+            if expected.len() == actual.len() =>
+        {
+            for (index, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                diff_values(&format!("{}[{}]", path, index), expected, actual, out);
+            }
+        }
+        _ if expected != actual => out.push(format!(
+            "{}: expected {}, got {}",
+            path,
+            describe(expected),
+            describe(actual)
+        )),
+        _ => {}
+    }
+}
+
+fn describe(value: &JsonValue) -> String {
+    match value {
+        JsonValue::Null => "nothing".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn compare_documents_reports_fields_and_resources() {
+        let bucket = |region: &str| {
+            json!({
+                "apiVersion": "s3.aws.upbound.io/v1beta1",
+                "kind": "Bucket",
+                "metadata": {
+                    "generateName": "demo-",
+                    "annotations": {COMPOSITION_RESOURCE_NAME: "bucket"}
+                },
+                "spec": {"forProvider": {"region": region, "tags": ["a"]}}
+            })
+        };
+        let xr = json!({"apiVersion": "example.org/v1", "kind": "XBucket", "metadata": {"name": "demo"}});
+        let queue = json!({"apiVersion": "sqs.aws.upbound.io/v1beta1", "kind": "Queue", "metadata": {"name": "q"}});
+
+        assert!(compare_documents(
+            &[xr.clone(), bucket("us-east-1")],
+            &[xr.clone(), bucket("us-east-1")]
+        )
+        .is_empty());
+        assert_eq!(
+            compare_documents(
+                &[xr.clone(), bucket("us-east-1")],
+                &[xr, bucket("us-west-2"), queue]
+            ),
+            [
+                "changed    s3.aws.upbound.io/v1beta1 Bucket bucket",
+                "  spec.forProvider.region: expected \"us-east-1\", got \"us-west-2\"",
+                "unexpected sqs.aws.upbound.io/v1beta1 Queue q",
+            ]
+        );
+    }
+}
@@ -1,12 +1,23 @@
 mod generate_configuration;
+mod project;
+
+pub use project::validate_project;
 
 use clap::{Args, Subcommand};
 use std::error::Error;
+use std::path::PathBuf;
 
 #[derive(Args, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct ValidateArgs {
     #[command(subcommand)]
-    pub command: ValidateCommands,
+    pub command: Option<ValidateCommands>,
+
+    /// Project to check when no subcommand is given: XRD and Composition
+    /// structure, example XRs against their XRD schemas, connection details,
+    /// and dependsOn entries (defaults to current directory)
+    #[arg(long, default_value = ".")]
+    pub path: PathBuf,
 }
 
 #[derive(Subcommand, Debug)]
@@ -17,8 +28,9 @@ pub enum ValidateCommands {
 
 pub fn run(args: &ValidateArgs) -> Result<(), Box<dyn Error>> {
     match &args.command {
-        ValidateCommands::GenerateConfiguration(generate_args) => {
+        Some(ValidateCommands::GenerateConfiguration(generate_args)) => {
             generate_configuration::run(generate_args)
         }
+        None => validate_project(&args.path),
     }
 }
@@ -0,0 +1,711 @@
+use crate::commands::config::{package_dependencies, strip_registry};
+use crate::commands::render::{read_documents, yaml_files};
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const META_FILES: &[&str] = &["upbound.yaml", "crossplane.yaml", "package/crossplane.yaml"];
+const XRD_KIND: &str = "CompositeResourceDefinition";
+const COMPOSITION_KIND: &str = "Composition";
+/// Fields Crossplane adds to every XR and claim spec, which XRD schemas
+/// don't declare.
+const CROSSPLANE_SPEC_FIELDS: &[&str] = &[
+    "claimRef",
+    "compositeDeletePolicy",
+    "compositionRef",
+    "compositionRevisionRef",
+    "compositionRevisionSelector",
+    "compositionSelector",
+    "compositionUpdatePolicy",
+    "crossplane",
+    "environmentConfigRefs",
+    "publishConnectionDetailsTo",
+    "resourceRef",
+    "resourceRefs",
+    "writeConnectionSecretToRef",
+];
+const CONNECTION_DETAIL_TYPES: &[(&str, &str)] = &[
+    ("FromConnectionSecretKey", "fromConnectionSecretKey"),
+    ("FromFieldPath", "fromFieldPath"),
+    ("FromValue", "value"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug)]
+struct Issue {
+    severity: Severity,
+    file: PathBuf,
+    message: String,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.file.display(), self.message)
+    }
+}
+
+/// A YAML document and the project file it came from.
+struct Manifest {
+    file: PathBuf,
+    document: JsonValue,
+}
+
+/// Check a project's XRDs, Compositions, examples, and `dependsOn` before
+/// building it: logs every finding and fails if any is an error.
+pub fn validate_project(dir: &Path) -> Result<(), Box<dyn Error>> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()).into());
+    }
+    let issues = check_project(dir);
+    for issue in &issues {
+        match issue.severity {
+            Severity::Error => log::error!("{}", issue),
+            Severity::Warning => log::warn!("{}", issue),
+        }
+    }
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(format!(
+            "{} failed validation with {} error(s)",
+            dir.display(),
+            errors
+        )
+        .into());
+    }
+    crate::logging::success(&format!(
+        "{} is valid ({} warning(s))",
+        dir.display(),
+        issues.len()
+    ));
+    Ok(())
+}
+
+fn check_project(dir: &Path) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let mut manifests = Vec::new();
+    for file in yaml_files(dir) {
+        let relative = file.strip_prefix(dir).unwrap_or(&file).to_path_buf();
+        match read_documents(&file) {
+            Ok(documents) => manifests.extend(documents.into_iter().map(|document| Manifest {
+                file: relative.clone(),
+                document,
+            })),
+            Err(err) => issues.push(Issue {
+                severity: Severity::Warning,
+                file: relative,
+                message: format!("skipped: {}", err),
+            }),
+        }
+    }
+
+    let meta = META_FILES
+        .iter()
+        .find_map(|file| Some((*file, fs::read_to_string(dir.join(file)).ok()?)));
+    let dependencies = match &meta {
+        Some((file, contents)) => {
+            check_dependencies(file, contents, &mut issues);
+            package_dependencies(contents)
+        }
+        None => BTreeMap::new(),
+    };
+    let embedded_functions = embedded_functions(dir);
+
+    let xrds: Vec<&Manifest> = manifests
+        .iter()
+        .filter(|manifest| kind(&manifest.document) == XRD_KIND)
+        .collect();
+    for manifest in &manifests {
+        let mut problems = Vec::new();
+        let document = &manifest.document;
+        check_type_meta(document, &mut problems);
+        match kind(document) {
+            XRD_KIND => check_xrd(document, &mut problems),
+            COMPOSITION_KIND => check_composition(
+                document,
+                &xrds,
+                &dependencies,
+                &embedded_functions,
+                &mut problems,
+            ),
+            _ => {
+                if let Some(schema) = xr_schema(document, &xrds) {
+                    let mut path = "spec".to_string();
+                    check_schema(&document["spec"], schema, &mut path, true, &mut problems);
+                }
+            }
+        }
+        issues.extend(problems.into_iter().map(|(severity, message)| Issue {
+            severity,
+            file: manifest.file.clone(),
+            message: format!("{}: {}", describe(document), message),
+        }));
+    }
+    issues
+}
+
+/// Names of the functions under `functions/` that `up project build` embeds.
+fn embedded_functions(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir.join("functions"))
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| normalize(&entry.file_name().to_string_lossy()))
+        .collect()
+}
+
+fn kind(document: &JsonValue) -> &str {
+    document["kind"].as_str().unwrap_or_default()
+}
+
+fn describe(document: &JsonValue) -> String {
+    let name = document
+        .pointer("/metadata/name")
+        .and_then(JsonValue::as_str)
+        .unwrap_or("<unnamed>");
+    match kind(document) {
+        "" => name.to_string(),
+        kind => format!("{} {}", kind, name),
+    }
+}
+
+/// Documents that look like Kubernetes objects must say what they are.
+fn check_type_meta(document: &JsonValue, problems: &mut Vec<(Severity, String)>) {
+    let looks_like_object = ["apiVersion", "kind", "metadata"]
+        .iter()
+        .any(|key| document.get(key).is_some());
+    if !looks_like_object {
+        return;
+    }
+    for key in ["apiVersion", "kind"] {
+        if document[key].as_str().is_none_or(str::is_empty) {
+            problems.push((Severity::Error, format!("missing {}", key)));
+        }
+    }
+}
+
+fn check_xrd(xrd: &JsonValue, problems: &mut Vec<(Severity, String)>) {
+    let mut error = |message: String| problems.push((Severity::Error, message));
+    let group = xrd.pointer("/spec/group").and_then(JsonValue::as_str);
+    let plural = xrd
+        .pointer("/spec/names/plural")
+        .and_then(JsonValue::as_str);
+    for (field, value) in [
+        ("spec.group", group),
+        (
+            "spec.names.kind",
+            xrd.pointer("/spec/names/kind").and_then(JsonValue::as_str),
+        ),
+        ("spec.names.plural", plural),
+    ] {
+        if value.is_none() {
+            error(format!("missing {}", field));
+        }
+    }
+    if let (Some(group), Some(plural)) = (group, plural) {
+        let expected = format!("{}.{}", plural, group);
+        if xrd.pointer("/metadata/name").and_then(JsonValue::as_str) != Some(&expected) {
+            error(format!(
+                "metadata.name must be {} (<plural>.<group>)",
+                expected
+            ));
+        }
+    }
+    if let Some(claim_names) = xrd.pointer("/spec/claimNames") {
+        for field in ["kind", "plural"] {
+            if claim_names[field].as_str().is_none() {
+                error(format!("missing spec.claimNames.{}", field));
+            }
+        }
+    }
+    if let Some(keys) = xrd.pointer("/spec/connectionSecretKeys") {
+        let valid = keys
+            .as_array()
+            .is_some_and(|keys| keys.iter().all(JsonValue::is_string));
+        if !valid {
+            error("spec.connectionSecretKeys must be a list of strings".to_string());
+        }
+    }
+
+    let versions = xrd
+        .pointer("/spec/versions")
+        .and_then(JsonValue::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    if versions.is_empty() {
+        error("spec.versions is empty".to_string());
+        return;
+    }
+    let referenceable = versions
+        .iter()
+        .filter(|version| version["referenceable"] == true)
+        .count();
+    if referenceable != 1 {
+        error(format!(
+            "exactly one version must be referenceable, found {}",
+            referenceable
+        ));
+    }
+    for (index, version) in versions.iter().enumerate() {
+        let name = version["name"].as_str().unwrap_or("<unnamed>");
+        if version.pointer("/schema/openAPIV3Schema").is_none() {
+            error(format!(
+                "spec.versions[{}] ({}) has no schema.openAPIV3Schema",
+                index, name
+            ));
+        }
+    }
+}
+
+fn check_composition(
+    composition: &JsonValue,
+    xrds: &[&Manifest],
+    dependencies: &BTreeMap<String, String>,
+    embedded_functions: &[String],
+    problems: &mut Vec<(Severity, String)>,
+) {
+    let api_version = composition
+        .pointer("/spec/compositeTypeRef/apiVersion")
+        .and_then(JsonValue::as_str);
+    let xr_kind = composition
+        .pointer("/spec/compositeTypeRef/kind")
+        .and_then(JsonValue::as_str);
+    let mut xrd = None;
+    match (api_version, xr_kind) {
+        (Some(api_version), Some(xr_kind)) => {
+            let group = api_version.split('/').next().unwrap_or_default();
+            xrd = xrds
+                .iter()
+                .map(|manifest| &manifest.document)
+                .find(|xrd| xrd_serves(xrd, api_version, xr_kind));
+            let same_group = xrds
+                .iter()
+                .any(|manifest| manifest.document.pointer("/spec/group") == Some(&group.into()));
+            if xrd.is_none() {
+                // An XRD in another group may come from a dependency.
+                problems.push((
+                    if same_group {
+                        Severity::Error
+                    } else {
+                        Severity::Warning
+                    },
+                    format!(
+                        "compositeTypeRef {} {} matches no served version of an XRD in the project",
+                        api_version, xr_kind
+                    ),
+                ));
+            }
+        }
+        _ => problems.push((
+            Severity::Error,
+            "missing spec.compositeTypeRef.apiVersion or kind".to_string(),
+        )),
+    }
+
+    let mut resources: Vec<(String, &JsonValue)> = Vec::new();
+    let mode = composition
+        .pointer("/spec/mode")
+        .and_then(JsonValue::as_str)
+        .unwrap_or("Pipeline");
+    if mode == "Resources" {
+        for (index, resource) in array(composition.pointer("/spec/resources"))
+            .iter()
+            .enumerate()
+        {
+            resources.push((format!("spec.resources[{}]", index), resource));
+        }
+    } else {
+        let steps = array(composition.pointer("/spec/pipeline"));
+        if steps.is_empty() {
+            problems.push((Severity::Error, "spec.pipeline has no steps".to_string()));
+        }
+        let mut seen = BTreeSet::new();
+        for (index, step) in steps.iter().enumerate() {
+            let label = format!("spec.pipeline[{}]", index);
+            match step["step"].as_str() {
+                Some(name) if !seen.insert(name) => problems.push((
+                    Severity::Error,
+                    format!("{}: duplicate step name {}", label, name),
+                )),
+                Some(_) => {}
+                None => problems.push((Severity::Error, format!("{}: missing step", label))),
+            }
+            match step
+                .pointer("/functionRef/name")
+                .and_then(JsonValue::as_str)
+            {
+                Some(function) => {
+                    if !function_resolves(function, dependencies, embedded_functions) {
+                        problems.push((
+                            Severity::Warning,
+                            format!(
+                                "{}: function {} is neither a dependsOn entry nor an embedded function",
+                                label, function
+                            ),
+                        ));
+                    }
+                }
+                None => problems.push((
+                    Severity::Error,
+                    format!("{}: missing functionRef.name", label),
+                )),
+            }
+            // function-patch-and-transform input
+            for (resource_index, resource) in
+                array(step.pointer("/input/resources")).iter().enumerate()
+            {
+                resources.push((
+                    format!("{}.input.resources[{}]", label, resource_index),
+                    resource,
+                ));
+            }
+        }
+    }
+
+    let secret_keys: Option<Vec<&str>> = xrd
+        .and_then(|xrd| xrd.pointer("/spec/connectionSecretKeys"))
+        .and_then(JsonValue::as_array)
+        .map(|keys| keys.iter().filter_map(JsonValue::as_str).collect());
+    for (label, resource) in resources {
+        for (index, detail) in array(resource.get("connectionDetails")).iter().enumerate() {
+            let label = format!("{}.connectionDetails[{}]", label, index);
+            match connection_detail_name(detail) {
+                Ok(name) => {
+                    if let Some(keys) = &secret_keys {
+                        if !keys.contains(&name) {
+                            problems.push((
+                                Severity::Warning,
+                                format!(
+                                    "{}: {} is not in the XRD's connectionSecretKeys and will be dropped",
+                                    label, name
+                                ),
+                            ));
+                        }
+                    }
+                }
+                Err(message) => problems.push((Severity::Error, format!("{}: {}", label, message))),
+            }
+        }
+    }
+}
+
+/// The key a connection detail is published under, or what's wrong with it.
+fn connection_detail_name(detail: &JsonValue) -> Result<&str, String> {
+    let detail_type = match detail["type"].as_str() {
+        Some(detail_type) => detail_type,
+        None => CONNECTION_DETAIL_TYPES
+            .iter()
+            .find(|(_, field)| detail.get(field).is_some())
+            .map(|(detail_type, _)| *detail_type)
+            .ok_or("needs one of fromConnectionSecretKey, fromFieldPath, or value")?,
+    };
+    let Some((_, field)) = CONNECTION_DETAIL_TYPES
+        .iter()
+        .find(|(known, _)| *known == detail_type)
+    else {
+        return Err(format!("unknown type {}", detail_type));
+    };
+    if detail.get(field).is_none_or(JsonValue::is_null) {
+        return Err(format!("type {} needs {}", detail_type, field));
+    }
+    match detail["name"].as_str() {
+        Some(name) => Ok(name),
+        // The secret key doubles as the name.
+        None if detail_type == "FromConnectionSecretKey" => detail[field]
+            .as_str()
+            .ok_or_else(|| format!("{} must be a string", field)),
+        None => Err(format!("type {} needs a name", detail_type)),
+    }
+}
+
+fn function_resolves(
+    function: &str,
+    dependencies: &BTreeMap<String, String>,
+    embedded_functions: &[String],
+) -> bool {
+    let key = normalize(function);
+    dependencies
+        .keys()
+        .any(|package| normalize(strip_registry(package)) == key)
+        || embedded_functions
+            .iter()
+            .any(|embedded| key.ends_with(embedded.as_str()))
+}
+
+/// `spec.dependsOn` entries need a package and a version constraint, once each.
+fn check_dependencies(file: &str, contents: &str, issues: &mut Vec<Issue>) {
+    let Ok(meta) = serde_yaml::from_str::<JsonValue>(contents) else {
+        issues.push(Issue {
+            severity: Severity::Error,
+            file: PathBuf::from(file),
+            message: "invalid YAML".to_string(),
+        });
+        return;
+    };
+    let mut seen = BTreeSet::new();
+    for (index, dependency) in array(meta.pointer("/spec/dependsOn")).iter().enumerate() {
+        let mut error = |message: String| {
+            issues.push(Issue {
+                severity: Severity::Error,
+                file: PathBuf::from(file),
+                message: format!("spec.dependsOn[{}]: {}", index, message),
+            })
+        };
+        let Some(package) = ["package", "provider", "function", "configuration"]
+            .iter()
+            .find_map(|key| dependency[key].as_str())
+        else {
+            error("needs one of package, provider, function, or configuration".to_string());
+            continue;
+        };
+        if dependency.get("package").is_some()
+            && (dependency["apiVersion"].as_str().is_none()
+                || dependency["kind"].as_str().is_none())
+        {
+            error(format!("{} needs apiVersion and kind", package));
+        }
+        if !seen.insert(package) {
+            error(format!("{} is listed more than once", package));
+        }
+        match dependency["version"].as_str() {
+            Some(version) if is_version_constraint(version) => {}
+            Some(version) => error(format!(
+                "{} has an invalid version constraint {:?}",
+                package, version
+            )),
+            None => error(format!("{} has no version", package)),
+        }
+    }
+}
+
+/// A semver version or constraint such as `v1.2.3`, `>=v0.2.0, <1.0.0`,
+/// or `^1`.
+fn is_version_constraint(constraint: &str) -> bool {
+    let constraint = constraint.trim();
+    !constraint.is_empty()
+        && constraint.split(['|', ',']).all(|alternative| {
+            alternative.split_whitespace().all(|part| {
+                let version = part.trim_start_matches(['<', '>', '=', '!', '~', '^']);
+                let version = version.strip_prefix('v').unwrap_or(version);
+                let core = version.split(['-', '+']).next().unwrap_or_default();
+                !version.is_empty()
+                    && core.split('.').count() <= 3
+                    && core.split('.').all(|number| {
+                        number == "*"
+                            || number == "x"
+                            || (!number.is_empty() && number.chars().all(|c| c.is_ascii_digit()))
+                    })
+            })
+        })
+}
+
+fn xrd_serves(xrd: &JsonValue, api_version: &str, xr_kind: &str) -> bool {
+    let Some((group, version)) = api_version.split_once('/') else {
+        return false;
+    };
+    xrd.pointer("/spec/group") == Some(&group.into())
+        && xrd.pointer("/spec/names/kind") == Some(&xr_kind.into())
+        && array(xrd.pointer("/spec/versions"))
+            .iter()
+            .any(|served| served["name"] == version && served["served"] == true)
+}
+
+/// The spec schema of the XRD version an XR or claim document belongs to.
+fn xr_schema<'a>(document: &JsonValue, xrds: &[&'a Manifest]) -> Option<&'a JsonValue> {
+    let (group, version) = document["apiVersion"].as_str()?.split_once('/')?;
+    let document_kind = kind(document);
+    xrds.iter()
+        .map(|manifest| &manifest.document)
+        .filter(|xrd| xrd.pointer("/spec/group") == Some(&group.into()))
+        .filter(|xrd| {
+            xrd.pointer("/spec/names/kind") == Some(&document_kind.into())
+                || xrd.pointer("/spec/claimNames/kind") == Some(&document_kind.into())
+        })
+        .flat_map(|xrd| array(xrd.pointer("/spec/versions")))
+        .find(|served| served["name"] == version)?
+        .pointer("/schema/openAPIV3Schema/properties/spec")
+}
+
+/// Check `value` against an OpenAPI v3 schema: types, enums, required and
+/// unknown fields. `path` is the field path, extended while recursing.
+fn check_schema(
+    value: &JsonValue,
+    schema: &JsonValue,
+    path: &mut String,
+    is_spec: bool,
+    problems: &mut Vec<(Severity, String)>,
+) {
+    if value.is_null() {
+        return;
+    }
+    let preserve_unknown = schema["x-kubernetes-preserve-unknown-fields"] == true;
+    if schema["x-kubernetes-int-or-string"] == true {
+        if !value.is_i64() && !value.is_u64() && !value.is_string() {
+            problems.push((
+                Severity::Error,
+                format!("{}: expected an integer or string, got {}", path, value),
+            ));
+        }
+        return;
+    }
+    let expected = schema["type"].as_str().unwrap_or_default();
+    let matches = match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        _ => true,
+    };
+    if !matches {
+        problems.push((
+            Severity::Error,
+            format!("{}: expected {}, got {}", path, expected, value),
+        ));
+        return;
+    }
+    if let Some(allowed) = schema["enum"].as_array() {
+        if !allowed.contains(value) {
+            problems.push((
+                Severity::Error,
+                format!(
+                    "{}: {} is not one of {}",
+                    path,
+                    value,
+                    JsonValue::from(allowed.clone())
+                ),
+            ));
+        }
+    }
+
+    let len = path.len();
+    match value {
+        JsonValue::Object(fields) => {
+            for required in array(schema.get("required")) {
+                let required = required.as_str().unwrap_or_default();
+                if !fields.contains_key(required) {
+                    problems.push((
+                        Severity::Error,
+                        format!("{}.{}: required field is missing", path, required),
+                    ));
+                }
+            }
+            let properties = schema["properties"].as_object();
+            for (key, field) in fields {
+                path.push('.');
+                path.push_str(key);
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(field_schema) => check_schema(field, field_schema, path, false, problems),
+                    None if schema["additionalProperties"].is_object() => check_schema(
+                        field,
+                        &schema["additionalProperties"],
+                        path,
+                        false,
+                        problems,
+                    ),
+                    None if preserve_unknown
+                        || schema["additionalProperties"] == true
+                        || (is_spec && CROSSPLANE_SPEC_FIELDS.contains(&key.as_str())) => {}
+                    None if properties.is_some() => {
+                        problems.push((Severity::Error, format!("{}: unknown field", path)))
+                    }
+                    None => {}
+                }
+                path.truncate(len);
+            }
+        }
+        JsonValue::Array(items) if schema["items"].is_object() => {
+            for (index, item) in items.iter().enumerate() {
+                path.push_str(&format!("[{}]", index));
+                check_schema(item, &schema["items"], path, false, problems);
+                path.truncate(len);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn array(value: Option<&JsonValue>) -> &[JsonValue] {
+    value
+        .and_then(JsonValue::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn check_schema_reports_types_required_and_unknown_fields() {
+        let schema = json!({
+            "type": "object",
+            "required": ["region"],
+            "properties": {
+                "region": {"type": "string", "enum": ["us-east-1", "us-west-2"]},
+                "size": {"type": "integer"},
+                "tags": {"type": "object", "additionalProperties": {"type": "string"}}
+            }
+        });
+        let spec = json!({
+            "region": "eu-west-1",
+            "size": "large",
+            "tags": {"team": 7},
+            "regoin": "us-east-1",
+            "compositionRef": {"name": "default"}
+        });
+        let mut problems = Vec::new();
+        check_schema(&spec, &schema, &mut "spec".to_string(), true, &mut problems);
+        let messages: Vec<&str> = problems
+            .iter()
+            .map(|(_, message)| message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "spec.region: \"eu-west-1\" is not one of [\"us-east-1\",\"us-west-2\"]",
+                "spec.regoin: unknown field",
+                "spec.size: expected integer, got \"large\"",
+                "spec.tags.team: expected string, got 7",
+            ]
+        );
+    }
+
+    #[test]
+    fn connection_detail_name_checks_type_fields() {
+        assert_eq!(
+            connection_detail_name(&json!({"fromConnectionSecretKey": "endpoint"})),
+            Ok("endpoint")
+        );
+        assert_eq!(
+            connection_detail_name(&json!({"type": "FromFieldPath", "name": "arn"})),
+            Err("type FromFieldPath needs fromFieldPath".to_string())
+        );
+        assert_eq!(
+            connection_detail_name(&json!({"value": "5432"})),
+            Err("type FromValue needs a name".to_string())
+        );
+        assert!(is_version_constraint(">=v0.2.0, <1.0.0"));
+        assert!(!is_version_constraint("latest"));
+    }
+}
@@ -218,7 +218,7 @@ fn populate_autoekscluster_adoption_patch(
     Ok(())
 }
 
-fn composition_resource_name<'a>(item: &'a JsonValue) -> Option<&'a str> {
+fn composition_resource_name(item: &JsonValue) -> Option<&str> {
     item.get("metadata")
         .and_then(|m| m.get("annotations"))
         .and_then(JsonValue::as_object)
@@ -229,7 +229,7 @@ fn composition_resource_name<'a>(item: &'a JsonValue) -> Option<&'a str> {
         .and_then(JsonValue::as_str)
 }
 
-fn external_name_annotation<'a>(item: &'a JsonValue) -> Option<&'a str> {
+fn external_name_annotation(item: &JsonValue) -> Option<&str> {
     item.get("metadata")
         .and_then(|m| m.get("annotations"))
         .and_then(|ann| ann.get("crossplane.io/external-name"))
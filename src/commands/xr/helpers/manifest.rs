@@ -11,8 +11,8 @@ pub(crate) fn load_specs() -> Result<Vec<ReclaimSpec>, Box<dyn Error>> {
     load_cluster_specs()
 }
 
-pub(crate) fn match_spec<'a>(
-    specs: &'a [ReclaimSpec],
+pub(crate) fn match_spec(
+    specs: &[ReclaimSpec],
     needle: &str,
 ) -> Result<ReclaimSpec, Box<dyn Error>> {
     let needle_lower = normalize_identity(needle);
@@ -303,11 +303,8 @@ pub(crate) fn log_report(report: &ReclaimReport, live_aws: bool) {
 }
 
 pub(crate) fn emit_report(
-    spec: &ReclaimSpec,
+    report: &ReclaimReport,
     manifest: &Value,
-    live_notes: &[String],
-    cluster_notes: &[String],
-    source: ManifestSource,
     output: Option<&str>,
     apply: bool,
     label: &str,
@@ -317,14 +314,7 @@ pub(crate) fn emit_report(
         manifest_yaml = manifest_yaml.replacen("---\n", "", 1);
     }
 
-    let report = ReclaimReport {
-        spec: spec.clone(),
-        live_notes: live_notes.to_vec(),
-        cluster_notes: cluster_notes.to_vec(),
-        source,
-    };
-
-    log_report(&report, true);
+    log_report(report, true);
 
     if let Some(output) = output {
         fs::write(output, &manifest_yaml)?;
@@ -333,7 +323,7 @@ pub(crate) fn emit_report(
 
     if apply {
         kubectl_apply_stdin(&manifest_yaml)?;
-        log::info!("{label} applied to cluster");
+        crate::logging::success(&format!("{label} applied to cluster"));
     } else if output.is_none() {
         print!("{manifest_yaml}");
     }
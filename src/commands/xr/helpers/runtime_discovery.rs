@@ -145,6 +145,7 @@ fn resolve_function_source_dir(image: &str) -> Result<Option<String>, Box<dyn Er
 }
 
 fn export_function_filesystem(image: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    crate::logging::log_command("docker", &["create", image]);
     let create_output = Command::new("docker").args(["create", image]).output()?;
     if !create_output.status.success() {
         return Err(format!(
@@ -158,6 +159,7 @@ fn export_function_filesystem(image: &str) -> Result<Vec<u8>, Box<dyn Error>> {
     let container_id = String::from_utf8_lossy(&create_output.stdout)
         .trim()
         .to_string();
+    crate::logging::log_command("docker", &["export", &container_id]);
     let export_result = Command::new("docker")
         .args(["export", &container_id])
         .output();
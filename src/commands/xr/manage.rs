@@ -38,7 +38,7 @@ pub(crate) fn run(args: &ManageXrArgs) -> Result<(), Box<dyn Error>> {
 
     if args.apply {
         kubectl_patch_merge(&resource, &args.name, &args.namespace, &patch_json)?;
-        log::info!("applied managed XR merge patch to the cluster");
+        crate::logging::success("applied managed XR merge patch to the cluster");
     } else if args.output.is_none() {
         print!("{rendered_manifest}");
     }
@@ -4,7 +4,9 @@ use crate::commands::xr::helpers::manifest::{
     emit_report, ensure_mapping, load_specs, match_spec, render_manifest,
     sanitize_manifest_defaults, set_observe_only_management, strip_external_name_fields, vs,
 };
-use crate::commands::xr::helpers::types::{ManifestSource, ObserveArgs, ReclaimSpec};
+use crate::commands::xr::helpers::types::{
+    ManifestSource, ObserveArgs, ReclaimReport, ReclaimSpec,
+};
 use serde_json::Value as JsonValue;
 use serde_yaml::Value;
 use std::error::Error;
@@ -28,12 +30,15 @@ pub(crate) fn run(args: &ObserveArgs) -> Result<(), Box<dyn Error>> {
     strip_external_name_fields(&mut manifest);
     set_observe_only_management(&mut manifest);
 
+    let report = ReclaimReport {
+        spec,
+        live_notes,
+        cluster_notes: vec!["generated bootstrap observe-only manifest".to_string()],
+        source: ManifestSource::Generated,
+    };
     emit_report(
-        &spec,
+        &report,
         &manifest,
-        &live_notes,
-        &["generated bootstrap observe-only manifest".to_string()],
-        ManifestSource::Generated,
         args.output.as_deref(),
         args.apply,
         "observe manifest",
@@ -349,7 +354,7 @@ fn select_nodepool(
     }
 }
 
-fn json_name<'a>(value: &'a JsonValue) -> Option<&'a str> {
+fn json_name(value: &JsonValue) -> Option<&str> {
     value
         .get("metadata")
         .and_then(|metadata| metadata.get("name"))
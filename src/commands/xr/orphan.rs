@@ -43,7 +43,7 @@ pub(crate) fn run(args: &OrphanArgs) -> Result<(), Box<dyn Error>> {
 
     if args.apply {
         kubectl_patch_merge(&resource, &args.name, &args.namespace, &patch_json)?;
-        log::info!("applied XR orphaning merge patch to the cluster");
+        crate::logging::success("applied XR orphaning merge patch to the cluster");
     } else if args.output.is_none() {
         print!("{rendered_manifest}");
     }
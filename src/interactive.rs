@@ -0,0 +1,66 @@
+use std::error::Error;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ASSUME_YES: AtomicBool = AtomicBool::new(false);
+
+/// Record the global `--yes/--non-interactive` flag.
+pub fn set_assume_yes(value: bool) {
+    ASSUME_YES.store(value, Ordering::Relaxed);
+}
+
+/// True when `--yes/--non-interactive` was passed.
+pub fn assume_yes() -> bool {
+    ASSUME_YES.load(Ordering::Relaxed)
+}
+
+/// True when prompts may be shown: both stdin and stdout are terminals and
+/// `--yes/--non-interactive` was not passed.
+pub fn prompts_allowed() -> bool {
+    !assume_yes() && io::stdin().is_terminal() && io::stdout().is_terminal()
+}
+
+/// Ask a yes/no question. `--yes` answers yes without prompting; without a
+/// terminal this fails fast instead of blocking on stdin.
+pub fn confirm(prompt: &str, default: bool) -> Result<bool, Box<dyn Error>> {
+    if assume_yes() {
+        log::info!("{} yes (--yes)", prompt);
+        return Ok(true);
+    }
+
+    if !prompts_allowed() {
+        return Err(format!(
+            "confirmation required (\"{}\") but no interactive terminal was detected; re-run with --yes to confirm non-interactively",
+            prompt
+        )
+        .into());
+    }
+
+    Ok(dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(default)
+        .interact()?)
+}
+
+/// Let the user pick one of `items` with a fuzzy-searchable list. Callers
+/// should check [`prompts_allowed`] first and fall back to their own error.
+pub fn fuzzy_select(prompt: &str, items: &[String]) -> Result<String, Box<dyn Error>> {
+    if items.is_empty() {
+        return Err(format!("{}: nothing to choose from", prompt).into());
+    }
+    let index = dialoguer::FuzzySelect::new()
+        .with_prompt(prompt)
+        .items(items)
+        .default(0)
+        .interact()?;
+    Ok(items[index].clone())
+}
+
+/// Read a line of text, e.g. a one-time code. Callers should check
+/// [`prompts_allowed`] first and fall back to their own error.
+pub fn input(prompt: &str) -> Result<String, Box<dyn Error>> {
+    let value: String = dialoguer::Input::new()
+        .with_prompt(prompt)
+        .interact_text()?;
+    Ok(value.trim().to_string())
+}
@@ -0,0 +1,174 @@
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static TRACKED: Mutex<Tracked> = Mutex::new(Tracked::new());
+
+/// What an interrupted run has to undo: child processes still running,
+/// temp files and half-written directories, and work it didn't finish.
+#[derive(Debug, Default)]
+struct Tracked {
+    children: BTreeMap<u64, u32>,
+    paths: BTreeMap<u64, PathBuf>,
+    pending: BTreeMap<u64, String>,
+}
+
+impl Tracked {
+    const fn new() -> Self {
+        Self {
+            children: BTreeMap::new(),
+            paths: BTreeMap::new(),
+            pending: BTreeMap::new(),
+        }
+    }
+}
+
+/// Drops a tracked child, path, or pending task from the cleanup list once
+/// the work it covers has ended on its own.
+#[must_use]
+pub struct Guard(u64);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        let mut tracked = tracked();
+        tracked.children.remove(&self.0);
+        tracked.paths.remove(&self.0);
+        tracked.pending.remove(&self.0);
+    }
+}
+
+fn tracked() -> MutexGuard<'static, Tracked> {
+    TRACKED.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn track(add: impl FnOnce(&mut Tracked, u64)) -> Guard {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    add(&mut tracked(), id);
+    Guard(id)
+}
+
+/// Terminate child process `pid` if the run is interrupted.
+pub fn child(pid: u32) -> Guard {
+    track(|tracked, id| {
+        tracked.children.insert(id, pid);
+    })
+}
+
+/// Remove `path` (a file or directory) if the run is interrupted before
+/// the guard is dropped.
+pub fn temp_path(path: &Path) -> Guard {
+    track(|tracked, id| {
+        tracked.paths.insert(id, path.to_path_buf());
+    })
+}
+
+/// Report `what` as left incomplete if the run is interrupted.
+pub fn pending(what: &str) -> Guard {
+    track(|tracked, id| {
+        tracked.pending.insert(id, what.to_string());
+    })
+}
+
+/// `command.output()`, with the child terminated on Ctrl-C.
+pub fn output(command: &mut Command) -> io::Result<Output> {
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let _guard = self::child(child.id());
+    child.wait_with_output()
+}
+
+/// `command.status()`, with the child terminated on Ctrl-C.
+pub fn status(command: &mut Command) -> io::Result<ExitStatus> {
+    let mut child = command.spawn()?;
+    let _guard = self::child(child.id());
+    child.wait()
+}
+
+/// Handle Ctrl-C (and SIGTERM) by terminating tracked child processes,
+/// removing tracked temp paths, and saying what was left incomplete before
+/// exiting with 128 + the signal number.
+pub fn install() {
+    let mut signals = match Signals::new([SIGINT, SIGTERM]) {
+        Ok(signals) => signals,
+        Err(err) => {
+            log::debug!("Could not install the interrupt handler: {}", err);
+            return;
+        }
+    };
+    thread::spawn(move || {
+        if let Some(signal) = signals.forever().next() {
+            let tracked = std::mem::take(&mut *tracked());
+            let incomplete = clean_up(tracked);
+            eprintln!();
+            if incomplete.is_empty() {
+                log::warn!("Interrupted");
+            } else {
+                log::warn!("Interrupted; left incomplete: {}", incomplete.join(", "));
+            }
+            std::process::exit(128 + signal);
+        }
+    });
+}
+
+/// Undo what `tracked` covers and return the tasks left incomplete.
+fn clean_up(tracked: Tracked) -> Vec<String> {
+    for pid in tracked.children.into_values() {
+        log::debug!("Stopping child process {}", pid);
+        let _ = Command::new("kill")
+            .args(["-TERM", &pid.to_string()])
+            .stderr(Stdio::null())
+            .status();
+    }
+    for path in tracked.paths.into_values() {
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match removed {
+            Ok(()) => log::debug!("Removed {}", path.display()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => log::warn!("Could not remove {}: {}", path.display(), err),
+        }
+    }
+    tracked.pending.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_up_removes_paths_and_reports_pending_work() {
+        let dir = std::env::temp_dir().join(format!("hops-interrupt-{}", std::process::id()));
+        fs::create_dir_all(dir.join("partial")).unwrap();
+        let file = dir.join("state.json.tmp");
+        fs::write(&file, "{").unwrap();
+
+        let guard = temp_path(&file);
+        assert!(tracked().paths.values().any(|path| *path == file));
+        drop(guard);
+        assert!(!tracked().paths.values().any(|path| *path == file));
+
+        let tracked = Tracked {
+            paths: BTreeMap::from([(0, file.clone()), (1, dir.join("partial"))]),
+            pending: BTreeMap::from([(2, "config install".to_string())]),
+            ..Tracked::default()
+        };
+        assert_eq!(clean_up(tracked), ["config install"]);
+        assert!(!file.exists());
+        assert!(!dir.join("partial").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,21 +1,94 @@
+use clap::ValueEnum;
 use colored::Colorize;
 use fern::Dispatch;
 use log::LevelFilter;
+use std::cell::RefCell;
 use std::env;
+use std::io::IsTerminal;
+use std::process::Output;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
 
-pub fn init_logging() -> Result<(), fern::InitError> {
-    // Read the desired log level from the environment variable `LOG_LEVEL`
-    // Default to "info" if not set.
-    let log_level = env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
-    // Parse the environment variable into a LevelFilter. If parsing fails, default to Info.
-    let level_filter = log_level
-        .parse::<LevelFilter>()
-        .unwrap_or(LevelFilter::Info);
-
-    Dispatch::new()
-        .level(level_filter)
-        .format(|out, message, record| {
+static DEBUG_COMMANDS: AtomicBool = AtomicBool::new(false);
+
+/// Subcommand path being run (e.g. "local start"), reported in JSON logs.
+static COMMAND: OnceLock<String> = OnceLock::new();
+/// Current step of a multi-step command, reported in JSON logs.
+static STEP: Mutex<Option<String>> = Mutex::new(None);
+
+thread_local! {
+    /// Step of the calling thread, for steps that run concurrently.
+    static THREAD_STEP: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Log target used by [`success`] so the formatter can mark completed steps.
+const SUCCESS_TARGET: &str = "hops::success";
+
+/// When to colorize output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Colorize when stderr is a terminal and NO_COLOR is unset
+    #[default]
+    Auto,
+    /// Always colorize
+    Always,
+    /// Never colorize
+    Never,
+}
+
+/// Log line format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable, leveled lines
+    #[default]
+    Text,
+    /// One JSON object per line with timestamp, level, command, and step
+    Json,
+}
+
+/// Logging options resolved from global CLI flags.
+#[derive(Clone, Debug, Default)]
+pub struct LogOptions {
+    /// Number of `-v` flags passed (1 = debug, 2+ = trace)
+    pub verbose: u8,
+    /// Only show warnings and errors
+    pub quiet: bool,
+    /// Echo every external command and its captured output
+    pub debug_commands: bool,
+    /// Color mode from `--color`
+    pub color: ColorChoice,
+    /// Line format from `--log-format`
+    pub format: LogFormat,
+    /// Subcommand path being run, reported in JSON logs
+    pub command: String,
+}
+
+pub fn init_logging(options: &LogOptions) -> Result<(), fern::InitError> {
+    let level_filter = resolve_level_filter(
+        options.verbose,
+        options.quiet,
+        env::var("LOG_LEVEL").ok().as_deref(),
+    );
+    DEBUG_COMMANDS.store(options.debug_commands, Ordering::Relaxed);
+    let _ = COMMAND.set(options.command.clone());
+    // JSON lines must stay free of ANSI escapes, whatever --color says.
+    colored::control::set_override(
+        options.format == LogFormat::Text
+            && resolve_color(
+                options.color,
+                env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+                std::io::stderr().is_terminal(),
+            ),
+    );
+
+    let dispatch = Dispatch::new().level(level_filter);
+    let dispatch = match options.format {
+        LogFormat::Text => dispatch.format(|out, message, record| {
             let level = match record.level() {
+                log::Level::Info if record.target() == SUCCESS_TARGET => {
+                    format!("{:>12}", "Success").green().bold()
+                }
                 log::Level::Error => format!("{:>12}", "Error").red().bold(),
                 log::Level::Warn => format!("{:>12}", "Warn").yellow().bold(),
                 log::Level::Info => format!("{:>12}", "Info").purple().bold(),
@@ -23,8 +96,195 @@ pub fn init_logging() -> Result<(), fern::InitError> {
                 log::Level::Trace => format!("{:>12}", "Trace").normal().bold(),
             };
             out.finish(format_args!("{} {}", level, message))
-        })
-        .chain(std::io::stderr())
-        .apply()?;
+        }),
+        LogFormat::Json => dispatch.format(|out, message, record| {
+            let line = json_line(
+                SystemTime::now(),
+                record.level(),
+                record.target() == SUCCESS_TARGET,
+                COMMAND.get().map(String::as_str).unwrap_or_default(),
+                current_step().as_deref(),
+                &message.to_string(),
+            );
+            out.finish(format_args!("{}", line))
+        }),
+    };
+    dispatch.chain(std::io::stderr()).apply()?;
     Ok(())
 }
+
+/// Pick the log level: `-q` and `-v` flags win over the `LOG_LEVEL`
+/// environment variable, which defaults to "info".
+fn resolve_level_filter(verbose: u8, quiet: bool, env_level: Option<&str>) -> LevelFilter {
+    match (quiet, verbose) {
+        (true, _) => LevelFilter::Warn,
+        (false, 0) => env_level
+            .and_then(|level| level.parse::<LevelFilter>().ok())
+            .unwrap_or(LevelFilter::Info),
+        (false, 1) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    }
+}
+
+/// Render one structured log event as a single JSON line.
+fn json_line(
+    timestamp: SystemTime,
+    level: log::Level,
+    success: bool,
+    command: &str,
+    step: Option<&str>,
+    message: &str,
+) -> String {
+    serde_json::json!({
+        "timestamp": humantime::format_rfc3339_millis(timestamp).to_string(),
+        "level": level.as_str().to_ascii_lowercase(),
+        "success": success,
+        "command": command,
+        "step": step,
+        "message": message,
+    })
+    .to_string()
+}
+
+/// Mark the start of a named step; it is attached to every following JSON
+/// log line until the next call. Lines logged from a thread that set its
+/// own step carry that one, so concurrent steps stay apart.
+pub fn set_step(step: &str) {
+    THREAD_STEP.with(|current| *current.borrow_mut() = Some(step.to_string()));
+    if let Ok(mut current) = STEP.lock() {
+        *current = Some(step.to_string());
+    }
+}
+
+fn current_step() -> Option<String> {
+    THREAD_STEP
+        .with(|current| current.borrow().clone())
+        .or_else(|| STEP.lock().ok().and_then(|current| current.clone()))
+}
+
+/// Decide whether to colorize: `--color always/never` win, otherwise color
+/// is used only on a terminal without NO_COLOR set.
+fn resolve_color(choice: ColorChoice, no_color: bool, is_terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => !no_color && is_terminal,
+    }
+}
+
+/// Log a completed step at info level with a success marker.
+pub fn success(message: &str) {
+    log::info!(target: SUCCESS_TARGET, "{}", message);
+}
+
+/// Log an external command before it runs. With `--debug-commands` the
+/// command is echoed at info level; otherwise it is only a debug message.
+pub fn log_command<S: AsRef<str>>(program: &str, args: &[S]) {
+    let args = args.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(" ");
+    if DEBUG_COMMANDS.load(Ordering::Relaxed) {
+        log::info!("{} {} {}", "$".bold(), program, args);
+    } else {
+        log::debug!("Running: {} {}", program, args);
+    }
+}
+
+/// Echo captured command output when `--debug-commands` is enabled.
+pub fn log_command_output(program: &str, output: &Output) {
+    if !DEBUG_COMMANDS.load(Ordering::Relaxed) {
+        return;
+    }
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        log::info!("{} | {}", program, line);
+    }
+    log_stderr_and_status(program, output);
+}
+
+/// [`log_command_output`] for commands whose stdout holds credentials: only
+/// its size is echoed.
+pub fn log_secret_command_output(program: &str, output: &Output) {
+    if !DEBUG_COMMANDS.load(Ordering::Relaxed) {
+        return;
+    }
+    log::info!("{} | {}", program, redacted(&output.stdout));
+    log_stderr_and_status(program, output);
+}
+
+fn log_stderr_and_status(program: &str, output: &Output) {
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        log::info!("{} ! {}", program, line);
+    }
+    log::info!("{} exited with {}", program, output.status);
+}
+
+fn redacted(stdout: &[u8]) -> String {
+    format!("<redacted {} bytes>", stdout.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secret_output_is_reduced_to_its_size() {
+        assert_eq!(
+            redacted(br#"{"AccessKeyId":"AKIA","SecretAccessKey":"s"}"#),
+            "<redacted 44 bytes>"
+        );
+    }
+
+    #[test]
+    fn resolve_level_filter_prefers_flags_over_env() {
+        assert_eq!(resolve_level_filter(0, false, None), LevelFilter::Info);
+        assert_eq!(
+            resolve_level_filter(0, false, Some("debug")),
+            LevelFilter::Debug
+        );
+        assert_eq!(
+            resolve_level_filter(0, false, Some("nonsense")),
+            LevelFilter::Info
+        );
+        assert_eq!(
+            resolve_level_filter(1, false, Some("error")),
+            LevelFilter::Debug
+        );
+        assert_eq!(resolve_level_filter(2, false, None), LevelFilter::Trace);
+        assert_eq!(
+            resolve_level_filter(2, true, Some("trace")),
+            LevelFilter::Warn
+        );
+    }
+
+    #[test]
+    fn resolve_color_honors_flag_then_no_color() {
+        assert!(resolve_color(ColorChoice::Always, true, false));
+        assert!(!resolve_color(ColorChoice::Never, false, true));
+        assert!(resolve_color(ColorChoice::Auto, false, true));
+        assert!(!resolve_color(ColorChoice::Auto, true, true));
+        assert!(!resolve_color(ColorChoice::Auto, false, false));
+    }
+
+    #[test]
+    fn json_line_includes_structured_fields() {
+        let timestamp = SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(1_500);
+        let line = json_line(
+            timestamp,
+            log::Level::Warn,
+            false,
+            "local start",
+            Some("crossplane"),
+            "slow \"start\"",
+        );
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["timestamp"], "1970-01-01T00:00:01.500Z");
+        assert_eq!(value["level"], "warn");
+        assert_eq!(value["success"], false);
+        assert_eq!(value["command"], "local start");
+        assert_eq!(value["step"], "crossplane");
+        assert_eq!(value["message"], "slow \"start\"");
+
+        let line = json_line(timestamp, log::Level::Info, true, "", None, "done");
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert!(value["step"].is_null());
+        assert_eq!(value["success"], true);
+    }
+}
@@ -1,11 +1,49 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
 use std::error::Error;
 mod commands;
+mod interactive;
+mod interrupt;
 mod logging;
+mod notification;
+mod settings;
 
 #[derive(Parser, Debug)]
 #[command(version, about = "hops CLI", long_about = None)]
 struct Args {
+    /// Increase log verbosity (-v for debug, -vv for trace)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Only log warnings and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Echo every external command and its captured output
+    #[arg(long, global = true)]
+    debug_commands: bool,
+
+    /// When to colorize output (NO_COLOR is honored in auto mode)
+    #[arg(long, global = true, value_enum, default_value_t = logging::ColorChoice::Auto)]
+    color: logging::ColorChoice,
+
+    /// Log line format (json emits one object per line for CI and log collectors)
+    #[arg(long, global = true, value_enum, default_value_t = logging::LogFormat::Text)]
+    log_format: logging::LogFormat,
+
+    /// Answer yes to confirmations and fail instead of prompting for input
+    #[arg(
+        short = 'y',
+        long = "yes",
+        visible_alias = "non-interactive",
+        global = true
+    )]
+    yes: bool,
+
+    /// Send a desktop notification when long operations (local start,
+    /// config install/uninstall) finish or fail
+    #[arg(long, global = true)]
+    notify: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -18,19 +56,51 @@ enum Commands {
     Secrets(commands::secrets::SecretsArgs),
     /// Manage Crossplane configuration packages in the connected cluster
     Config(commands::config::ConfigArgs),
+    /// Create an XRD project from a template
+    New(commands::new::NewArgs),
+    /// Render an XR with a project's Composition and functions, locally
+    Render(commands::render::RenderArgs),
+    /// Render test-case XRs and compare them with golden files
+    Test(commands::test::TestArgs),
+    /// Generate CI workflows and other files for XRD projects
+    Generate(commands::generate::GenerateArgs),
     /// Manage validation helpers for Crossplane projects
     Validate(commands::validate::ValidateArgs),
     /// Manage live XR observe/manage/adopt workflows
     Xr(commands::xr::XrArgs),
     /// Install AI agent skills and configuration (Claude Code, Codex)
     Ai(commands::ai::AiArgs),
+    /// Check prerequisites, tool versions, disk space, ports, and kube context
+    Doctor(commands::doctor::DoctorArgs),
+}
+
+/// Space-separated names of the invoked subcommands, e.g. "local start".
+fn subcommand_path(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub)) = current.subcommand() {
+        names.push(name);
+        current = sub;
+    }
+    names.join(" ")
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    logging::init_logging().expect("Failed to initialize logging");
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    logging::init_logging(&logging::LogOptions {
+        verbose: args.verbose,
+        quiet: args.quiet,
+        debug_commands: args.debug_commands,
+        color: args.color,
+        format: args.log_format,
+        command: subcommand_path(&matches),
+    })
+    .expect("Failed to initialize logging");
+    interactive::set_assume_yes(args.yes);
+    notification::set_enabled(args.notify);
+    interrupt::install();
     log::debug!("Starting hops CLI...");
-
-    let args = Args::parse();
     log::debug!("Command line args: {:?}", args);
 
     match &args.command {
@@ -43,6 +113,18 @@ fn main() -> Result<(), Box<dyn Error>> {
         Some(Commands::Config(config_args)) => {
             commands::config::run(config_args)?;
         }
+        Some(Commands::New(new_args)) => {
+            commands::new::run(new_args)?;
+        }
+        Some(Commands::Render(render_args)) => {
+            commands::render::run(render_args)?;
+        }
+        Some(Commands::Test(test_args)) => {
+            commands::test::run(test_args)?;
+        }
+        Some(Commands::Generate(generate_args)) => {
+            commands::generate::run(generate_args)?;
+        }
         Some(Commands::Validate(validate_args)) => {
             commands::validate::run(validate_args)?;
         }
@@ -52,6 +134,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         Some(Commands::Ai(ai_args)) => {
             commands::ai::run(ai_args)?;
         }
+        Some(Commands::Doctor(doctor_args)) => {
+            commands::doctor::run(doctor_args)?;
+        }
         None => {
             log::info!("No command specified, use --help for usage information");
         }
@@ -59,3 +144,41 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Args::command().debug_assert();
+    }
+
+    #[test]
+    fn global_logging_flags_parse_after_subcommands() {
+        let args = Args::try_parse_from(["hops", "local", "stop", "-vv", "--debug-commands"])
+            .expect("parse");
+        assert_eq!(args.verbose, 2);
+        assert!(args.debug_commands);
+
+        assert!(Args::try_parse_from(["hops", "-q", "-v", "local", "stop"]).is_err());
+    }
+
+    #[test]
+    fn subcommand_path_joins_nested_subcommands() {
+        let matches = Args::command()
+            .try_get_matches_from(["hops", "--log-format", "json", "local", "start"])
+            .unwrap();
+        assert_eq!(subcommand_path(&matches), "local start");
+    }
+
+    #[test]
+    fn yes_flag_accepts_non_interactive_alias() {
+        let args = Args::try_parse_from(["hops", "local", "uninstall", "--non-interactive"])
+            .expect("parse");
+        assert!(args.yes);
+
+        let args = Args::try_parse_from(["hops", "secrets", "sync", "aws", "-y"]).expect("parse");
+        assert!(args.yes);
+    }
+}
@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+const TITLE: &str = "hops";
+
+/// Record the global `--notify` flag.
+pub fn set_enabled(value: bool) {
+    ENABLED.store(value, Ordering::Relaxed);
+}
+
+/// Send a desktop notification describing how `operation` finished, then
+/// hand the result back unchanged. Does nothing unless `--notify` was passed.
+pub fn notify_result<T>(
+    operation: &str,
+    result: Result<T, Box<dyn Error>>,
+) -> Result<T, Box<dyn Error>> {
+    if ENABLED.load(Ordering::Relaxed) {
+        match &result {
+            Ok(_) => send(&format!("{} finished", operation)),
+            Err(err) => send(&format!("{} failed: {}", operation, err)),
+        }
+    }
+    result
+}
+
+/// Best-effort notification via `osascript` on macOS or `notify-send`
+/// (libnotify) elsewhere. Failures are only logged at debug level.
+fn send(message: &str) {
+    let (program, args) = notification_command(message);
+    crate::logging::log_command(program, &args);
+    let status = Command::new(program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => log::debug!("{} exited with {}", program, status),
+        Err(err) => log::debug!("Could not send desktop notification: {}", err),
+    }
+}
+
+fn notification_command(message: &str) -> (&'static str, Vec<String>) {
+    if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {} with title {}",
+            applescript_string(message),
+            applescript_string(TITLE)
+        );
+        ("osascript", vec!["-e".to_string(), script])
+    } else {
+        (
+            "notify-send",
+            vec![
+                "--app-name".to_string(),
+                TITLE.to_string(),
+                TITLE.to_string(),
+                message.to_string(),
+            ],
+        )
+    }
+}
+
+fn applescript_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applescript_string_escapes_quotes_and_backslashes() {
+        assert_eq!(
+            applescript_string(r#"failed: "x" at C:\tmp"#),
+            r#""failed: \"x\" at C:\\tmp""#
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use serde::{Deserialize, Deserializer};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Env var that points hops at a config file other than `~/.hops/config.yaml`.
+pub const HOPS_CONFIG_ENV: &str = "HOPS_CONFIG";
+const DEFAULT_CONFIG_PATH: &str = ".hops/config.yaml";
+
+/// User settings from `~/.hops/config.yaml`. Every field is optional; a
+/// missing file means defaults everywhere.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Settings {
+    pub start: StartSettings,
+    pub kubefwd: KubefwdSettings,
+    pub registry: RegistrySettings,
+    pub timeouts: TimeoutSettings,
+    /// Kube contexts besides `colima` that commands changing the cluster
+    /// (`config install`, `local aws`, ...) may act on.
+    pub allowed_contexts: Vec<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct StartSettings {
+    /// Start kubefwd once `hops local start` has the cluster ready.
+    pub with_kubefwd: bool,
+    /// Colima VM CPUs.
+    pub cpu: Option<u32>,
+    /// Colima VM memory in GiB.
+    pub memory: Option<u32>,
+    /// Colima VM disk size in GiB.
+    pub disk: Option<u32>,
+    /// Stop Colima after this long without kubectl or provider activity
+    /// (humantime, e.g. `4h`). Unset: never.
+    pub idle_stop: Option<String>,
+    /// Extra provider/function packages `hops local preload` (and
+    /// `hops local start --preload`) pull into the VM.
+    pub preload: Vec<String>,
+}
+
+/// How long each kind of wait may take before failing (humantime, e.g.
+/// `10m`). Unset keys use the defaults in `Timeouts::default`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TimeoutSettings {
+    /// Kubernetes API to answer after the VM starts or Docker restarts.
+    #[serde(deserialize_with = "duration")]
+    pub kubernetes: Option<Duration>,
+    /// Docker daemon in the VM to come back after a restart.
+    #[serde(deserialize_with = "duration")]
+    pub docker: Option<Duration>,
+    /// Crossplane Helm install and deployment.
+    #[serde(deserialize_with = "duration")]
+    pub crossplane: Option<Duration>,
+    /// Provider CRDs (`local start` and `local aws`/`azure`/`github`).
+    #[serde(deserialize_with = "duration")]
+    pub providers: Option<Duration>,
+    /// Package registry deployment.
+    #[serde(deserialize_with = "duration")]
+    pub registry: Option<Duration>,
+    /// Configurations and their lock entries to go away in `config uninstall`.
+    #[serde(deserialize_with = "duration")]
+    pub uninstall: Option<Duration>,
+    /// Configurations to become healthy in `config install`/`rollback`.
+    #[serde(deserialize_with = "duration")]
+    pub configuration: Option<Duration>,
+    /// ingress-nginx Helm install (`local start --ingress`).
+    #[serde(deserialize_with = "duration")]
+    pub ingress: Option<Duration>,
+    /// LocalStack Helm install (`local aws --localstack`).
+    #[serde(deserialize_with = "duration")]
+    pub localstack: Option<Duration>,
+    /// Each deletion in `local teardown`.
+    #[serde(deserialize_with = "duration")]
+    pub teardown: Option<Duration>,
+    /// Claims, XRs, and what they composed to go away in
+    /// `config uninstall --cascade`.
+    #[serde(deserialize_with = "duration")]
+    pub cascade: Option<Duration>,
+}
+
+/// Resolved wait timeouts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeouts {
+    pub kubernetes: Duration,
+    pub docker: Duration,
+    pub crossplane: Duration,
+    pub providers: Duration,
+    pub registry: Duration,
+    pub uninstall: Duration,
+    pub configuration: Duration,
+    pub ingress: Duration,
+    pub localstack: Duration,
+    pub teardown: Duration,
+    pub cascade: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            kubernetes: Duration::from_secs(5 * 60),
+            docker: Duration::from_secs(60),
+            crossplane: Duration::from_secs(5 * 60),
+            providers: Duration::from_secs(5 * 60),
+            registry: Duration::from_secs(5 * 60),
+            uninstall: Duration::from_secs(2 * 60),
+            configuration: Duration::from_secs(5 * 60),
+            ingress: Duration::from_secs(5 * 60),
+            localstack: Duration::from_secs(5 * 60),
+            teardown: Duration::from_secs(5 * 60),
+            cascade: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl TimeoutSettings {
+    /// `flag` (a command's `--timeout`) for every wait when given, else the
+    /// configured value, else the default.
+    pub fn resolve(&self, flag: Option<Duration>) -> Timeouts {
+        let defaults = Timeouts::default();
+        let pick = |configured: Option<Duration>, default: Duration| {
+            flag.or(configured).unwrap_or(default)
+        };
+        Timeouts {
+            kubernetes: pick(self.kubernetes, defaults.kubernetes),
+            docker: pick(self.docker, defaults.docker),
+            crossplane: pick(self.crossplane, defaults.crossplane),
+            providers: pick(self.providers, defaults.providers),
+            registry: pick(self.registry, defaults.registry),
+            uninstall: pick(self.uninstall, defaults.uninstall),
+            configuration: pick(self.configuration, defaults.configuration),
+            ingress: pick(self.ingress, defaults.ingress),
+            localstack: pick(self.localstack, defaults.localstack),
+            teardown: pick(self.teardown, defaults.teardown),
+            cascade: pick(self.cascade, defaults.cascade),
+        }
+    }
+}
+
+/// Timeouts from the config file, overridden by a command's `--timeout`.
+pub fn timeouts(flag: Option<Duration>) -> Result<Timeouts, Box<dyn Error>> {
+    Ok(load()?.timeouts.resolve(flag))
+}
+
+fn duration<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Duration>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| humantime::parse_duration(&value).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RegistrySettings {
+    /// NodePort (and host port) the local package registry is pushed to.
+    pub node_port: u16,
+    pub mode: RegistryMode,
+}
+
+impl Default for RegistrySettings {
+    fn default() -> Self {
+        Self {
+            node_port: 30500,
+            mode: RegistryMode::default(),
+        }
+    }
+}
+
+/// Where the local package registry runs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RegistryMode {
+    /// A Deployment in crossplane-system, exposed on a NodePort.
+    #[default]
+    InCluster,
+    /// A Docker container (`hops-registry`) with its data in ~/.hops/local,
+    /// reached by the cluster through the VM gateway.
+    Host,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct KubefwdSettings {
+    /// Namespaces forwarded when kubefwd is started by `hops local start`
+    /// (empty: all namespaces).
+    pub namespaces: Vec<String>,
+    /// Label selectors (key=value) used when started by `hops local start`.
+    pub selectors: Vec<String>,
+    pub log_rotation: LogRotation,
+}
+
+/// When to rotate a background process log and how many old files to keep.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct LogRotation {
+    /// Rotate once the log exceeds this size.
+    pub max_size_mb: u64,
+    /// Rotate once the log was last written this many days ago (0 disables).
+    pub max_age_days: u64,
+    /// Rotated files to keep (`<log>.1` is the newest).
+    pub keep: usize,
+}
+
+impl Default for LogRotation {
+    fn default() -> Self {
+        Self {
+            max_size_mb: 10,
+            max_age_days: 7,
+            keep: 3,
+        }
+    }
+}
+
+pub fn config_path() -> Result<PathBuf, Box<dyn Error>> {
+    if let Ok(path) = std::env::var(HOPS_CONFIG_ENV) {
+        if !path.is_empty() {
+            return Ok(PathBuf::from(path));
+        }
+    }
+    let home = std::env::var("HOME").map_err(|_| "HOME is not set; unable to find hops config")?;
+    Ok(PathBuf::from(home).join(DEFAULT_CONFIG_PATH))
+}
+
+/// Load settings, falling back to defaults when the file does not exist.
+pub fn load() -> Result<Settings, Box<dyn Error>> {
+    let path = config_path()?;
+    match fs::read_to_string(&path) {
+        Ok(contents) => parse(&contents)
+            .map_err(|err| format!("invalid hops config {}: {}", path.display(), err).into()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(err) => Err(format!("failed to read {}: {}", path.display(), err).into()),
+    }
+}
+
+fn parse(contents: &str) -> Result<Settings, serde_yaml::Error> {
+    if contents.trim().is_empty() {
+        return Ok(Settings::default());
+    }
+    serde_yaml::from_str(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_fills_defaults() {
+        let settings = parse("kubefwd:\n  log-rotation:\n    keep: 5\n").unwrap();
+        assert_eq!(
+            settings.kubefwd.log_rotation,
+            LogRotation {
+                keep: 5,
+                ..LogRotation::default()
+            }
+        );
+        assert_eq!(parse("").unwrap().kubefwd.log_rotation.max_size_mb, 10);
+        assert_eq!(parse("").unwrap().registry.node_port, 30500);
+
+        let settings =
+            parse("start:\n  with-kubefwd: true\nkubefwd:\n  namespaces: [apps]\n").unwrap();
+        assert!(settings.start.with_kubefwd);
+        assert_eq!(
+            parse("start:\n  idle-stop: 4h\n")
+                .unwrap()
+                .start
+                .idle_stop
+                .as_deref(),
+            Some("4h")
+        );
+        assert_eq!(
+            parse("registry:\n  mode: host\n").unwrap().registry.mode,
+            RegistryMode::Host
+        );
+        assert_eq!(settings.kubefwd.namespaces, vec!["apps"]);
+
+        let timeouts = parse("timeouts:\n  providers: 10m\n  configuration: 15m\n")
+            .unwrap()
+            .timeouts;
+        assert_eq!(timeouts.resolve(None).providers, Duration::from_secs(600));
+        assert_eq!(
+            timeouts.resolve(None).configuration,
+            Duration::from_secs(900)
+        );
+        assert_eq!(
+            timeouts.resolve(None).registry,
+            Timeouts::default().registry
+        );
+        assert_eq!(
+            timeouts.resolve(Some(Duration::from_secs(30))).providers,
+            Duration::from_secs(30)
+        );
+        assert!(parse("timeouts:\n  docker: soon\n").is_err());
+    }
+}